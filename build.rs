@@ -0,0 +1,16 @@
+use prost::Message;
+use std::path::PathBuf;
+
+// Compiles the gRPC service definition with protox so that building does not need protoc
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    let descriptor_path = PathBuf::from(std::env::var("OUT_DIR")?).join("vanity_descriptor.bin");
+    let file_descriptors = protox::compile(["proto/vanity.proto"], ["proto"])?;
+    std::fs::write(&descriptor_path, file_descriptors.encode_to_vec())?;
+
+    tonic_build::configure()
+        .file_descriptor_set_path(&descriptor_path)
+        .skip_protoc_run()
+        .compile(&["proto/vanity.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+use rlp::RlpStream;
+use serde::Serialize;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Address of the contract created by `sender` with a CREATE at `nonce`.
+pub fn get_contract_address(sender: Address, nonce: U256) -> Address {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&sender);
+    stream.append(&nonce);
+    let out = stream.out();
+
+    let mut hasher = Keccak::v256();
+    hasher.update(&out);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+
+    Address::from_slice(&hash[12..])
+}
+
+/// Init code of the proxy CREATE3 factories deploy with CREATE2, which then CREATEs the contract
+/// (the proxy used by Solady and 0xSequence).
+pub const CREATE3_PROXY_INIT_CODE: [u8; 16] =
+    [0x67, 0x36, 0x3d, 0x3d, 0x37, 0x36, 0x3d, 0x34, 0xf0, 0x3d, 0x52, 0x60, 0x08, 0x60, 0x18, 0xf3];
+
+/// Address of the contract `deployer` creates with CREATE2 from `salt` and the init code's hash.
+pub fn get_create2_address(deployer: Address, salt: H256, init_code_hash: H256) -> Address {
+    ethers::utils::get_create2_address_from_hash(deployer, salt, init_code_hash)
+}
+
+/// Address of the contract a CREATE3 factory at `deployer` creates from `salt`: its CREATE2
+/// proxy's first CREATE. `salt` is what the factory passes to CREATE2, after any mixing with
+/// `msg.sender` it does.
+pub fn get_create3_address(deployer: Address, salt: H256) -> Address {
+    let proxy = get_create2_address(deployer, salt, H256(keccak256(CREATE3_PROXY_INIT_CODE)));
+    get_contract_address(proxy, U256::one())
+}
+
+/// A predicted deployment address and what it was derived from, for `predict-address`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Prediction {
+    pub scheme: &'static str,
+    pub deployer: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt: Option<H256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_code_hash: Option<H256>,
+    pub address: Address,
+}
+
+impl Prediction {
+    pub fn create(deployer: Address, nonce: U256) -> Self {
+        let address = get_contract_address(deployer, nonce);
+        Self { scheme: "create", deployer, nonce: Some(nonce), salt: None, init_code_hash: None, address }
+    }
+
+    pub fn create2(deployer: Address, salt: H256, init_code_hash: H256) -> Self {
+        let address = get_create2_address(deployer, salt, init_code_hash);
+        Self { scheme: "create2", deployer, nonce: None, salt: Some(salt), init_code_hash: Some(init_code_hash), address }
+    }
+
+    pub fn create3(deployer: Address, salt: H256) -> Self {
+        let address = get_create3_address(deployer, salt);
+        Self { scheme: "create3", deployer, nonce: None, salt: Some(salt), init_code_hash: None, address }
+    }
+}
+
+impl std::fmt::Display for Prediction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} from {:?}", self.scheme.to_uppercase(), self.deployer)?;
+        if let Some(nonce) = self.nonce {
+            write!(f, " at nonce {}", nonce)?;
+        }
+        if let Some(salt) = self.salt {
+            write!(f, " with salt {:?}", salt)?;
+        }
+        if let Some(init_code_hash) = self.init_code_hash {
+            write!(f, " and init code hash {:?}", init_code_hash)?;
+        }
+        write!(f, ": {:?}", self.address)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::difficulty::Difficulty;
+use crate::pattern;
+use ethers::types::U256;
+use std::fmt;
+
+/// Where a vanity word appears in the transaction hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// At the start, right after `0x`, where explorers and wallets show it.
+    Prefix,
+    /// At the end, also visible in shortened hashes.
+    Suffix,
+    /// Anywhere, usually hidden in the middle of a shortened hash.
+    Contains,
+}
+
+impl Placement {
+    pub const ALL: [Placement; 3] = [Placement::Prefix, Placement::Suffix, Placement::Contains];
+
+    /// Whether the search can mine for it; it only compares prefixes.
+    pub fn minable(self) -> bool {
+        self == Self::Prefix
+    }
+
+    /// Expected candidates per hit: 16^digits at a fixed position, and about that divided by the
+    /// number of positions when the word can appear anywhere.
+    pub fn expected_attempts(self, digits: usize) -> f64 {
+        let single = 16f64.powi(digits as i32);
+        match self {
+            Self::Prefix | Self::Suffix => single,
+            Self::Contains => -1.0 / (f64::from(65 - digits as i32) * (-1.0 / single).ln_1p()).exp_m1(),
+        }
+    }
+
+    /// How a hash with `digits` in this place looks, e.g. `0xdead…`.
+    pub fn preview(self, digits: &str) -> String {
+        match self {
+            Self::Prefix => format!("0x{}…", digits),
+            Self::Suffix => format!("0x…{}", digits),
+            Self::Contains => format!("0x…{}…", digits),
+        }
+    }
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Prefix => "prefix",
+            Self::Suffix => "suffix",
+            Self::Contains => "contains",
+        })
+    }
+}
+
+/// One way to place a word, with what it costs to find.
+#[derive(Debug, Clone)]
+pub struct Advice {
+    pub placement: Placement,
+    pub difficulty: Difficulty,
+    /// Worst-case extra cost of the max fee climbing over the expected attempts, in wei, when the
+    /// gas limit is known.
+    pub fee_overhead: Option<U256>,
+}
+
+/// Cost of each placement of `word`, spelled in hex, at `hashrate` candidates per second.
+pub fn advise(
+    word: &str,
+    hashrate: f64,
+    fee_step: U256,
+    thread_count: usize,
+    gas_limit: Option<U256>,
+) -> eyre::Result<(String, Vec<Advice>)> {
+    let digits = pattern::spell(word)?;
+    let advice = Placement::ALL
+        .iter()
+        .map(|&placement| {
+            let attempts = placement.expected_attempts(digits.len());
+            let difficulty = Difficulty::from_attempts(placement.preview(&digits), attempts, hashrate, fee_step, thread_count);
+            let fee_overhead = gas_limit.map(|gas_limit| gas_limit * U256::from(difficulty.fee_window_per_worker.min(u128::MAX as f64) as u128));
+            Advice { placement, difficulty, fee_overhead }
+        })
+        .collect();
+    Ok((digits, advice))
+}
+
+/// The longest leading part of `digits` a prefix search is expected to find within `seconds`.
+pub fn longest_prefix_within(digits: &str, hashrate: f64, seconds: f64) -> Option<&str> {
+    (1..=digits.len())
+        .rev()
+        .map(|len| &digits[..len])
+        .find(|prefix| Placement::Prefix.expected_attempts(prefix.len()) / hashrate <= seconds)
+}
@@ -0,0 +1,162 @@
+use crate::config::non_empty_var;
+use crate::exit;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, NameOrAddress, Signature, H256, U256};
+use ethers::utils::keccak256;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// How often the approval service is asked whether the match has been approved.
+pub const APPROVAL_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long to wait for an approval when APPROVAL_TIMEOUT is not set.
+pub const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+const APPROVAL_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A match waiting for a second person's approval, as posted to APPROVAL_URL.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    pub from: Address,
+    pub nonce: U256,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub max_fee_per_gas: U256,
+    /// Address of the contract a deployment creates.
+    pub contract_address: Option<Address>,
+    pub raw_tx: Bytes,
+    /// What an approver signs with `personal_sign` to approve it.
+    pub message: String,
+}
+
+impl ApprovalRequest {
+    pub fn new(chain_id: u64, raw_tx: Bytes, tx: &TypedTransaction, from: Address, contract_address: Option<Address>) -> Self {
+        let tx_hash = H256::from(keccak256(&raw_tx));
+        let max_fee_per_gas = match tx {
+            TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas,
+            _ => tx.gas_price(),
+        };
+        Self {
+            chain_id,
+            tx_hash,
+            from,
+            nonce: tx.nonce().copied().unwrap_or_default(),
+            to: tx.to().and_then(NameOrAddress::as_address).copied(),
+            value: tx.value().copied().unwrap_or_default(),
+            max_fee_per_gas: max_fee_per_gas.unwrap_or_default(),
+            contract_address,
+            raw_tx,
+            message: approval_message(chain_id, tx_hash),
+        }
+    }
+}
+
+/// What the approval service answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Pending,
+    /// Approved with a valid signature by this approver.
+    Approved(Address),
+    Rejected(String),
+}
+
+/// The message an approver signs (EIP-191 `personal_sign`) to approve exactly this transaction.
+pub fn approval_message(chain_id: u64, tx_hash: H256) -> String {
+    format!("Approve transaction {:?} on chain {}", tx_hash, chain_id)
+}
+
+/// Reads an answer from the approval service: `{"status": "pending"}`,
+/// `{"status": "rejected", "reason": ...}` or `{"status": "approved", "approver": ..., "signature": ...}`.
+/// An approval only counts if its signature over `message` recovers to the approver, who must be
+/// one of `approvers` and not the deployer.
+pub fn decide(response: &Value, message: &str, approvers: &[Address], deployer: Address) -> eyre::Result<Decision> {
+    match response["status"].as_str() {
+        Some("pending") => Ok(Decision::Pending),
+        Some("rejected") => Ok(Decision::Rejected(response["reason"].as_str().unwrap_or("no reason given").to_string())),
+        Some("approved") => {
+            let field = |name: &str| response[name].as_str().ok_or_else(|| eyre::eyre!("approval has no {}", name));
+            let approver: Address = field("approver")?.parse().map_err(|e| eyre::eyre!("invalid approver: {}", e))?;
+            let signature: Signature = field("signature")?.parse().map_err(|e| eyre::eyre!("invalid approval signature: {}", e))?;
+            eyre::ensure!(approver != deployer, "the deployer {:?} cannot approve its own transaction", approver);
+            eyre::ensure!(approvers.contains(&approver), "{:?} is not one of APPROVERS", approver);
+            signature
+                .verify(message, approver)
+                .map_err(|_| eyre::eyre!("the approval signature was not made by {:?} over {:?}", approver, message))?;
+            Ok(Decision::Approved(approver))
+        }
+        other => eyre::bail!("unexpected approval status {:?}", other),
+    }
+}
+
+/// Where matches go for a second person to approve before they are broadcast, from APPROVAL_URL,
+/// APPROVERS, APPROVAL_TOKEN and APPROVAL_TIMEOUT (minutes).
+#[derive(Debug, Clone)]
+pub struct ApprovalConfig {
+    pub url: String,
+    pub approvers: Vec<Address>,
+    pub token: Option<String>,
+    pub timeout: Duration,
+}
+
+impl ApprovalConfig {
+    /// `None` without APPROVAL_URL, when matches are sent on the local confirmation alone.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        Self::read_env().map_err(exit::config_error)
+    }
+
+    fn read_env() -> eyre::Result<Option<Self>> {
+        let Some(url) = non_empty_var("APPROVAL_URL") else {
+            return Ok(None);
+        };
+        let approvers = non_empty_var("APPROVERS")
+            .ok_or_else(|| eyre::eyre!("APPROVAL_URL is set, so APPROVERS must list the addresses allowed to approve"))?
+            .split(',')
+            .map(|approver| approver.trim().parse::<Address>().map_err(|e| eyre::eyre!("invalid address {:?} in APPROVERS: {}", approver, e)))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let timeout = match non_empty_var("APPROVAL_TIMEOUT") {
+            Some(minutes) => Duration::from_secs(minutes.parse::<u64>().map_err(|e| eyre::eyre!("invalid APPROVAL_TIMEOUT: {}", e))? * 60),
+            None => DEFAULT_APPROVAL_TIMEOUT,
+        };
+        Ok(Some(Self { url: url.trim_end_matches('/').to_string(), approvers, token: non_empty_var("APPROVAL_TOKEN"), timeout }))
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Posts `request` to APPROVAL_URL, then polls `APPROVAL_URL/<tx_hash>` until it is approved,
+    /// rejected or the timeout passes (`Ok(None)`). Failed polls are retried; an approval that
+    /// fails [`decide`] ends the wait with an error.
+    pub async fn request(&self, request: &ApprovalRequest) -> eyre::Result<Option<Decision>> {
+        let client = reqwest::Client::builder().timeout(APPROVAL_REQUEST_TIMEOUT).build()?;
+        let response = self.authorized(client.post(&self.url)).json(request).send().await?;
+        let status = response.status();
+        eyre::ensure!(status.is_success(), "the approval service answered {}: {}", status, response.text().await?.trim());
+
+        let poll_url = format!("{}/{:?}", self.url, request.tx_hash);
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(APPROVAL_POLL_INTERVAL).await;
+            let answer = match self.authorized(client.get(&poll_url)).send().await {
+                Ok(response) if response.status().is_success() => response.json::<Value>().await,
+                Ok(response) => {
+                    println!("Warning: the approval service answered {}, retrying", response.status());
+                    continue;
+                }
+                Err(e) => Err(e),
+            };
+            match answer {
+                Ok(answer) => match decide(&answer, &request.message, &self.approvers, request.from)? {
+                    Decision::Pending => {}
+                    decision => return Ok(Some(decision)),
+                },
+                Err(e) => println!("Warning: could not reach the approval service ({}), retrying", e),
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,226 @@
+use crate::envelope;
+use crate::pattern;
+use crate::search::{self, round_up_to_step, Salt, SearchParams, THREAD_OFFSET_SPACING};
+use ethers::core::rand::Rng;
+use ethers::types::U256;
+use ethers::utils::keccak256;
+use std::fmt;
+
+/// Prefix lengths expected to match fewer times than this in the sample are listed but not judged.
+pub const MIN_EXPECTED_MATCHES: f64 = 5.0;
+/// Standard deviations from the theoretical match rate before a prefix length is flagged. Wide
+/// enough that an honest run is essentially never flagged across all the lengths checked.
+pub const Z_SCORE_LIMIT: f64 = 4.5;
+/// Chi-square of the first hex digit's 16 counts above which the digits are flagged as uneven,
+/// about a one in a million chance for 15 degrees of freedom.
+pub const CHI_SQUARE_LIMIT: f64 = 55.0;
+
+/// How often hashes matched the leading `digits` digits of the pattern, against the theory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixRate {
+    pub digits: usize,
+    pub matches: u64,
+    pub expected: f64,
+    pub z_score: f64,
+}
+
+impl PrefixRate {
+    pub fn suspicious(&self) -> bool {
+        self.expected >= MIN_EXPECTED_MATCHES && self.z_score.abs() > Z_SCORE_LIMIT
+    }
+}
+
+/// The outcome of re-deriving a random sample of the candidates a search would try.
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    pub samples: u64,
+    pub sign_failures: u64,
+    /// Signed candidates whose reported hash is not the keccak of their bytes.
+    pub hash_mismatches: u64,
+    /// Signed candidates that do not decode back to the candidate that was signed.
+    pub encoding_mismatches: u64,
+    /// Hashes the search's matcher judged differently from a digit-by-digit comparison.
+    pub matcher_disagreements: u64,
+    /// Hashes matching the first 1, 2, ... digits of the pattern.
+    pub prefix_matches: Vec<u64>,
+    pub first_digit_counts: [u64; 16],
+    /// A description of the first failed check, to start debugging from.
+    pub first_problem: Option<String>,
+}
+
+impl AuditReport {
+    /// Combines reports of parts of one sample, e.g. from several threads.
+    pub fn merge(reports: impl IntoIterator<Item = Self>) -> Self {
+        reports.into_iter().fold(Self::default(), |mut total, report| {
+            total.samples += report.samples;
+            total.sign_failures += report.sign_failures;
+            total.hash_mismatches += report.hash_mismatches;
+            total.encoding_mismatches += report.encoding_mismatches;
+            total.matcher_disagreements += report.matcher_disagreements;
+            total.prefix_matches.resize(total.prefix_matches.len().max(report.prefix_matches.len()), 0);
+            for (sum, count) in total.prefix_matches.iter_mut().zip(&report.prefix_matches) {
+                *sum += count;
+            }
+            for (sum, count) in total.first_digit_counts.iter_mut().zip(&report.first_digit_counts) {
+                *sum += count;
+            }
+            total.first_problem = total.first_problem.or(report.first_problem);
+            total
+        })
+    }
+
+    /// The observed match rate of each leading part of the pattern against 16^-digits.
+    pub fn rates(&self) -> Vec<PrefixRate> {
+        let signed = self.samples - self.sign_failures;
+        self.prefix_matches
+            .iter()
+            .enumerate()
+            .map(|(i, &matches)| {
+                let probability = 16f64.powi(-(i as i32 + 1));
+                PrefixRate {
+                    digits: i + 1,
+                    matches,
+                    expected: signed as f64 * probability,
+                    z_score: z_score(matches, signed, probability),
+                }
+            })
+            .collect()
+    }
+
+    pub fn first_digit_chi_square(&self) -> f64 {
+        chi_square(&self.first_digit_counts)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.hash_mismatches == 0
+            && self.encoding_mismatches == 0
+            && self.matcher_disagreements == 0
+            && self.first_digit_chi_square() <= CHI_SQUARE_LIMIT
+            && !self.rates().iter().any(PrefixRate::suspicious)
+    }
+
+    fn problem(&mut self, description: impl FnOnce() -> String) {
+        if self.first_problem.is_none() {
+            self.first_problem = Some(description());
+        }
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Audited {} candidates ({} failed to sign)", self.samples, self.sign_failures)?;
+        writeln!(f, "  Hash is keccak of the signed bytes: {} mismatch(es)", self.hash_mismatches)?;
+        writeln!(f, "  Signed bytes decode to the candidate: {} mismatch(es)", self.encoding_mismatches)?;
+        writeln!(f, "  Matcher agrees digit by digit: {} disagreement(s)", self.matcher_disagreements)?;
+        let chi_square = self.first_digit_chi_square();
+        writeln!(
+            f,
+            "  First hex digit spread: chi-square {:.1} (limit {:.0}){}",
+            chi_square,
+            CHI_SQUARE_LIMIT,
+            if chi_square > CHI_SQUARE_LIMIT { "  <- uneven" } else { "" }
+        )?;
+        for rate in self.rates() {
+            let verdict = if rate.suspicious() {
+                "  <- suspicious"
+            } else if rate.expected < MIN_EXPECTED_MATCHES {
+                "  (too rare to judge)"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "  {} digit(s): {} matches, expected {:.1}, z = {:+.2}{}",
+                rate.digits, rate.matches, rate.expected, rate.z_score, verdict
+            )?;
+        }
+        if let Some(problem) = &self.first_problem {
+            writeln!(f, "  First problem: {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Signs `samples` candidates picked at random from the fees and salts the search in `params`
+/// would sweep, re-derives each hash independently and counts how often they match each leading
+/// part of the pattern, for [`AuditReport::rates`] to compare with the theory.
+pub fn run(params: &SearchParams, samples: u64, rng: &mut impl Rng) -> AuditReport {
+    let digits: Vec<u8> = pattern::normalize(&params.hash_prefix).bytes().collect();
+    let fee_step = params.fee_step.max(U256::one());
+    let first_fee = round_up_to_step(params.base_fee_start + params.priority_fee, fee_step);
+    let fee_span = params.max_fee_per_gas.map_or(
+        U256::from(THREAD_OFFSET_SPACING) * params.thread_count.max(1),
+        |ceiling| ceiling.saturating_sub(first_fee),
+    ) / fee_step;
+
+    let mut report = AuditReport { samples, prefix_matches: vec![0; digits.len()], ..Default::default() };
+    for _ in 0..samples {
+        let steps = U256::from(rng.gen::<u64>()) % (fee_span + 1);
+        let salt = if params.salt == Salt::None { 0 } else { rng.gen::<u32>() as u64 };
+        let tx = search::candidate(params, first_fee + steps * fee_step, salt);
+        let (signed_rlp, tx_hash) = match params.signer.sign_candidate(&tx) {
+            Ok(signed) => signed,
+            Err(_) => {
+                report.sign_failures += 1;
+                continue;
+            }
+        };
+
+        if keccak256(&signed_rlp) != tx_hash {
+            report.hash_mismatches += 1;
+            report.problem(|| format!("0x{} is reported as hash 0x{}", hex::encode(&signed_rlp), hex::encode(tx_hash)));
+        }
+        match envelope::decode_signed(&signed_rlp) {
+            Ok(decoded)
+                if decoded.reencoded == signed_rlp
+                    && decoded.tx.nonce() == tx.nonce.as_ref()
+                    && decoded.tx.data().map_or(&[][..], |data| data.as_ref()) == tx.data.as_deref().unwrap_or_default()
+                    && decoded.tx.value().copied().unwrap_or_default() == tx.value.unwrap_or_default()
+                    && decoded.tx.as_eip1559_ref().is_some_and(|decoded| {
+                        decoded.max_fee_per_gas == tx.max_fee_per_gas
+                            && decoded.max_priority_fee_per_gas == tx.max_priority_fee_per_gas
+                    }) => {}
+            Ok(_) => {
+                report.encoding_mismatches += 1;
+                report.problem(|| format!("0x{} does not decode to the candidate that was signed", hex::encode(&signed_rlp)));
+            }
+            Err(e) => {
+                report.encoding_mismatches += 1;
+                report.problem(|| format!("0x{} does not decode: {}", hex::encode(&signed_rlp), e));
+            }
+        }
+
+        let hash_digits = hex::encode(tx_hash);
+        let matched = hash_digits.bytes().zip(&digits).take_while(|(a, b)| a == *b).count();
+        for count in &mut report.prefix_matches[..matched] {
+            *count += 1;
+        }
+        if search::hash_matches(&tx_hash, &params.hash_prefix) != (matched == digits.len()) {
+            report.matcher_disagreements += 1;
+            report.problem(|| format!("the matcher misjudges 0x{} against {}", hash_digits, params.hash_prefix));
+        }
+        report.first_digit_counts[(tx_hash[0] >> 4) as usize] += 1;
+    }
+
+    report
+}
+
+/// How many standard deviations `observed` successes in `trials` are from the binomial mean.
+pub fn z_score(observed: u64, trials: u64, probability: f64) -> f64 {
+    let mean = trials as f64 * probability;
+    let deviation = (mean * (1.0 - probability)).sqrt();
+    if deviation == 0.0 {
+        return 0.0;
+    }
+    (observed as f64 - mean) / deviation
+}
+
+/// Pearson's chi-square of `counts` against an even spread.
+pub fn chi_square(counts: &[u64]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let expected = total as f64 / counts.len() as f64;
+    counts.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum()
+}
@@ -1,45 +1,98 @@
+use clap::{Parser, Subcommand};
 use ethers::prelude::*;
-use std::convert::TryFrom;
-use std::env;
+use find_tx_hash_prefix::chains;
+use find_tx_hash_prefix::config;
+use find_tx_hash_prefix::exit;
+use find_tx_hash_prefix::gastrend::{FeeSample, GasHistory, Trend};
+use find_tx_hash_prefix::oracle::FeeSource;
+use find_tx_hash_prefix::overhead;
+use find_tx_hash_prefix::rpc::{self, RpcConfig};
+use find_tx_hash_prefix::secrets;
+use find_tx_hash_prefix::units::format_gwei;
 use eyre::Result;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Prints the current gas prices, recording them to GAS_HISTORY if it is set")]
+struct Cli {
+    /// Keep sampling every this many seconds instead of exiting
+    #[arg(long)]
+    every: Option<u64>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Summarize the fees recorded in GAS_HISTORY by weekday and hour (UTC)
+    Trend {
+        /// Only use samples from the last this many days
+        #[arg(long, default_value_t = 28)]
+        days: u64,
+    },
+}
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    dotenv::from_filename("./.env").ok();
-
-    // Get RPC URL from .env
-    let rpc_url = env::var("RPC")?;
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-
-    // Get latest block to get base fee
-    let block = provider.get_block(BlockNumber::Latest).await?.unwrap();
-    let base_fee = block.base_fee_per_gas.unwrap_or_default();
-    
-    // Get fee history for priority fee estimation
-    let fee_history = provider
-        .fee_history(10, BlockNumber::Latest, &[10.0])
-        .await?;
-    
-    // Calculate average priority fee from recent blocks
-    let priority_fees: Vec<U256> = fee_history.reward.iter()
-        .flat_map(|reward| reward.first().cloned())
-        .collect();
-    
-    let avg_priority_fee = if !priority_fees.is_empty() {
-        let sum = priority_fees.iter().fold(U256::zero(), |acc, &x| acc + x);
-        sum / U256::from(priority_fees.len())
-    } else {
-        U256::zero()
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            exit::classify(&e).into()
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    config::load_dotenv();
+    secrets::load().await.map_err(exit::config_error)?;
+
+    // Get RPC URL and credentials from .env
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let history = match config::gas_history_path() {
+        Some(path) => Some((GasHistory::open(&path)?, path)),
+        None => None,
     };
 
-    // Convert to Gwei (1 Gwei = 10^9 wei)
-    let base_fee_gwei = base_fee.as_u128() as f64 / 1_000_000_000.0;
-    let priority_fee_gwei = avg_priority_fee.as_u128() as f64 / 1_000_000_000.0;
+    if let Some(Command::Trend { days }) = cli.command {
+        let (history, path) = history.ok_or_else(|| exit::config_error(eyre::eyre!("trend needs GAS_HISTORY, where gas_checker records its samples")))?;
+        let since = chrono::Utc::now().timestamp() - (days * 24 * 60 * 60) as i64;
+        let samples = history.samples(chain_id, since)?;
+        println!(
+            "Fees on chain {} over the last {} days: {} samples from {}",
+            chains::describe(chain_id),
+            days,
+            samples.len(),
+            path
+        );
+        print!("{}", Trend::new(&samples).report());
+        return Ok(());
+    }
 
-    println!("Current Base Network Gas Prices:");
-    println!("Base Fee: {:.5} Gwei", base_fee_gwei);
-    println!("Priority Fee: {:.5} Gwei", priority_fee_gwei);
-    println!("Total: {:.5} Gwei", base_fee_gwei + priority_fee_gwei);
+    loop {
+        // Base fee and priority fee from FEE_SOURCE, by default the average 10th percentile
+        // priority fee of the last 10 blocks
+        let market = overhead::market_fees(&provider).await?;
+        println!("Current Gas Prices on chain {} (from {}):", chains::describe(chain_id), FeeSource::from_env(chain_id)?);
+        println!("Base Fee: {} Gwei", format_gwei(market.base_fee));
+        println!("Priority Fee: {} Gwei", format_gwei(market.priority_fee));
+        println!("Total: {} Gwei", format_gwei(market.base_fee + market.priority_fee));
 
-    Ok(())
+        if let Some((history, path)) = &history {
+            let sample = FeeSample {
+                chain_id,
+                timestamp: chrono::Utc::now().timestamp(),
+                base_fee: market.base_fee,
+                priority_fee: market.priority_fee,
+            };
+            history.record(&sample)?;
+            println!("Recorded to {}", path);
+        }
+        match cli.every {
+            Some(seconds) => tokio::time::sleep(Duration::from_secs(seconds.max(1))).await,
+            None => return Ok(()),
+        }
+    }
 }
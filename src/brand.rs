@@ -0,0 +1,85 @@
+use crate::config::chain_var;
+use crate::pattern;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+const BRAND_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something on the explorer whose address or hash starts with the same digits as the match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Association {
+    /// The explorer's item type, such as `token`, `contract` or `label`.
+    pub kind: String,
+    pub name: String,
+    /// Address or transaction hash, as the explorer returned it.
+    pub id: String,
+    /// A priced or certified token, or a label the explorer assigned, rather than just a name
+    /// someone gave their own contract.
+    pub prominent: bool,
+}
+
+impl fmt::Display for Association {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} at {}", self.kind, self.name, self.id)?;
+        if self.prominent {
+            write!(f, " (prominent)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The Blockscout instance BRAND_CHECK_URL (or BRAND_CHECK_URL_<chain_id>) names, if any.
+pub fn url_from_env(chain_id: u64) -> Option<String> {
+    chain_var("BRAND_CHECK_URL", chain_id).map(|url| url.trim_end_matches('/').to_string())
+}
+
+/// Searches the Blockscout API at `url` for named tokens, contracts and labels starting with
+/// `prefix`. Etherscan-style APIs cannot search by prefix, so only Blockscout is supported.
+pub async fn search(url: &str, prefix: &str) -> eyre::Result<Vec<Association>> {
+    let client = reqwest::Client::builder().timeout(BRAND_CHECK_TIMEOUT).build()?;
+    let query = pattern::to_prefix(prefix);
+    let response = client
+        .get(format!("{}/api/v2/search", url))
+        .query(&[("q", query.as_str())])
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(associations(&serde_json::from_str(&response)?, prefix))
+}
+
+/// Reads the named items of a Blockscout search result whose address or hash starts with
+/// `prefix`, prominent ones first. The search also matches names, which are left out.
+pub fn associations(response: &Value, prefix: &str) -> Vec<Association> {
+    let prefix = pattern::to_prefix(prefix);
+    let mut found: Vec<Association> = response["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let id = ["address_hash", "address", "transaction_hash", "tx_hash"].iter().find_map(|field| item[*field].as_str())?;
+            let name = item["name"].as_str().filter(|name| !name.is_empty())?;
+            if !id.to_lowercase().starts_with(&prefix) {
+                return None;
+            }
+            let kind = item["type"].as_str().unwrap_or("item");
+            let name = match item["symbol"].as_str() {
+                Some(symbol) if !symbol.is_empty() => format!("{} ({})", name, symbol),
+                _ => name.to_string(),
+            };
+            let prominent = kind == "label"
+                || !item["exchange_rate"].is_null()
+                || item["certified"].as_bool() == Some(true)
+                || item["is_verified_via_admin_panel"].as_bool() == Some(true);
+            Some(Association { kind: kind.to_string(), name, id: id.to_string(), prominent })
+        })
+        .collect();
+    found.sort_by_key(|association| !association.prominent);
+    // A token is also listed as its contract
+    let mut seen = HashSet::new();
+    found.retain(|association| seen.insert(association.id.to_lowercase()));
+    found
+}
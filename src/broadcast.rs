@@ -0,0 +1,424 @@
+use crate::config::{self, chain_var};
+use crate::exit;
+use crate::rpc::{self, RpcConfig, RpcProvider};
+use crate::sealed::{Opener, Sealed, SealingKey};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use eyre::{ensure, eyre};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const DEFAULT_PROTECT_URL: &str = "https://rpc.flashbots.net";
+pub const DEFAULT_RELAY_URL: &str = "https://relay.flashbots.net";
+pub const DEFAULT_SIGNED_TX_PATH: &str = "signed_transactions.txt";
+/// Blocks a bundle is submitted for, one bundle per block.
+pub const DEFAULT_BUNDLE_BLOCKS: u64 = 25;
+/// Flashbots Protect keeps retrying a transaction for 25 blocks before dropping it.
+const PROTECT_BLOCKS: u64 = 25;
+const PRIVATE_RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What became of a signed transaction handed to a broadcaster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Submission {
+    /// Sent to the network (or a private relay) under this hash.
+    Sent(H256),
+    /// Written to a file for sending later, e.g. from an air-gapped machine.
+    Saved { tx_hash: H256, path: PathBuf },
+}
+
+/// A way of getting a signed transaction included.
+#[async_trait]
+pub trait Broadcaster: Send + Sync + fmt::Display {
+    async fn submit(&self, raw_tx: &Bytes) -> eyre::Result<Submission>;
+
+    /// Exercises the connections `submit` will use, so a confirmed match is not held up by TCP
+    /// and TLS handshakes. Called periodically while a search runs.
+    async fn warm_up(&self) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    /// Waits for the receipt of a transaction this broadcaster sent, or `None` once it can no
+    /// longer be included. `provider` is the chain's regular RPC.
+    async fn wait_for_receipt(&self, provider: &RpcProvider, tx_hash: H256) -> eyre::Result<Option<TransactionReceipt>> {
+        Ok(rpc::wait_for_receipt(provider, tx_hash).await?)
+    }
+}
+
+/// Which broadcaster to use for a chain, from `BROADCASTER` (or `BROADCASTER_<chain_id>`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastConfig {
+    /// `rpc` (the default): `eth_sendRawTransaction` on the chain's RPC.
+    Rpc,
+    /// `race`: the chain's RPC and every URL in `BROADCAST_RPCS` at once; the first to accept wins.
+    Race(Vec<String>),
+    /// `flashbots-protect`: the private mempool at `FLASHBOTS_PROTECT_URL`.
+    FlashbotsProtect(String),
+    /// `bundle`: a single-transaction `eth_sendBundle` to `BUNDLE_RELAY_URL` for each of the next
+    /// `BUNDLE_BLOCKS` blocks, authenticated with `BUNDLE_SIGNING_KEY`.
+    Bundle { relay_url: String, signing_key: Option<String>, blocks: u64 },
+    /// `file`: appends the signed transaction to `SIGNED_TX_PATH` without sending it.
+    File(PathBuf),
+}
+
+impl BroadcastConfig {
+    pub fn from_env(chain_id: u64) -> eyre::Result<Self> {
+        Self::read_env(chain_id).map_err(exit::config_error)
+    }
+
+    fn read_env(chain_id: u64) -> eyre::Result<Self> {
+        let var = |name: &str| chain_var(name, chain_id);
+        Ok(match var("BROADCASTER").as_deref().unwrap_or("rpc") {
+            "rpc" => Self::Rpc,
+            "race" => {
+                let urls: Vec<String> = var("BROADCAST_RPCS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .collect();
+                ensure!(!urls.is_empty(), "BROADCASTER=race needs BROADCAST_RPCS, a comma-separated list of extra RPC URLs");
+                Self::Race(urls)
+            }
+            "flashbots-protect" => Self::FlashbotsProtect(var("FLASHBOTS_PROTECT_URL").unwrap_or_else(|| DEFAULT_PROTECT_URL.to_string())),
+            "bundle" => Self::Bundle {
+                relay_url: var("BUNDLE_RELAY_URL").unwrap_or_else(|| DEFAULT_RELAY_URL.to_string()),
+                signing_key: var("BUNDLE_SIGNING_KEY"),
+                blocks: match var("BUNDLE_BLOCKS") {
+                    Some(blocks) => blocks.parse().map_err(|e| eyre!("invalid BUNDLE_BLOCKS: {}", e))?,
+                    None => DEFAULT_BUNDLE_BLOCKS,
+                },
+            },
+            "file" => Self::File(var("SIGNED_TX_PATH").unwrap_or_else(|| DEFAULT_SIGNED_TX_PATH.to_string()).into()),
+            other => eyre::bail!("unknown BROADCASTER {:?}, expected rpc, race, flashbots-protect, bundle or file", other),
+        })
+    }
+
+    /// Builds the broadcaster, with `provider` as the chain's regular RPC.
+    pub async fn connect(&self, provider: RpcProvider) -> eyre::Result<Box<dyn Broadcaster>> {
+        Ok(match self {
+            Self::Rpc => Box::new(PublicRpc { provider }),
+            Self::Race(urls) => {
+                let mut providers = vec![("RPC".to_string(), provider)];
+                for url in urls {
+                    providers.push((url.clone(), rpc::connect(&RpcConfig::for_url(url.clone())?).await?));
+                }
+                Box::new(RpcRace { providers })
+            }
+            Self::FlashbotsProtect(url) => Box::new(FlashbotsProtect {
+                protect: rpc::connect(&RpcConfig::for_url(url.clone())?).await?,
+                url: url.clone(),
+                provider,
+                deadline: Mutex::new(None),
+            }),
+            Self::Bundle { relay_url, signing_key, blocks } => Box::new(BundleRelay {
+                relay_url: relay_url.clone(),
+                client: reqwest::Client::new(),
+                // The key only builds relay reputation, so a throwaway one works
+                signing_key: match signing_key {
+                    Some(key) => key.parse()?,
+                    None => LocalWallet::new(&mut ethers::core::rand::thread_rng()),
+                },
+                blocks: *blocks,
+                provider,
+                deadline: Mutex::new(None),
+            }),
+            Self::File(path) => Box::new(FileOnly::new(path.clone(), config::journal_passphrase().as_deref())?),
+        })
+    }
+}
+
+/// `eth_sendRawTransaction` on the chain's RPC.
+pub struct PublicRpc {
+    pub provider: RpcProvider,
+}
+
+impl fmt::Display for PublicRpc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the RPC")
+    }
+}
+
+#[async_trait]
+impl Broadcaster for PublicRpc {
+    async fn submit(&self, raw_tx: &Bytes) -> eyre::Result<Submission> {
+        Ok(Submission::Sent(self.provider.send_raw_transaction(raw_tx.clone()).await?.tx_hash()))
+    }
+
+    async fn warm_up(&self) -> eyre::Result<()> {
+        self.provider.get_block_number().await?;
+        Ok(())
+    }
+}
+
+/// Sends to several RPCs at once so one slow or censoring endpoint cannot hold the transaction
+/// back. The others keep going in the background after the first accepts it.
+pub struct RpcRace {
+    providers: Vec<(String, RpcProvider)>,
+}
+
+impl fmt::Display for RpcRace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} RPCs at once", self.providers.len())
+    }
+}
+
+#[async_trait]
+impl Broadcaster for RpcRace {
+    async fn submit(&self, raw_tx: &Bytes) -> eyre::Result<Submission> {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        for (name, provider) in &self.providers {
+            let (name, provider, raw_tx, sender) = (name.clone(), provider.clone(), raw_tx.clone(), sender.clone());
+            tokio::spawn(async move {
+                let sent = provider.send_raw_transaction(raw_tx).await.map(|pending| pending.tx_hash());
+                let _ = sender.send((name, sent));
+            });
+        }
+        drop(sender);
+
+        let mut errors = Vec::new();
+        while let Some((name, sent)) = receiver.recv().await {
+            match sent {
+                Ok(tx_hash) => return Ok(Submission::Sent(tx_hash)),
+                Err(e) => errors.push(format!("{}: {}", name, e)),
+            }
+        }
+        eyre::bail!("every RPC rejected the transaction: {}", errors.join("; "))
+    }
+
+    async fn warm_up(&self) -> eyre::Result<()> {
+        let mut errors = Vec::new();
+        for (name, provider) in &self.providers {
+            if let Err(e) = provider.get_block_number().await {
+                errors.push(format!("{}: {}", name, e));
+            }
+        }
+        ensure!(errors.is_empty(), "{}", errors.join("; "));
+        Ok(())
+    }
+}
+
+/// Flashbots Protect: kept out of the public mempool, so the chain's RPC only sees it once it
+/// is included.
+pub struct FlashbotsProtect {
+    url: String,
+    protect: RpcProvider,
+    provider: RpcProvider,
+    /// Last block the transaction can still be included in.
+    deadline: Mutex<Option<u64>>,
+}
+
+impl fmt::Display for FlashbotsProtect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Flashbots Protect ({})", self.url)
+    }
+}
+
+#[async_trait]
+impl Broadcaster for FlashbotsProtect {
+    async fn submit(&self, raw_tx: &Bytes) -> eyre::Result<Submission> {
+        let block = self.provider.get_block_number().await?.as_u64();
+        let tx_hash = self.protect.send_raw_transaction(raw_tx.clone()).await?.tx_hash();
+        *self.deadline.lock() = Some(block + PROTECT_BLOCKS);
+        Ok(Submission::Sent(tx_hash))
+    }
+
+    async fn warm_up(&self) -> eyre::Result<()> {
+        self.provider.get_block_number().await?;
+        self.protect.get_chainid().await?;
+        Ok(())
+    }
+
+    async fn wait_for_receipt(&self, provider: &RpcProvider, tx_hash: H256) -> eyre::Result<Option<TransactionReceipt>> {
+        let deadline = *self.deadline.lock();
+        wait_for_private_receipt(provider, tx_hash, deadline).await
+    }
+}
+
+/// A one-transaction bundle sent straight to a block builder relay.
+pub struct BundleRelay {
+    relay_url: String,
+    /// Kept across submissions so its pooled connection to the relay can be reused.
+    client: reqwest::Client,
+    signing_key: LocalWallet,
+    blocks: u64,
+    provider: RpcProvider,
+    deadline: Mutex<Option<u64>>,
+}
+
+impl fmt::Display for BundleRelay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bundles to {} for {} blocks", self.relay_url, self.blocks)
+    }
+}
+
+#[async_trait]
+impl Broadcaster for BundleRelay {
+    async fn submit(&self, raw_tx: &Bytes) -> eyre::Result<Submission> {
+        let next_block = self.provider.get_block_number().await?.as_u64() + 1;
+        let mut accepted = 0;
+        let mut last_error = None;
+        for block in next_block..next_block + self.blocks {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_sendBundle",
+                "params": [{ "txs": [raw_tx], "blockNumber": format!("{:#x}", block) }],
+            })
+            .to_string();
+            // Flashbots-style auth: the relay checks a personal_sign over the body's hash
+            let body_hash = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+            let signature = self.signing_key.sign_message(body_hash).await?;
+            let response = self
+                .client
+                .post(&self.relay_url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header("X-Flashbots-Signature", format!("{:?}:0x{}", self.signing_key.address(), signature))
+                .body(body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            let response: serde_json::Value = match response {
+                Ok(response) => serde_json::from_str(&response.text().await?)?,
+                Err(e) => {
+                    last_error = Some(e.to_string());
+                    continue;
+                }
+            };
+            match response.get("error") {
+                Some(error) => last_error = Some(error.to_string()),
+                None => accepted += 1,
+            }
+        }
+        ensure!(accepted > 0, "the relay accepted none of the bundles: {}", last_error.unwrap_or_default());
+        if let Some(error) = last_error {
+            println!("Warning: the relay rejected {} of {} bundles: {}", self.blocks - accepted, self.blocks, error);
+        }
+        *self.deadline.lock() = Some(next_block + self.blocks - 1);
+        Ok(Submission::Sent(H256::from(keccak256(raw_tx))))
+    }
+
+    async fn warm_up(&self) -> eyre::Result<()> {
+        self.provider.get_block_number().await?;
+        // Relays only answer signed bundles, but any response leaves a pooled connection behind
+        self.client.get(&self.relay_url).send().await?;
+        Ok(())
+    }
+
+    async fn wait_for_receipt(&self, provider: &RpcProvider, tx_hash: H256) -> eyre::Result<Option<TransactionReceipt>> {
+        let deadline = *self.deadline.lock();
+        wait_for_private_receipt(provider, tx_hash, deadline).await
+    }
+}
+
+/// Writes the signed transaction to a file instead of sending it, encrypted like the journal
+/// when JOURNAL_PASSPHRASE is set.
+pub struct FileOnly {
+    pub path: PathBuf,
+    key: Option<SealingKey>,
+}
+
+impl FileOnly {
+    /// Like the journal, refuses to add plain lines to an encrypted file and seals new lines with
+    /// the key of the first encrypted one.
+    pub fn new(path: impl Into<PathBuf>, passphrase: Option<&str>) -> eyre::Result<Self> {
+        let path = path.into();
+        let key = match passphrase {
+            Some(passphrase) => {
+                let mut opener = Opener::new(passphrase);
+                read_saved_with(&path, Some(&mut opener))?;
+                match opener.first_key() {
+                    Some(key) => Some(key),
+                    None => Some(SealingKey::generate(passphrase)?),
+                }
+            }
+            None => {
+                read_saved(&path, None)?;
+                None
+            }
+        };
+        Ok(Self { path, key })
+    }
+}
+
+/// A line of the saved-transactions file encrypted with JOURNAL_PASSPHRASE; plain lines are hex.
+#[derive(Serialize, Deserialize)]
+struct SealedLine {
+    sealed: Sealed,
+}
+
+impl fmt::Display for FileOnly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the file {}", self.path.display())
+    }
+}
+
+#[async_trait]
+impl Broadcaster for FileOnly {
+    async fn submit(&self, raw_tx: &Bytes) -> eyre::Result<Submission> {
+        let line = match &self.key {
+            Some(key) => serde_json::to_string(&SealedLine { sealed: key.seal(raw_tx) })?,
+            None => raw_tx.to_string(),
+        };
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        file.sync_data()?;
+        Ok(Submission::Saved { tx_hash: H256::from(keccak256(raw_tx)), path: self.path.clone() })
+    }
+
+    async fn wait_for_receipt(&self, _provider: &RpcProvider, _tx_hash: H256) -> eyre::Result<Option<TransactionReceipt>> {
+        Ok(None)
+    }
+}
+
+/// Signed transactions written by [`FileOnly`], oldest first, decrypting encrypted lines with
+/// `passphrase`. A missing file has none.
+pub fn read_saved(path: impl AsRef<std::path::Path>, passphrase: Option<&str>) -> eyre::Result<Vec<Bytes>> {
+    let mut opener = passphrase.map(Opener::new);
+    read_saved_with(path.as_ref(), opener.as_mut())
+}
+
+fn read_saved_with(path: &std::path::Path, mut opener: Option<&mut Opener>) -> eyre::Result<Vec<Bytes>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut saved = Vec::new();
+    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if !line.starts_with('{') {
+            saved.push(line.parse()?);
+            continue;
+        }
+        let SealedLine { sealed } = serde_json::from_str(line)?;
+        let opener = opener.as_mut().ok_or_else(|| eyre!("{} is encrypted; set JOURNAL_PASSPHRASE", path.display()))?;
+        let raw_tx = opener.open(&sealed).map_err(|e| e.wrap_err(format!("cannot decrypt {}", path.display())))?;
+        saved.push(raw_tx.into());
+    }
+    Ok(saved)
+}
+
+/// Polls for the receipt of a transaction the public mempool never sees, giving up once the
+/// chain is past `deadline`.
+async fn wait_for_private_receipt(
+    provider: &RpcProvider,
+    tx_hash: H256,
+    deadline: Option<u64>,
+) -> eyre::Result<Option<TransactionReceipt>> {
+    loop {
+        if let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? {
+            return Ok(Some(receipt));
+        }
+        if let Some(deadline) = deadline {
+            if provider.get_block_number().await?.as_u64() > deadline {
+                return Ok(None);
+            }
+        }
+        tokio::time::sleep(PRIVATE_RECEIPT_POLL_INTERVAL).await;
+    }
+}
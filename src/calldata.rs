@@ -0,0 +1,118 @@
+use ethers::abi::param_type::Reader;
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::ParamType;
+use ethers::types::Bytes;
+use eyre::{bail, eyre};
+use std::collections::BTreeMap;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Values for calldata placeholders, by name.
+pub type Parameters = BTreeMap<String, String>;
+
+/// Parses one `NAME=VALUE` pair, as given to `--param`.
+pub fn parse_parameter(pair: &str) -> eyre::Result<(String, String)> {
+    let (name, value) = pair.split_once('=').ok_or_else(|| eyre!("expected NAME=VALUE, got {:?}", pair))?;
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("missing parameter name in {:?}", pair);
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Parses a comma-separated list of `NAME=VALUE` pairs, as in PARAMETERS.
+pub fn parse_parameters(list: &str) -> eyre::Result<Parameters> {
+    list.split(',').filter(|pair| !pair.trim().is_empty()).map(parse_parameter).collect()
+}
+
+/// Renders a hex calldata template, replacing each `${NAME}` or `${NAME:type}` placeholder with
+/// its value ABI-encoded as a static type, e.g. `${OWNER:address}` becomes a 32-byte word. Without
+/// a type, `true`/`false` is a bool, a 20-byte hex value an address, a decimal number a uint256
+/// and any other hex value is inserted as is; `raw` asks for the latter explicitly.
+pub fn render(template: &str, parameters: &Parameters) -> eyre::Result<Bytes> {
+    let mut rest = template.trim();
+    rest = rest.strip_prefix("0x").unwrap_or(rest);
+    let mut out = Vec::new();
+
+    while let Some(start) = rest.find("${") {
+        out.extend(decode_hex(&rest[..start])?);
+        let end = rest[start..].find('}').ok_or_else(|| eyre!("unterminated placeholder in calldata template"))?;
+        let placeholder = &rest[start + 2..start + end];
+        let (name, kind) = match placeholder.split_once(':') {
+            Some((name, kind)) => (name.trim(), Some(kind.trim())),
+            None => (placeholder.trim(), None),
+        };
+        let value = parameters.get(name).ok_or_else(|| {
+            eyre!("calldata uses ${{{}}}, but it has no value; add {}=... to PARAMETERS or pass --param {}=...", name, name, name)
+        })?;
+        out.extend(encode(name, kind, value)?);
+        rest = &rest[start + end + 1..];
+    }
+    out.extend(decode_hex(rest)?);
+    Ok(out.into())
+}
+
+fn encode(name: &str, kind: Option<&str>, value: &str) -> eyre::Result<Vec<u8>> {
+    let kind = match kind {
+        Some("raw") => return decode_hex(value.strip_prefix("0x").unwrap_or(value)),
+        Some(kind) => Reader::read(kind).map_err(|e| eyre!("${{{}}}: unknown type {:?}: {}", name, kind, e))?,
+        None => match infer(value) {
+            Some(kind) => kind,
+            None => return decode_hex(value.strip_prefix("0x").unwrap_or(value)),
+        },
+    };
+    // A dynamic value would need an offset into the tail of the arguments, which an inline
+    // placeholder cannot know
+    if kind.is_dynamic() {
+        bail!("${{{}}}: {} is a dynamic type, only static types can be substituted inline", name, kind);
+    }
+    let token = LenientTokenizer::tokenize(&kind, value).map_err(|e| eyre!("${{{}}}: invalid {} {:?}: {}", name, kind, value, e))?;
+    Ok(ethers::abi::encode(&[token]))
+}
+
+fn infer(value: &str) -> Option<ParamType> {
+    if value == "true" || value == "false" {
+        Some(ParamType::Bool)
+    } else if value.strip_prefix("0x").is_some_and(|hex| hex.len() == 40) {
+        Some(ParamType::Address)
+    } else if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        Some(ParamType::Uint(256))
+    } else {
+        None
+    }
+}
+
+fn decode_hex(hex_str: &str) -> eyre::Result<Vec<u8>> {
+    hex::decode(hex_str.trim()).map_err(|e| eyre!("invalid hex in calldata template: {}", e))
+}
+
+/// Reads calldata from `path`, as CALLDATA_FILE: hex, optionally with `0x` and line breaks. Plain
+/// hex is decoded as it is read, so the text is never held in memory; a file with placeholders is
+/// read whole and rendered like [`render`].
+pub fn load(path: &Path, parameters: &Parameters) -> eyre::Result<Bytes> {
+    let file = std::fs::File::open(path).map_err(|e| eyre!("cannot open {}: {}", path.display(), e))?;
+    let mut out = Vec::with_capacity(file.metadata().map_or(0, |metadata| metadata.len() as usize / 2));
+    let mut digits = BufReader::new(file).bytes().filter(|byte| !byte.as_ref().is_ok_and(u8::is_ascii_whitespace)).peekable();
+    let mut high = None;
+    let mut position = 0;
+    while let Some(byte) = digits.next() {
+        let byte = byte?;
+        if position == 0 && byte == b'0' && digits.peek().is_some_and(|next| next.as_ref().is_ok_and(|next| *next == b'x')) {
+            digits.next();
+            continue;
+        }
+        if byte == b'$' {
+            return render(&std::fs::read_to_string(path)?, parameters);
+        }
+        let nibble = (byte as char).to_digit(16).ok_or_else(|| eyre!("invalid hex digit {:?} in {}", byte as char, path.display()))? as u8;
+        match high.take() {
+            Some(high) => out.push(high << 4 | nibble),
+            None => high = Some(nibble),
+        }
+        position += 1;
+    }
+    if high.is_some() {
+        bail!("{} has an odd number of hex digits", path.display());
+    }
+    Ok(out.into())
+}
@@ -0,0 +1,44 @@
+use crate::envelope;
+use crate::journal::JournalEntry;
+use crate::pattern;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::U256;
+
+/// One match a campaign wants: a hash pattern at a nonce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    /// Normalized, without `0x`.
+    pub pattern: String,
+    pub nonce: U256,
+}
+
+/// Every slot of a campaign collecting `count` matches per pattern at consecutive nonces from
+/// `start_nonce`, nonce by nonce so an interrupted campaign leaves the nearest nonces covered.
+pub fn plan(patterns: &[String], start_nonce: U256, count: u64) -> Vec<Slot> {
+    (0..count)
+        .flat_map(|offset| {
+            patterns.iter().map(move |p| Slot { pattern: pattern::normalize(p), nonce: start_nonce + offset })
+        })
+        .collect()
+}
+
+/// Whether a journal entry already fills `slot` for `template`: same chain and nonce, a hash
+/// starting with the pattern, and the same transaction up to the salt and fees.
+pub fn fills(entry: &JournalEntry, slot: &Slot, template: &Eip1559TransactionRequest) -> bool {
+    if Some(entry.chain_id.into()) != template.chain_id
+        || entry.nonce != slot.nonce
+        || !hex::encode(entry.tx_hash).starts_with(&slot.pattern)
+    {
+        return false;
+    }
+    let Ok(decoded) = envelope::decode_signed(&entry.raw_tx) else {
+        return false;
+    };
+    let tx = decoded.tx;
+    // Padding and dust salts extend the calldata and value, so compare what the template fixes
+    let template_data = template.data.as_deref().unwrap_or_default();
+    tx.to() == template.to.as_ref()
+        && tx.gas() == template.gas.as_ref()
+        && tx.data().map_or(&[][..], |data| data.as_ref()).starts_with(template_data)
+        && tx.value().copied().unwrap_or_default() >= template.value.unwrap_or_default()
+}
@@ -0,0 +1,159 @@
+use ethers::types::{Address, U256};
+use ethers::utils::keccak256;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Default location of the chain cache, relative to the working directory.
+pub const DEFAULT_CHAIN_CACHE_PATH: &str = "chain_cache.json";
+/// Cached values older than this are not used, the RPC error is reported instead.
+pub const MAX_CACHE_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// A value as the RPC last returned it, with the unix time it did.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stamped<T> {
+    pub value: T,
+    pub at: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CacheFile {
+    /// Chain served by each RPC endpoint, keyed by a hash of its URL so credentials in it stay out of the file.
+    #[serde(default)]
+    endpoints: BTreeMap<String, Stamped<u64>>,
+    #[serde(default)]
+    base_fees: BTreeMap<u64, Stamped<U256>>,
+    /// Keyed by `<chain_id>:<address>`.
+    #[serde(default)]
+    nonces: BTreeMap<String, Stamped<U256>>,
+}
+
+/// A cached value and how long ago the RPC returned it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cached<T> {
+    pub value: T,
+    pub age: Duration,
+}
+
+impl<T> Cached<T> {
+    /// How old the value is, e.g. `cached 2m 5s ago`.
+    pub fn staleness(&self) -> String {
+        let seconds = self.age.as_secs();
+        match seconds {
+            0..=59 => format!("cached {}s ago", seconds),
+            60..=3599 => format!("cached {}m {}s ago", seconds / 60, seconds % 60),
+            _ => format!("cached {}h {}m ago", seconds / 3600, seconds / 60 % 60),
+        }
+    }
+}
+
+/// Chain ids, base fees and account nonces the RPC returned, kept on disk so a brief outage during a
+/// long search does not stop the checks that need them. Every update is written through.
+pub struct ChainCache {
+    path: PathBuf,
+    file: Mutex<CacheFile>,
+}
+
+impl ChainCache {
+    /// Reads the cache at `path`. A missing or unreadable file starts an empty cache, since it only
+    /// stands in for the RPC.
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let file = std::fs::read(&path).ok().and_then(|contents| serde_json::from_slice(&contents).ok()).unwrap_or_default();
+        Self { path, file: Mutex::new(file) }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record_chain_id(&self, rpc_url: &str, chain_id: u64) {
+        self.update(|file, at| file.endpoints.insert(endpoint_key(rpc_url), Stamped { value: chain_id, at }));
+    }
+
+    pub fn record_base_fee(&self, chain_id: u64, base_fee: U256) {
+        self.update(|file, at| file.base_fees.insert(chain_id, Stamped { value: base_fee, at }));
+    }
+
+    pub fn record_nonce(&self, chain_id: u64, address: Address, nonce: U256) {
+        self.update(|file, at| file.nonces.insert(nonce_key(chain_id, address), Stamped { value: nonce, at }));
+    }
+
+    /// The chain `rpc_url` last served, if that is recent enough to go on.
+    pub fn chain_id(&self, rpc_url: &str) -> Option<Cached<u64>> {
+        fresh(self.file.lock().endpoints.get(&endpoint_key(rpc_url)))
+    }
+
+    pub fn base_fee(&self, chain_id: u64) -> Option<Cached<U256>> {
+        fresh(self.file.lock().base_fees.get(&chain_id))
+    }
+
+    pub fn nonce(&self, chain_id: u64, address: Address) -> Option<Cached<U256>> {
+        fresh(self.file.lock().nonces.get(&nonce_key(chain_id, address)))
+    }
+
+    /// The chain id `rpc_url` returned, recorded, or the cached one if the request failed.
+    pub fn chain_id_or_cached(&self, rpc_url: &str, fetched: eyre::Result<u64>) -> eyre::Result<u64> {
+        if let Ok(chain_id) = &fetched {
+            self.record_chain_id(rpc_url, *chain_id);
+        }
+        fallback("chain id", fetched, self.chain_id(rpc_url), u64::to_string)
+    }
+
+    /// The base fee fetched for `chain_id`, recorded, or the cached one if the request failed.
+    pub fn base_fee_or_cached(&self, chain_id: u64, fetched: eyre::Result<U256>) -> eyre::Result<U256> {
+        if let Ok(base_fee) = &fetched {
+            self.record_base_fee(chain_id, *base_fee);
+        }
+        fallback("base fee", fetched, self.base_fee(chain_id), |base_fee| crate::units::format_wei(*base_fee))
+    }
+
+    /// The nonce fetched for `address`, recorded, or the cached one if the request failed.
+    pub fn nonce_or_cached(&self, chain_id: u64, address: Address, fetched: eyre::Result<U256>) -> eyre::Result<U256> {
+        if let Ok(nonce) = &fetched {
+            self.record_nonce(chain_id, address, *nonce);
+        }
+        fallback("account nonce", fetched, self.nonce(chain_id, address), |nonce| format!("nonce {}", nonce))
+    }
+
+    fn update<R>(&self, change: impl FnOnce(&mut CacheFile, i64) -> R) {
+        let mut file = self.file.lock();
+        change(&mut file, chrono::Utc::now().timestamp());
+        // Losing the cache only costs the fallback, so a failed write is not worth stopping for
+        let temporary = self.path.with_extension("tmp");
+        if let Ok(contents) = serde_json::to_vec_pretty(&*file) {
+            if std::fs::write(&temporary, contents).is_ok() {
+                let _ = std::fs::rename(&temporary, &self.path);
+            }
+        }
+    }
+}
+
+fn fresh<T: Clone>(stamped: Option<&Stamped<T>>) -> Option<Cached<T>> {
+    let stamped = stamped?;
+    let age = Duration::from_secs((chrono::Utc::now().timestamp() - stamped.at).max(0) as u64);
+    (age <= MAX_CACHE_AGE).then(|| Cached { value: stamped.value.clone(), age })
+}
+
+fn endpoint_key(rpc_url: &str) -> String {
+    hex::encode(&keccak256(rpc_url.as_bytes())[..8])
+}
+
+fn nonce_key(chain_id: u64, address: Address) -> String {
+    format!("{}:{:?}", chain_id, address)
+}
+
+/// Returns `fetched`, or falls back to `cached` with a staleness warning naming `what` when the RPC
+/// failed. Without a usable cached value the RPC error is returned.
+fn fallback<T>(what: &str, fetched: eyre::Result<T>, cached: Option<Cached<T>>, show: impl FnOnce(&T) -> String) -> eyre::Result<T> {
+    match (fetched, cached) {
+        (Ok(value), _) => Ok(value),
+        (Err(e), Some(cached)) => {
+            println!("Warning: could not fetch the {} ({}), using {} ({}), which may be stale", what, e, show(&cached.value), cached.staleness());
+            Ok(cached.value)
+        }
+        (Err(e), None) => Err(e),
+    }
+}
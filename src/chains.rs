@@ -0,0 +1,109 @@
+use crate::config::non_empty_var;
+use crate::envelope::EnvelopeKind;
+use crate::exit;
+use ethers::types::{Address, H256};
+
+/// Metadata for a well-known chain, so output can name it and link to its block explorer.
+#[derive(Debug, Clone, Copy)]
+pub struct Chain {
+    pub id: u64,
+    /// Preset name accepted by `CHAIN=` and `--chains`, e.g. `base-sepolia`.
+    pub name: &'static str,
+    /// Native currency that fees are paid in.
+    pub symbol: &'static str,
+    /// Etherscan-style explorer with `/tx/<hash>` and `/address/<address>` pages.
+    pub explorer: Option<&'static str>,
+    /// Transaction envelopes the chain accepts besides canonical EIP-1559.
+    pub envelope: EnvelopeKind,
+}
+
+impl Chain {
+    pub fn tx_url(&self, tx_hash: H256) -> Option<String> {
+        self.explorer.map(|explorer| format!("{}/tx/{:?}", explorer, tx_hash))
+    }
+
+    pub fn address_url(&self, address: Address) -> Option<String> {
+        self.explorer.map(|explorer| format!("{}/address/{:?}", explorer, address))
+    }
+}
+
+pub const CHAINS: &[Chain] = &[
+    Chain { id: 1, name: "ethereum", symbol: "ETH", explorer: Some("https://etherscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 10, name: "optimism", symbol: "ETH", explorer: Some("https://optimistic.etherscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 56, name: "bsc", symbol: "BNB", explorer: Some("https://bscscan.com"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 100, name: "gnosis", symbol: "xDAI", explorer: Some("https://gnosisscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 137, name: "polygon", symbol: "POL", explorer: Some("https://polygonscan.com"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 8453, name: "base", symbol: "ETH", explorer: Some("https://basescan.org"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 42161, name: "arbitrum", symbol: "ETH", explorer: Some("https://arbiscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 42220, name: "celo", symbol: "CELO", explorer: Some("https://celoscan.io"), envelope: EnvelopeKind::Celo },
+    Chain { id: 43114, name: "avalanche", symbol: "AVAX", explorer: Some("https://snowtrace.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 59144, name: "linea", symbol: "ETH", explorer: Some("https://lineascan.build"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 534352, name: "scroll", symbol: "ETH", explorer: Some("https://scrollscan.com"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 17000, name: "holesky", symbol: "ETH", explorer: Some("https://holesky.etherscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 11155111, name: "sepolia", symbol: "ETH", explorer: Some("https://sepolia.etherscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 84532, name: "base-sepolia", symbol: "ETH", explorer: Some("https://sepolia.basescan.org"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 11155420, name: "optimism-sepolia", symbol: "ETH", explorer: Some("https://sepolia-optimism.etherscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 421614, name: "arbitrum-sepolia", symbol: "ETH", explorer: Some("https://sepolia.arbiscan.io"), envelope: EnvelopeKind::Eip1559 },
+    Chain { id: 31337, name: "anvil", symbol: "ETH", explorer: None, envelope: EnvelopeKind::Eip1559 },
+];
+
+pub fn by_id(id: u64) -> Option<&'static Chain> {
+    CHAINS.iter().find(|chain| chain.id == id)
+}
+
+pub fn by_name(name: &str) -> Option<&'static Chain> {
+    CHAINS.iter().find(|chain| chain.name.eq_ignore_ascii_case(name))
+}
+
+/// Native currency symbol for `id`, falling back to ETH for chains not in the registry.
+pub fn symbol(id: u64) -> &'static str {
+    by_id(id).map_or("ETH", |chain| chain.symbol)
+}
+
+/// Display name for `id`, e.g. `base (8453)`, or just the id for chains not in the registry.
+pub fn describe(id: u64) -> String {
+    match by_id(id) {
+        Some(chain) => format!("{} ({})", chain.name, id),
+        None => id.to_string(),
+    }
+}
+
+/// Parses a chain given either as an id or as a preset name.
+pub fn resolve(value: &str) -> eyre::Result<u64> {
+    let value = value.trim();
+    if let Ok(id) = value.parse() {
+        return Ok(id);
+    }
+    by_name(value)
+        .map(|chain| chain.id)
+        .ok_or_else(|| eyre::eyre!("unknown chain {:?}, use a chain id or one of: {}", value, preset_names()))
+}
+
+/// Chain id from CHAIN_ID, or from a CHAIN preset such as `base` or `sepolia`. When both are set
+/// they must agree.
+pub fn chain_id_from_env() -> eyre::Result<u64> {
+    read_chain_id().map_err(exit::config_error)
+}
+
+fn read_chain_id() -> eyre::Result<u64> {
+    let chain_id = non_empty_var("CHAIN_ID");
+    let preset = non_empty_var("CHAIN");
+    match (chain_id, preset) {
+        (Some(chain_id), preset) => {
+            let chain_id = chain_id.parse().map_err(|e| eyre::eyre!("CHAIN_ID: {}", e))?;
+            if let Some(preset) = preset {
+                let preset_id = resolve(&preset)?;
+                if preset_id != chain_id {
+                    eyre::bail!("CHAIN={} is chain {}, but CHAIN_ID is {}", preset, preset_id, chain_id);
+                }
+            }
+            Ok(chain_id)
+        }
+        (None, Some(preset)) => resolve(&preset),
+        (None, None) => eyre::bail!("set CHAIN_ID, or CHAIN to one of: {}", preset_names()),
+    }
+}
+
+fn preset_names() -> String {
+    CHAINS.iter().map(|chain| chain.name).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,124 @@
+use crate::search::{FeeClaims, SearchParams};
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Default location of the search checkpoint, relative to the working directory.
+pub const DEFAULT_CHECKPOINT_PATH: &str = "search_checkpoint.json";
+/// How often the progress of a running search is saved.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Everything that decides which hash a given max fee produces. Swept fees are only worth
+/// skipping if all of it is unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateFingerprint {
+    pub chain_id: u64,
+    pub from: Address,
+    pub nonce: U256,
+    pub gas_limit: U256,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub calldata_hash: H256,
+    pub calldata_size: usize,
+    pub priority_fee: U256,
+    pub fee_step: U256,
+    pub hash_prefix: String,
+}
+
+impl TemplateFingerprint {
+    pub fn of(params: &SearchParams, from: Address) -> Self {
+        let template = &params.template;
+        let calldata = template.data.clone().unwrap_or_default();
+        Self {
+            chain_id: template.chain_id.unwrap_or_default().as_u64(),
+            from,
+            nonce: template.nonce.unwrap_or_default(),
+            gas_limit: template.gas.unwrap_or_default(),
+            to: template.to.as_ref().and_then(|to| to.as_address().copied()),
+            value: template.value.unwrap_or_default(),
+            calldata_hash: H256::from(keccak256(&calldata)),
+            calldata_size: calldata.len(),
+            priority_fee: params.priority_fee,
+            fee_step: params.fee_step,
+            hash_prefix: params.hash_prefix.clone(),
+        }
+    }
+
+    /// keccak256 of the fingerprint's JSON.
+    pub fn hash(&self) -> H256 {
+        H256::from(keccak256(serde_json::to_vec(self).expect("fingerprint serializes")))
+    }
+
+    /// The fields that differ from `other`, as `name: old -> new`.
+    pub fn differences(&self, other: &Self) -> Vec<String> {
+        let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+            (serde_json::to_value(self), serde_json::to_value(other))
+        else {
+            return Vec::new();
+        };
+        old.iter()
+            .filter(|(name, value)| new.get(*name) != Some(value))
+            .map(|(name, value)| format!("{}: {} -> {}", name, value, new.get(name).cloned().unwrap_or_default()))
+            .collect()
+    }
+}
+
+/// Fee ranges already swept for one template, saved so an interrupted search can pick up where
+/// it stopped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub template_hash: H256,
+    pub template: TemplateFingerprint,
+    /// Swept max fees as `[start, end)` in wei.
+    pub swept: Vec<(U256, U256)>,
+    pub updated_at: i64,
+}
+
+impl Checkpoint {
+    pub fn new(template: TemplateFingerprint, claims: &FeeClaims) -> Self {
+        Self { template_hash: template.hash(), template, swept: claims.ranges(), updated_at: chrono::Utc::now().timestamp() }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes to a temporary file and renames it over `path`, so a crash never leaves half a
+    /// checkpoint behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> eyre::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// Fee claims to start a search with: those in the checkpoint at `path` if it was saved for the
+/// same template, or none. A checkpoint for a different template is an error unless
+/// `force_restart` is set, since skipping its fees would skip candidates never tried.
+pub fn resume(path: impl AsRef<Path>, template: &TemplateFingerprint, force_restart: bool) -> eyre::Result<FeeClaims> {
+    let path = path.as_ref();
+    let Some(checkpoint) = Checkpoint::load(path)? else {
+        return Ok(FeeClaims::default());
+    };
+    if force_restart {
+        println!("Discarding the checkpoint in {} (--force-restart)", path.display());
+        return Ok(FeeClaims::default());
+    }
+    if checkpoint.template_hash != template.hash() {
+        let mut message = format!("the checkpoint in {} was saved for a different transaction:", path.display());
+        for difference in checkpoint.template.differences(template) {
+            message.push_str(&format!("\n  {}", difference));
+        }
+        message.push_str("\nResuming would skip fees that were never tried for this one; pass --force-restart to discard it");
+        eyre::bail!(message);
+    }
+    Ok(FeeClaims::from_ranges(checkpoint.swept))
+}
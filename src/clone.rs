@@ -0,0 +1,34 @@
+use crate::config::non_empty_var;
+use ethers::types::{Address, Bytes};
+
+/// Init code that copies the runtime code below it into memory and returns it.
+const INIT_PREFIX: [u8; 10] = [0x3d, 0x60, 0x2d, 0x80, 0x60, 0x0a, 0x3d, 0x39, 0x81, 0xf3];
+/// Runtime code up to the implementation address: copy the calldata and DELEGATECALL.
+const RUNTIME_PREFIX: [u8; 10] = [0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36, 0x3d, 0x73];
+/// Runtime code after the address: return or revert with whatever the implementation returned.
+const RUNTIME_SUFFIX: [u8; 15] = [0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3];
+/// Size of a minimal proxy's runtime code.
+pub const RUNTIME_SIZE: usize = RUNTIME_PREFIX.len() + 20 + RUNTIME_SUFFIX.len();
+
+/// The 45 bytes of an EIP-1167 minimal proxy delegating every call to `implementation`.
+pub fn runtime_code(implementation: Address) -> Vec<u8> {
+    [&RUNTIME_PREFIX[..], implementation.as_bytes(), &RUNTIME_SUFFIX[..]].concat()
+}
+
+/// Init code deploying a minimal proxy for `implementation`, to use as CALLDATA.
+pub fn init_code(implementation: Address) -> Bytes {
+    [&INIT_PREFIX[..], &runtime_code(implementation)].concat().into()
+}
+
+/// The implementation `code` delegates to, if it is a minimal proxy's runtime code.
+pub fn implementation_of(code: &[u8]) -> Option<Address> {
+    let address = code.strip_prefix(&RUNTIME_PREFIX[..])?.strip_suffix(&RUNTIME_SUFFIX[..])?;
+    (address.len() == 20).then(|| Address::from_slice(address))
+}
+
+/// The contract to deploy a minimal proxy for, from CLONE_IMPLEMENTATION.
+pub fn implementation_from_env() -> eyre::Result<Option<Address>> {
+    non_empty_var("CLONE_IMPLEMENTATION")
+        .map(|address| address.parse().map_err(|e| eyre::eyre!("invalid CLONE_IMPLEMENTATION {:?}: {}", address, e)))
+        .transpose()
+}
@@ -0,0 +1,95 @@
+use crate::config::chain_var;
+use crate::exit;
+use std::fmt;
+
+/// Largest runtime bytecode a deployment may leave behind (EIP-170).
+pub const MAX_CODE_SIZE: usize = 24_576;
+
+/// The runtime code size limit on `chain_id`: MAX_CODE_SIZE (or `MAX_CODE_SIZE_<chain_id>`) for
+/// chains that raised it, otherwise EIP-170's. Init code may be twice as big (EIP-3860).
+pub fn max_code_size(chain_id: u64) -> eyre::Result<usize> {
+    match chain_var("MAX_CODE_SIZE", chain_id) {
+        Some(size) => size
+            .parse::<usize>()
+            .map_err(|e| exit::config_error(eyre::eyre!("invalid MAX_CODE_SIZE for chain {}: {}", chain_id, e))),
+        None => Ok(MAX_CODE_SIZE),
+    }
+}
+
+/// Size of the runtime code Solidity init code returns, read from its copy-and-return epilogue
+/// (`PUSH2 size DUP1 PUSH2 offset PUSH0 CODECOPY PUSH0 RETURN`, or `PUSH1 0` before PUSH0).
+/// `None` for init code written any other way.
+pub fn runtime_size_hint(init_code: &[u8]) -> Option<usize> {
+    (0..init_code.len()).find_map(|start| {
+        let code = &init_code[start..];
+        let [0x61, size_hi, size_lo, 0x80, 0x61, _, _, rest @ ..] = code else {
+            return None;
+        };
+        let rest = zero(rest)?;
+        let rest = rest.strip_prefix(&[0x39])?;
+        zero(rest)?.strip_prefix(&[0xf3])?;
+        Some(usize::from(*size_hi) << 8 | usize::from(*size_lo))
+    })
+}
+
+/// Skips a push of zero, as `PUSH0` or `PUSH1 0`.
+fn zero(code: &[u8]) -> Option<&[u8]> {
+    code.strip_prefix(&[0x5f]).or_else(|| code.strip_prefix(&[0x60, 0x00]))
+}
+
+/// Whether a node's error for a deployment says the runtime code is over the size limit.
+pub fn is_code_size_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["max code size exceeded", "codesizelimit", "contractsizelimit", "code is too big", "code size"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+/// Why a deployment can never be included, and how to split it so it can.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPlan {
+    /// Runtime code size, when known; `None` if only the node's error says it is too big.
+    pub runtime_size: Option<usize>,
+    pub init_code_size: usize,
+    pub limit: usize,
+}
+
+impl SplitPlan {
+    /// `None` if the deployment fits within `limit`.
+    pub fn check(init_code_size: usize, runtime_size: Option<usize>, oversized: bool, limit: usize) -> Option<Self> {
+        let too_big = oversized || runtime_size.is_some_and(|size| size > limit) || init_code_size > 2 * limit;
+        too_big.then_some(Self { runtime_size, init_code_size, limit })
+    }
+
+    /// Contracts of at most `limit` bytes the runtime code has to be split into, at the least.
+    pub fn pieces(&self) -> Option<usize> {
+        self.runtime_size.map(|size| size.div_ceil(self.limit))
+    }
+}
+
+impl fmt::Display for SplitPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.runtime_size {
+            Some(size) if size > self.limit => writeln!(
+                f,
+                "The contract's runtime code is {} bytes, over the {} byte limit (EIP-170), so every node would reject the deployment.",
+                size, self.limit
+            )?,
+            _ if self.init_code_size > 2 * self.limit => writeln!(
+                f,
+                "The init code is {} bytes, over the {} byte limit (EIP-3860), so every node would reject the deployment.",
+                self.init_code_size,
+                2 * self.limit
+            )?,
+            _ => writeln!(f, "The node reports the contract's runtime code is over the {} byte limit (EIP-170).", self.limit)?,
+        }
+        writeln!(f, "To deploy it, split the code across contracts that each fit, then mine the vanity hash for the one that matters:")?;
+        match self.pieces() {
+            Some(pieces) => writeln!(f, "  - Move code into at least {} libraries or facets (e.g. an EIP-2535 diamond), deployed first", pieces)?,
+            None => writeln!(f, "  - Move code into libraries or facets (e.g. an EIP-2535 diamond), deployed first")?,
+        }
+        writeln!(f, "  - Deploy the logic as separately sized implementations behind a small proxy, and mine the proxy's deployment")?;
+        writeln!(f, "  - Deploy each piece through the CREATE2 factory mode (FACTORY_ADDRESS), one chunk per transaction")?;
+        write!(f, "  - Compile with fewer optimizer runs, or remove unused code, to shrink it below the limit")
+    }
+}
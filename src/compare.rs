@@ -0,0 +1,130 @@
+use crate::difficulty::Difficulty;
+use crate::overhead::{EffectiveCost, MarketFees};
+use crate::search::{self, Salt, SearchParams};
+use crate::units::wei_to_f64;
+use ethers::prelude::*;
+use ethers::types::U256;
+
+/// Blocks to average the block time over.
+const BLOCK_TIME_SAMPLE: u64 = 100;
+
+/// One way of getting the transaction on chain: how long it takes and what it pays.
+#[derive(Debug, Clone, Copy)]
+pub struct Route {
+    /// Time spent mining before the transaction can be sent, zero when sending right away.
+    pub mining_seconds: f64,
+    /// Expected blocks from sending to inclusion, infinite if the fees cannot be included and
+    /// `None` if the fee history to estimate it from was unavailable.
+    pub inclusion_blocks: Option<f64>,
+    pub cost: EffectiveCost,
+}
+
+impl Route {
+    /// Mining plus inclusion, `None` without a block time to turn blocks into seconds or without
+    /// an inclusion estimate.
+    pub fn total_seconds(&self, block_seconds: Option<f64>) -> Option<f64> {
+        Some(self.mining_seconds + self.inclusion_blocks? * block_seconds?)
+    }
+}
+
+/// Sending the transaction now at the market fee, against mining a vanity hash first.
+#[derive(Debug, Clone, Copy)]
+pub struct Comparison {
+    pub immediate: Route,
+    pub vanity: Route,
+    /// Average seconds between recent blocks, if the chain's timestamps gave one.
+    pub block_seconds: Option<f64>,
+}
+
+impl Comparison {
+    /// How much later the vanity transaction is expected to be included. Inclusion only counts
+    /// when both routes have an estimate and there is a block time.
+    pub fn extra_seconds(&self) -> f64 {
+        let inclusion = match (self.vanity.inclusion_blocks, self.immediate.inclusion_blocks, self.block_seconds) {
+            (Some(vanity), Some(immediate), Some(block_seconds)) => (vanity - immediate) * block_seconds,
+            _ => 0.0,
+        };
+        self.vanity.mining_seconds - self.immediate.mining_seconds + inclusion
+    }
+
+    /// Extra expected cost as a percentage, negative when the vanity transaction is cheaper.
+    pub fn expected_premium_percent(&self) -> f64 {
+        percent(self.vanity.cost.expected(), self.immediate.cost.expected())
+    }
+
+    /// Extra worst-case cost as a percentage, negative when the vanity transaction is cheaper.
+    pub fn worst_case_premium_percent(&self) -> f64 {
+        percent(self.vanity.cost.worst_case(), self.immediate.cost.worst_case())
+    }
+}
+
+fn percent(vanity: U256, immediate: U256) -> f64 {
+    if immediate.is_zero() {
+        0.0
+    } else {
+        (wei_to_f64(vanity) - wei_to_f64(immediate)) / wei_to_f64(immediate) * 100.0
+    }
+}
+
+/// The immediate route: the transaction signed at the market's recommended fees and sent now.
+pub fn immediate(gas_used: U256, gas_limit: U256, market: &MarketFees, inclusion_blocks: Option<f64>) -> Route {
+    Route {
+        mining_seconds: 0.0,
+        inclusion_blocks,
+        cost: EffectiveCost::new(gas_used, gas_limit, market.max_fee_per_gas, market.priority_fee, market.base_fee),
+    }
+}
+
+/// The vanity route for `params`: mining for the expected time, then sending at the max fee a
+/// match is expected to carry.
+pub fn vanity(params: &SearchParams, difficulty: &Difficulty, gas_used: U256, market: &MarketFees, inclusion_blocks: Option<f64>) -> Route {
+    let gas_limit = params.template.gas.unwrap_or_default();
+    let max_fee_per_gas = expected_match_fee(params, difficulty);
+    Route {
+        mining_seconds: difficulty.expected_seconds,
+        inclusion_blocks,
+        cost: EffectiveCost::new(gas_used, gas_limit, max_fee_per_gas, expected_tip(params), market.base_fee),
+    }
+}
+
+/// The max fee a match is expected to be signed at. Each worker climbs about
+/// `fee_window_per_worker` above its start before one of them finds a match, and without a salt the
+/// workers start spread over the fee range; the ceiling, if any, caps the climb.
+pub fn expected_match_fee(params: &SearchParams, difficulty: &Difficulty) -> U256 {
+    let first_fee = search::first_fee_for_salt(params, 0);
+    let mean_offset = match params.salt {
+        Salt::None => wei_to_f64(search::worker_spacing(params)) * (params.thread_count.max(1) - 1) as f64 / 2.0,
+        _ => 0.0,
+    };
+    let climb = U256::from((mean_offset + difficulty.fee_window_per_worker).min(u128::MAX as f64) as u128);
+    let fee = first_fee.saturating_add(search::round_up_to_step(climb, params.fee_step));
+    params.max_fee_per_gas.map_or(fee, |ceiling| fee.min(ceiling))
+}
+
+/// The tip a match is expected to pay: the priority fee, or the average of the lanes.
+pub fn expected_tip(params: &SearchParams) -> U256 {
+    let lanes = &params.priority_fee_lanes;
+    match lanes.len() {
+        0 => params.priority_fee,
+        count => lanes.iter().fold(U256::zero(), |total, &fee| total + fee) / count,
+    }
+}
+
+/// Average seconds per block over the last `BLOCK_TIME_SAMPLE` blocks, `None` if the timestamps
+/// do not give one, e.g. on a dev chain that mines on demand.
+pub async fn block_seconds<M: Middleware>(provider: &M) -> eyre::Result<Option<f64>>
+where
+    M::Error: 'static,
+{
+    let latest = provider.get_block(BlockNumber::Latest).await?.ok_or_else(|| eyre::eyre!("latest block not available"))?;
+    let latest_number = latest.number.unwrap_or_default().as_u64();
+    let blocks = BLOCK_TIME_SAMPLE.min(latest_number);
+    if blocks == 0 {
+        return Ok(None);
+    }
+    let Some(earlier) = provider.get_block(latest_number - blocks).await? else {
+        return Ok(None);
+    };
+    let seconds = latest.timestamp.saturating_sub(earlier.timestamp).as_u64() as f64 / blocks as f64;
+    Ok((seconds > 0.0).then_some(seconds))
+}
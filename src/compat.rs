@@ -0,0 +1,143 @@
+use crate::config::non_empty_var;
+use crate::exit;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Largest signed transaction geth-based txpools accept (`txMaxSize`, 4 slots of 32 KiB).
+pub const MAX_POOL_TX_SIZE: usize = 128 * 1024;
+
+/// How NODE_CHECK asks the target node whether it would accept a signed match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeCheck {
+    /// `eth_simulateV1` with validation, which applies the node's fee and nonce rules.
+    Simulate,
+    /// `eth_estimateGas` with every field as signed.
+    Estimate,
+    /// `eth_simulateV1`, falling back to `eth_estimateGas` on nodes without it.
+    Auto,
+}
+
+impl NodeCheck {
+    /// `None` without NODE_CHECK, when matches go to the confirmation unchecked.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        non_empty_var("NODE_CHECK")
+            .map(|check| check.parse().map_err(|e: eyre::Report| exit::config_error(e.wrap_err("invalid NODE_CHECK"))))
+            .transpose()
+    }
+}
+
+impl std::str::FromStr for NodeCheck {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "simulate" => Ok(Self::Simulate),
+            "estimate" => Ok(Self::Estimate),
+            "auto" | "1" => Ok(Self::Auto),
+            _ => Err(eyre::eyre!("unknown node check {:?}, expected auto, simulate or estimate", s)),
+        }
+    }
+}
+
+/// What the node said about a signed transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeVerdict {
+    Accepted { method: &'static str, gas_used: Option<U256> },
+    Rejected { method: &'static str, reason: String },
+    /// The node does not offer the method, so nothing was checked.
+    Unsupported { method: &'static str },
+}
+
+impl fmt::Display for NodeVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accepted { method, gas_used: Some(gas_used) } => write!(f, "accepted by {} ({} gas used)", method, gas_used),
+            Self::Accepted { method, gas_used: None } => write!(f, "accepted by {}", method),
+            Self::Rejected { method, reason } => write!(f, "rejected by {}: {}", method, reason),
+            Self::Unsupported { method } => write!(f, "the node does not support {}, not checked", method),
+        }
+    }
+}
+
+/// Checks `tx`, signed by `from` as `signed_size` bytes, against `provider` the way `check` says.
+/// Nothing is sent; the node only validates and executes it against its latest state, so the
+/// account must already be at the transaction's nonce.
+pub async fn check<M: Middleware>(provider: &M, check: NodeCheck, tx: &TypedTransaction, from: Address, signed_size: usize) -> NodeVerdict {
+    if signed_size > MAX_POOL_TX_SIZE {
+        return NodeVerdict::Rejected {
+            method: "size check",
+            reason: format!("{} bytes signed, above the {} bytes geth-based txpools accept", signed_size, MAX_POOL_TX_SIZE),
+        };
+    }
+    if check != NodeCheck::Estimate {
+        let verdict = simulate(provider, tx, from).await;
+        if check == NodeCheck::Simulate || !matches!(verdict, NodeVerdict::Unsupported { .. }) {
+            return verdict;
+        }
+    }
+    estimate(provider, tx, from).await
+}
+
+async fn simulate<M: Middleware>(provider: &M, tx: &TypedTransaction, from: Address) -> NodeVerdict {
+    const METHOD: &str = "eth_simulateV1";
+    let payload = json!({ "blockStateCalls": [{ "calls": [call_object(tx, from)] }], "validation": true });
+    match provider.provider().request::<_, Value>(METHOD, (payload, "latest")).await {
+        Ok(result) => simulation_verdict(&result),
+        Err(e) => match RpcError::as_error_response(&e) {
+            Some(error) if is_unsupported(error.code, &error.message) => NodeVerdict::Unsupported { method: METHOD },
+            Some(error) => NodeVerdict::Rejected { method: METHOD, reason: error.message.clone() },
+            None => NodeVerdict::Rejected { method: METHOD, reason: e.to_string() },
+        },
+    }
+}
+
+async fn estimate<M: Middleware>(provider: &M, tx: &TypedTransaction, from: Address) -> NodeVerdict {
+    const METHOD: &str = "eth_estimateGas";
+    let mut call = tx.clone();
+    call.set_from(from);
+    match provider.estimate_gas(&call, None).await {
+        Ok(gas_used) => NodeVerdict::Accepted { method: METHOD, gas_used: Some(gas_used) },
+        Err(e) => NodeVerdict::Rejected { method: METHOD, reason: e.to_string() },
+    }
+}
+
+/// `tx` as an `eth_simulateV1` call with every field as signed, including the nonce and fees.
+pub fn call_object(tx: &TypedTransaction, from: Address) -> Value {
+    let mut call = serde_json::to_value(tx).unwrap_or_else(|_| json!({}));
+    if let Value::Object(fields) = &mut call {
+        fields.insert("from".to_string(), json!(from));
+        // ethers names the calldata `data`; the simulation API also reads `input`
+        if let Some(data) = fields.get("data").cloned() {
+            fields.insert("input".to_string(), data);
+        }
+    }
+    call
+}
+
+/// Reads the outcome of the only call in an `eth_simulateV1` result.
+pub fn simulation_verdict(result: &Value) -> NodeVerdict {
+    const METHOD: &str = "eth_simulateV1";
+    let call = &result[0]["calls"][0];
+    if call.is_null() {
+        return NodeVerdict::Rejected { method: METHOD, reason: format!("unexpected result {}", result) };
+    }
+    let gas_used = call["gasUsed"].as_str().and_then(|gas| U256::from_str_radix(gas.trim_start_matches("0x"), 16).ok());
+    match call["status"].as_str() {
+        Some("0x1") => NodeVerdict::Accepted { method: METHOD, gas_used },
+        _ => {
+            let reason = call["error"]["message"].as_str().unwrap_or("the transaction reverted");
+            NodeVerdict::Rejected { method: METHOD, reason: format!("reverted: {}", reason) }
+        }
+    }
+}
+
+/// Whether a JSON-RPC error means the node lacks the method rather than rejecting the call.
+pub fn is_unsupported(code: i64, message: &str) -> bool {
+    let message = message.to_lowercase();
+    code == -32601
+        || ["not found", "not supported", "does not exist", "not available", "unsupported"]
+            .iter()
+            .any(|phrase| message.contains(phrase))
+}
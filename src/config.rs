@@ -0,0 +1,188 @@
+use crate::calldata::{self, Parameters};
+use crate::factory::Factory;
+use crate::units::parse_amount;
+use crate::{chaincache, checkpoint, clone, exit, history, journal, profile, sampling, templates};
+use ethers::types::{transaction::eip1559::Eip1559TransactionRequest, Address, Bytes, U256};
+use std::env;
+
+/// Loads `.env` from the working directory or one of its parents, as every binary does on start.
+pub fn load_dotenv() {
+    dotenv::dotenv().ok();
+}
+
+/// Reads an environment variable, treating an empty value (as left in `.env`) as unset.
+pub fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Reads `NAME_<chain_id>`, falling back to `NAME`, so one `.env` can hold per-chain overrides.
+pub fn chain_var(name: &str, chain_id: u64) -> Option<String> {
+    non_empty_var(&format!("{}_{}", name, chain_id)).or_else(|| non_empty_var(name))
+}
+
+/// HISTORY_DB, defaulting to `history::DEFAULT_HISTORY_PATH`.
+pub fn history_path() -> String {
+    non_empty_var("HISTORY_DB").unwrap_or_else(|| history::DEFAULT_HISTORY_PATH.to_string())
+}
+
+/// JOURNAL_PATH, defaulting to `journal::DEFAULT_JOURNAL_PATH`.
+pub fn journal_path() -> String {
+    non_empty_var("JOURNAL_PATH").unwrap_or_else(|| journal::DEFAULT_JOURNAL_PATH.to_string())
+}
+
+/// Where the search saves its progress, from CHECKPOINT_PATH.
+pub fn checkpoint_path() -> String {
+    non_empty_var("CHECKPOINT_PATH").unwrap_or_else(|| checkpoint::DEFAULT_CHECKPOINT_PATH.to_string())
+}
+
+/// Where measured hashrates are kept, from HASHRATE_PROFILE.
+pub fn profile_path() -> String {
+    non_empty_var("HASHRATE_PROFILE").unwrap_or_else(|| profile::DEFAULT_PROFILE_PATH.to_string())
+}
+
+/// Where RPC values are cached for outages, from CHAIN_CACHE_PATH.
+pub fn chain_cache_path() -> String {
+    non_empty_var("CHAIN_CACHE_PATH").unwrap_or_else(|| chaincache::DEFAULT_CHAIN_CACHE_PATH.to_string())
+}
+
+/// Where `gas_checker` keeps the fees it samples, from GAS_HISTORY. Unset keeps nothing.
+pub fn gas_history_path() -> Option<String> {
+    non_empty_var("GAS_HISTORY")
+}
+
+/// Where `deploy --template` reads templates, from DEPLOY_TEMPLATES.
+pub fn templates_path() -> String {
+    non_empty_var("DEPLOY_TEMPLATES").unwrap_or_else(|| templates::DEFAULT_TEMPLATES_PATH.to_string())
+}
+
+/// Log every this many candidates to SAMPLE_PATH, from SAMPLE_EVERY. Unset turns sampling off.
+pub fn sample_every() -> eyre::Result<Option<u64>> {
+    non_empty_var("SAMPLE_EVERY").map(|every| every.parse().map_err(|e| eyre::eyre!("invalid SAMPLE_EVERY: {}", e))).transpose()
+}
+
+/// Where sampled candidates are logged, from SAMPLE_PATH.
+pub fn sample_path() -> String {
+    non_empty_var("SAMPLE_PATH").unwrap_or_else(|| sampling::DEFAULT_SAMPLE_PATH.to_string())
+}
+
+/// Whether THERMAL_WATCHDOG is on, parking workers while throughput collapses.
+pub fn thermal_watchdog() -> bool {
+    non_empty_var("THERMAL_WATCHDOG").is_some_and(|value| value != "0")
+}
+
+/// Whether PARANOID is on, cross-checking every match with a second keccak implementation before it is sent.
+pub fn paranoid() -> bool {
+    non_empty_var("PARANOID").is_some_and(|value| value != "0")
+}
+
+/// Whether KEEP_AWAKE is on, stopping the machine from sleeping while a search runs.
+pub fn keep_awake() -> bool {
+    non_empty_var("KEEP_AWAKE").is_some_and(|value| value != "0")
+}
+
+/// Whether LOW_MEMORY is on, trading some speed for a small resident set: small batches and
+/// only the match to send kept in memory.
+pub fn low_memory() -> bool {
+    non_empty_var("LOW_MEMORY").is_some_and(|value| value != "0")
+}
+
+/// Label for the contract being deployed, from DEPLOYMENT_NAME, under which deployments are
+/// recorded so a redeployment can be compared with the last one.
+pub fn deployment_name() -> Option<String> {
+    non_empty_var("DEPLOYMENT_NAME")
+}
+
+/// Passphrase that encrypts the journal's signed transactions, from JOURNAL_PASSPHRASE.
+pub fn journal_passphrase() -> Option<String> {
+    non_empty_var("JOURNAL_PASSPHRASE")
+}
+
+/// What to send, from CALLDATA (or CALLDATA_FILE), GAS_LIMIT, TO_ADDRESS (or FACTORY_ADDRESS) and TRANSFER_AMOUNT. Placeholders in
+/// CALLDATA are filled in from PARAMETERS, with `parameters` (e.g. from `--param`) taking precedence.
+/// With CLONE_IMPLEMENTATION, a minimal proxy for it is deployed instead.
+pub struct TxSpec {
+    pub calldata: Option<Bytes>,
+    pub gas_limit: U256,
+    pub to: Option<Address>,
+    pub value: Option<U256>,
+}
+
+impl TxSpec {
+    pub fn from_env(parameters: &Parameters) -> eyre::Result<Self> {
+        let gas_limit = env::var("GAS_LIMIT")?.parse::<u64>()?.into();
+        Self::from_env_with_gas_limit(parameters, gas_limit)
+    }
+
+    /// Like `from_env`, but with `gas_limit` instead of GAS_LIMIT, for when it is not known yet.
+    pub fn from_env_with_gas_limit(parameters: &Parameters, gas_limit: U256) -> eyre::Result<Self> {
+        Self::read_env(parameters, gas_limit).map_err(exit::config_error)
+    }
+
+    fn read_env(parameters: &Parameters, gas_limit: U256) -> eyre::Result<Self> {
+        if let Some(implementation) = clone::implementation_from_env()? {
+            for conflicting in ["CALLDATA", "CALLDATA_FILE", "TO_ADDRESS", "TRANSFER_AMOUNT", "FACTORY_ADDRESS"] {
+                eyre::ensure!(
+                    non_empty_var(conflicting).is_none(),
+                    "CLONE_IMPLEMENTATION deploys a minimal proxy with calldata of its own, so {} must be empty",
+                    conflicting
+                );
+            }
+            return Ok(Self { calldata: Some(clone::init_code(implementation)), gas_limit, to: None, value: None });
+        }
+        let calldata = env::var("CALLDATA").ok();
+        let calldata_file = non_empty_var("CALLDATA_FILE");
+        eyre::ensure!(
+            calldata_file.is_none() || calldata.as_deref().is_none_or(str::is_empty),
+            "CALLDATA and CALLDATA_FILE are both set, keep only one"
+        );
+        let mut all_parameters = match non_empty_var("PARAMETERS") {
+            Some(list) => calldata::parse_parameters(&list).map_err(|e| eyre::eyre!("PARAMETERS: {}", e))?,
+            None => Parameters::new(),
+        };
+        all_parameters.extend(parameters.clone());
+        let to_address = env::var("TO_ADDRESS").ok();
+        let transfer_amount = env::var("TRANSFER_AMOUNT")
+            .ok()
+            .map(|v| parse_amount(&v).map_err(|e| eyre::eyre!("TRANSFER_AMOUNT: {}", e)))
+            .transpose()?;
+
+        let to_address = match transfer_amount {
+            Some(_) => Some(to_address.ok_or_else(|| eyre::eyre!("TO_ADDRESS is required for ETH transfer"))?),
+            None => to_address,
+        };
+        let to = to_address
+            .map(|addr| addr.parse::<Address>().map_err(|e| eyre::eyre!("invalid TO_ADDRESS: {}", e)))
+            .transpose()?;
+        // A factory call is a call to the factory, with CALLDATA as the create call
+        let to = match (to, Factory::from_env()?) {
+            (Some(to), Some(factory)) if to != factory.address => {
+                eyre::bail!("TO_ADDRESS {:?} differs from FACTORY_ADDRESS {:?}", to, factory.address)
+            }
+            (to, factory) => to.or(factory.map(|factory| factory.address)),
+        };
+        Ok(Self {
+            // A transfer carries no calldata
+            calldata: match (calldata, calldata_file) {
+                _ if transfer_amount.is_some() => None,
+                (_, Some(path)) => Some(calldata::load(path.as_ref(), &all_parameters)?),
+                (Some(template), None) => Some(calldata::render(&template, &all_parameters)?),
+                (None, None) => None,
+            },
+            gas_limit,
+            to,
+            value: transfer_amount,
+        })
+    }
+
+    /// The unsigned transaction to mine, without fees.
+    pub fn template(&self, chain_id: u64, nonce: U256) -> Eip1559TransactionRequest {
+        let mut tx = Eip1559TransactionRequest::new();
+        tx.value = self.value;
+        tx.data = self.calldata.clone();
+        tx.nonce = Some(nonce);
+        tx.gas = Some(self.gas_limit);
+        tx.chain_id = Some(chain_id.into());
+        tx.to = self.to.map(Into::into);
+        tx
+    }
+}
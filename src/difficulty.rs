@@ -0,0 +1,79 @@
+use crate::pattern;
+use crate::search::{self, THREAD_OFFSET_SPACING};
+use ethers::prelude::*;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How hard a pattern is to mine at a given hashrate and fee step.
+#[derive(Debug, Clone)]
+pub struct Difficulty {
+    pub prefix: String,
+    /// 16^digits, the mean of the geometric distribution of attempts.
+    pub expected_attempts: f64,
+    /// Attempts within which 95% of searches succeed, about 3× the mean.
+    pub attempts_95: f64,
+    pub expected_seconds: f64,
+    pub seconds_95: f64,
+    /// How far each worker's max fee climbs above its start over the expected attempts, in wei.
+    pub fee_window_per_worker: f64,
+    /// Whether the workers would run into each other's fee ranges first, after which fees already
+    /// tried are skipped and the sweep climbs faster.
+    pub overlaps_workers: bool,
+}
+
+impl Difficulty {
+    pub fn estimate(pattern: &str, hashrate: f64, fee_step: U256, thread_count: usize) -> eyre::Result<Self> {
+        pattern::validate(pattern)?;
+        Ok(Self::from_attempts(pattern::to_prefix(pattern), pattern::expected_attempts(pattern), hashrate, fee_step, thread_count))
+    }
+
+    /// Like [`estimate`](Self::estimate), for a match expected once in `expected_attempts`
+    /// candidates, shown as `prefix`.
+    pub fn from_attempts(prefix: String, expected_attempts: f64, hashrate: f64, fee_step: U256, thread_count: usize) -> Self {
+        let attempts_95 = expected_attempts * 20f64.ln();
+        let fee_step = crate::units::wei_to_f64(fee_step.max(U256::one()));
+        let fee_window_per_worker = expected_attempts / thread_count.max(1) as f64 * fee_step;
+        Self {
+            prefix,
+            expected_attempts,
+            attempts_95,
+            expected_seconds: expected_attempts / hashrate,
+            seconds_95: attempts_95 / hashrate,
+            fee_window_per_worker,
+            overlaps_workers: thread_count > 1 && fee_window_per_worker > THREAD_OFFSET_SPACING as f64,
+        }
+    }
+}
+
+/// Signs a typical deployment with a throwaway key on `thread_count` threads for `duration`,
+/// returning candidates per second.
+pub fn benchmark(duration: Duration, thread_count: usize) -> f64 {
+    let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng()).with_chain_id(1u64);
+    let attempts = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for i in 0..thread_count {
+            let (wallet, attempts, stop) = (&wallet, &attempts, &stop);
+            scope.spawn(move || {
+                let mut tx = Eip1559TransactionRequest::new()
+                    .chain_id(1)
+                    .nonce(0)
+                    .gas(100_000)
+                    .data(vec![0x60; 1024])
+                    .max_priority_fee_per_gas(search::PRIORITY_FEE);
+                let mut max_fee = U256::from(search::BASE_FEE_START) + i as u64 * THREAD_OFFSET_SPACING;
+                while !stop.load(Ordering::Relaxed) {
+                    tx.max_fee_per_gas = Some(max_fee);
+                    let _ = search::encode_and_sign_eip1559(wallet, &tx);
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    max_fee += U256::one();
+                }
+            });
+        }
+        std::thread::sleep(duration);
+        stop.store(true, Ordering::Relaxed);
+    });
+    attempts.load(Ordering::Relaxed) as f64 / start.elapsed().as_secs_f64()
+}
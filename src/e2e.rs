@@ -0,0 +1,118 @@
+use crate::address::get_contract_address;
+use crate::config::TxSpec;
+use crate::journal::Journal;
+use crate::limits::ResourceLimits;
+use crate::rpc::{self, RpcConfig};
+use crate::search::{self, MatchPolicy, Salt, SearchParams};
+use crate::stats::SearchStats;
+use crate::verify;
+use ethers::prelude::*;
+use ethers::utils::{parse_ether, Anvil};
+use eyre::{ensure, eyre};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Prefix mined by the smoke test, short enough to take a moment on any machine.
+pub const E2E_HASH_PREFIX: &str = "0x00";
+/// Init code that deploys `RUNTIME_CODE`, a contract returning 42 from any call.
+const INIT_CODE: &str = "0x600a600c600039600a6000f3602a60005260206000f3";
+const RUNTIME_CODE: &str = "0x602a60005260206000f3";
+const GAS_LIMIT: u64 = 100_000;
+
+/// Whether the `anvil` binary from Foundry can be run.
+pub fn anvil_available() -> bool {
+    std::process::Command::new("anvil").arg("--version").output().is_ok()
+}
+
+/// Smoke test of the whole pipeline against a real node: starts Anvil, funds a throwaway key,
+/// mines a deployment whose hash starts with `E2E_HASH_PREFIX`, verifies and broadcasts it, and
+/// checks the receipt, the contract address and the deployed code.
+pub async fn run() -> eyre::Result<()> {
+    ensure!(anvil_available(), "anvil not found; install Foundry (https://getfoundry.sh) to run the end-to-end test");
+    let anvil = tokio::task::spawn_blocking(|| Anvil::new().spawn()).await?;
+    let chain_id = anvil.chain_id();
+    let provider = rpc::connect(&RpcConfig::for_url(anvil.endpoint())?).await?;
+    println!("Started anvil on {} (chain {})", anvil.endpoint(), chain_id);
+
+    // Fund a fresh key from one of anvil's prefunded accounts
+    let funder = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(chain_id);
+    let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng()).with_chain_id(chain_id);
+    let from = wallet.address();
+    let funding = TransactionRequest::pay(from, parse_ether(1)?);
+    SignerMiddleware::new(provider.clone(), funder)
+        .send_transaction(funding, None)
+        .await?
+        .await?
+        .ok_or_else(|| eyre!("funding transaction was dropped"))?;
+    println!("Funded throwaway account {:?}", from);
+
+    let tx_spec = TxSpec { calldata: Some(INIT_CODE.parse()?), gas_limit: GAS_LIMIT.into(), to: None, value: None };
+    let nonce = provider.get_transaction_count(from, None).await?;
+    let template = tx_spec.template(chain_id, nonce);
+    let base_fee = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .and_then(|block| block.base_fee_per_gas)
+        .unwrap_or_default();
+
+    let dir = tempfile_dir()?;
+    let journal = Arc::new(Journal::open(dir.join("found_candidates.jsonl"))?);
+    let params = SearchParams {
+        signer: Arc::new(wallet),
+        template,
+        hash_prefix: E2E_HASH_PREFIX.to_string(),
+        patterns: None,
+        base_fee_start: base_fee * 2,
+        priority_fee: U256::exp10(9),
+        max_fee_per_gas: None,
+        fee_step: U256::one(),
+        salt: Salt::None,
+        priority_fee_lanes: Vec::new(),
+        match_policy: MatchPolicy::First,
+        max_retained_matches: search::DEFAULT_MAX_RETAINED_MATCHES,
+        max_failure_rate: search::DEFAULT_MAX_FAILURE_RATE,
+        batch_size: search::BATCH_SIZE,
+        thread_count: search::default_thread_count(),
+        job_id: None,
+        claims: None,
+        sampler: None,
+        throttle: None,
+        stop_policy: None,
+        verifier: None,
+        limits: ResourceLimits::default(),
+    };
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let found_match = search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal)
+        .await?
+        .ok_or_else(|| eyre!("the search ended without a match"))?;
+    let tx_hash = H256::from(found_match.tx_hash);
+    println!("Mined {:?} after {} attempts", tx_hash, crate::units::format_count(stats.total_attempts()));
+
+    verify::verify_for_broadcast(&found_match.signed_rlp, tx_hash, from, chain_id, nonce)?;
+    let sent = provider.send_raw_transaction(found_match.signed_rlp.clone()).await?.tx_hash();
+    ensure!(sent == tx_hash, "node reports hash {:?} for mined hash {:?}", sent, tx_hash);
+    let receipt = rpc::wait_for_receipt(&provider, sent).await?.ok_or_else(|| eyre!("transaction was dropped"))?;
+
+    ensure!(receipt.transaction_hash == tx_hash, "receipt is for {:?}, not {:?}", receipt.transaction_hash, tx_hash);
+    ensure!(receipt.status == Some(1.into()), "deployment failed with status {:?}", receipt.status);
+    let expected_address = get_contract_address(from, nonce);
+    ensure!(
+        receipt.contract_address == Some(expected_address),
+        "contract deployed at {:?}, expected {:?}",
+        receipt.contract_address,
+        expected_address
+    );
+    let code = provider.get_code(expected_address, None).await?;
+    ensure!(code == RUNTIME_CODE.parse::<Bytes>()?, "deployed code is {}, expected {}", code, RUNTIME_CODE);
+
+    println!("End-to-end test passed: {:?} deployed {:?}", tx_hash, expected_address);
+    std::fs::remove_dir_all(&dir).ok();
+    Ok(())
+}
+
+/// A scratch directory for the test's journal, so it never mixes with real matches.
+fn tempfile_dir() -> eyre::Result<std::path::PathBuf> {
+    let dir = std::env::temp_dir().join(format!("vanity-e2e-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
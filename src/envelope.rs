@@ -0,0 +1,160 @@
+use crate::chains;
+use crate::config::chain_var;
+use crate::search::CandidateSigner;
+use ethers::prelude::*;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use ethers::utils::rlp::{Rlp, RlpStream};
+use eyre::{ensure, eyre};
+use std::sync::Arc;
+
+/// Transaction type of Celo's CIP-64 envelope: EIP-1559 plus the token fees are paid in.
+pub const CIP64_TX_TYPE: u8 = 0x7b;
+const EIP1559_FIELDS: usize = 9;
+
+/// Which envelopes a chain preset accepts beyond the canonical EIP-2718 type 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeKind {
+    /// Only the canonical EIP-1559 layout.
+    Eip1559,
+    /// Celo: CIP-64 when FEE_CURRENCY is set, EIP-1559 otherwise.
+    Celo,
+}
+
+/// How a chain lays out a signed EIP-1559-style transaction: what is signed and what is sent.
+/// Chains with envelope quirks implement this instead of changing the search.
+pub trait Envelope: Send + Sync {
+    fn sighash(&self, tx: &Eip1559TransactionRequest) -> H256;
+    fn encode_signed(&self, tx: &Eip1559TransactionRequest, signature: &Signature) -> Bytes;
+}
+
+/// The canonical EIP-2718 type 2 envelope.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Eip1559;
+
+impl Envelope for Eip1559 {
+    fn sighash(&self, tx: &Eip1559TransactionRequest) -> H256 {
+        TypedTransaction::Eip1559(tx.clone()).sighash()
+    }
+
+    fn encode_signed(&self, tx: &Eip1559TransactionRequest, signature: &Signature) -> Bytes {
+        TypedTransaction::Eip1559(tx.clone()).rlp_signed(signature)
+    }
+}
+
+/// Celo's CIP-64 envelope: `0x7b || rlp([...eip1559 fields, feeCurrency, yParity, r, s])`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cip64 {
+    /// ERC-20 (or adapter) the fees are paid in.
+    pub fee_currency: Address,
+}
+
+impl Cip64 {
+    fn encode(&self, tx: &Eip1559TransactionRequest, signature: Option<&Signature>) -> Bytes {
+        // Reuse the EIP-1559 field encoding, minus its list header
+        let fields = tx.rlp();
+        let header_len = Rlp::new(&fields).payload_info().expect("ethers encodes a list").header_len;
+        let mut rlp = RlpStream::new();
+        rlp.begin_list(EIP1559_FIELDS + 1 + if signature.is_some() { 3 } else { 0 });
+        rlp.append_raw(&fields[header_len..], EIP1559_FIELDS);
+        rlp.append(&self.fee_currency);
+        if let Some(signature) = signature {
+            rlp.append(&y_parity(signature.v, tx.chain_id.unwrap_or_default().as_u64()));
+            rlp.append(&signature.r);
+            rlp.append(&signature.s);
+        }
+        let mut encoded = vec![CIP64_TX_TYPE];
+        encoded.extend_from_slice(&rlp.out());
+        encoded.into()
+    }
+
+    /// Splits a signed CIP-64 transaction into the envelope, the transaction and its signature.
+    pub fn decode_signed(raw: &[u8]) -> eyre::Result<(Self, Eip1559TransactionRequest, Signature)> {
+        ensure!(raw.first() == Some(&CIP64_TX_TYPE), "not a CIP-64 transaction");
+        let rlp = Rlp::new(&raw[1..]);
+        ensure!(rlp.item_count()? == EIP1559_FIELDS + 4, "a signed CIP-64 transaction has {} fields", EIP1559_FIELDS + 4);
+        let mut offset = 0;
+        let tx = Eip1559TransactionRequest::decode_base_rlp(&rlp, &mut offset)?;
+        let fee_currency = rlp.val_at(offset)?;
+        let signature = Signature { v: rlp.val_at(offset + 1)?, r: rlp.val_at(offset + 2)?, s: rlp.val_at(offset + 3)? };
+        Ok((Self { fee_currency }, tx, signature))
+    }
+}
+
+impl Envelope for Cip64 {
+    fn sighash(&self, tx: &Eip1559TransactionRequest) -> H256 {
+        H256::from(keccak256(self.encode(tx, None)))
+    }
+
+    fn encode_signed(&self, tx: &Eip1559TransactionRequest, signature: &Signature) -> Bytes {
+        self.encode(tx, Some(signature))
+    }
+}
+
+/// The y parity typed envelopes carry, from a `v` that may be raw, `27`-based or EIP-155.
+fn y_parity(v: u64, chain_id: u64) -> u64 {
+    match v {
+        0 | 1 => v,
+        27 | 28 => v - 27,
+        v => (v - 35 - 2 * chain_id) % 2,
+    }
+}
+
+/// A signed transaction in any envelope this tool produces, with what its signature covers.
+#[derive(Debug, Clone)]
+pub struct DecodedTx {
+    pub tx: TypedTransaction,
+    pub signature: Signature,
+    pub sighash: H256,
+    /// The transaction encoded again from its fields, which must equal the raw bytes.
+    pub reencoded: Bytes,
+}
+
+/// Decodes a signed transaction, recognizing chain-specific envelopes by their type byte.
+pub fn decode_signed(raw: &[u8]) -> eyre::Result<DecodedTx> {
+    if raw.first() == Some(&CIP64_TX_TYPE) {
+        let (envelope, tx, signature) = Cip64::decode_signed(raw)?;
+        return Ok(DecodedTx {
+            sighash: envelope.sighash(&tx),
+            reencoded: envelope.encode_signed(&tx, &signature),
+            tx: TypedTransaction::Eip1559(tx),
+            signature,
+        });
+    }
+    let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))?;
+    Ok(DecodedTx { sighash: tx.sighash(), reencoded: tx.rlp_signed(&signature), tx, signature })
+}
+
+/// Signs candidates with a wallet in a chain-specific envelope.
+pub struct EnvelopeSigner<E> {
+    pub wallet: LocalWallet,
+    pub envelope: E,
+}
+
+impl<E: Envelope> CandidateSigner for EnvelopeSigner<E> {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        let mut signature = self.wallet.sign_hash(self.envelope.sighash(tx))?;
+        // sign_hash returns 27 or 28; typed envelopes carry the bare y parity
+        signature.v -= 27;
+        let signed = self.envelope.encode_signed(tx, &signature);
+        let tx_hash = keccak256(&signed);
+        Ok((signed, tx_hash))
+    }
+}
+
+/// The signer for `chain_id`: the plain wallet unless the chain's preset and FEE_CURRENCY call
+/// for another envelope.
+pub fn signer_for(wallet: LocalWallet, chain_id: u64) -> eyre::Result<Arc<dyn CandidateSigner>> {
+    let kind = chains::by_id(chain_id).map_or(EnvelopeKind::Eip1559, |chain| chain.envelope);
+    let fee_currency = chain_var("FEE_CURRENCY", chain_id)
+        .map(|address| address.parse::<Address>().map_err(|e| eyre!("invalid FEE_CURRENCY: {}", e)))
+        .transpose()?;
+    Ok(match (kind, fee_currency) {
+        (EnvelopeKind::Celo, Some(fee_currency)) => Arc::new(EnvelopeSigner { wallet, envelope: Cip64 { fee_currency } }),
+        (EnvelopeKind::Eip1559, Some(_)) => {
+            eyre::bail!("FEE_CURRENCY is set, but chain {} does not support paying fees in tokens", chains::describe(chain_id))
+        }
+        (_, None) => Arc::new(wallet),
+    })
+}
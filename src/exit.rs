@@ -0,0 +1,131 @@
+use crate::rpc::{RpcProvider, TransportError};
+use ethers::middleware::signer::SignerMiddlewareError;
+use ethers::providers::{HttpClientError, ProviderError, WsClientError};
+use ethers::signers::{LocalWallet, WalletError};
+use std::fmt;
+use std::process::ExitCode;
+
+/// How a run ended, as the process exit code, so scripts and CI jobs can branch on the outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// A match was found and sent, or a command without a search finished.
+    Sent,
+    /// Anything not covered below.
+    Error,
+    /// A setting is missing or invalid.
+    ConfigError,
+    /// The RPC endpoint failed or could not be reached.
+    RpcError,
+    /// A match was found but not sent, e.g. saved to a file or blocked by the nonce.
+    NotSent,
+    /// The search ended without a match: its time limit passed or the fee range ran out.
+    NoMatch,
+    /// Declined at a prompt or interrupted with Ctrl-C.
+    UserAbort,
+    /// The transaction was sent but reverted, was dropped or was refused by the node.
+    BroadcastFailed,
+}
+
+impl Status {
+    pub const ALL: [Status; 8] = [
+        Status::Sent,
+        Status::Error,
+        Status::ConfigError,
+        Status::RpcError,
+        Status::NotSent,
+        Status::NoMatch,
+        Status::UserAbort,
+        Status::BroadcastFailed,
+    ];
+
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Sent => 0,
+            Self::Error => 1,
+            Self::ConfigError => 2,
+            Self::RpcError => 3,
+            Self::NotSent => 4,
+            Self::NoMatch => 5,
+            Self::UserAbort => 6,
+            Self::BroadcastFailed => 7,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Sent => "done",
+            Self::Error => "failed",
+            Self::ConfigError => "configuration error",
+            Self::RpcError => "RPC error",
+            Self::NotSent => "match found but not sent",
+            Self::NoMatch => "no match found",
+            Self::UserAbort => "aborted",
+            Self::BroadcastFailed => "broadcast failed",
+        })
+    }
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status.code())
+    }
+}
+
+/// An error tagged with the exit status it should end the run with, shown as the error itself.
+#[derive(Debug)]
+struct WithStatus {
+    status: Status,
+    report: eyre::Report,
+}
+
+impl fmt::Display for WithStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.report, f)
+    }
+}
+
+impl std::error::Error for WithStatus {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.report.chain().nth(1)
+    }
+}
+
+/// Tags `report` so the run exits with `status`, keeping its message and causes.
+pub fn with_status(report: eyre::Report, status: Status) -> eyre::Report {
+    eyre::Report::new(WithStatus { status, report })
+}
+
+/// Tags `report` as a missing or invalid setting.
+pub fn config_error(report: eyre::Report) -> eyre::Report {
+    with_status(report, Status::ConfigError)
+}
+
+/// The exit status for an error: the outermost one it was tagged with by [`with_status`],
+/// otherwise one inferred from the errors in its chain.
+pub fn classify(report: &eyre::Report) -> Status {
+    if let Some(tagged) = report.chain().find_map(|cause| cause.downcast_ref::<WithStatus>()) {
+        return tagged.status;
+    }
+    for cause in report.chain() {
+        if cause.is::<ProviderError>()
+            || cause.is::<TransportError>()
+            || cause.is::<HttpClientError>()
+            || cause.is::<WsClientError>()
+            || cause.is::<SignerMiddlewareError<RpcProvider, LocalWallet>>()
+            || cause.is::<reqwest::Error>()
+        {
+            return Status::RpcError;
+        }
+        if cause.is::<std::env::VarError>()
+            || cause.is::<WalletError>()
+            || cause.is::<std::num::ParseIntError>()
+            || cause.is::<std::num::ParseFloatError>()
+            || cause.is::<ethers::abi::ethereum_types::FromDecStrErr>()
+        {
+            return Status::ConfigError;
+        }
+    }
+    Status::Error
+}
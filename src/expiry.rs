@@ -0,0 +1,69 @@
+use crate::units::format_gwei;
+use ethers::prelude::*;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How often a match waiting for confirmation checks whether the base fee has passed it.
+pub const EXPIRY_POLL_INTERVAL: Duration = Duration::from_secs(12);
+
+/// Why a match that was never confirmed is no longer worth sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    /// Nobody confirmed it within the validity window.
+    Elapsed(Duration),
+    /// The base fee rose above its max fee, so it cannot be included.
+    BaseFeeAbove { base_fee: U256, max_fee_per_gas: U256 },
+}
+
+impl fmt::Display for Expiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Elapsed(window) => write!(f, "not confirmed within {} min", window.as_secs() / 60),
+            Self::BaseFeeAbove { base_fee, max_fee_per_gas } => write!(
+                f,
+                "the base fee rose to {} gwei, above the max fee of {} gwei",
+                format_gwei(*base_fee),
+                format_gwei(*max_fee_per_gas)
+            ),
+        }
+    }
+}
+
+/// Whether a match found at `found_at` has expired by `now`, given the latest base fee if known.
+pub fn check(found_at: Instant, now: Instant, window: Duration, base_fee: Option<U256>, max_fee_per_gas: U256) -> Option<Expiry> {
+    if let Some(base_fee) = base_fee.filter(|base_fee| *base_fee > max_fee_per_gas) {
+        return Some(Expiry::BaseFeeAbove { base_fee, max_fee_per_gas });
+    }
+    (now.duration_since(found_at) >= window).then_some(Expiry::Elapsed(window))
+}
+
+/// Whether a match with `max_fee_per_gas` can be included at `base_fee`, as one line for a live
+/// display while the match waits for confirmation.
+pub fn base_fee_status(base_fee: U256, max_fee_per_gas: U256) -> String {
+    let fees = format!("base fee {} gwei, max fee {} gwei", format_gwei(base_fee), format_gwei(max_fee_per_gas));
+    if base_fee > max_fee_per_gas {
+        format!("{}: not includable until the base fee drops", fees)
+    } else if base_fee.is_zero() {
+        format!("{}: includable", fees)
+    } else {
+        let headroom = crate::units::wei_to_f64(max_fee_per_gas) / crate::units::wei_to_f64(base_fee);
+        format!("{}: includable ({:.1}x headroom)", fees, headroom)
+    }
+}
+
+/// Resolves once the match found at `found_at` expires. Failed base fee lookups are skipped, so
+/// an RPC outage only leaves the window to expire it.
+pub async fn watch<M: Middleware>(provider: &M, found_at: Instant, window: Duration, max_fee_per_gas: U256) -> Expiry {
+    loop {
+        let base_fee = match provider.get_block(BlockNumber::Latest).await {
+            Ok(block) => block.and_then(|block| block.base_fee_per_gas),
+            Err(_) => None,
+        };
+        let now = Instant::now();
+        if let Some(expiry) = check(found_at, now, window, base_fee, max_fee_per_gas) {
+            return expiry;
+        }
+        let remaining = window.saturating_sub(now.duration_since(found_at));
+        tokio::time::sleep(remaining.min(EXPIRY_POLL_INTERVAL)).await;
+    }
+}
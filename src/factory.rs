@@ -0,0 +1,76 @@
+use crate::address::get_contract_address;
+use crate::config::non_empty_var;
+use crate::exit;
+use ethers::prelude::*;
+use ethers::utils::get_create2_address_from_hash;
+
+/// How a factory contract derives the address of the contract it creates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactoryScheme {
+    /// CREATE from the factory, so the child address depends on the factory's own nonce.
+    Create,
+    /// CREATE2 with the salt the factory passes to the opcode (after any mixing it does, e.g. with
+    /// `msg.sender`) and the keccak256 of the child's init code.
+    Create2 { salt: H256, init_code_hash: H256 },
+}
+
+/// A project-specific factory the mined transaction calls, with CALLDATA as the create call.
+#[derive(Debug, Clone, Copy)]
+pub struct Factory {
+    pub address: Address,
+    pub scheme: FactoryScheme,
+}
+
+impl Factory {
+    /// From FACTORY_ADDRESS and FACTORY_SCHEME (`create`, or `create2` with FACTORY_SALT and
+    /// FACTORY_INIT_CODE_HASH). `None` when FACTORY_ADDRESS is unset.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        Self::read_env().map_err(exit::config_error)
+    }
+
+    fn read_env() -> eyre::Result<Option<Self>> {
+        let Some(address) = non_empty_var("FACTORY_ADDRESS") else {
+            return Ok(None);
+        };
+        let address = address.parse().map_err(|e| eyre::eyre!("FACTORY_ADDRESS: {}", e))?;
+        let hash = |name: &str| -> eyre::Result<H256> {
+            non_empty_var(name)
+                .ok_or_else(|| eyre::eyre!("{} is required with FACTORY_SCHEME=create2", name))?
+                .parse()
+                .map_err(|e| eyre::eyre!("{}: {}", name, e))
+        };
+        let scheme = match non_empty_var("FACTORY_SCHEME").as_deref().unwrap_or("create") {
+            "create" => FactoryScheme::Create,
+            "create2" => FactoryScheme::Create2 { salt: hash("FACTORY_SALT")?, init_code_hash: hash("FACTORY_INIT_CODE_HASH")? },
+            other => eyre::bail!("unknown FACTORY_SCHEME {:?}, expected create or create2", other),
+        };
+        Ok(Some(Self { address, scheme }))
+    }
+
+    /// Address of the contract the factory will create. For CREATE this uses the factory's nonce
+    /// now, so it only holds if nothing else deploys through the factory first.
+    pub async fn child_address<M: Middleware>(&self, provider: &M) -> eyre::Result<Address>
+    where
+        M::Error: 'static,
+    {
+        match self.scheme {
+            FactoryScheme::Create => {
+                let nonce = provider.get_transaction_count(self.address, None).await?;
+                eyre::ensure!(!nonce.is_zero(), "FACTORY_ADDRESS {:?} is not a deployed contract", self.address);
+                Ok(get_contract_address(self.address, nonce))
+            }
+            FactoryScheme::Create2 { salt, init_code_hash } => {
+                Ok(get_create2_address_from_hash(self.address, salt, init_code_hash))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FactoryScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Create => f.write_str("CREATE"),
+            Self::Create2 { salt, .. } => write!(f, "CREATE2, salt {:?}", salt),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::config::chain_var;
+use crate::exit;
+use crate::overhead::MarketFees;
+use crate::search;
+use crate::units::{self, format_gwei};
+use ethers::types::U256;
+
+/// Fee settings for one chain, in wei: where the sweep starts and the bounds it must stay within.
+#[derive(Debug, Clone)]
+pub struct FeeProfile {
+    pub base_fee_start: U256,
+    pub priority_fee: U256,
+    /// Floor for the priority fee, e.g. the minimum the chain's sequencer or builders accept.
+    pub min_priority_fee: Option<U256>,
+    /// Ceiling for `max_fee_per_gas`; workers stop sweeping once they reach it.
+    pub max_fee_per_gas: Option<U256>,
+    /// Keep `max_fee_per_gas` within this many percent of the recommended market fee.
+    pub market_tolerance: Option<f64>,
+    /// Only sign max fees that are multiples of this, e.g. 1 gwei; 1 wei when unset.
+    pub fee_step: U256,
+    /// Pay exactly this effective gas price, e.g. 4.20 gwei, so the fee itself is a vanity number.
+    pub vanity_fee: Option<U256>,
+    /// Distinct priority fees the workers take turns over, each sweeping the same max fees.
+    pub priority_fee_lanes: Vec<U256>,
+}
+
+impl FeeProfile {
+    /// Reads `BASE_FEE_START`, `PRIORITY_FEE`, `MIN_PRIORITY_FEE`, `MAX_FEE_PER_GAS`,
+    /// `MARKET_TOLERANCE`, `FEE_STEP`, `VANITY_FEE` and `PRIORITY_FEE_LANES`, each of which can be
+    /// set for a single chain with a `_<chain_id>` suffix, e.g. `MAX_FEE_PER_GAS_1`. The sweep
+    /// start and priority fee default to the constants in `search`.
+    pub fn from_env(chain_id: u64) -> eyre::Result<Self> {
+        Self::read_env(chain_id).map_err(exit::config_error)
+    }
+
+    fn read_env(chain_id: u64) -> eyre::Result<Self> {
+        let var = |name: &str| {
+            let Some(input) = chain_var(name, chain_id) else {
+                return Ok(None);
+            };
+            let fee = units::parse_fee(&format!("{} for chain {}", name, chain_id), &input)?;
+            // A step of a few wei is deliberate, since 1 wei is the default
+            if let Some(warning) = units::unitless_fee_warning(name, &input, fee).filter(|_| name != "FEE_STEP") {
+                println!("{}", warning);
+            }
+            Ok::<_, eyre::Report>(Some(fee))
+        };
+        let market_tolerance = match chain_var("MARKET_TOLERANCE", chain_id) {
+            Some(tolerance) => match tolerance.parse::<f64>().map_err(|e| eyre::eyre!("invalid MARKET_TOLERANCE: {}", e))? {
+                tolerance if tolerance > 0.0 && tolerance < 100.0 => Some(tolerance),
+                _ => eyre::bail!("MARKET_TOLERANCE must be a percentage between 0 and 100"),
+            },
+            None => None,
+        };
+        let mut profile = Self {
+            base_fee_start: var("BASE_FEE_START")?.unwrap_or_else(|| search::BASE_FEE_START.into()),
+            priority_fee: var("PRIORITY_FEE")?.unwrap_or_else(|| search::PRIORITY_FEE.into()),
+            min_priority_fee: var("MIN_PRIORITY_FEE")?,
+            max_fee_per_gas: var("MAX_FEE_PER_GAS")?,
+            market_tolerance,
+            fee_step: var("FEE_STEP")?.unwrap_or_else(U256::one),
+            vanity_fee: chain_var("VANITY_FEE", chain_id)
+                .map(|fee| units::parse_gwei(&fee).map_err(|e| e.wrap_err(format!("invalid VANITY_FEE for chain {}", chain_id))))
+                .transpose()?,
+            priority_fee_lanes: read_lanes(chain_id)?,
+        };
+        if profile.fee_step.is_zero() {
+            eyre::bail!("FEE_STEP must be at least 1 wei");
+        }
+        if let Some(min) = profile.min_priority_fee {
+            profile.priority_fee = profile.priority_fee.max(min);
+        }
+        // A tip above the max fee makes a transaction every node rejects
+        if let Some(max_fee) = profile.max_fee_per_gas.filter(|_| profile.vanity_fee.is_none()) {
+            let name = if profile.min_priority_fee.is_some_and(|min| min == profile.priority_fee) { "MIN_PRIORITY_FEE" } else { "PRIORITY_FEE" };
+            eyre::ensure!(
+                profile.priority_fee <= max_fee,
+                "{} {} gwei for chain {} is above MAX_FEE_PER_GAS {} gwei, and the priority fee can never exceed the max fee",
+                name,
+                format_gwei(profile.priority_fee),
+                chain_id,
+                format_gwei(max_fee)
+            );
+        }
+        if let Some(fee) = profile.vanity_fee {
+            profile = profile.pinned_to(fee)?;
+        }
+        profile.check_lanes(chain_id)?;
+
+        if let Some(max_fee) = profile.max_fee_per_gas {
+            let first_fee = profile.first_fee();
+            if first_fee > max_fee {
+                eyre::bail!(
+                    "MAX_FEE_PER_GAS {} for chain {} is below the first fee of the sweep ({})",
+                    max_fee,
+                    chain_id,
+                    first_fee
+                );
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Each lane is a tip some worker signs with, so it is held to the same bounds as `PRIORITY_FEE`.
+    fn check_lanes(&self, chain_id: u64) -> eyre::Result<()> {
+        let lanes = &self.priority_fee_lanes;
+        if lanes.is_empty() {
+            return Ok(());
+        }
+        eyre::ensure!(self.vanity_fee.is_none(), "VANITY_FEE fixes the priority fee, so it cannot be combined with PRIORITY_FEE_LANES");
+        for (i, lane) in lanes.iter().enumerate() {
+            eyre::ensure!(!lanes[..i].contains(lane), "PRIORITY_FEE_LANES for chain {} lists {} gwei twice", chain_id, format_gwei(*lane));
+            if let Some(min) = self.min_priority_fee.filter(|min| lane < min) {
+                eyre::bail!(
+                    "PRIORITY_FEE_LANES lane {} gwei for chain {} is below MIN_PRIORITY_FEE {} gwei",
+                    format_gwei(*lane),
+                    chain_id,
+                    format_gwei(min)
+                );
+            }
+            if let Some(max_fee) = self.max_fee_per_gas.filter(|max_fee| lane > max_fee) {
+                eyre::bail!(
+                    "PRIORITY_FEE_LANES lane {} gwei for chain {} is above MAX_FEE_PER_GAS {} gwei, and the priority fee can never exceed the max fee",
+                    format_gwei(*lane),
+                    chain_id,
+                    format_gwei(max_fee)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Signs every candidate with both fees set to `fee`, so the effective gas price is exactly
+    /// `fee` whatever the base fee (as long as it stays below it). Only a salt varies the hash.
+    pub fn pinned_to(mut self, fee: U256) -> eyre::Result<Self> {
+        eyre::ensure!(!fee.is_zero(), "VANITY_FEE must be above zero");
+        eyre::ensure!(self.market_tolerance.is_none(), "VANITY_FEE fixes the fee, so it cannot be combined with MARKET_TOLERANCE");
+        if let Some(max_fee) = self.max_fee_per_gas.filter(|max_fee| fee > *max_fee) {
+            eyre::bail!("VANITY_FEE {} gwei is above MAX_FEE_PER_GAS {} gwei", format_gwei(fee), format_gwei(max_fee));
+        }
+        if let Some(min) = self.min_priority_fee.filter(|min| fee < *min) {
+            eyre::bail!("VANITY_FEE {} gwei is below MIN_PRIORITY_FEE {} gwei", format_gwei(fee), format_gwei(min));
+        }
+        self.vanity_fee = Some(fee);
+        self.base_fee_start = U256::zero();
+        self.priority_fee = fee;
+        self.max_fee_per_gas = Some(fee);
+        self.fee_step = U256::one();
+        Ok(self)
+    }
+
+    /// The lowest max fee the sweep signs.
+    pub fn first_fee(&self) -> U256 {
+        search::round_up_to_step(self.base_fee_start + self.priority_fee, self.fee_step)
+    }
+
+    /// Moves the sweep to start at the market's recommended max fee, e.g. to remine after the
+    /// network has moved past a match. With a market tolerance the band is re-centered instead.
+    pub fn rebased_on(mut self, market: &MarketFees) -> eyre::Result<Self> {
+        if let Some(fee) = self.vanity_fee {
+            eyre::ensure!(
+                market.base_fee <= fee,
+                "the base fee of {} gwei is above VANITY_FEE {} gwei",
+                format_gwei(market.base_fee),
+                format_gwei(fee)
+            );
+            return Ok(self);
+        }
+        if self.market_tolerance.is_some() {
+            return self.centered_on(market);
+        }
+        self.base_fee_start = market.max_fee_per_gas.saturating_sub(self.priority_fee);
+        if let Some(max_fee) = self.max_fee_per_gas {
+            if self.first_fee() > max_fee {
+                eyre::bail!("the market fee {} wei is above MAX_FEE_PER_GAS {} wei", market.max_fee_per_gas, max_fee);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Narrows the sweep to `market_tolerance` percent either side of the market's recommended max
+    /// fee, still respecting `max_fee_per_gas`. Without a tolerance the profile is unchanged.
+    pub fn centered_on(mut self, market: &MarketFees) -> eyre::Result<Self> {
+        let Some(tolerance) = self.market_tolerance else {
+            return Ok(self);
+        };
+        // Basis points keep the arithmetic in integers
+        let bps = U256::from((tolerance * 100.0).round() as u64);
+        let low = market.max_fee_per_gas * (U256::from(10_000) - bps) / 10_000;
+        let mut high = market.max_fee_per_gas * (U256::from(10_000) + bps) / 10_000;
+        if let Some(max_fee) = self.max_fee_per_gas {
+            high = high.min(max_fee);
+        }
+        let low = low.max(self.priority_fee);
+        self.base_fee_start = low - self.priority_fee;
+        self.max_fee_per_gas = Some(high);
+        if self.first_fee() > high {
+            eyre::bail!(
+                "no fee between {} and {} wei is within {}% of the market fee {} wei",
+                low,
+                high,
+                tolerance,
+                market.max_fee_per_gas
+            );
+        }
+        Ok(self)
+    }
+}
+
+/// Reads `PRIORITY_FEE_LANES`, a comma-separated list of priority fees, e.g. `0.01gwei,0.02gwei`.
+fn read_lanes(chain_id: u64) -> eyre::Result<Vec<U256>> {
+    let Some(input) = chain_var("PRIORITY_FEE_LANES", chain_id) else {
+        return Ok(Vec::new());
+    };
+    let name = format!("PRIORITY_FEE_LANES for chain {}", chain_id);
+    let mut lanes = Vec::new();
+    for lane in input.split(',').map(str::trim).filter(|lane| !lane.is_empty()) {
+        let fee = units::parse_fee(&name, lane)?;
+        if let Some(warning) = units::unitless_fee_warning("PRIORITY_FEE_LANES", lane, fee) {
+            println!("{}", warning);
+        }
+        lanes.push(fee);
+    }
+    Ok(lanes)
+}
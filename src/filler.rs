@@ -0,0 +1,91 @@
+use crate::nonces;
+use crate::overhead::MarketFees;
+use crate::rpc::{self, RpcProvider};
+use crate::search::encode_and_sign_eip1559;
+use ethers::prelude::*;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use eyre::eyre;
+use std::path::Path;
+
+/// Gas used by a plain transfer, all a filler needs.
+pub const FILLER_GAS: u64 = 21_000;
+
+/// Zero-value self-transfers that advance an account's nonce to the one a match was mined for.
+#[derive(Debug, Clone)]
+pub struct FillerPlan {
+    pub chain_id: u64,
+    pub from: Address,
+    /// First nonce to fill; the fillers take `first_nonce..target_nonce`.
+    pub first_nonce: U256,
+    pub target_nonce: U256,
+    pub max_fee_per_gas: U256,
+    pub priority_fee: U256,
+}
+
+impl FillerPlan {
+    /// Fillers paying the market's recommended fees.
+    pub fn new(chain_id: u64, from: Address, first_nonce: U256, target_nonce: U256, market: &MarketFees) -> Self {
+        Self {
+            chain_id,
+            from,
+            first_nonce,
+            target_nonce,
+            max_fee_per_gas: market.max_fee_per_gas,
+            priority_fee: market.priority_fee.min(market.max_fee_per_gas),
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.target_nonce.saturating_sub(self.first_nonce).as_u64()
+    }
+
+    /// Worst case for all fillers together, gas × max fee each.
+    pub fn max_cost(&self) -> U256 {
+        U256::from(FILLER_GAS) * self.max_fee_per_gas * self.count()
+    }
+
+    /// Expected cost for all fillers at `base_fee`.
+    pub fn expected_cost(&self, base_fee: U256) -> U256 {
+        let gas_price = self.max_fee_per_gas.min(base_fee + self.priority_fee);
+        U256::from(FILLER_GAS) * gas_price * self.count()
+    }
+
+    /// Signs every filler, in nonce order.
+    pub fn sign(&self, wallet: &LocalWallet) -> eyre::Result<Vec<(Bytes, H256)>> {
+        let wallet = wallet.clone().with_chain_id(self.chain_id);
+        (self.first_nonce.as_u64()..self.target_nonce.as_u64())
+            .map(|nonce| {
+                let tx = Eip1559TransactionRequest::new()
+                    .from(self.from)
+                    .to(self.from)
+                    .value(0)
+                    .nonce(nonce)
+                    .gas(FILLER_GAS)
+                    .chain_id(self.chain_id)
+                    .max_fee_per_gas(self.max_fee_per_gas)
+                    .max_priority_fee_per_gas(self.priority_fee);
+                let (raw, hash) = encode_and_sign_eip1559(&wallet, &tx)?;
+                Ok((raw, H256::from(hash)))
+            })
+            .collect()
+    }
+
+    /// Signs and sends the fillers, then waits until every one of them is included.
+    pub async fn send(&self, provider: &RpcProvider, wallet: &LocalWallet, history_path: impl AsRef<Path>) -> eyre::Result<()> {
+        let signed = self.sign(wallet)?;
+        let mut hashes = Vec::new();
+        for ((raw, hash), nonce) in signed.into_iter().zip(self.first_nonce.as_u64()..) {
+            let sent = provider.send_raw_transaction(raw).await?.tx_hash();
+            eyre::ensure!(sent == hash, "node reports hash {:?} for filler {:?}", sent, hash);
+            nonces::record_sent(history_path.as_ref(), self.from, self.chain_id, nonce.into());
+            hashes.push(hash);
+        }
+        for hash in hashes {
+            let receipt = rpc::wait_for_receipt(provider, hash)
+                .await?
+                .ok_or_else(|| eyre!("filler {:?} was dropped from the mempool", hash))?;
+            println!("Filler {:?} included in block {}", hash, receipt.block_number.unwrap_or_default());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use crate::intrinsic;
+use ethers::prelude::*;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use std::future::Future;
+
+/// Added on top of the smallest limit that works, for state that changes before inclusion.
+pub const DEFAULT_MARGIN_PERCENT: u64 = 10;
+
+/// The smallest gas limit a transaction executes with, found by `eth_call`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasLimitSweep {
+    /// What `eth_estimateGas` reported, if it succeeded.
+    pub estimate: Option<U256>,
+    pub minimal: U256,
+    /// `minimal` plus the margin.
+    pub recommended: U256,
+    /// `eth_call`s made to find `minimal`.
+    pub calls: usize,
+}
+
+/// Finds the smallest gas limit in `(lower, upper]` for which `succeeds` holds, assuming a limit
+/// that works keeps working when raised. `None` if even `upper` fails.
+pub async fn bisect<F, Fut>(mut lower: U256, mut upper: U256, mut succeeds: F) -> eyre::Result<Option<U256>>
+where
+    F: FnMut(U256) -> Fut,
+    Fut: Future<Output = eyre::Result<bool>>,
+{
+    if !succeeds(upper).await? {
+        return Ok(None);
+    }
+    while upper - lower > U256::one() {
+        let middle = lower + (upper - lower) / 2;
+        if succeeds(middle).await? {
+            upper = middle;
+        } else {
+            lower = middle;
+        }
+    }
+    Ok(Some(upper))
+}
+
+/// Adds `margin_percent` to `gas`, rounding up.
+pub fn with_margin(gas: U256, margin_percent: u64) -> U256 {
+    (gas * (100 + margin_percent) + 99) / 100
+}
+
+/// Sweeps gas limits for `call` (sent from `from`) with `eth_call`, starting from
+/// `eth_estimateGas` and bounded by the latest block's gas limit.
+pub async fn sweep<M: Middleware>(
+    provider: &M,
+    mut call: Eip1559TransactionRequest,
+    from: Address,
+    margin_percent: u64,
+) -> eyre::Result<GasLimitSweep>
+where
+    M::Error: 'static,
+{
+    // Without fees, so an empty or underfunded account can still be simulated
+    call.from = Some(from);
+    call.nonce = None;
+    call.max_fee_per_gas = None;
+    call.max_priority_fee_per_gas = None;
+    let block_gas_limit = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| eyre::eyre!("the RPC returned no latest block"))?
+        .gas_limit;
+    call.gas = None;
+    let estimate = match provider.estimate_gas(&call.clone().into(), None).await {
+        Ok(estimate) => Some(estimate),
+        Err(e) => {
+            println!("Warning: eth_estimateGas failed ({}), searching up to the block gas limit", e);
+            None
+        }
+    };
+
+    let mut calls = 0;
+    let mut succeeds = |gas: U256| {
+        calls += 1;
+        let mut call = call.clone();
+        call.gas = Some(gas);
+        async move { Ok(provider.call(&call.into(), None).await.is_ok()) }
+    };
+    // No limit below the intrinsic gas is worth trying
+    let lower = U256::from(intrinsic::intrinsic_gas(&call) - 1);
+    // The estimate usually works, which narrows the search to below it
+    let minimal = match estimate.filter(|estimate| *estimate > lower && *estimate <= block_gas_limit) {
+        Some(estimate) => match bisect(lower, estimate, &mut succeeds).await? {
+            Some(minimal) => Some(minimal),
+            None => bisect(estimate, block_gas_limit, &mut succeeds).await?,
+        },
+        None => bisect(lower, block_gas_limit, &mut succeeds).await?,
+    };
+    let minimal = minimal.ok_or_else(|| {
+        eyre::eyre!("the transaction fails even with the block gas limit of {}, so no gas limit will do", block_gas_limit)
+    })?;
+    Ok(GasLimitSweep { estimate, minimal, recommended: with_margin(minimal, margin_percent), calls })
+}
@@ -0,0 +1,224 @@
+use crate::units::format_gwei;
+use chrono::{DateTime, Datelike, Timelike};
+use ethers::types::U256;
+use rusqlite::{params, Connection};
+use std::fmt::Write;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// Samples an hour of the week needs before it can be named the cheapest window, so one lucky
+/// reading does not decide it.
+pub const MIN_WINDOW_SAMPLES: usize = 3;
+
+/// Fees `gas_checker` observed at one moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSample {
+    pub chain_id: u64,
+    /// Unix time in seconds.
+    pub timestamp: i64,
+    pub base_fee: U256,
+    pub priority_fee: U256,
+}
+
+impl FeeSample {
+    /// What a transaction paid per gas at the time, base fee plus tip.
+    pub fn total(&self) -> U256 {
+        self.base_fee + self.priority_fee
+    }
+}
+
+enum Store {
+    Sqlite(Connection),
+    /// Appended as `chain_id,timestamp,base_fee_wei,priority_fee_wei` lines.
+    Csv(PathBuf),
+}
+
+/// Fee samples kept by `gas_checker`, in SQLite, or in CSV when the path ends in `.csv`.
+pub struct GasHistory {
+    store: Store,
+}
+
+impl GasHistory {
+    /// Opens (creating if needed) the store at `path`.
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv")) {
+            return Ok(Self { store: Store::Csv(path.to_path_buf()) });
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS fee_samples (
+                chain_id     INTEGER NOT NULL,
+                timestamp    INTEGER NOT NULL,
+                base_fee     TEXT NOT NULL,
+                priority_fee TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS fee_samples_chain ON fee_samples (chain_id, timestamp);",
+        )?;
+        Ok(Self { store: Store::Sqlite(conn) })
+    }
+
+    pub fn record(&self, sample: &FeeSample) -> eyre::Result<()> {
+        match &self.store {
+            Store::Sqlite(conn) => {
+                conn.execute(
+                    "INSERT INTO fee_samples (chain_id, timestamp, base_fee, priority_fee) VALUES (?1, ?2, ?3, ?4)",
+                    params![sample.chain_id as i64, sample.timestamp, sample.base_fee.to_string(), sample.priority_fee.to_string()],
+                )?;
+            }
+            Store::Csv(path) => {
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{},{},{},{}", sample.chain_id, sample.timestamp, sample.base_fee, sample.priority_fee)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Samples for `chain_id` taken at or after `since` (unix seconds), oldest first.
+    pub fn samples(&self, chain_id: u64, since: i64) -> eyre::Result<Vec<FeeSample>> {
+        let mut samples = match &self.store {
+            Store::Sqlite(conn) => {
+                let mut stmt = conn.prepare(
+                    "SELECT timestamp, base_fee, priority_fee FROM fee_samples WHERE chain_id = ?1 AND timestamp >= ?2 ORDER BY timestamp",
+                )?;
+                let rows = stmt.query_map(params![chain_id as i64, since], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                })?;
+                let mut samples = Vec::new();
+                for row in rows {
+                    let (timestamp, base_fee, priority_fee) = row?;
+                    samples.push(FeeSample { chain_id, timestamp, base_fee: U256::from_dec_str(&base_fee)?, priority_fee: U256::from_dec_str(&priority_fee)? });
+                }
+                samples
+            }
+            Store::Csv(path) => match std::fs::read_to_string(path) {
+                Ok(contents) => contents
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| !line.trim().is_empty())
+                    .map(|(i, line)| parse_csv_line(line).map_err(|e| e.wrap_err(format!("{} line {}", path.display(), i + 1))))
+                    .filter(|sample| sample.as_ref().map_or(true, |sample| sample.chain_id == chain_id && sample.timestamp >= since))
+                    .collect::<eyre::Result<_>>()?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e.into()),
+            },
+        };
+        samples.sort_by_key(|sample| sample.timestamp);
+        Ok(samples)
+    }
+}
+
+fn parse_csv_line(line: &str) -> eyre::Result<FeeSample> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [chain_id, timestamp, base_fee, priority_fee] = fields[..] else {
+        eyre::bail!("expected chain_id,timestamp,base_fee_wei,priority_fee_wei, got {:?}", line);
+    };
+    Ok(FeeSample {
+        chain_id: chain_id.parse()?,
+        timestamp: timestamp.parse()?,
+        base_fee: U256::from_dec_str(base_fee)?,
+        priority_fee: U256::from_dec_str(priority_fee)?,
+    })
+}
+
+/// Mean fee (base fee plus tip) of the samples falling in one bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bucket {
+    pub samples: usize,
+    /// In wei per gas.
+    pub mean: f64,
+}
+
+impl Bucket {
+    fn add(&mut self, fee: f64) {
+        self.samples += 1;
+        self.mean += (fee - self.mean) / self.samples as f64;
+    }
+}
+
+/// Recorded fees grouped by UTC weekday, hour of the day and hour of the week.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trend {
+    pub overall: Bucket,
+    /// Monday first.
+    pub by_weekday: [Bucket; 7],
+    pub by_hour: [Bucket; 24],
+    /// Indexed by `weekday * 24 + hour`.
+    pub by_hour_of_week: Vec<Bucket>,
+}
+
+impl Trend {
+    pub fn new(samples: &[FeeSample]) -> Self {
+        let mut trend =
+            Self { overall: Bucket::default(), by_weekday: [Bucket::default(); 7], by_hour: [Bucket::default(); 24], by_hour_of_week: vec![Bucket::default(); 7 * 24] };
+        for sample in samples {
+            let Some(time) = DateTime::from_timestamp(sample.timestamp, 0) else {
+                continue;
+            };
+            // Fees far beyond f64's exact range are still ordered correctly, which is all a mean needs
+            let fee = sample.total().to_string().parse::<f64>().unwrap_or(f64::MAX);
+            let (weekday, hour) = (time.weekday().num_days_from_monday() as usize, time.hour() as usize);
+            trend.overall.add(fee);
+            trend.by_weekday[weekday].add(fee);
+            trend.by_hour[hour].add(fee);
+            trend.by_hour_of_week[weekday * 24 + hour].add(fee);
+        }
+        trend
+    }
+
+    /// The hour of the week with the lowest mean fee among those with at least
+    /// `MIN_WINDOW_SAMPLES` samples, as `(weekday, hour, bucket)` with Monday as 0.
+    pub fn cheapest_window(&self) -> Option<(usize, usize, Bucket)> {
+        self.by_hour_of_week
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.samples >= MIN_WINDOW_SAMPLES)
+            .min_by(|(_, a), (_, b)| a.mean.total_cmp(&b.mean))
+            .map(|(i, bucket)| (i / 24, i % 24, *bucket))
+    }
+
+    /// The weekday and hourly tables with the cheapest window, as `gas_checker trend` prints them.
+    pub fn report(&self) -> String {
+        let mut out = String::from("By weekday (UTC):\n");
+        for (day, bucket) in WEEKDAYS.iter().zip(&self.by_weekday) {
+            let _ = writeln!(out, "  {}    {}", day, describe(bucket));
+        }
+        out.push_str("By hour (UTC):\n");
+        for (hour, bucket) in self.by_hour.iter().enumerate() {
+            let _ = writeln!(out, "  {:02}:00  {}", hour, describe(bucket));
+        }
+        match self.cheapest_window() {
+            Some((weekday, hour, bucket)) => {
+                let _ = write!(
+                    out,
+                    "Cheapest window: {} {:02}:00-{:02}:00 UTC, {} gwei on average over {} samples",
+                    WEEKDAYS[weekday],
+                    hour,
+                    (hour + 1) % 24,
+                    gwei(bucket.mean),
+                    bucket.samples
+                );
+                if self.overall.mean > 0.0 {
+                    let _ = write!(out, ", {:.0}% below the overall average", (1.0 - bucket.mean / self.overall.mean) * 100.0);
+                }
+                out.push('\n');
+            }
+            None => {
+                let _ = writeln!(out, "Cheapest window: not enough samples yet, each hour of the week needs {}", MIN_WINDOW_SAMPLES);
+            }
+        }
+        out
+    }
+}
+
+fn describe(bucket: &Bucket) -> String {
+    match bucket.samples {
+        0 => "no samples".to_string(),
+        samples => format!("{} gwei ({} samples)", gwei(bucket.mean), samples),
+    }
+}
+
+fn gwei(wei: f64) -> String {
+    format_gwei(U256::from(wei.round() as u128))
+}
@@ -0,0 +1,210 @@
+// tonic returns `Status` by value throughout its API
+#![allow(clippy::result_large_err)]
+
+use crate::limits::ResourceLimits;
+use crate::pattern;
+use crate::server::{JobError, JobEvent, JobRequest, JobResult, JobStatus, JobView, State};
+use crate::units;
+use ethers::types::{Address, Bytes, U256};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+/// Types and service stubs generated from `proto/vanity.proto`.
+pub mod proto {
+    tonic::include_proto!("vanity.v1");
+}
+
+use proto::job_service_server::{JobService, JobServiceServer};
+
+/// Serves the `vanity.v1.JobService` gRPC API on `addr`, backed by the same jobs as the REST API.
+pub(crate) async fn serve(addr: SocketAddr, state: Arc<State>) -> eyre::Result<()> {
+    let expected = state.config.api_token.as_ref().map(|token| format!("Bearer {}", token));
+    let service = JobServiceServer::with_interceptor(GrpcService { state }, move |req: Request<()>| {
+        match &expected {
+            Some(expected) => {
+                let authorized = req
+                    .metadata()
+                    .get("authorization")
+                    .is_some_and(|value| value.as_bytes() == expected.as_bytes());
+                if authorized {
+                    Ok(req)
+                } else {
+                    Err(Status::unauthenticated("missing or invalid bearer token"))
+                }
+            }
+            None => Ok(req),
+        }
+    });
+
+    println!("Serving gRPC job API on {}", addr);
+    tonic::transport::Server::builder().add_service(service).serve(addr).await?;
+    Ok(())
+}
+
+struct GrpcService {
+    state: Arc<State>,
+}
+
+#[tonic::async_trait]
+impl JobService for GrpcService {
+    async fn submit_job(
+        &self,
+        request: Request<proto::SubmitJobRequest>,
+    ) -> Result<Response<proto::SubmitJobResponse>, Status> {
+        let request = job_request(request.into_inner())?;
+        let id = self.state.submit(request);
+        Ok(Response::new(proto::SubmitJobResponse { id }))
+    }
+
+    async fn list_jobs(
+        &self,
+        _request: Request<proto::ListJobsRequest>,
+    ) -> Result<Response<proto::ListJobsResponse>, Status> {
+        let jobs = self.state.list().into_iter().map(job).collect();
+        Ok(Response::new(proto::ListJobsResponse { jobs }))
+    }
+
+    async fn get_job(&self, request: Request<proto::JobId>) -> Result<Response<proto::Job>, Status> {
+        let view = self.state.view(request.into_inner().id).map_err(status)?;
+        Ok(Response::new(job(view)))
+    }
+
+    async fn cancel_job(&self, request: Request<proto::JobId>) -> Result<Response<proto::CancelJobResponse>, Status> {
+        self.state.cancel(request.into_inner().id).map_err(status)?;
+        Ok(Response::new(proto::CancelJobResponse {}))
+    }
+
+    async fn get_result(&self, request: Request<proto::JobId>) -> Result<Response<proto::JobResult>, Status> {
+        let result = self.state.result(request.into_inner().id).map_err(status)?;
+        Ok(Response::new(job_result(result)))
+    }
+
+    async fn broadcast_job(
+        &self,
+        request: Request<proto::JobId>,
+    ) -> Result<Response<proto::BroadcastJobResponse>, Status> {
+        let tx_hash = self.state.broadcast(request.into_inner().id).await.map_err(status)?;
+        Ok(Response::new(proto::BroadcastJobResponse { tx_hash: format!("{:?}", tx_hash) }))
+    }
+
+    type WatchEventsStream = Pin<Box<dyn Stream<Item = Result<proto::JobEvent, Status>> + Send>>;
+
+    async fn watch_events(
+        &self,
+        request: Request<proto::WatchEventsRequest>,
+    ) -> Result<Response<Self::WatchEventsStream>, Status> {
+        let only = request.into_inner().job;
+        // Events missed by a slow client are dropped rather than ending the stream
+        let events = BroadcastStream::new(self.state.subscribe()).filter_map(move |event| match event {
+            Ok(event) if only.is_none_or(|job| job == event.job) => Some(Ok(job_event(event))),
+            _ => None,
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+fn job_request(request: proto::SubmitJobRequest) -> Result<JobRequest, Status> {
+    pattern::validate(&request.hash_prefix).map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let job = JobRequest {
+        hash_prefix: request.hash_prefix,
+        gas_limit: request.gas_limit,
+        calldata: request.calldata.map(Bytes::from),
+        to: request.to.map(|to| to.parse::<Address>()).transpose().map_err(|e| invalid("to", e))?,
+        value: request.value.map(|value| amount("value", &value)).transpose()?,
+        nonce: request.nonce.map(|nonce| quantity("nonce", &nonce)).transpose()?,
+        priority: request.priority,
+        threads: request.threads.map(|threads| threads as usize),
+        base_fee_start: request.base_fee_start.map(|fee| amount("base_fee_start", &fee)).transpose()?,
+        max_cpu_seconds: request.max_cpu_seconds,
+        max_signatures: request.max_signatures,
+        swept: request
+            .swept
+            .iter()
+            .map(|range| Ok((quantity("swept", &range.start)?, quantity("swept", &range.end)?)))
+            .collect::<Result<_, Status>>()?,
+    };
+    job.limits(ResourceLimits::default()).map_err(|e| Status::invalid_argument(e.to_string()))?;
+    Ok(job)
+}
+
+fn quantity(field: &str, value: &str) -> Result<U256, Status> {
+    U256::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|e| invalid(field, e))
+}
+
+/// A hex quantity like [`quantity`], or an amount with a unit such as `1.5gwei`.
+fn amount(field: &str, value: &str) -> Result<U256, Status> {
+    let lowercase = value.trim().to_lowercase();
+    if ["wei", "eth", "ether"].iter().any(|unit| lowercase.ends_with(unit)) {
+        units::parse_amount(value).map_err(|e| invalid(field, e))
+    } else {
+        quantity(field, value)
+    }
+}
+
+fn invalid(field: &str, e: impl std::fmt::Display) -> Status {
+    Status::invalid_argument(format!("invalid {}: {}", field, e))
+}
+
+fn job(view: JobView) -> proto::Job {
+    let status = match view.status {
+        JobStatus::Queued => proto::JobStatus::Queued,
+        JobStatus::Running => proto::JobStatus::Running,
+        JobStatus::Found => proto::JobStatus::Found,
+        JobStatus::Broadcast => proto::JobStatus::Broadcast,
+        JobStatus::Failed => proto::JobStatus::Failed,
+        JobStatus::Cancelled => proto::JobStatus::Cancelled,
+        JobStatus::Limited => proto::JobStatus::Limited,
+    };
+    proto::Job {
+        id: view.id,
+        status: status.into(),
+        priority: view.priority,
+        threads: view.threads as u32,
+        attempts: view.attempts,
+        hashrate: view.hashrate,
+        error: view.error,
+        cpu_seconds: view.cpu_seconds,
+        signatures: view.signatures,
+        swept: view
+            .swept
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(start, end)| proto::FeeRange { start: format!("{:#x}", start), end: format!("{:#x}", end) })
+            .collect(),
+    }
+}
+
+fn job_result(result: JobResult) -> proto::JobResult {
+    proto::JobResult {
+        tx_hash: format!("{:?}", result.tx_hash),
+        raw_tx: result.raw_tx.to_vec(),
+        nonce: format!("{:#x}", result.nonce),
+        max_fee_per_gas: format!("{:#x}", result.max_fee_per_gas),
+        contract_address: result.contract_address.map(|address| format!("{:?}", address)),
+        calldata_hash: result.calldata_hash.map(|hash| format!("{:?}", hash)),
+        match_json: result.payload.map(|payload| payload.to_value().to_string()),
+    }
+}
+
+fn job_event(event: JobEvent) -> proto::JobEvent {
+    proto::JobEvent {
+        job: event.job,
+        event: event.event.to_string(),
+        attempts: event.attempts,
+        hashrate: event.hashrate,
+        timestamp: event.timestamp,
+        detail: event.detail.unwrap_or_default(),
+    }
+}
+
+fn status(e: JobError) -> Status {
+    match e {
+        JobError::NotFound(message) => Status::not_found(message),
+        JobError::Conflict(message) => Status::failed_precondition(message),
+        JobError::Other(e) => Status::internal(e.to_string()),
+    }
+}
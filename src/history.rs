@@ -0,0 +1,216 @@
+use ethers::types::{Address, H256, U256};
+use eyre::eyre;
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use std::path::Path;
+
+/// Default location of the deployment history database, relative to the working directory.
+pub const DEFAULT_HISTORY_PATH: &str = "deployments.db";
+
+/// A confirmed vanity transaction as recorded in the history database.
+#[derive(Debug, Clone, Serialize)]
+pub struct Deployment {
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    pub from: Address,
+    pub contract_address: Option<Address>,
+    pub nonce: u64,
+    pub calldata_hash: Option<H256>,
+    /// Calldata length in bytes; unknown for deployments recorded before it was tracked.
+    pub calldata_size: Option<u64>,
+    /// DEPLOYMENT_NAME at the time, identifying redeployments of the same contract.
+    pub name: Option<String>,
+    /// Actual cost paid in wei, stored as a decimal string since it can exceed 64 bits.
+    #[serde(serialize_with = "serialize_decimal")]
+    pub cost_wei: U256,
+    /// Unix timestamp (seconds) at which the receipt was observed.
+    pub timestamp: i64,
+}
+
+/// Optional filters for [`HistoryDb::list`].
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub chain_id: Option<u64>,
+    pub from: Option<Address>,
+    pub limit: Option<usize>,
+}
+
+pub struct HistoryDb {
+    conn: Connection,
+}
+
+impl HistoryDb {
+    /// Opens (creating if needed) the SQLite history database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS deployments (
+                id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                chain_id         INTEGER NOT NULL,
+                tx_hash          TEXT NOT NULL,
+                sender           TEXT NOT NULL,
+                contract_address TEXT,
+                nonce            INTEGER NOT NULL,
+                calldata_hash    TEXT,
+                cost_wei         TEXT NOT NULL,
+                timestamp        INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS deployments_chain ON deployments (chain_id);
+            CREATE TABLE IF NOT EXISTS nonces (
+                sender     TEXT NOT NULL,
+                chain_id   INTEGER NOT NULL,
+                nonce      INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (sender, chain_id)
+            );",
+        )?;
+        add_column(&conn, "deployments", "calldata_size", "INTEGER")?;
+        add_column(&conn, "deployments", "name", "TEXT")?;
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, deployment: &Deployment) -> eyre::Result<()> {
+        self.conn.execute(
+            "INSERT INTO deployments
+                (chain_id, tx_hash, sender, contract_address, nonce, calldata_hash, cost_wei, timestamp, calldata_size, name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                deployment.chain_id as i64,
+                format!("{:?}", deployment.tx_hash),
+                format!("{:?}", deployment.from),
+                deployment.contract_address.map(|a| format!("{:?}", a)),
+                deployment.nonce as i64,
+                deployment.calldata_hash.map(|h| format!("{:?}", h)),
+                deployment.cost_wei.to_string(),
+                deployment.timestamp,
+                deployment.calldata_size.map(|size| size as i64),
+                deployment.name,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remembers that `from` sent a transaction with `nonce` on `chain_id`.
+    pub fn record_nonce(&self, from: Address, chain_id: u64, nonce: u64) -> eyre::Result<()> {
+        self.conn.execute(
+            "INSERT INTO nonces (sender, chain_id, nonce, updated_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (sender, chain_id) DO UPDATE SET nonce = excluded.nonce, updated_at = excluded.updated_at",
+            params![format!("{:?}", from), chain_id as i64, nonce as i64, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// The last nonce `from` was recorded sending on `chain_id`, if any.
+    pub fn last_nonce(&self, from: Address, chain_id: u64) -> eyre::Result<Option<u64>> {
+        let mut stmt = self.conn.prepare("SELECT nonce FROM nonces WHERE sender = ?1 AND chain_id = ?2")?;
+        let mut rows = stmt.query(params![format!("{:?}", from), chain_id as i64])?;
+        Ok(match rows.next()? {
+            Some(row) => Some(row.get::<_, i64>(0)? as u64),
+            None => None,
+        })
+    }
+
+    /// Returns recorded deployments, newest first.
+    pub fn list(&self, filter: &HistoryFilter) -> eyre::Result<Vec<Deployment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id, tx_hash, sender, contract_address, nonce, calldata_hash, cost_wei, timestamp, calldata_size, name
+             FROM deployments
+             WHERE (?1 IS NULL OR chain_id = ?1) AND (?2 IS NULL OR sender = ?2)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?3",
+        )?;
+        let limit = filter.limit.map(|l| l as i64).unwrap_or(-1);
+        let rows = stmt.query_map(
+            params![
+                filter.chain_id.map(|c| c as i64),
+                filter.from.map(|a| format!("{:?}", a)),
+                limit
+            ],
+            |row| Ok(read_row(row)),
+        )?;
+
+        let mut deployments = Vec::new();
+        for row in rows {
+            deployments.push(row??);
+        }
+        Ok(deployments)
+    }
+
+    /// The most recent deployment recorded under `name`, on any chain.
+    pub fn latest_named(&self, name: &str) -> eyre::Result<Option<Deployment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT chain_id, tx_hash, sender, contract_address, nonce, calldata_hash, cost_wei, timestamp, calldata_size, name
+             FROM deployments
+             WHERE name = ?1
+             ORDER BY timestamp DESC, id DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query(params![name])?;
+        rows.next()?.map(read_row).transpose()
+    }
+
+    /// Deployments already sent by `from` at `nonce` on chains other than `chain_id` with calldata
+    /// other than `calldata_hash`. Sending here too would put different contracts (or a contract
+    /// and a transfer) behind the same address and nonce on different chains.
+    pub fn key_reuse(&self, from: Address, nonce: u64, chain_id: u64, calldata_hash: Option<H256>) -> eyre::Result<Vec<Deployment>> {
+        let filter = HistoryFilter { from: Some(from), ..Default::default() };
+        Ok(self
+            .list(&filter)?
+            .into_iter()
+            .filter(|d| d.nonce == nonce && d.chain_id != chain_id && d.calldata_hash != calldata_hash)
+            .collect())
+    }
+}
+
+fn read_row(row: &Row) -> eyre::Result<Deployment> {
+    let contract_address: Option<String> = row.get(3)?;
+    let calldata_hash: Option<String> = row.get(5)?;
+    let cost_wei: String = row.get(6)?;
+    Ok(Deployment {
+        chain_id: row.get::<_, i64>(0)? as u64,
+        tx_hash: row.get::<_, String>(1)?.parse()?,
+        from: row.get::<_, String>(2)?.parse()?,
+        contract_address: contract_address.map(|a| a.parse()).transpose()?,
+        nonce: row.get::<_, i64>(4)? as u64,
+        calldata_hash: calldata_hash.map(|h| h.parse()).transpose()?,
+        cost_wei: U256::from_dec_str(&cost_wei).map_err(|e| eyre!("invalid cost in history: {}", e))?,
+        timestamp: row.get(7)?,
+        calldata_size: row.get::<_, Option<i64>>(8)?.map(|size| size as u64),
+        name: row.get(9)?,
+    })
+}
+
+/// Adds a column introduced after the table was first created, if the database predates it.
+fn add_column(conn: &Connection, table: &str, column: &str, kind: &str) -> eyre::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?.collect::<Result<Vec<_>, _>>()?;
+    if !columns.iter().any(|c| c == column) {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, kind), [])?;
+    }
+    Ok(())
+}
+
+fn serialize_decimal<S: serde::Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Renders deployments as CSV with a header row.
+pub fn to_csv(deployments: &[Deployment]) -> String {
+    let mut out = String::from("chain_id,tx_hash,from,contract_address,nonce,calldata_hash,cost_wei,timestamp,calldata_size,name\n");
+    for d in deployments {
+        out.push_str(&format!(
+            "{},{:?},{:?},{},{},{},{},{},{},{}\n",
+            d.chain_id,
+            d.tx_hash,
+            d.from,
+            d.contract_address.map(|a| format!("{:?}", a)).unwrap_or_default(),
+            d.nonce,
+            d.calldata_hash.map(|h| format!("{:?}", h)).unwrap_or_default(),
+            d.cost_wei,
+            d.timestamp,
+            d.calldata_size.map(|size| size.to_string()).unwrap_or_default(),
+            d.name.as_deref().unwrap_or_default(),
+        ));
+    }
+    out
+}
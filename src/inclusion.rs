@@ -0,0 +1,82 @@
+use crate::units::wei_to_f64;
+use ethers::prelude::*;
+use ethers::types::{FeeHistory, U256};
+
+/// Blocks of fee history the estimate is based on.
+pub const HISTORY_BLOCKS: u64 = 20;
+/// Reward percentiles requested per block; the tip is ranked against these.
+const PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+/// Blocks fuller than this are treated as contested, so only competitive tips get in.
+const FULL_BLOCK_RATIO: f64 = 0.95;
+/// The most the base fee can rise per block under EIP-1559.
+const MAX_BASE_FEE_CHANGE: f64 = 1.125;
+
+/// Rough odds of inclusion for a fee pair, in the spirit of a wallet's speed estimate.
+#[derive(Debug, Clone, Copy)]
+pub struct InclusionEstimate {
+    /// Chance of inclusion in any one block, from how often recent blocks had room for this tip.
+    pub per_block: f64,
+    /// Highest percentile of recent tips (median over the blocks) this tip matches, if any.
+    pub tip_percentile: Option<f64>,
+    /// Consecutive full blocks the max fee can absorb before the base fee outgrows it, `None`
+    /// when the base fee is zero.
+    pub headroom_blocks: Option<u32>,
+}
+
+impl InclusionEstimate {
+    /// Chance of being included within `blocks` blocks.
+    pub fn within(&self, blocks: u32) -> f64 {
+        1.0 - (1.0 - self.per_block).powi(blocks as i32)
+    }
+
+    /// Expected number of blocks until inclusion, infinite when the fees cannot be included.
+    pub fn expected_blocks(&self) -> f64 {
+        1.0 / self.per_block
+    }
+}
+
+/// Estimates inclusion odds for `max_fee_per_gas` and `priority_fee` from the last
+/// `HISTORY_BLOCKS` blocks of `eth_feeHistory`.
+pub async fn estimate<M: Middleware>(provider: &M, max_fee_per_gas: U256, priority_fee: U256) -> eyre::Result<InclusionEstimate>
+where
+    M::Error: 'static,
+{
+    let history = provider.fee_history(HISTORY_BLOCKS, BlockNumber::Latest, &PERCENTILES).await?;
+    Ok(from_history(&history, max_fee_per_gas, priority_fee))
+}
+
+/// The estimate itself, separate from fetching so it only depends on the fee history.
+pub fn from_history(history: &FeeHistory, max_fee_per_gas: U256, priority_fee: U256) -> InclusionEstimate {
+    // The last entry is the base fee of the next block
+    let next_base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+    if max_fee_per_gas < next_base_fee {
+        return InclusionEstimate { per_block: 0.0, tip_percentile: None, headroom_blocks: Some(0) };
+    }
+    let tip = priority_fee.min(max_fee_per_gas - next_base_fee);
+
+    let headroom_blocks = (!next_base_fee.is_zero())
+        .then(|| (wei_to_f64(max_fee_per_gas) / wei_to_f64(next_base_fee)).log(MAX_BASE_FEE_CHANGE).floor() as u32);
+
+    // Per block, the share of contested gas this tip outbids; blocks with spare room take anything
+    let beaten = |rewards: &[U256]| {
+        PERCENTILES.iter().zip(rewards).filter(|(_, reward)| tip >= **reward).map(|(percentile, _)| *percentile).next_back()
+    };
+    let chances: Vec<f64> = history
+        .gas_used_ratio
+        .iter()
+        .zip(&history.reward)
+        .map(|(ratio, rewards)| if *ratio < FULL_BLOCK_RATIO { 1.0 } else { beaten(rewards).unwrap_or(0.0) / 100.0 })
+        .collect();
+    let per_block = if chances.is_empty() { 0.0 } else { chances.iter().sum::<f64>() / chances.len() as f64 };
+
+    let median_rewards: Vec<U256> = (0..PERCENTILES.len())
+        .map(|i| {
+            let mut rewards: Vec<U256> = history.reward.iter().filter_map(|r| r.get(i).copied()).collect();
+            rewards.sort();
+            rewards.get(rewards.len() / 2).copied().unwrap_or_default()
+        })
+        .collect();
+    let tip_percentile = if history.reward.is_empty() { None } else { beaten(&median_rewards) };
+
+    InclusionEstimate { per_block, tip_percentile, headroom_blocks }
+}
@@ -0,0 +1,99 @@
+use std::io;
+use std::time::Duration;
+
+/// How long a helper process must stay up to count as holding the inhibition.
+#[cfg(not(target_os = "windows"))]
+const HELPER_STARTUP: Duration = Duration::from_millis(200);
+/// How often a running search checks that the machine is still being kept awake.
+pub const INHIBIT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keeps the machine from sleeping while held, and lets it sleep again once dropped. Uses
+/// `systemd-inhibit` on Linux, `caffeinate` on macOS and `SetThreadExecutionState` on Windows.
+pub struct SleepInhibitor {
+    #[cfg(not(target_os = "windows"))]
+    helper: std::process::Child,
+}
+
+impl SleepInhibitor {
+    /// Stops the machine sleeping (the display may still turn off), with `reason` shown by tools
+    /// listing inhibitors.
+    #[cfg(target_os = "linux")]
+    pub fn acquire(reason: &str) -> io::Result<Self> {
+        let command = std::process::Command::new("systemd-inhibit")
+            .args(["--what=sleep:idle", "--who=find_tx_hash_prefix", "--mode=block"])
+            .arg(format!("--why={}", reason))
+            .args(["sleep", "infinity"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+        Self::started(command, "systemd-inhibit")
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn acquire(_reason: &str) -> io::Result<Self> {
+        // -i keeps the system awake, -w releases it should this process die without dropping it
+        let command = std::process::Command::new("caffeinate").arg("-i").arg("-w").arg(std::process::id().to_string()).spawn();
+        Self::started(command, "caffeinate")
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    pub fn acquire(_reason: &str) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "keeping the machine awake is not supported on this platform"))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn acquire(_reason: &str) -> io::Result<Self> {
+        // The state belongs to the calling thread, which is the runtime's main thread here
+        if unsafe { windows::SetThreadExecutionState(windows::ES_CONTINUOUS | windows::ES_SYSTEM_REQUIRED) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {})
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn started(command: io::Result<std::process::Child>, name: &str) -> io::Result<Self> {
+        let mut helper = command.map_err(|e| io::Error::new(e.kind(), format!("{} is not available: {}", name, e)))?;
+        std::thread::sleep(HELPER_STARTUP);
+        match helper.try_wait()? {
+            Some(status) => Err(io::Error::other(format!("{} exited with {}", name, status))),
+            None => Ok(Self { helper }),
+        }
+    }
+
+    /// Whether the machine is still being kept awake; the helper process can be killed from outside.
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_held(&mut self) -> bool {
+        matches!(self.helper.try_wait(), Ok(None))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn is_held(&mut self) -> bool {
+        true
+    }
+}
+
+impl Drop for SleepInhibitor {
+    #[cfg(not(target_os = "windows"))]
+    fn drop(&mut self) {
+        let _ = self.helper.kill();
+        let _ = self.helper.wait();
+    }
+
+    #[cfg(target_os = "windows")]
+    fn drop(&mut self) {
+        unsafe {
+            windows::SetThreadExecutionState(windows::ES_CONTINUOUS);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    pub const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+}
@@ -0,0 +1,80 @@
+use crate::exit;
+use crate::search::{self, SearchParams};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::U256;
+use std::fmt;
+
+/// Gas every transaction pays before it executes anything.
+pub const TX_GAS: u64 = 21_000;
+/// Extra gas for a transaction that creates a contract.
+pub const CREATE_GAS: u64 = 32_000;
+const ZERO_BYTE_GAS: u64 = 4;
+const NON_ZERO_BYTE_GAS: u64 = 16;
+/// Per 32-byte word of init code (EIP-3860).
+const INIT_CODE_WORD_GAS: u64 = 2;
+const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+const ACCESS_LIST_KEY_GAS: u64 = 1_900;
+/// Geth and most other clients drop larger transactions from their mempool.
+pub const MEMPOOL_SIZE_LIMIT: usize = 128 * 1024;
+
+/// The gas `tx` is charged before executing: the base cost, its calldata, contract creation
+/// and its access list. A gas limit below this is rejected by every node.
+pub fn intrinsic_gas(tx: &Eip1559TransactionRequest) -> u64 {
+    let data = tx.data.as_deref().unwrap_or_default();
+    let zeros = data.iter().filter(|&&byte| byte == 0).count() as u64;
+    let mut gas = TX_GAS + zeros * ZERO_BYTE_GAS + (data.len() as u64 - zeros) * NON_ZERO_BYTE_GAS;
+    if tx.to.is_none() {
+        gas += CREATE_GAS + (data.len() as u64).div_ceil(32) * INIT_CODE_WORD_GAS;
+    }
+    for item in &tx.access_list.0 {
+        gas += ACCESS_LIST_ADDRESS_GAS + item.storage_keys.len() as u64 * ACCESS_LIST_KEY_GAS;
+    }
+    gas
+}
+
+/// What the largest candidate of a search costs before executing and how big it is signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preflight {
+    pub intrinsic_gas: u64,
+    pub gas_limit: U256,
+    /// Bytes of the signed RLP.
+    pub signed_size: usize,
+}
+
+impl Preflight {
+    /// Signs one candidate at the top of the fee range with the largest salt, so padding and
+    /// fees are counted at their biggest.
+    pub fn of(params: &SearchParams) -> eyre::Result<Self> {
+        let max_fee = params.max_fee_per_gas.unwrap_or_else(|| search::first_fee_for_salt(params, 0));
+        let tx = search::candidate(params, max_fee, u64::MAX);
+        let (signed_rlp, _) = params.signer.sign_candidate(&tx)?;
+        Ok(Self { intrinsic_gas: intrinsic_gas(&tx), gas_limit: tx.gas.unwrap_or_default(), signed_size: signed_rlp.len() })
+    }
+
+    /// Fails if no node would accept the transaction's gas limit.
+    pub fn check(&self) -> eyre::Result<()> {
+        if self.gas_limit < self.intrinsic_gas.into() {
+            return Err(exit::config_error(eyre::eyre!(
+                "GAS_LIMIT {} is below the transaction's intrinsic gas of {}, so every node would reject it",
+                self.gas_limit,
+                self.intrinsic_gas
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the signed transaction is too big for most mempools.
+    pub fn oversized(&self) -> bool {
+        self.signed_size > MEMPOOL_SIZE_LIMIT
+    }
+}
+
+impl fmt::Display for Preflight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "intrinsic gas {} of a {} gas limit, {} bytes signed",
+            self.intrinsic_gas, self.gas_limit, self.signed_size
+        )
+    }
+}
@@ -0,0 +1,100 @@
+use crate::chains;
+use crate::config::TxSpec;
+use crate::pattern;
+use crate::units;
+use ethers::types::{Address, Bytes, U256};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// One independent search in a `jobs` file: its own key, chain, pattern and transaction.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    /// Label used in progress and results.
+    pub name: String,
+    /// Environment variable holding the job's private key, so keys stay out of the file.
+    #[serde(default = "default_key_var")]
+    pub key_var: String,
+    /// Chain id or preset name, e.g. `8453` or `base`.
+    pub chain: String,
+    pub hash_prefix: String,
+    pub gas_limit: u64,
+    #[serde(default)]
+    pub calldata: Option<Bytes>,
+    #[serde(default)]
+    pub to: Option<Address>,
+    #[serde(default, deserialize_with = "units::deserialize_optional_amount")]
+    pub value: Option<U256>,
+    /// Nonce to mine at instead of the account's current nonce.
+    #[serde(default)]
+    pub nonce: Option<U256>,
+    /// Relative share of the worker threads.
+    #[serde(default = "default_share")]
+    pub share: f64,
+}
+
+fn default_key_var() -> String {
+    "PRIVATE_KEY".to_string()
+}
+
+fn default_share() -> f64 {
+    1.0
+}
+
+impl JobSpec {
+    pub fn chain_id(&self) -> eyre::Result<u64> {
+        chains::resolve(&self.chain).map_err(|e| e.wrap_err(format!("job {}", self.name)))
+    }
+
+    pub fn tx_spec(&self) -> TxSpec {
+        TxSpec { calldata: self.calldata.clone(), gas_limit: self.gas_limit.into(), to: self.to, value: self.value }
+    }
+}
+
+/// Reads and checks a JSON array of jobs.
+pub fn load(path: impl AsRef<Path>) -> eyre::Result<Vec<JobSpec>> {
+    let path = path.as_ref();
+    let specs: Vec<JobSpec> = serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(|e| eyre::eyre!("{} is not a JSON array of jobs: {}", path.display(), e))?;
+    validate(&specs)?;
+    Ok(specs)
+}
+
+pub fn validate(specs: &[JobSpec]) -> eyre::Result<()> {
+    eyre::ensure!(!specs.is_empty(), "no jobs to run");
+    let mut names = HashSet::new();
+    for spec in specs {
+        eyre::ensure!(names.insert(spec.name.as_str()), "two jobs are named {:?}", spec.name);
+        pattern::validate(&spec.hash_prefix).map_err(|e| eyre::eyre!("job {}: {}", spec.name, e))?;
+        eyre::ensure!(spec.share.is_finite() && spec.share > 0.0, "job {}: share must be positive", spec.name);
+        eyre::ensure!(spec.gas_limit > 0, "job {}: gas_limit must be positive", spec.name);
+        spec.chain_id()?;
+    }
+    Ok(())
+}
+
+/// Splits `total` worker threads in proportion to `shares`, handing leftover threads to the
+/// largest remainders. Every job gets at least one, taken from the jobs with the most.
+pub fn split_threads(shares: &[f64], total: usize) -> Vec<usize> {
+    let total = total.max(shares.len());
+    let sum: f64 = shares.iter().sum();
+    let exact: Vec<f64> = shares.iter().map(|share| share / sum * total as f64).collect();
+    let mut threads: Vec<usize> = exact.iter().map(|share| (share.floor() as usize).max(1)).collect();
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    let mut assigned: usize = threads.iter().sum();
+    for &i in by_remainder.iter().cycle() {
+        if assigned >= total {
+            break;
+        }
+        threads[i] += 1;
+        assigned += 1;
+    }
+    while assigned > total {
+        let largest = (0..threads.len()).max_by_key(|&i| threads[i]).expect("at least one job");
+        threads[largest] -= 1;
+        assigned -= 1;
+    }
+    threads
+}
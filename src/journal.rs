@@ -0,0 +1,155 @@
+use crate::sealed::{Opener, Sealed, SealingKey};
+use ethers::types::{Bytes, H256, U256};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Default location of the found-candidate journal, relative to the working directory.
+pub const DEFAULT_JOURNAL_PATH: &str = "found_candidates.jsonl";
+
+/// A matching signed transaction, written as one JSON line per match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: i64,
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub tx_hash: H256,
+    pub raw_tx: Bytes,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    /// Server job that produced the match, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<u64>,
+    /// [`crate::reuse::template_hash`] of the transaction searched for; absent in older journals.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<H256>,
+}
+
+/// A journal line: an entry in the clear, or one encrypted with JOURNAL_PASSPHRASE.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Sealed { sealed: Sealed },
+    Plain(JournalEntry),
+}
+
+/// Append-only journal of every match, synced to disk before the match is reported so that
+/// nothing found after hours of searching is lost to a crash or a closed terminal.
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<File>,
+    /// Encrypts each entry when set, since a signed transaction can be broadcast by anyone who
+    /// reads it.
+    key: Option<SealingKey>,
+}
+
+impl Journal {
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        Self::open_with_passphrase(path, None)
+    }
+
+    /// Opens the journal, encrypting new entries with `passphrase` if given. Checks first that
+    /// the passphrase opens the entries already there, and that an encrypted journal is not
+    /// reopened without one, so one journal is never split between two. New entries are sealed
+    /// with the key of the first encrypted entry, so reading the journal derives it only once.
+    pub fn open_with_passphrase(path: impl AsRef<Path>, passphrase: Option<&str>) -> eyre::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let key = match passphrase {
+            Some(passphrase) => {
+                let mut opener = Opener::new(passphrase);
+                Self::read(&path, Some(&mut opener))?;
+                match opener.first_key() {
+                    Some(key) => Some(key),
+                    None => Some(SealingKey::generate(passphrase)?),
+                }
+            }
+            None => {
+                eyre::ensure!(
+                    !Self::has_sealed_entries(&path)?,
+                    "the journal {} is encrypted; set JOURNAL_PASSPHRASE so new matches are encrypted too",
+                    path.display()
+                );
+                None
+            }
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file: Mutex::new(file), key })
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `entry` and fsyncs before returning.
+    pub fn append(&self, entry: &JournalEntry) -> eyre::Result<()> {
+        let mut line = match &self.key {
+            Some(key) => serde_json::to_string(&Line::Sealed { sealed: key.seal(&serde_json::to_vec(entry)?) })?,
+            None => serde_json::to_string(entry)?,
+        };
+        line.push('\n');
+        let mut file = self.file.lock();
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Reads every entry in the journal at `path`, skipping a trailing line torn by a crash. Any
+    /// other line that cannot be read is an error, since it may hold a match.
+    pub fn read_entries(path: impl AsRef<Path>) -> eyre::Result<Vec<JournalEntry>> {
+        Self::read_entries_with_passphrase(path, None)
+    }
+
+    /// Like `read_entries`, decrypting encrypted entries with `passphrase`. Fails if there are
+    /// encrypted entries and no passphrase, or the passphrase is wrong.
+    pub fn read_entries_with_passphrase(path: impl AsRef<Path>, passphrase: Option<&str>) -> eyre::Result<Vec<JournalEntry>> {
+        let mut opener = passphrase.map(Opener::new);
+        Self::read(path.as_ref(), opener.as_mut())
+    }
+
+    fn read(path: &Path, mut opener: Option<&mut Opener>) -> eyre::Result<Vec<JournalEntry>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries = Vec::new();
+        let mut lines = BufReader::new(file).lines().enumerate().peekable();
+        while let Some((number, line)) = lines.next() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(Line::Plain(entry)) => entries.push(entry),
+                Ok(Line::Sealed { sealed }) => {
+                    let opener = opener.as_mut().ok_or_else(|| eyre::eyre!("the journal is encrypted; set JOURNAL_PASSPHRASE"))?;
+                    let plaintext = opener.open(&sealed).map_err(|e| e.wrap_err("cannot decrypt the journal"))?;
+                    entries.push(serde_json::from_slice(&plaintext)?);
+                }
+                // Only the last line can be torn by a crash; anywhere else a match would be lost
+                Err(e) => eyre::ensure!(lines.peek().is_none(), "{} line {} is corrupt: {}", path.display(), number + 1, e),
+            }
+        }
+        Ok(entries)
+    }
+
+    fn has_sealed_entries(path: &Path) -> eyre::Result<bool> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        for line in BufReader::new(file).lines() {
+            if let Ok(Line::Sealed { .. }) = serde_json::from_str(&line?) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,69 @@
+//! Shared building blocks for the vanity transaction hash miner and its utilities.
+
+pub mod address;
+pub mod advise;
+pub mod approval;
+pub mod audit;
+pub mod brand;
+pub mod broadcast;
+pub mod calldata;
+pub mod campaign;
+pub mod chaincache;
+pub mod chains;
+pub mod checkpoint;
+pub mod clone;
+pub mod compare;
+pub mod compat;
+pub mod codesize;
+pub mod config;
+pub mod difficulty;
+pub mod e2e;
+pub mod envelope;
+pub mod exit;
+pub mod expiry;
+pub mod factory;
+pub mod fees;
+pub mod filler;
+pub mod gaslimit;
+pub mod gastrend;
+pub mod grpc;
+pub mod history;
+pub mod inclusion;
+pub mod inhibit;
+pub mod intrinsic;
+pub mod jobs;
+pub mod journal;
+pub mod limits;
+pub mod lint;
+pub mod metrics;
+pub mod nonces;
+pub mod notify;
+pub mod oracle;
+pub mod overhead;
+pub mod pattern;
+pub mod patternfile;
+pub mod payload;
+pub mod profile;
+pub mod proof;
+pub mod redeploy;
+pub mod reuse;
+pub mod sampling;
+pub mod rpc;
+pub mod sealed;
+pub mod search;
+pub mod secrets;
+pub mod selftest;
+pub mod server;
+pub mod smtp;
+pub mod standby;
+pub mod stats;
+pub mod stop;
+pub mod summary;
+pub mod telemetry;
+pub mod templates;
+pub mod throttle;
+pub mod units;
+pub mod userop;
+pub mod verifier;
+pub mod vectors;
+pub mod verify;
@@ -0,0 +1,76 @@
+use crate::config::non_empty_var;
+use crate::exit;
+use std::fmt;
+use std::time::Duration;
+
+/// Caps on what a single search may consume, so usage of a shared machine can be metered per
+/// run or per job. Both are checked after every batch, so a search may go over by up to one batch
+/// per worker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU time of the search workers, not wall-clock time.
+    pub cpu_time: Option<Duration>,
+    /// Candidates signed.
+    pub signatures: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Reads MAX_CPU_SECONDS and MAX_SIGNATURES; unlimited when neither is set.
+    pub fn from_env() -> eyre::Result<Self> {
+        Self::read_env().map_err(exit::config_error)
+    }
+
+    fn read_env() -> eyre::Result<Self> {
+        let cpu_time = non_empty_var("MAX_CPU_SECONDS")
+            .map(|seconds| match seconds.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Ok(Duration::from_secs_f64(seconds)),
+                _ => Err(eyre::eyre!("MAX_CPU_SECONDS must be a positive number of seconds, not {:?}", seconds)),
+            })
+            .transpose()?;
+        let signatures = non_empty_var("MAX_SIGNATURES")
+            .map(|count| match count.replace('_', "").parse::<u64>() {
+                Ok(count) if count > 0 => Ok(count),
+                _ => Err(eyre::eyre!("MAX_SIGNATURES must be a positive whole number, not {:?}", count)),
+            })
+            .transpose()?;
+        Ok(Self { cpu_time, signatures })
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.cpu_time.is_none() && self.signatures.is_none()
+    }
+
+    /// The tighter of each limit, e.g. a job's own limits within the server's.
+    pub fn min(self, other: Self) -> Self {
+        Self { cpu_time: tighter(self.cpu_time, other.cpu_time), signatures: tighter(self.signatures, other.signatures) }
+    }
+
+    /// The limit a search that has used `cpu_time` and made `signatures` has reached, if any.
+    pub fn exceeded(&self, cpu_time: Duration, signatures: u64) -> Option<String> {
+        if let Some(limit) = self.cpu_time.filter(|limit| cpu_time >= *limit) {
+            return Some(format!("limit of {} CPU seconds", limit.as_secs_f64()));
+        }
+        if let Some(limit) = self.signatures.filter(|limit| signatures >= *limit) {
+            return Some(format!("limit of {} signatures", limit));
+        }
+        None
+    }
+}
+
+fn tighter<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl fmt::Display for ResourceLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.cpu_time, self.signatures) {
+            (Some(cpu_time), Some(signatures)) => write!(f, "at most {} CPU seconds and {} signatures", cpu_time.as_secs_f64(), signatures),
+            (Some(cpu_time), None) => write!(f, "at most {} CPU seconds", cpu_time.as_secs_f64()),
+            (None, Some(signatures)) => write!(f, "at most {} signatures", signatures),
+            (None, None) => f.write_str("unlimited"),
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use crate::approval::ApprovalConfig;
+use crate::broadcast::BroadcastConfig;
+use crate::calldata::Parameters;
+use crate::chains;
+use crate::compat::NodeCheck;
+use crate::config::{self, non_empty_var, TxSpec};
+use crate::factory::Factory;
+use crate::fees::FeeProfile;
+use crate::limits::ResourceLimits;
+use crate::notify::Routes;
+use crate::oracle::FeeSource;
+use crate::pattern;
+use crate::patternfile;
+use crate::rpc::RpcConfig;
+use crate::search::MatchPolicy;
+use crate::secrets::is_secret_name;
+use crate::stop::StopPolicy;
+use ethers::signers::LocalWallet;
+use ethers::types::{Address, U256};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Shown in place of the value of a secret setting.
+pub const REDACTED: &str = "<redacted>";
+
+/// Settings that are not read from secret files but whose values often carry credentials all the
+/// same: RPC and relay URLs with API keys in them, webhook URLs and `<bot token>@<chat>` channels.
+const CREDENTIAL_SETTINGS: &[&str] = &[
+    "APPROVAL_URL",
+    "BRAND_CHECK_URL",
+    "BROADCAST_RPCS",
+    "BUNDLE_RELAY_URL",
+    "BUNDLER_URL",
+    "FLASHBOTS_PROTECT_URL",
+    "NOTIFY_WEBHOOK",
+];
+const CREDENTIAL_PREFIXES: &[&str] = &["NOTIFY_CHANNEL_"];
+
+/// Every setting the tools read from the environment, `.env` or a deployment template.
+pub const SETTINGS: &[&str] = &[
+    "ACCOUNT_INIT_CODE",
+    "API_TOKEN",
+    "APPROVAL_TIMEOUT",
+    "APPROVAL_TOKEN",
+    "APPROVAL_URL",
+    "APPROVERS",
+    "BASE_FEE_START",
+    "BLOCKNATIVE_API_KEY",
+    "BRAND_CHECK_URL",
+    "BROADCAST_RPCS",
+    "BROADCASTER",
+    "BUNDLE_BLOCKS",
+    "BUNDLE_RELAY_URL",
+    "BUNDLE_SIGNING_KEY",
+    "BUNDLER_URL",
+    "CALLDATA",
+    "CALLDATA_FILE",
+    "CHAIN",
+    "CHAIN_CACHE_PATH",
+    "CHAIN_ID",
+    "CHAINS",
+    "CHECKPOINT_PATH",
+    "CLONE_IMPLEMENTATION",
+    "CREATE2_SALT",
+    "DEPLOY_TEMPLATES",
+    "DEPLOYMENT_NAME",
+    "ENTRY_POINT",
+    "ETHERSCAN_API_KEY",
+    "FACTORY_ADDRESS",
+    "FACTORY_INIT_CODE_HASH",
+    "FACTORY_SALT",
+    "FACTORY_SCHEME",
+    "FEE_CURRENCY",
+    "FEE_SOURCE",
+    "FEE_STEP",
+    "FLASHBOTS_PROTECT_URL",
+    "GAS_HISTORY",
+    "GAS_LIMIT",
+    "HASH_PREFIX",
+    "HASHRATE_PROFILE",
+    "HISTORY_DB",
+    "JOURNAL_PASSPHRASE",
+    "JOURNAL_PATH",
+    "KEEP_AWAKE",
+    "LOW_MEMORY",
+    "MARKET_TOLERANCE",
+    "MATCH_POLICY",
+    "MAX_CODE_SIZE",
+    "MAX_CPU_SECONDS",
+    "MAX_FAILURE_RATE",
+    "MAX_FEE_PER_GAS",
+    "MAX_RETAINED_MATCHES",
+    "MAX_SIGNATURES",
+    "MIN_PRIORITY_FEE",
+    "NATIVE_USD_PRICE",
+    "NODE_CHECK",
+    "NONCE",
+    "NOTIFY_DESKTOP",
+    "NOTIFY_WEBHOOK",
+    "PARAMETERS",
+    "PARANOID",
+    "PATTERN_FILE",
+    "PAYMASTER_AND_DATA",
+    "PRIORITY_FEE",
+    "PRIORITY_FEE_LANES",
+    "PRIVATE_KEY",
+    "REHEARSAL_CHAIN",
+    "RPC",
+    "RPC_BASIC_AUTH",
+    "RPC_BEARER_TOKEN",
+    "RPC_CONNECT_TIMEOUT",
+    "RPC_HEADERS",
+    "RPC_JWT_SECRET",
+    "RPC_PROXY",
+    "RPC_RATE_LIMIT",
+    "RPC_REQUEST_TIMEOUT",
+    "SALT",
+    "SAMPLE_EVERY",
+    "SAMPLE_PATH",
+    "SCHEDULING",
+    "SECRETS_DIR",
+    "SIGNED_TX_PATH",
+    "SMART_ACCOUNT",
+    "SMTP_FROM",
+    "SMTP_URL",
+    "STOP_POLICY",
+    "THERMAL_WATCHDOG",
+    "THOUSANDS_SEPARATOR",
+    "TO_ADDRESS",
+    "TRANSFER_AMOUNT",
+    "VANITY_FEE",
+    "VAULT_ADDR",
+    "VAULT_K8S_MOUNT",
+    "VAULT_K8S_ROLE",
+    "VAULT_K8S_TOKEN_PATH",
+    "VAULT_NAMESPACE",
+    "VAULT_TOKEN",
+    "VERIFIER_COMMAND",
+    "VERIFIER_SOCKET",
+];
+
+/// Prefixes of families of settings, such as `NOTIFY_CHANNEL_ONCALL`, and of other tools' settings
+/// read along the way.
+const SETTING_PREFIXES: &[&str] = &["NOTIFY_CHANNEL_", "NOTIFY_ROUTE_", "NOTIFY_TEMPLATE_", "OTEL_", "RUST_LOG"];
+
+/// Settings that still work but have a better replacement, with what to use instead.
+pub const DEPRECATED: &[(&str, &str)] = &[
+    ("NOTIFY_WEBHOOK", "NOTIFY_CHANNEL_<NAME>=webhook:<url> routed with NOTIFY_ROUTE_MATCH_FOUND"),
+    ("NOTIFY_DESKTOP", "NOTIFY_CHANNEL_<NAME>=desktop routed with NOTIFY_ROUTE_MATCH_FOUND"),
+];
+
+/// Settings that do nothing unless another setting has a given value (any value for `None`).
+const IGNORED_UNLESS: &[(&str, &str, Option<&str>)] = &[
+    ("BROADCAST_RPCS", "BROADCASTER", Some("race")),
+    ("FLASHBOTS_PROTECT_URL", "BROADCASTER", Some("flashbots-protect")),
+    ("BUNDLE_RELAY_URL", "BROADCASTER", Some("bundle")),
+    ("BUNDLE_SIGNING_KEY", "BROADCASTER", Some("bundle")),
+    ("BUNDLE_BLOCKS", "BROADCASTER", Some("bundle")),
+    ("SIGNED_TX_PATH", "BROADCASTER", Some("file")),
+    ("APPROVERS", "APPROVAL_URL", None),
+    ("APPROVAL_TOKEN", "APPROVAL_URL", None),
+    ("APPROVAL_TIMEOUT", "APPROVAL_URL", None),
+    ("FACTORY_SCHEME", "FACTORY_ADDRESS", None),
+    ("FACTORY_SALT", "FACTORY_ADDRESS", None),
+    ("FACTORY_INIT_CODE_HASH", "FACTORY_ADDRESS", None),
+    ("SAMPLE_PATH", "SAMPLE_EVERY", None),
+    ("SMTP_FROM", "SMTP_URL", None),
+];
+
+/// Settings VANITY_FEE overrides, since it pins the fee.
+const PINNED_BY_VANITY_FEE: &[&str] = &["BASE_FEE_START", "PRIORITY_FEE", "FEE_STEP"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration will not run as it is.
+    Error,
+    /// It runs, but probably not as intended.
+    Warning,
+}
+
+/// Something wrong with the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, message: message.into() }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, message: message.into() }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "Error: {}", self.message),
+            Severity::Warning => write!(f, "Warning: {}", self.message),
+        }
+    }
+}
+
+/// Where the effective value of a setting came from, later ones taking precedence over `.env`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    DotEnv,
+    /// A deployment template, by name.
+    Template(String),
+    /// A mounted file or Vault, through `NAME_FILE`, `NAME_VAULT` or SECRETS_DIR.
+    SecretStore,
+    /// Set before `.env` was loaded, which takes precedence over everything else.
+    Shell,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DotEnv => write!(f, ".env"),
+            Self::Template(name) => write!(f, "template {}", name),
+            Self::SecretStore => write!(f, "secret store"),
+            Self::Shell => write!(f, "shell"),
+        }
+    }
+}
+
+/// A setting's value and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub value: String,
+    pub origin: Origin,
+}
+
+/// Whether `name` is a setting the tools read: a known one, one of them for a single chain (`_<chain_id>`),
+/// a secret given through `_FILE` or `_VAULT`, or one of a family such as `NOTIFY_CHANNEL_<NAME>`.
+/// Keys named by `jobs`, such as `DEPLOYER_KEY`, are not known until a job file names them.
+/// `name` without a per-chain `_<chain_id>` suffix.
+fn without_chain_id(name: &str) -> &str {
+    match name.rsplit_once('_') {
+        Some((base, chain_id)) if !chain_id.is_empty() && chain_id.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
+/// Whether `config check` hides the value of `name`: secrets, and settings carrying credentials
+/// such as URLs with API keys, including their per-chain variants.
+pub fn is_redacted(name: &str) -> bool {
+    let base = without_chain_id(name);
+    is_secret_name(name) || CREDENTIAL_SETTINGS.contains(&base) || CREDENTIAL_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+pub fn is_known(name: &str) -> bool {
+    let base = without_chain_id(name);
+    let secret_reference = [name.strip_suffix("_FILE"), name.strip_suffix("_VAULT")].into_iter().flatten().any(is_secret_name);
+    SETTINGS.contains(&base) || secret_reference || SETTING_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// The names `.env` file `contents` assigns, e.g. `GAS_LIMIT` for `GAS_LIMIT=21000` or `export GAS_LIMIT=21000`.
+pub fn dotenv_names(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, _)| name.trim().trim_start_matches("export ").trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// The known setting closest to a misspelt `name`, within two edits.
+pub fn suggest(name: &str) -> Option<&'static str> {
+    SETTINGS
+        .iter()
+        .map(|setting| (edit_distance(name, setting), *setting))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, setting)| setting)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            current.push((previous[j] + usize::from(a != *b)).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Checks the names of `settings` and how they combine: unknown names in `.env` or a template
+/// (the shell holds too much else to judge), deprecated settings, and settings another one makes
+/// pointless.
+pub fn lint(settings: &BTreeMap<String, Setting>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (name, setting) in settings {
+        // A credential-like name may be a key for `jobs`
+        if setting.origin != Origin::Shell && !is_known(name) && !is_secret_name(name) {
+            problems.push(Problem::warning(match suggest(name) {
+                Some(known) => format!("{} in {} is not a setting, did you mean {}?", name, setting.origin, known),
+                None => format!("{} in {} is not a setting and is ignored", name, setting.origin),
+            }));
+        }
+    }
+    for (name, instead) in DEPRECATED {
+        if let Some(setting) = settings.get(*name) {
+            problems.push(Problem::warning(format!("{} in {} is deprecated, use {}", name, setting.origin, instead)));
+        }
+    }
+    for (name, needed, value) in IGNORED_UNLESS {
+        if !settings.contains_key(*name) {
+            continue;
+        }
+        let active = settings.get(*needed).is_some_and(|setting| value.is_none_or(|value| setting.value == value));
+        if !active {
+            problems.push(Problem::warning(match value {
+                Some(value) => format!("{} is set, but only used with {}={}", name, needed, value),
+                None => format!("{} is set, but only used with {}", name, needed),
+            }));
+        }
+    }
+    if settings.contains_key("VANITY_FEE") {
+        for name in PINNED_BY_VANITY_FEE.iter().filter(|name| settings.contains_key(**name)) {
+            problems.push(Problem::warning(format!("{} is set, but VANITY_FEE pins the fee and overrides it", name)));
+        }
+    }
+    problems
+}
+
+/// Reads the configuration from the environment the way a search does and reports what would
+/// stop it, along with the resolved values (chain, pattern and fees) where they could be worked out.
+pub fn validate(parameters: &Parameters) -> (Vec<Problem>, Value) {
+    let mut problems = Vec::new();
+    let mut resolved = Map::new();
+    let mut check = |result: eyre::Result<()>| {
+        if let Err(e) = result {
+            problems.push(Problem::error(format!("{:#}", e)));
+        }
+    };
+
+    match non_empty_var("PRIVATE_KEY") {
+        // The key itself must stay out of the message
+        Some(key) => check(key.parse::<LocalWallet>().map(drop).map_err(|_| eyre::eyre!("PRIVATE_KEY is not a valid private key"))),
+        None => check(Err(eyre::eyre!("PRIVATE_KEY is not set"))),
+    }
+    match non_empty_var("RPC") {
+        Some(_) => check(RpcConfig::from_env().map(drop)),
+        None => check(Err(eyre::eyre!("RPC is not set"))),
+    }
+    let chain_id = chains::chain_id_from_env();
+    if let Ok(chain_id) = &chain_id {
+        resolved.insert("chain_id".to_string(), json!(chain_id));
+    }
+    check(chain_id.as_ref().map(drop).map_err(|e| eyre::eyre!("{:#}", e)));
+    let hash_prefix = non_empty_var("HASH_PREFIX");
+    match &hash_prefix {
+        Some(prefix) => {
+            match pattern::validate(prefix) {
+                Ok(()) => {
+                    resolved.insert("hash_prefix".to_string(), json!(pattern::to_prefix(prefix)));
+                }
+                Err(e) => check(Err(eyre::eyre!("HASH_PREFIX: {}", e))),
+            }
+        }
+        None => check(Err(eyre::eyre!("HASH_PREFIX is not set"))),
+    }
+
+    let gas_limit = match non_empty_var("GAS_LIMIT").map(|gas_limit| gas_limit.parse::<u64>()) {
+        Some(Ok(gas_limit)) => Some(gas_limit),
+        Some(Err(e)) => {
+            check(Err(eyre::eyre!("invalid GAS_LIMIT: {}", e)));
+            None
+        }
+        None => {
+            check(Err(eyre::eyre!("GAS_LIMIT is not set, the gas-limit command can find it")));
+            None
+        }
+    };
+    // TxSpec insists on these rather than reporting them
+    let to_address = non_empty_var("TO_ADDRESS");
+    let addresses_ok = match &to_address {
+        Some(address) => address.parse::<Address>().map_err(|e| eyre::eyre!("invalid TO_ADDRESS: {}", e)),
+        None if non_empty_var("TRANSFER_AMOUNT").is_some() => Err(eyre::eyre!("TRANSFER_AMOUNT is set, so TO_ADDRESS is required")),
+        None => Ok(Address::zero()),
+    };
+    match addresses_ok {
+        Ok(_) => check(TxSpec::from_env_with_gas_limit(parameters, U256::from(gas_limit.unwrap_or(0))).map(drop)),
+        Err(e) => check(Err(e)),
+    }
+    check(Factory::from_env().map(drop));
+
+    if let Ok(chain_id) = chain_id {
+        match FeeProfile::from_env(chain_id) {
+            Ok(fees) => {
+                resolved.insert(
+                    "fees".to_string(),
+                    json!({
+                        "first_max_fee_per_gas": fees.first_fee().to_string(),
+                        "priority_fee": fees.priority_fee.to_string(),
+                        "priority_fee_lanes": fees.priority_fee_lanes.iter().map(|fee| fee.to_string()).collect::<Vec<_>>(),
+                        "max_fee_per_gas": fees.max_fee_per_gas.map(|fee| fee.to_string()),
+                        "fee_step": fees.fee_step.to_string(),
+                        "market_tolerance": fees.market_tolerance,
+                    }),
+                );
+            }
+            Err(e) => check(Err(e)),
+        }
+        check(FeeSource::from_env(chain_id).map(drop));
+        check(BroadcastConfig::from_env(chain_id).map(drop));
+    }
+    if let Some(path) = non_empty_var("PATTERN_FILE") {
+        match patternfile::read(Path::new(&path)) {
+            Ok(patterns) => {
+                resolved.insert("patterns".to_string(), json!(patterns));
+            }
+            Err(e) => check(Err(e.wrap_err("invalid PATTERN_FILE"))),
+        }
+    }
+    check(non_empty_var("MATCH_POLICY").map(|policy| policy.parse::<MatchPolicy>().map(drop)).transpose().map(drop));
+    if let Some(policy) = non_empty_var("STOP_POLICY") {
+        let digits = hash_prefix.as_deref().map_or(0, |prefix| pattern::normalize(prefix).len());
+        check(
+            policy
+                .parse::<StopPolicy>()
+                .and_then(|policy| if digits > 0 { policy.validate(digits) } else { Ok(()) })
+                .map_err(|e| e.wrap_err("invalid STOP_POLICY")),
+        );
+    }
+    check(NodeCheck::from_env().map(drop));
+    check(ResourceLimits::from_env().map(drop));
+    check(ApprovalConfig::from_env().map(drop));
+    check(Routes::from_env().map(drop));
+    check(config::sample_every().map(drop));
+    (problems, Value::Object(resolved))
+}
+
+/// The effective configuration as JSON: each setting with its source, secrets redacted, the
+/// calldata parameters after `--param`, the resolved values and the problems found.
+pub fn effective(settings: &BTreeMap<String, Setting>, parameters: &Parameters, resolved: Value, problems: &[Problem]) -> Value {
+    let settings: Map<String, Value> = settings
+        .iter()
+        .filter(|(name, setting)| setting.origin != Origin::Shell || is_known(name))
+        .map(|(name, setting)| {
+            let value = if is_redacted(name) { REDACTED } else { setting.value.as_str() };
+            (name.clone(), json!({ "value": value, "source": setting.origin.to_string() }))
+        })
+        .collect();
+    let problems: Vec<Value> = problems
+        .iter()
+        .map(|problem| {
+            let severity = match problem.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            json!({ "severity": severity, "message": problem.message })
+        })
+        .collect();
+    json!({ "settings": settings, "parameters": parameters, "resolved": resolved, "problems": problems })
+}
@@ -1,216 +1,3494 @@
+use clap::{Args, Parser, Subcommand};
 use ethers::prelude::*;
 use ethers::types::{
     transaction::eip1559::Eip1559TransactionRequest,
     transaction::eip2718::TypedTransaction,
-    Address, Bytes, U256,
+    Address, U256,
 };
-use std::convert::TryFrom;
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::address::{get_contract_address, Prediction};
+use find_tx_hash_prefix::advise;
+use find_tx_hash_prefix::approval::{ApprovalConfig, ApprovalRequest, Decision};
+use find_tx_hash_prefix::audit;
+use find_tx_hash_prefix::brand;
+use find_tx_hash_prefix::broadcast::{self, BroadcastConfig, Broadcaster, PublicRpc, Submission};
+use find_tx_hash_prefix::calldata::{self, Parameters};
+use find_tx_hash_prefix::campaign;
+use find_tx_hash_prefix::chaincache::ChainCache;
+use find_tx_hash_prefix::chains;
+use find_tx_hash_prefix::checkpoint::{self, Checkpoint, TemplateFingerprint};
+use find_tx_hash_prefix::clone;
+use find_tx_hash_prefix::codesize::{self, SplitPlan};
+use find_tx_hash_prefix::compare::{self, Comparison, Route};
+use find_tx_hash_prefix::compat::{self, NodeCheck, NodeVerdict};
+use find_tx_hash_prefix::config::{self, TxSpec};
+use find_tx_hash_prefix::difficulty::{self, Difficulty};
+use find_tx_hash_prefix::e2e;
+use find_tx_hash_prefix::envelope;
+use find_tx_hash_prefix::exit::{self, Status};
+use find_tx_hash_prefix::expiry::{self, Expiry};
+use find_tx_hash_prefix::factory::Factory;
+use find_tx_hash_prefix::fees::FeeProfile;
+use find_tx_hash_prefix::filler::{self, FillerPlan};
+use find_tx_hash_prefix::gaslimit;
+use find_tx_hash_prefix::history::{self, Deployment, HistoryDb, HistoryFilter};
+use find_tx_hash_prefix::inclusion;
+use find_tx_hash_prefix::inhibit::{self, SleepInhibitor};
+use find_tx_hash_prefix::intrinsic::{self, Preflight};
+use find_tx_hash_prefix::jobs::{self, JobSpec};
+use find_tx_hash_prefix::journal::Journal;
+use find_tx_hash_prefix::limits::ResourceLimits;
+use find_tx_hash_prefix::lint;
+use find_tx_hash_prefix::metrics;
+use find_tx_hash_prefix::nonces::{self, NonceConflict};
+use find_tx_hash_prefix::notify::{self, Event};
+use find_tx_hash_prefix::overhead::{self, EffectiveCost, MarketFees, Overhead};
+use find_tx_hash_prefix::pattern;
+use find_tx_hash_prefix::patternfile::{self, LivePatterns};
+use find_tx_hash_prefix::payload::{self, MatchPayload};
+use find_tx_hash_prefix::profile;
+use find_tx_hash_prefix::proof;
+use find_tx_hash_prefix::redeploy;
+use find_tx_hash_prefix::reuse::{self, Freshness, Origin};
+use find_tx_hash_prefix::rpc::{self, RpcConfig};
+use find_tx_hash_prefix::sampling::CandidateSampler;
+use find_tx_hash_prefix::search::{self, CandidateSigner, Match, MatchPolicy, Salt, SearchParams};
+use find_tx_hash_prefix::secrets;
+use find_tx_hash_prefix::selftest;
+use find_tx_hash_prefix::server::{self, ServerConfig};
+use find_tx_hash_prefix::standby::{self, Turn};
+use find_tx_hash_prefix::stats::{find_slow_workers, SearchStats};
+use find_tx_hash_prefix::stop::StopPolicy;
+use find_tx_hash_prefix::summary::ShareSummary;
+use find_tx_hash_prefix::telemetry;
+use find_tx_hash_prefix::templates::{self, Applied, DeployTemplates};
+use find_tx_hash_prefix::throttle::{self, CpuSample, Throttle};
+use find_tx_hash_prefix::units::{self, format_eth, format_gwei};
+use find_tx_hash_prefix::userop::{self, UserOperation};
+use find_tx_hash_prefix::vectors;
+use find_tx_hash_prefix::verifier::Verifier;
+use find_tx_hash_prefix::verify::{self, VerifiedCandidate};
+use std::collections::BTreeMap;
 use std::env;
-use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::io::{self, IsTerminal, Write};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
-use tokio::sync::mpsc;
-use parking_lot::RwLock;
-use rlp::RlpStream; 
-// Constants for optimization
-const BUFFER_SIZE: usize = 1024;
-const BATCH_SIZE: usize = 1000;
-const DEFAULT_THREAD_COUNT: usize = 8;
-const THREAD_OFFSET_SPACING: u64 = 100_000_000;
+use std::time::{Duration, Instant};
+use tracing::{info_span, Instrument};
+const BASE_FEE_POLL_INTERVAL: Duration = Duration::from_secs(12);
+const IMBALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const RATE_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+// Workers doing less than this fraction of the median worker's attempts are reported as slow
+const SLOW_WORKER_RATIO: f64 = 0.5;
+// Above this chance of sweeping the whole fee range without a match, the search space is too small
+const EXHAUSTION_WARNING: f64 = 0.05;
+const PREREQUISITE_POLL_MIN: Duration = Duration::from_secs(2);
+const PREREQUISITE_POLL_MAX: Duration = Duration::from_secs(60);
+const DIFFICULTY_BENCHMARK: Duration = Duration::from_secs(3);
+// Well inside the idle timeout of HTTP keep-alive connections on common RPC providers
+const WARM_UP_INTERVAL: Duration = Duration::from_secs(20);
+// How often `jobs` prints the combined progress of its searches
+const JOBS_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+// Explorer items listed by the brand check before the rest are counted
+const BRAND_CHECK_SHOWN: usize = 5;
+// How often the confirmation prompt refreshes the base fee shown above it
+const PROMPT_BASE_FEE_INTERVAL: Duration = Duration::from_secs(4);
+// A block polled this recently is trusted for the base fee check right before broadcasting
+const LATEST_BLOCK_MAX_AGE: Duration = Duration::from_secs(2 * BASE_FEE_POLL_INTERVAL.as_secs());
+
+#[derive(Parser)]
+#[command(about = "Brute-force an EIP-1559 transaction whose hash starts with HASH_PREFIX")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    search: SearchArgs,
+    /// Value for a ${NAME} placeholder in CALLDATA, overriding PARAMETERS; can be repeated
+    #[arg(long = "param", value_name = "NAME=VALUE", global = true, value_parser = calldata::parse_parameter)]
+    params: Vec<(String, String)>,
+    /// Smoke-test the whole pipeline against a local Anvil node and exit; no .env needed
+    #[arg(long)]
+    e2e_test: bool,
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Serve Prometheus metrics for the running search on this address, e.g. 0.0.0.0:9100
+    #[arg(long, value_name = "ADDR")]
+    metrics: Option<SocketAddr>,
+    /// Idle until the base fee drops to this many gwei (or e.g. 500000wei) before searching
+    #[arg(long, value_name = "GWEI", value_parser = units::parse_gwei)]
+    wait_for_gas: Option<U256>,
+    /// Wait for this transaction (e.g. a funding transfer) to confirm before broadcasting the match
+    #[arg(long, value_name = "HASH")]
+    after_tx: Option<H256>,
+    /// Discard a checkpoint saved for a different transaction instead of refusing to start
+    #[arg(long)]
+    force_restart: bool,
+    /// Mine even if the journal already holds a fresh match for this transaction and pattern
+    #[arg(long)]
+    mine_again: bool,
+    /// Run the workers at the lowest OS priority so the machine stays responsive
+    #[arg(long)]
+    nice: bool,
+    /// With --nice, pause the workers while other processes use more than this percentage of the CPU
+    #[arg(long, value_name = "PERCENT", requires = "nice")]
+    idle_below: Option<f64>,
+    /// Remine from the current market fee if a match is not confirmed within this many minutes
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+    valid_for: Option<u64>,
+    /// Mine the nonce after the account's pending transaction(s) and send the match without asking as soon as they confirm
+    #[arg(long, conflicts_with_all = ["valid_for", "after_tx"])]
+    standby: bool,
+    /// Give up if no match is found within this many minutes (exit code 5)
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+    /// Print each match as one line of JSON in the match_found schema (see the schema command)
+    #[arg(long)]
+    json: bool,
+    /// Save each match to this file as JSON in the match_found schema, replacing what it held
+    #[arg(long, value_name = "PATH")]
+    result_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the search with the settings of a named template from DEPLOY_TEMPLATES over .env
+    Deploy(DeployArgs),
+    /// List, filter or export past vanity deployments
+    History(HistoryArgs),
+    /// Run an HTTP API for submitting search jobs, polling them and triggering broadcasts
+    Serve(ServeArgs),
+    /// Mine and deploy the same transaction on several chains, each using its RPC_<chain_id> endpoint
+    Multichain(MultichainArgs),
+    /// Show the filler transactions needed for a CREATE to land on the same address on every chain
+    PlanAddress(PlanAddressArgs),
+    /// Deploy on a testnet with a shorter prefix first, then run the real search on CHAIN_ID
+    Rehearse(RehearseArgs),
+    /// Deploy through an ERC-4337 bundler with a paymaster paying the gas, mining the userOpHash
+    UserOp(UserOpArgs),
+    /// Compare how long patterns take to mine: expected attempts, time and fee window for each
+    Difficulty(DifficultyArgs),
+    /// Suggest where to put a vanity word in the hash, with the search time and fee overhead of each option
+    Advise(AdviseArgs),
+    /// Print the found-candidate journal as JSON lines, decrypting it with JOURNAL_PASSPHRASE
+    Journal,
+    /// Send a signed transaction saved earlier, through the configured BROADCASTER
+    Broadcast(BroadcastArgs),
+    /// Find the smallest GAS_LIMIT the transaction executes with, by bisecting with eth_call
+    GasLimit(GasLimitArgs),
+    /// Print the address a CREATE, CREATE2 or CREATE3 deployment will get, without any RPC
+    PredictAddress(PredictAddressArgs),
+    /// Package a sent transaction, its receipt and a script to recompute them into a signed archive for auditors
+    ExportProof(ExportProofArgs),
+    /// Pre-mine a stock of matches for several patterns at consecutive future nonces, kept in the encrypted journal
+    Campaign(CampaignArgs),
+    /// Re-derive a random sample of candidates and check their hashes and match rate against theory
+    Audit(AuditArgs),
+    /// Compare sending the transaction now at the market fee with mining it for HASH_PREFIX first: time and cost of each
+    Compare(CompareArgs),
+    /// Run several independent searches (own key, chain and pattern each) side by side, splitting the threads
+    Jobs(JobsArgs),
+    /// Check the configuration, or print it as it takes effect
+    Config(ConfigArgs),
+    /// Re-encode, re-hash and re-sign a corpus of known transactions to catch encoder or dependency changes
+    VerifyVectors(VerifyVectorsArgs),
+    /// Print the JSON schema of found matches, as given by --json, --result-file, webhooks and the API server
+    Schema,
+}
+
+#[derive(Args)]
+struct VerifyVectorsArgs {
+    /// Also check the vectors in this JSON file, e.g. transactions recorded from a chain
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Validate the settings from the shell, .env, secrets, a template and --param, then print the effective configuration as JSON with secrets redacted
+    Check {
+        /// Apply this deployment template first, as deploy --template does
+        #[arg(long)]
+        template: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct JobsArgs {
+    /// JSON array of jobs, see the README
+    file: PathBuf,
+    /// Worker threads to split between the jobs by their shares
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+}
+
+#[derive(Args)]
+struct AuditArgs {
+    /// Candidates to sign and check
+    #[arg(long, default_value_t = 20_000, value_parser = clap::value_parser!(u64).range(1..))]
+    samples: u64,
+}
+
+#[derive(Args)]
+struct CompareArgs {
+    /// Mine a match for real (it is journaled, not sent) instead of estimating the search
+    #[arg(long)]
+    mine: bool,
+    /// Candidates per second to assume instead of this machine's profiled or benchmarked rate
+    #[arg(long, conflicts_with_all = ["benchmark", "mine"])]
+    hashrate: Option<f64>,
+    /// Benchmark even if a hashrate has been profiled for this machine
+    #[arg(long, conflicts_with = "mine")]
+    benchmark: bool,
+}
+
+#[derive(Args)]
+struct CampaignArgs {
+    /// Hash patterns to collect, e.g. 0xdead,0xbeef
+    #[arg(long, value_delimiter = ',', required = true)]
+    patterns: Vec<String>,
+    /// Matches to collect per pattern, one per nonce
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    count: u64,
+    /// First nonce to mine for, defaulting to the account's current nonce
+    #[arg(long)]
+    start_nonce: Option<U256>,
+}
+
+#[derive(Args)]
+struct DeployArgs {
+    /// Name of the template, e.g. prod-base
+    #[arg(long)]
+    template: String,
+    #[command(flatten)]
+    search: SearchArgs,
+}
+
+#[derive(Args)]
+struct ExportProofArgs {
+    /// Hash of the sent transaction
+    tx_hash: H256,
+    /// Archive to write, defaults to proof-<tx_hash>.tar
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct PredictAddressArgs {
+    #[command(subcommand)]
+    scheme: PredictScheme,
+    /// Print the predictions as JSON
+    #[arg(long, global = true)]
+    json: bool,
+}
+
+#[derive(Subcommand)]
+enum PredictScheme {
+    /// A contract deployed by an account (or CREATEd by a contract) at given nonces
+    Create {
+        /// Deploying account or contract
+        #[arg(long)]
+        sender: Address,
+        /// Nonces to predict, e.g. 0,1,2
+        #[arg(long, value_delimiter = ',', required = true)]
+        nonce: Vec<U256>,
+    },
+    /// A contract deployed with CREATE2, e.g. through the deterministic deployment proxy
+    Create2 {
+        /// Contract executing CREATE2
+        #[arg(long)]
+        deployer: Address,
+        /// Salt passed to CREATE2; can be repeated
+        #[arg(long, value_delimiter = ',', required = true)]
+        salt: Vec<H256>,
+        /// keccak256 of the init code
+        #[arg(long, required_unless_present = "init_code", conflicts_with = "init_code")]
+        init_code_hash: Option<H256>,
+        /// The init code itself, hashed for you
+        #[arg(long)]
+        init_code: Option<Bytes>,
+    },
+    /// A contract deployed through a CREATE3 factory
+    Create3 {
+        /// CREATE3 factory
+        #[arg(long)]
+        deployer: Address,
+        /// Salt the factory passes to CREATE2 (after any mixing with the caller it does); can be repeated
+        #[arg(long, value_delimiter = ',', required = true)]
+        salt: Vec<H256>,
+    },
+}
+
+#[derive(Args)]
+struct GasLimitArgs {
+    /// Percentage added to the smallest working limit
+    #[arg(long, default_value_t = gaslimit::DEFAULT_MARGIN_PERCENT)]
+    margin: u64,
+}
+
+#[derive(Args)]
+struct BroadcastArgs {
+    /// Raw signed transaction hex, or the hash of one in SIGNED_TX_PATH or the journal
+    tx: String,
+}
+
+#[derive(Args)]
+struct DifficultyArgs {
+    /// Hash patterns to compare, e.g. 0xdead 0xbeef00
+    #[arg(required = true, value_delimiter = ',')]
+    patterns: Vec<String>,
+    /// Candidates per second to assume instead of this machine's profiled or benchmarked rate
+    #[arg(long, conflicts_with = "benchmark")]
+    hashrate: Option<f64>,
+    /// Benchmark even if a hashrate has been profiled for this machine
+    #[arg(long)]
+    benchmark: bool,
+    /// Fee step in wei, gwei or eth (defaults to FEE_STEP, or 1 wei)
+    #[arg(long, value_parser = units::parse_amount)]
+    fee_step: Option<U256>,
+    /// Worker threads to benchmark and split the fee window across
+    #[arg(long, default_value_t = search::default_thread_count())]
+    threads: usize,
+}
+
+#[derive(Args)]
+struct AdviseArgs {
+    /// Word to spell in the hash, e.g. coffee; asked for if not given
+    word: Option<String>,
+    /// Candidates per second to assume instead of this machine's profiled or benchmarked rate
+    #[arg(long, conflicts_with = "benchmark")]
+    hashrate: Option<f64>,
+    /// Benchmark even if a hashrate has been profiled for this machine
+    #[arg(long)]
+    benchmark: bool,
+    /// Fee step in wei, gwei or eth (defaults to FEE_STEP, or 1 wei)
+    #[arg(long, value_parser = units::parse_amount)]
+    fee_step: Option<U256>,
+    /// Worker threads to benchmark and split the fee window across
+    #[arg(long, default_value_t = search::default_thread_count())]
+    threads: usize,
+    /// Gas limit to price the fee overhead with (defaults to GAS_LIMIT)
+    #[arg(long)]
+    gas_limit: Option<u64>,
+    /// Search time to suggest the longest prefix for, in minutes
+    #[arg(long, default_value_t = 60)]
+    within: u64,
+}
+
+#[derive(Args)]
+struct UserOpArgs {
+    /// Bundler RPC endpoint
+    #[arg(long, env = "BUNDLER_URL")]
+    bundler: String,
+    /// Smart account to send from, owned by PRIVATE_KEY
+    #[arg(long, env = "SMART_ACCOUNT")]
+    account: Address,
+    /// Factory call that creates the account if it is not deployed yet
+    #[arg(long, env = "ACCOUNT_INIT_CODE")]
+    account_init_code: Option<Bytes>,
+    /// Paymaster address and data; it must not sign over the nonce, which is what gets mined
+    #[arg(long, env = "PAYMASTER_AND_DATA")]
+    paymaster_and_data: Option<Bytes>,
+    /// EntryPoint contract (v0.6)
+    #[arg(long, env = "ENTRY_POINT", default_value = userop::ENTRY_POINT_V06)]
+    entry_point: Address,
+    /// CREATE2 salt used with the deterministic deployment proxy when deploying, zero by default
+    #[arg(long, env = "CREATE2_SALT")]
+    salt: Option<H256>,
+}
+
+#[derive(Args)]
+struct RehearseArgs {
+    /// Testnet chain id or preset to rehearse on, using its RPC_<chain_id> endpoint
+    #[arg(long, env = "REHEARSAL_CHAIN", value_parser = parse_chain)]
+    testnet: u64,
+    /// Number of leading hex digits of HASH_PREFIX to mine on the testnet
+    #[arg(long, default_value_t = 2)]
+    prefix_digits: usize,
+}
+
+#[derive(Args)]
+struct MultichainArgs {
+    /// Chain ids or presets (e.g. base, optimism) to deploy on, in order
+    #[arg(long, env = "CHAINS", value_delimiter = ',', required = true, value_parser = parse_chain)]
+    chains: Vec<u64>,
+    /// Mine every chain at the highest current nonce so the contract address is the same everywhere
+    #[arg(long)]
+    same_address: bool,
+}
+
+#[derive(Args)]
+struct PlanAddressArgs {
+    /// Chain ids or presets to plan for
+    #[arg(long, env = "CHAINS", value_delimiter = ',', required = true, value_parser = parse_chain)]
+    chains: Vec<u64>,
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Also serve the gRPC job API on this address
+    #[arg(long)]
+    grpc: Option<SocketAddr>,
+}
+
+#[derive(Args)]
+struct HistoryArgs {
+    /// Only show deployments on this chain id or preset
+    #[arg(long, value_parser = parse_chain)]
+    chain: Option<u64>,
+    /// Only show deployments sent from this address
+    #[arg(long)]
+    from: Option<Address>,
+    /// Maximum number of entries to show
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Print the entries as csv or json instead of a table
+    #[arg(long, value_parser = ["csv", "json"])]
+    export: Option<String>,
+}
 
 #[tokio::main]
-async fn main() -> eyre::Result<()> {
-    dotenv::dotenv().ok();
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(status) => status.into(),
+        Err(e) => {
+            let status = exit::classify(&e);
+            eprintln!("Error: {:?}", e);
+            eprintln!("Exit status {}: {}", status.code(), status);
+            status.into()
+        }
+    }
+}
+
+async fn run() -> eyre::Result<Status> {
+    // Deployment templates take precedence over .env but not the shell, so note what it set
+    let shell_vars: Vec<String> = env::vars_os().filter_map(|(name, _)| name.into_string().ok()).collect();
+    config::load_dotenv();
+    let cli = Cli::parse();
+    let loaded = secrets::load().await.map_err(exit::config_error)?;
+    if !loaded.is_empty() {
+        println!("Loaded {} from mounted secrets or Vault", loaded.join(", "));
+    }
+    let _telemetry = telemetry::init(env!("CARGO_PKG_NAME"))?;
+    selftest::run().map_err(|e| e.wrap_err("startup self-test failed, refusing to sign anything"))?;
+
+    if cli.e2e_test {
+        return e2e::run().await.map(|()| Status::Sent);
+    }
+    let parameters: Parameters = cli.params.into_iter().collect();
+    // Commands that never send anything exit with 0 when they succeed
+    let done = |result: eyre::Result<()>| result.map(|()| Status::Sent);
+    match cli.command {
+        Some(Command::Deploy(args)) => {
+            apply_template(&args.template, &shell_vars)?;
+            run_search(args.search, &parameters).await
+        }
+        Some(Command::History(args)) => done(show_history(args)),
+        Some(Command::Serve(args)) => done(serve(args).await),
+        Some(Command::Multichain(args)) => multichain(args, &parameters).await,
+        Some(Command::PlanAddress(args)) => done(plan_address(args).await),
+        Some(Command::PredictAddress(args)) => done(predict_address(args)),
+        Some(Command::ExportProof(args)) => done(export_proof(args).await),
+        Some(Command::Campaign(args)) => campaign(args, &parameters).await,
+        Some(Command::Audit(args)) => done(audit(args, &parameters).await),
+        Some(Command::Compare(args)) => done(compare(args, &parameters).await),
+        Some(Command::Jobs(args)) => run_jobs(args).await,
+        Some(Command::Rehearse(args)) => rehearse(args, cli.search, &parameters).await,
+        Some(Command::UserOp(args)) => user_op(args, &parameters).await,
+        Some(Command::Difficulty(args)) => done(difficulty(args)),
+        Some(Command::Advise(args)) => done(advise(args)),
+        Some(Command::Journal) => done(show_journal()),
+        Some(Command::Broadcast(args)) => broadcast_saved(args).await,
+        Some(Command::GasLimit(args)) => done(gas_limit(args, &parameters).await),
+        Some(Command::Config(ConfigArgs { action: ConfigAction::Check { template } })) => {
+            check_config(template.as_deref(), &shell_vars, &loaded, &parameters)
+        }
+        Some(Command::VerifyVectors(args)) => done(verify_vectors(args)),
+        Some(Command::Schema) => {
+            print!("{}", payload::SCHEMA);
+            Ok(Status::Sent)
+        }
+        None => run_search(cli.search, &parameters).await,
+    }
+}
+
+/// Applies deployment template `name` to the environment and lists what it set.
+fn apply_template(name: &str, shell_vars: &[String]) -> eyre::Result<()> {
+    let path = config::templates_path();
+    let templates = DeployTemplates::load(&path).map_err(exit::config_error)?;
+    let settings = templates.get(name).map_err(exit::config_error)?;
+    println!("Deployment template {} from {}:", name, path);
+    for applied in templates::apply(settings, shell_vars) {
+        match applied {
+            Applied::Set(setting) => println!("  {}={}", setting, settings[&setting]),
+            Applied::KeptFromShell(setting) => {
+                println!("  {}={} (kept from the shell instead of {})", setting, env::var(&setting).unwrap_or_default(), settings[&setting])
+            }
+        }
+    }
+    Ok(())
+}
 
+async fn run_search(args: SearchArgs, parameters: &Parameters) -> eyre::Result<Status> {
     // Load environment variables
     let private_key = env::var("PRIVATE_KEY")?;
-    let rpc_url = env::var("RPC")?;
-    let chain_id: u64 = env::var("CHAIN_ID")?.parse()?;
-    let hash_prefix = env::var("HASH_PREFIX")?.to_lowercase();
-    let calldata = env::var("CALLDATA").ok();
-    let gas_limit: U256 = env::var("GAS_LIMIT")?.parse::<u64>()?.into();
-    let to_address = env::var("TO_ADDRESS").ok();
-    let transfer_amount: Option<U256> = env::var("TRANSFER_AMOUNT").ok().map(|v| v.parse::<u64>().unwrap().into());
+    let rpc_config = RpcConfig::from_env()?;
+    let chain_id = chains::chain_id_from_env()?;
+    let hash_prefix = hash_prefix_from_env()?;
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let history_path = config::history_path();
+    let journal_path = config::journal_path();
+    let journal = Arc::new(Journal::open_with_passphrase(&journal_path, config::journal_passphrase().as_deref())?);
 
     let wallet: LocalWallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id);
-    let provider = Provider::<Http>::try_from(rpc_url.clone())?;
+    let provider = rpc::connect(&rpc_config).await?;
     let client = Arc::new(SignerMiddleware::new(provider, wallet.clone()));
 
     let from = client.default_sender().expect("no sender address found");
-    let nonce = client.get_transaction_count(from, None).await?;
+    let cache = Arc::new(ChainCache::open(config::chain_cache_path()));
+    let current_nonce = cache.nonce_or_cached(
+        chain_id,
+        from,
+        client
+            .get_transaction_count(from, None)
+            .instrument(info_span!("rpc.eth_getTransactionCount", %from))
+            .await
+            .map_err(Into::into),
+    )?;
+    // NONCE mines ahead of the account, e.g. to line up an address with another chain
+    let nonce = match (config::non_empty_var("NONCE"), args.standby) {
+        (Some(_), true) => return Err(exit::config_error(eyre::eyre!("NONCE and --standby both choose the nonce, set only one"))),
+        (Some(nonce), false) => U256::from_dec_str(&nonce)?,
+        // Standby pipelines the next deployment behind one that is still waiting to be included
+        (None, true) => {
+            let pending = client.get_transaction_count(from, Some(BlockNumber::Pending.into())).await?;
+            let nonce = standby::standby_nonce(current_nonce, pending)?;
+            println!(
+                "Standby: nonce {} is pending, mining nonce {} to send automatically once it confirms",
+                nonce - 1,
+                nonce
+            );
+            nonce
+        }
+        (None, false) => current_nonce,
+    };
+    if nonce < current_nonce {
+        eyre::bail!("NONCE {} has already been used, the account is at nonce {}", nonce, current_nonce);
+    }
+    if nonce > current_nonce && !args.standby {
+        println!(
+            "Mining at nonce {}: {} earlier transaction(s) must be sent before this one can be included",
+            nonce,
+            nonce - current_nonce
+        );
+    }
     let contract_address = get_contract_address(from, nonce);
+    let factory = Factory::from_env()?;
+    let broadcaster = BroadcastConfig::from_env(chain_id)?.connect(client.provider().clone()).await?;
+    let approval = ApprovalConfig::from_env()?;
+    let node_check = NodeCheck::from_env()?;
+    if !confirm_nonce_cache(client.provider(), &history_path, from, chain_id, args.standby).await?
+        || !confirm_calldata_change(&history_path, &tx_spec)?
+    {
+        println!("Aborted by user.");
+        return Ok(Status::UserAbort);
+    }
+
+    if let Some(threshold) = args.wait_for_gas {
+        wait_for_gas(client.provider(), threshold).await?;
+    }
 
     // Base fee and priority fee configuration
-    let base_fee_start = U256::from(18_000_000u64);
-    let priority_fee = U256::from(1_250_000u64);
+    let (fees, salt) = search_fees(chain_id, client.provider(), &tx_spec).await?;
 
     // Prepare transaction template
-    let mut eip1559_tx = Eip1559TransactionRequest::new();
-    if let Some(amount) = transfer_amount {
-        eip1559_tx.value = Some(amount);
-    } else if let Some(data) = calldata {
-        eip1559_tx.data = Some(data.parse::<Bytes>().unwrap());
-    }
-    eip1559_tx.nonce = Some(nonce);
-    eip1559_tx.gas = Some(gas_limit);
-    eip1559_tx.chain_id = Some(chain_id.into());
-
-    if transfer_amount.is_some() {
-        eip1559_tx.to = Some(to_address.expect("TO_ADDRESS is required for ETH transfer").parse::<Address>().unwrap());
-    } else {
-        eip1559_tx.to = to_address.map(|addr| addr.parse::<Address>().unwrap());
-    }
+    let eip1559_tx = tx_spec.template(chain_id, nonce);
+    warn_on_key_reuse(&history_path, chain_id, from, nonce, &eip1559_tx);
 
     println!("Starting parallel search for transaction hash with prefix: {}", hash_prefix);
+    println!(
+        "Matches are journaled to {}{}",
+        journal.path().display(),
+        if journal.is_encrypted() { " (encrypted)" } else { "" }
+    );
 
-    let thread_count = num_cpus::get().min(DEFAULT_THREAD_COUNT);
-    let (tx_result, mut rx_result) = mpsc::channel::<(Bytes, [u8; 32], U256)>(BUFFER_SIZE);
+    let thread_count = search::default_thread_count();
     let found = Arc::new(AtomicBool::new(false));
-    let tx_template = Arc::new(RwLock::new(eip1559_tx.clone()));
-
-    let tasks: Vec<_> = (0..thread_count)
-        .map(|i| {
-            let wallet_clone = wallet.clone();
-            let hash_prefix = hash_prefix.clone();
-            let tx_result = tx_result.clone();
-            let found = found.clone();
-            let tx_template = tx_template.clone();
-            let base_fee_start = base_fee_start;
-            let priority_fee = priority_fee;
-            let gas_limit = gas_limit;
-            
-            tokio::spawn(async move {
-                let base_fee_offset = U256::from(i as u64 * THREAD_OFFSET_SPACING);
-                let mut base_fee = base_fee_start + base_fee_offset;
-                let mut batch = Vec::with_capacity(BATCH_SIZE);
-
-                while !found.load(Ordering::Relaxed) {
-                    batch.clear();
-                    
-                    for _ in 0..BATCH_SIZE {
-                        let mut tx = tx_template.read().clone();
-                        tx.max_fee_per_gas = Some(base_fee + priority_fee);
-                        tx.max_priority_fee_per_gas = Some(priority_fee);
-                        batch.push(tx);
-                        base_fee = base_fee.saturating_add(U256::one());
-                    }
+    let stats = Arc::new(SearchStats::new(thread_count));
 
-                    if let Some((signed_rlp, tx_hash, total_fee_wei)) = process_batch(
-                        &batch,
-                        &wallet_clone,
-                        &hash_prefix,
-                        gas_limit,
-                        &found,
-                    ).await? {
-                        let _ = tx_result.send((signed_rlp, tx_hash, total_fee_wei)).await;
-                        break;
-                    }
+    let watchdog = config::thermal_watchdog();
+    let throttle = (args.nice || watchdog).then(|| Arc::new(Throttle::new(args.nice)));
+    if let (Some(throttle), true) = (&throttle, watchdog) {
+        println!("Thermal watchdog on: workers are parked while throughput collapses");
+        let (throttle, stats, found) = (throttle.clone(), stats.clone(), found.clone());
+        tokio::spawn(throttle::watch_throughput(throttle, stats, found, |action| println!("Thermal watchdog: {}", action)));
+    }
+    if let (Some(throttle), Some(percent)) = (&throttle, args.idle_below) {
+        eyre::ensure!((0.0..100.0).contains(&percent), "--idle-below must be between 0 and 100");
+        // Fail now rather than after the search started if the CPU counters are unavailable
+        CpuSample::read().map_err(|e| e.wrap_err("--idle-below needs /proc/stat"))?;
+        println!("Mining only while other processes use less than {}% of the CPU", percent);
+        let (throttle, found) = (throttle.clone(), found.clone());
+        tokio::spawn(async move {
+            if let Err(e) = throttle::pause_while_busy(throttle.clone(), percent / 100.0, found).await {
+                println!("Warning: stopped watching CPU usage, mining regardless: {}", e);
+                throttle.set_paused(false);
+            }
+        });
+    }
+
+    if let Some(addr) = args.metrics {
+        println!("Serving metrics on http://{}/metrics", addr);
+        let metrics_stats = stats.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr, metrics_stats).await {
+                println!("Warning: metrics endpoint stopped: {}", e);
+            }
+        });
+    }
+
+    // Follow the chain and keep the broadcast connections open while mining, so a confirmed
+    // match goes out without waiting on handshakes or a fresh block
+    let latest_block = Arc::new(rpc::LatestBlock::default());
+    {
+        let (fee_stats, latest_block, cache) = (stats.clone(), latest_block.clone(), cache.clone());
+        let provider = client.provider().clone();
+        tokio::spawn(async move {
+            rpc::watch_blocks(&provider, BASE_FEE_POLL_INTERVAL, |block| {
+                fee_stats.set_base_fee(block.base_fee_per_gas.unwrap_or_default());
+                if let Some(base_fee) = block.base_fee_per_gas {
+                    cache.record_base_fee(chain_id, base_fee);
                 }
-                Ok::<_, eyre::Report>(())
+                latest_block.update(block);
             })
+            .instrument(info_span!("rpc.new_heads"))
+            .await
+        });
+    }
+    let broadcaster: Arc<dyn Broadcaster> = Arc::from(broadcaster);
+    let warm_up = {
+        let broadcaster = broadcaster.clone();
+        tokio::spawn(async move {
+            let mut healthy = true;
+            loop {
+                match broadcaster.warm_up().await {
+                    Ok(()) => healthy = true,
+                    // Only report the first failure, the endpoint may just be flaky
+                    Err(e) if healthy => {
+                        println!("Warning: could not reach {} while preparing to broadcast: {}", broadcaster, e);
+                        healthy = false;
+                    }
+                    Err(_) => {}
+                }
+                tokio::time::sleep(WARM_UP_INTERVAL).await;
+            }
         })
-        .collect();
+    };
+
+    // Ctrl-C stops the workers so the post-mortem still gets printed
+    {
+        let found = found.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                interrupt(&found);
+            }
+        });
+    }
+    if let Some(minutes) = args.timeout {
+        let found = found.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+            if !found.swap(true, Ordering::Relaxed) {
+                println!("No match within {} minute(s), stopping search...", minutes);
+            }
+        });
+    }
+
+    {
+        let stats = stats.clone();
+        let found = found.clone();
+        tokio::spawn(async move {
+            let mut last = (0, std::time::Instant::now());
+            while !found.load(Ordering::Relaxed) {
+                tokio::time::sleep(RATE_SAMPLE_INTERVAL).await;
+                let now = (stats.total_attempts(), std::time::Instant::now());
+                stats.record_rate_sample((now.0 - last.0) as f64 / now.1.duration_since(last.1).as_secs_f64());
+                last = now;
+            }
+        });
+    }
+
+    {
+        let stats = stats.clone();
+        let found = found.clone();
+        let throttle = throttle.clone();
+        tokio::spawn(async move {
+            let mut previous = stats.attempts_snapshot();
+            loop {
+                tokio::time::sleep(IMBALANCE_CHECK_INTERVAL).await;
+                if found.load(Ordering::Relaxed) {
+                    break;
+                }
+                let current = stats.attempts_snapshot();
+                // Paused and parked workers are idle on purpose
+                let running = match &throttle {
+                    Some(throttle) if throttle.is_paused() => 0,
+                    Some(throttle) => throttle.active_workers().min(current.len()),
+                    None => current.len(),
+                };
+                let (slow, median) = find_slow_workers(&previous[..running], &current[..running], SLOW_WORKER_RATIO);
+                for i in slow {
+                    println!(
+                        "Warning: worker {} did {} attempts in the last {}s vs a median of {} (throttled or efficiency core?)",
+                        i,
+                        units::format_count(current[i] - previous[i]),
+                        IMBALANCE_CHECK_INTERVAL.as_secs(),
+                        units::format_count(median)
+                    );
+                }
+                previous = current;
+            }
+        });
+    }
 
-    for task in tasks {
-        if let Ok(result) = task.await {
-            if result.is_ok() {
-                break;
+    // Catch a bad routing config now rather than when the match is waiting
+    notify::Routes::from_env()?;
+    let stop_policy = stop_policy(&hash_prefix)?;
+    let verifier = verifier()?;
+    let limits = resource_limits()?;
+    let patterns = live_patterns(&hash_prefix)?;
+    if let Some(patterns) = &patterns {
+        let (patterns, found) = (patterns.clone(), found.clone());
+        tokio::spawn(async move {
+            let reloaded = patternfile::reload_on_hangup(patterns.clone(), found, |accepted| match accepted {
+                Ok(accepted) => println!("Re-read {}: now accepting {}", patterns.path().display(), accepted.join(", ")),
+                Err(e) => println!("Warning: keeping the current patterns: {}", e),
+            });
+            if let Err(e) = reloaded.await {
+                println!("Warning: cannot reload the patterns on SIGHUP: {}", e);
+            }
+        });
+    }
+    if config::low_memory() {
+        println!("Low-memory mode: {} candidates per batch, {} match(es) kept in memory", batch_size(), max_retained_matches()?);
+    }
+    let mut params = SearchParams {
+        patterns,
+        thread_count,
+        sampler: CandidateSampler::from_env(&format!("chain {} nonce {} prefix {}", chain_id, nonce, hash_prefix))?,
+        throttle: throttle.clone(),
+        stop_policy,
+        verifier,
+        limits,
+        ..search_params(envelope::signer_for(wallet.clone(), chain_id)?, eip1559_tx.clone(), hash_prefix.clone(), &fees, salt)?
+    };
+    preflight(&params)?;
+    check_code_size(client.provider(), from, &tx_spec, chain_id).await?;
+    let clone_implementation = check_clone_implementation(client.provider()).await?;
+    let reused = match args.mine_again {
+        true => None,
+        false => reusable_match(client.provider(), &params, from, &journal_path).await?,
+    };
+    // Progress is saved as swept fee ranges, which only describe an unsalted search on one lane
+    let checkpoint_path = config::checkpoint_path();
+    let fingerprint = TemplateFingerprint::of(&params, from);
+    let checkpoint_saver = if salt == Salt::None && params.priority_fee_lanes.is_empty() && reused.is_none() {
+        let claims = Arc::new(checkpoint::resume(&checkpoint_path, &fingerprint, args.force_restart)?);
+        let swept = claims.fee_count(fees.fee_step);
+        if !swept.is_zero() {
+            println!("Resuming from {}: {} fees already swept are skipped", checkpoint_path, units::format_count(swept));
+        }
+        params.claims = Some(claims.clone());
+        let (checkpoint_path, fingerprint) = (checkpoint_path.clone(), fingerprint.clone());
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(checkpoint::CHECKPOINT_INTERVAL).await;
+                if let Err(e) = Checkpoint::new(fingerprint.clone(), &claims).save(&checkpoint_path) {
+                    println!("Warning: failed to save the checkpoint to {}: {}", checkpoint_path, e);
+                }
             }
+        }))
+    } else {
+        None
+    };
+    let inhibitor = config::keep_awake().then(|| keep_awake(&params, &checkpoint_path, &fingerprint)).flatten();
+    let mut origin = match reused {
+        Some(_) => Origin::Journal,
+        None => Origin::Search(stats.clone()),
+    };
+    let mut found_match = match reused {
+        Some(found_match) => Some(found_match),
+        None => {
+            print_search_space(&params);
+            print_expected_time(&params);
+            let found_match = search::run(params.clone(), stats.clone(), found.clone(), journal.clone()).await?;
+            record_hashrate(&params, &stats);
+            found_match
         }
+    };
+    // Standby keeps the machine awake until the match can be sent
+    let inhibitor = inhibitor.filter(|_| args.standby);
+    if let Some(claims) = &params.claims {
+        if let Err(e) = Checkpoint::new(fingerprint.clone(), claims).save(&checkpoint_path) {
+            println!("Warning: failed to save the checkpoint to {}: {}", checkpoint_path, e);
+        }
+    }
+    if stats.limit_reached().is_some() && found_match.is_none() {
+        match &params.claims {
+            Some(_) => println!("Progress is saved to {}; run again to resume", checkpoint_path),
+            None => println!("Salted and laned searches are not checkpointed, so running again starts over"),
+        }
+    }
+
+    if let Origin::Search(_) = origin {
+        print!("{}", stats.postmortem(&hash_prefix));
+        print!("{}", stats.worker_report());
     }
 
-    if let Some((signed_rlp, tx_hash_bytes, total_fee_wei)) = rx_result.recv().await {
+    let validity = args.valid_for.map(|minutes| Duration::from_secs(minutes * 60));
+    let mut pending_answer = None;
+    let status = loop {
+        let found_at = Instant::now();
+        let Some(Match { signed_rlp, tx_hash: tx_hash_bytes, max_fee_per_gas, total_fee_wei, tx }) = found_match.take() else {
+            println!("No solution found (interrupted, timed out, or the sweep reached MAX_FEE_PER_GAS?)");
+            break no_match_status();
+        };
         let tx_hash_hex = format!("0x{}", hex::encode(tx_hash_bytes));
-        let total_fee_eth = wei_to_eth(total_fee_wei);
+        let total_fee_eth = format_eth(total_fee_wei);
 
-        println!("Match found!");
+        println!("{}", origin.announcement());
         println!("Transaction Hash: {}", tx_hash_hex);
-        println!("Contract Address: {:?}", contract_address);
-        println!("Estimated Gas Cost: {} ETH", total_fee_eth);
-
-        print!("Send this transaction? (y/n): ");
-        io::stdout().flush()?;
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        
-        if input.trim().to_lowercase() == "y" {
-            let provider = Provider::<Http>::try_from(rpc_url)?;
-            let pending_tx = provider.send_raw_transaction(signed_rlp).await?;
-            let receipt = pending_tx.await?;
-            println!("Transaction sent! Receipt: {:?}", receipt);
+        // Worked out now rather than at startup, since a CREATE factory's nonce may have moved
+        let child_address = match &factory {
+            Some(factory) => {
+                let child = factory.child_address(client.provider()).await?;
+                println!("Child Contract: {:?} (created by factory {:?} with {})", child, factory.address, factory.scheme);
+                Some(child)
+            }
+            None => {
+                println!("Contract Address: {:?}", contract_address);
+                None
+            }
+        };
+        println!("Worst-Case Gas Cost: {} {}", total_fee_eth, chains::symbol(chain_id));
+        if let Some(lane) = params.lane_of(&tx) {
+            println!(
+                "Found on priority fee lane {} of {}, tipping {} gwei",
+                lane + 1,
+                params.priority_fee_lanes.len(),
+                format_gwei(params.priority_fee_lanes[lane])
+            );
+        }
+        // The pattern file may list several, so report the one this match is for
+        let matched_prefix = params.patterns.as_ref().and_then(|patterns| patterns.matching(&tx_hash_bytes)).unwrap_or_else(|| hash_prefix.clone());
+        if params.patterns.is_some() {
+            println!("Matched Pattern: {}", matched_prefix);
+        }
+        let payload = origin.describe(MatchPayload::from_raw(&matched_prefix, &signed_rlp)?, &params);
+        if args.json {
+            println!("{}", serde_json::to_string(&payload)?);
+        }
+        if let Some(path) = &args.result_file {
+            match serde_json::to_string_pretty(&payload).map_err(eyre::Report::from).and_then(|json| Ok(std::fs::write(path, json + "\n")?)) {
+                Ok(()) => println!("Saved the match to {}", path.display()),
+                Err(e) => println!("Warning: failed to save the match to {}: {} (it is in the journal)", path.display(), e),
+            }
+        }
+        let contract = format!("{:?}", child_address.unwrap_or(contract_address));
+        let event_vars = |extra: &[(&'static str, String)]| {
+            let mut vars = vec![("tx_hash", tx_hash_hex.clone()), ("chain", chains::describe(chain_id)), ("contract", contract.clone())];
+            vars.extend_from_slice(extra);
+            vars
+        };
+        notify::notify_match(Event::MatchFound, &event_vars(&[]), Some(&payload)).await;
+
+        // Check what will actually be sent, independently of the search that produced it
+        let verified = verify_before_sending(&signed_rlp, tx_hash_bytes.into(), from, chain_id, nonce)
+            .map_err(|e| e.wrap_err("the signed transaction failed verification, refusing to send it"))?;
+        println!("Verified: signed by {:?} for chain {} at nonce {}", from, chain_id, nonce);
+        print_transaction_preview(&verified.tx, chain_id);
+        if let Some(check) = node_check {
+            if let Some(status) = check_with_node(client.provider(), check, &verified.tx, from, nonce, signed_rlp.len()).await? {
+                break status;
+            }
+        }
+        if let Some(url) = brand::url_from_env(chain_id) {
+            check_brand(&url, &matched_prefix).await;
+        }
+
+        // Compare against what the same transaction would cost without the vanity constraint
+        let market = overhead::market_fees(client.provider()).await;
+        let overhead = match &market {
+            Ok(market) => {
+                let priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+                let overhead = Overhead::new(tx_spec.gas_limit, max_fee_per_gas, priority_fee, market);
+                println!(
+                    "Market Fee: {} gwei base + {} gwei priority, recommended max fee {} gwei",
+                    format_gwei(market.base_fee),
+                    format_gwei(market.priority_fee),
+                    format_gwei(market.max_fee_per_gas)
+                );
+                let usd_price = native_usd_price(chain_id)?;
+                println!(
+                    "Vanity Premium: {} at worst, {} at the current base fee",
+                    format_premium(
+                        overhead.vanity_max_cost,
+                        overhead.market_max_cost,
+                        overhead.max_premium_percent(),
+                        chain_id,
+                        usd_price
+                    ),
+                    format_premium(
+                        overhead.vanity_expected_cost,
+                        overhead.market_expected_cost,
+                        overhead.expected_premium_percent(),
+                        chain_id,
+                        usd_price
+                    )
+                );
+                Some(overhead)
+            }
+            Err(e) => {
+                println!("Warning: could not fetch market fees for the overhead report: {}", e);
+                None
+            }
+        };
+
+        let priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+        let cost = match &market {
+            Ok(market) => {
+                let gas_used = simulate_gas(client.provider(), &verified.tx, from, tx_spec.gas_limit).await;
+                let cost = EffectiveCost::new(gas_used, tx_spec.gas_limit, max_fee_per_gas, priority_fee, market.base_fee);
+                print_cost_breakdown(&cost, chain_id);
+                Some(cost)
+            }
+            Err(_) => None,
+        };
+        match inclusion::estimate(client.provider(), max_fee_per_gas, priority_fee).await {
+            Ok(estimate) => print_inclusion_estimate(&estimate),
+            Err(e) => println!("Warning: could not fetch fee history for the inclusion estimate: {}", e),
+        }
+
+        println!("Broadcast: through {}", broadcaster);
+        if args.standby {
+            println!("Standby: sending without confirmation once nonce {} confirms", nonce - 1);
         } else {
-            println!("Aborted by user.");
+            let expected = cost.map(|cost| format!("about {} {} expected, ", format_eth(cost.expected()), chains::symbol(chain_id)));
+            let prompt = match overhead {
+                Some(overhead) => format!(
+                    "Send this transaction ({}{:+.1}% vs a plain transaction at worst)? (y/n): ",
+                    expected.unwrap_or_default(),
+                    overhead.max_premium_percent()
+                ),
+                None => "Send this transaction? (y/n): ".to_string(),
+            };
+            let live_base_fee = io::stdout().is_terminal().then(|| {
+                println!("Live: checking the base fee...");
+                tokio::spawn(show_live_base_fee(client.provider().clone(), max_fee_per_gas))
+            });
+            print!("{}", prompt);
+            io::stdout().flush()?;
+            let expiry = async {
+                match validity {
+                    Some(window) => expiry::watch(client.provider(), found_at, window, max_fee_per_gas).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let answer = read_answer_until(&mut pending_answer, Instant::now(), expiry).await?;
+            if let Some(live_base_fee) = live_base_fee {
+                live_base_fee.abort();
+            }
+            let input = match answer {
+                Ok(input) => input,
+                Err(expiry) => {
+                    println!();
+                    println!("Match expired: {}", expiry);
+                    notify::notify(Event::MatchExpired, &event_vars(&[("reason", expiry.to_string())])).await;
+                    let market = overhead::market_fees(client.provider()).await?;
+                    (found_match, origin) = remine(&params, &market, chain_id, journal.clone()).await?;
+                    continue;
+                }
+            };
+            if input.trim().to_lowercase() != "y" {
+                println!("Aborted by user.");
+                break Status::UserAbort;
+            }
+        }
+        if let Some(approval) = &approval {
+            let created = child_address.or(tx.to.is_none().then_some(contract_address));
+            let request = ApprovalRequest::new(chain_id, signed_rlp.clone(), &verified.tx, from, created);
+            if let Some(status) = await_approval(approval, &request).await? {
+                break status;
+            }
+        }
+        if let Some(prerequisite) = args.after_tx {
+            wait_for_prerequisite(client.provider(), prerequisite).await?;
+        }
+        if args.standby {
+            tokio::select! {
+                ready = wait_for_turn(client.provider(), from, nonce) => if !ready {
+                    println!("Not sent: another transaction confirmed at nonce {} while this one stood by", nonce);
+                    break Status::NotSent;
+                },
+                _ = tokio::signal::ctrl_c() => {
+                    println!("Interrupted while standing by, nothing was sent");
+                    break Status::UserAbort;
+                }
+            }
+        }
+        let confirmed_at = Instant::now();
+        // Mined ahead with NONCE, so the nonces in between have to be used up first
+        if nonce > current_nonce {
+            let account_nonce = cache.nonce_or_cached(chain_id, from, client.get_transaction_count(from, None).await.map_err(Into::into))?;
+            if account_nonce < nonce
+                && !offer_fillers(client.provider(), &wallet, chain_id, account_nonce, nonce, &history_path).await?
+            {
+                println!("Not sent: the account is at nonce {}, the transaction needs nonce {}", account_nonce, nonce);
+                break Status::NotSent;
+            }
         }
-    } else {
-        println!("No solution found (interrupted?)");
-    }
 
-    Ok(())
-}
+        // The base fee may have moved past the match while it was being mined or reviewed
+        let base_fee = match latest_block.fresh(LATEST_BLOCK_MAX_AGE).and_then(|block| block.base_fee_per_gas) {
+            Some(base_fee) => base_fee,
+            None => cache.base_fee_or_cached(chain_id, overhead::market_fees(client.provider()).await.map(|market| market.base_fee))?,
+        };
+        if base_fee > max_fee_per_gas {
+            println!(
+                "The base fee is now {} gwei, above this transaction's max fee of {} gwei, so it cannot be included until the base fee falls back.",
+                format_gwei(base_fee),
+                format_gwei(max_fee_per_gas)
+            );
+            if args.standby {
+                let market = overhead::market_fees(client.provider()).await?;
+                (found_match, origin) = remine(&params, &market, chain_id, journal.clone()).await?;
+                continue;
+            }
+            print!("Remine from the current market fee (r), send anyway (s) or abort (n)? ");
+            io::stdout().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            match input.trim().to_lowercase().as_str() {
+                "r" => {
+                    let market = overhead::market_fees(client.provider()).await?;
+                    (found_match, origin) = remine(&params, &market, chain_id, journal.clone()).await?;
+                    continue;
+                }
+                "s" => {}
+                _ => {
+                    println!("Aborted by user.");
+                    break Status::UserAbort;
+                }
+            }
+        }
 
-async fn process_batch(
-    batch: &[Eip1559TransactionRequest],
-    wallet: &LocalWallet,
-    hash_prefix: &str,
-    gas_limit: U256,
-    found: &AtomicBool,
-) -> eyre::Result<Option<(Bytes, [u8; 32], U256)>> {
-    for tx in batch {
-        if found.load(Ordering::Relaxed) {
-            return Ok(None);
+        let broadcast_span = info_span!("broadcast", tx_hash = %tx_hash_hex);
+        let submission = broadcaster.submit(&signed_rlp).instrument(broadcast_span.clone()).await;
+        let receipt = match submission {
+            Ok(Submission::Saved { path, .. }) => {
+                println!("Saved to {} without sending it; send it later with `broadcast {}`", path.display(), tx_hash_hex);
+                break Status::NotSent;
+            }
+            Ok(Submission::Sent(tx_hash)) => {
+                println!("Sent {} ms after confirmation", confirmed_at.elapsed().as_millis());
+                nonces::record_sent(&history_path, from, chain_id, nonce);
+                notify::notify(Event::BroadcastSent, &event_vars(&[])).await;
+                broadcaster
+                    .wait_for_receipt(client.provider(), tx_hash)
+                    .instrument(info_span!(parent: &broadcast_span, "inclusion"))
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        notify_outcome(&receipt, event_vars).await;
+        let receipt = receipt.map_err(|e| exit::with_status(e, Status::BroadcastFailed))?;
+        println!("Transaction sent! Receipt: {:?}", receipt);
+
+        if let Some(receipt) = &receipt {
+            print_explorer_links(chain_id, receipt.transaction_hash, receipt.contract_address.or(child_address));
+            print_share_summary(chain_id, &hash_prefix, receipt, child_address);
+            if let Some(child) = child_address.filter(|_| receipt.status == Some(1.into())) {
+                if client.get_code(child, None).await?.is_empty() {
+                    println!("Warning: no code at the expected child contract {:?}; check the factory settings", child);
+                }
+            }
+            if let (Some(implementation), Some(clone), Some(1)) =
+                (clone_implementation, receipt.contract_address, receipt.status.map(|status| status.as_u64()))
+            {
+                match clone::implementation_of(&client.get_code(clone, None).await?) {
+                    Some(delegate) if delegate == implementation => println!("Verified: {:?} is a minimal proxy for {:?}", clone, implementation),
+                    _ => println!("Warning: the code at {:?} is not a minimal proxy for {:?}", clone, implementation),
+                }
+            }
+            record_deployment(&history_path, chain_id, from, nonce, &tx, receipt);
+        }
+        // The nonce is used now, so the swept fees will never be needed again
+        if let Some(saver) = &checkpoint_saver {
+            saver.abort();
+            if let Err(e) = std::fs::remove_file(&checkpoint_path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    println!("Warning: failed to remove the checkpoint {}: {}", checkpoint_path, e);
+                }
+            }
         }
+        break receipt_status(receipt.as_ref());
+    };
+    warm_up.abort();
+    drop(inhibitor);
+
+    Ok(status)
+}
+
+/// The machine kept awake for KEEP_AWAKE, until dropped.
+struct KeepAwake {
+    _inhibitor: Arc<parking_lot::Mutex<SleepInhibitor>>,
+    watcher: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for KeepAwake {
+    fn drop(&mut self) {
+        self.watcher.abort();
+    }
+}
 
-        if let Ok((signed_rlp, tx_hash)) = encode_and_sign_eip1559(wallet, tx).await {
-            let tx_hash_hex = format!("0x{}", hex::encode(tx_hash));
-            if tx_hash_hex.starts_with(hash_prefix) {
-                if !found.swap(true, Ordering::Relaxed) {
-                    let total_fee_wei = gas_limit * tx.max_fee_per_gas.unwrap_or_default();
-                    return Ok(Some((signed_rlp, tx_hash, total_fee_wei)));
+/// Stops the machine sleeping for KEEP_AWAKE until the returned guard is dropped, watching that it
+/// stays that way. If it cannot, or stops being able to, progress is checkpointed right away so
+/// a search cut short by sleep resumes where it left off.
+fn keep_awake(params: &SearchParams, checkpoint_path: &str, fingerprint: &TemplateFingerprint) -> Option<KeepAwake> {
+    let save_checkpoint = {
+        let (claims, checkpoint_path, fingerprint) = (params.claims.clone(), checkpoint_path.to_string(), fingerprint.clone());
+        move || match &claims {
+            Some(claims) => match Checkpoint::new(fingerprint.clone(), claims).save(&checkpoint_path) {
+                Ok(()) => println!("Progress saved to {}; after a sleep, run again to resume", checkpoint_path),
+                Err(e) => println!("Warning: failed to save the checkpoint to {}: {}", checkpoint_path, e),
+            },
+            None => println!("Salted and laned searches are not checkpointed, so one cut short by sleep starts over"),
+        }
+    };
+    let inhibitor = match SleepInhibitor::acquire("mining a vanity transaction hash") {
+        Ok(inhibitor) => Arc::new(parking_lot::Mutex::new(inhibitor)),
+        Err(e) => {
+            println!("Warning: could not keep the machine awake ({}); it may sleep during the search", e);
+            save_checkpoint();
+            return None;
+        }
+    };
+    println!("Keeping the machine awake while the search runs");
+    // Only a weak reference, so the inhibition ends where the guard is dropped
+    let watched = Arc::downgrade(&inhibitor);
+    let watcher = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(inhibit::INHIBIT_CHECK_INTERVAL).await;
+            match watched.upgrade().map(|inhibitor| inhibitor.lock().is_held()) {
+                Some(true) => {}
+                Some(false) => {
+                    println!("Warning: the machine is no longer kept awake; it may sleep during the search");
+                    save_checkpoint();
+                    return;
                 }
-                break;
+                None => return,
+            }
+        }
+    });
+    Some(KeepAwake { _inhibitor: inhibitor, watcher })
+}
+
+/// [`verify::verify_for_broadcast`], cross-checked with a second keccak implementation when
+/// PARANOID is on.
+fn verify_before_sending(raw_rlp: &[u8], tx_hash: H256, from: Address, chain_id: u64, nonce: U256) -> eyre::Result<VerifiedCandidate> {
+    let verified = verify::verify_for_broadcast(raw_rlp, tx_hash, from, chain_id, nonce)?;
+    if config::paranoid() {
+        verify::cross_check(raw_rlp, &verified).map_err(|e| e.wrap_err("paranoid cross-check failed"))?;
+        println!("Paranoid check: hash and signer confirmed with an independent keccak implementation");
+    }
+    Ok(verified)
+}
+
+/// Warns when named tokens, contracts or labels on the explorer at `url` already start with
+/// `prefix`, so the match could be taken for theirs. Advisory only: the confirmation decides.
+async fn check_brand(url: &str, prefix: &str) {
+    match brand::search(url, prefix).await {
+        Ok(found) if found.is_empty() => println!("Brand check: nothing named on {} starts with {}", url, pattern::to_prefix(prefix)),
+        Ok(found) => {
+            let strength = if found.iter().any(|association| association.prominent) { "strongly " } else { "" };
+            println!("Warning: {} is {}associated with other projects on {}:", pattern::to_prefix(prefix), strength, url);
+            for association in found.iter().take(BRAND_CHECK_SHOWN) {
+                println!("  {}", association);
+            }
+            if found.len() > BRAND_CHECK_SHOWN {
+                println!("  and {} more", found.len() - BRAND_CHECK_SHOWN);
             }
         }
+        Err(e) => println!("Warning: brand check against {} failed: {}", url, e),
+    }
+}
+
+/// Asks the node whether it would accept the signed `tx`, as NODE_CHECK says, while mining
+/// another match is still cheap. Returns the status to end with if it would not.
+async fn check_with_node(
+    provider: &rpc::RpcProvider,
+    check: NodeCheck,
+    tx: &TypedTransaction,
+    from: Address,
+    nonce: U256,
+    signed_size: usize,
+) -> eyre::Result<Option<Status>> {
+    // The node validates against its latest state, where any other nonce is refused anyway
+    let account_nonce = provider.get_transaction_count(from, None).await?;
+    if account_nonce != nonce {
+        println!("Node check: skipped, the account is at nonce {} rather than {}", account_nonce, nonce);
+        return Ok(None);
+    }
+    let verdict = compat::check(provider, check, tx, from, signed_size).await;
+    println!("Node check: {}", verdict);
+    if let NodeVerdict::Rejected { .. } = verdict {
+        println!("Not sent: the node would refuse this transaction. It is kept in the journal; adjust the settings (e.g. PRIORITY_FEE or GAS_LIMIT) and mine again");
+        return Ok(Some(Status::NotSent));
     }
     Ok(None)
 }
 
-async fn encode_and_sign_eip1559(
-    wallet: &LocalWallet,
-    eip1559_tx: &Eip1559TransactionRequest,
-) -> eyre::Result<(Bytes, [u8; 32])> {
-    // Convert to TypedTransaction
-    let typed_tx = TypedTransaction::Eip1559(eip1559_tx.clone());
-    
-    // Sign the transaction
-    let signature = wallet.sign_transaction(&typed_tx).await?;
-    
-    // Get the signed transaction bytes and hash
-    let signed_tx = typed_tx.rlp_signed(&signature);
-    let tx_hash: [u8; 32] = typed_tx.hash(&signature).into();
-
-    Ok((signed_tx, tx_hash))
-}
-
-fn get_contract_address(sender: Address, nonce: U256) -> Address {
-    use tiny_keccak::{Hasher, Keccak};
-    let mut stream = RlpStream::new_list(2);
-    stream.append(&sender);
-    stream.append(&nonce);
-    let out = stream.out();
-
-    let mut hasher = Keccak::v256();
-    hasher.update(&out);
-    let mut hash = [0u8; 32];
-    hasher.finalize(&mut hash);
-
-    Address::from_slice(&hash[12..])
-}
-
-fn wei_to_eth(value: U256) -> f64 {
-    const WEI_IN_ETH: f64 = 1e18;
-    let wei_str = value.to_string();
-    let wei_f64 = wei_str.parse::<f64>().unwrap_or(f64::MAX);
-    wei_f64 / WEI_IN_ETH
+/// Hands a confirmed match to the approval service and waits for a second person to approve it.
+/// `Some` with the status to end the run with unless it was approved.
+async fn await_approval(approval: &ApprovalConfig, request: &ApprovalRequest) -> eyre::Result<Option<Status>> {
+    println!(
+        "Waiting up to {} minute(s) for one of {} approver(s) to sign {:?} at {}...",
+        approval.timeout.as_secs() / 60,
+        approval.approvers.len(),
+        request.message,
+        approval.url
+    );
+    match approval.request(request).await? {
+        Some(Decision::Approved(approver)) => {
+            println!("Approved by {:?}", approver);
+            Ok(None)
+        }
+        Some(Decision::Rejected(reason)) => {
+            println!("Rejected by the approver: {}", reason);
+            Ok(Some(Status::UserAbort))
+        }
+        Some(Decision::Pending) | None => {
+            println!("Not sent: no approval within {} minute(s)", approval.timeout.as_secs() / 60);
+            Ok(Some(Status::NotSent))
+        }
+    }
+}
+
+/// Sent only for a receipt showing success; a revert or a dropped transaction is a failed broadcast.
+fn receipt_status(receipt: Option<&TransactionReceipt>) -> Status {
+    match receipt {
+        Some(receipt) if receipt.status == Some(1.into()) => Status::Sent,
+        _ => Status::BroadcastFailed,
+    }
+}
+
+/// Sends broadcast_confirmed or broadcast_failed for the receipt of a sent transaction.
+async fn notify_outcome<E: std::fmt::Display>(
+    receipt: &Result<Option<TransactionReceipt>, E>,
+    event_vars: impl Fn(&[(&'static str, String)]) -> Vec<(&'static str, String)>,
+) {
+    let (event, extra) = match receipt {
+        Ok(Some(receipt)) if receipt.status == Some(0.into()) => {
+            (Event::BroadcastFailed, vec![("error", "reverted".to_string())])
+        }
+        Ok(Some(receipt)) => {
+            let block = receipt.block_number.map_or_else(|| "-".to_string(), |block| block.to_string());
+            let mut extra = vec![("block", block)];
+            if let Some(address) = receipt.contract_address {
+                extra.push(("contract", format!("{:?}", address)));
+            }
+            (Event::BroadcastConfirmed, extra)
+        }
+        Ok(None) => (Event::BroadcastFailed, vec![("error", "dropped".to_string())]),
+        Err(e) => (Event::BroadcastFailed, vec![("error", e.to_string())]),
+    };
+    notify::notify(event, &event_vars(&extra)).await;
+}
+
+/// Gas the transaction is expected to use, from `eth_estimateGas` without fees so a max fee below
+/// the current base fee does not fail the simulation. Falls back to the gas limit.
+async fn simulate_gas<M: Middleware>(provider: &M, tx: &TypedTransaction, from: Address, gas_limit: U256) -> U256 {
+    let mut call = Eip1559TransactionRequest::new().from(from).value(tx.value().copied().unwrap_or_default());
+    call.to = tx.to().cloned();
+    call.data = tx.data().cloned();
+    match provider.estimate_gas(&call.into(), None).await {
+        Ok(gas_used) => gas_used,
+        Err(e) => {
+            println!("Warning: could not simulate the transaction ({}), assuming it uses its whole gas limit", e);
+            gas_limit
+        }
+    }
+}
+
+/// Expected versus worst-case cost, and why the two differ.
+fn print_cost_breakdown(cost: &EffectiveCost, chain_id: u64) {
+    let symbol = chains::symbol(chain_id);
+    println!("Cost:");
+    println!("  Gas:        {} used (simulated) of a {} limit", cost.gas_used, cost.gas_limit);
+    println!(
+        "  Per gas:    {} gwei base + {} gwei tip = {} gwei (max fee {} gwei)",
+        format_gwei(cost.base_fee),
+        format_gwei(cost.effective_tip),
+        format_gwei(cost.effective_gas_price()),
+        format_gwei(cost.max_fee_per_gas)
+    );
+    println!("  Expected:   {} {} (gas used × (base fee + tip))", format_eth(cost.expected()), symbol);
+    println!("  Worst case: {} {} (gas limit × max fee)", format_eth(cost.worst_case()), symbol);
+    println!(
+        "  The difference is never charged: {} {} for gas left unused, {} {} because only the base fee and tip are paid per gas, not the max fee",
+        format_eth(cost.unused_gas_saving()),
+        symbol,
+        format_eth(cost.fee_saving()),
+        symbol
+    );
+}
+
+/// Every field of the transaction as decoded from the signed bytes, so a wrong value can be
+/// spotted before it is sent.
+fn print_transaction_preview(tx: &TypedTransaction, chain_id: u64) {
+    let symbol = chains::symbol(chain_id);
+    let data = tx.data().cloned().unwrap_or_default();
+    let kind = match tx {
+        TypedTransaction::Legacy(_) => "legacy (type 0)",
+        TypedTransaction::Eip2930(_) => "EIP-2930 (type 1)",
+        TypedTransaction::Eip1559(_) => "EIP-1559 (type 2)",
+    };
+    let (max_fee, tip) = match tx {
+        TypedTransaction::Eip1559(tx) => (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+        _ => (tx.gas_price(), None),
+    };
+    let gwei = |fee: Option<U256>| fee.map_or_else(|| "-".to_string(), |fee| format!("{} gwei", format_gwei(fee)));
+
+    println!("Transaction:");
+    println!("  Type:      {}", kind);
+    println!("  Chain:     {}", tx.chain_id().map_or_else(|| "-".to_string(), |id| chains::describe(id.as_u64())));
+    println!("  Nonce:     {}", tx.nonce().map_or_else(|| "-".to_string(), ToString::to_string));
+    println!("  Gas limit: {}", tx.gas().map_or_else(|| "-".to_string(), ToString::to_string));
+    println!("  Max fee:   {}", gwei(max_fee));
+    println!("  Tip:       {}", gwei(tip));
+    match tx.to() {
+        Some(to) => println!("  To:        {:?}", to),
+        None => println!("  To:        - (contract creation)"),
+    }
+    println!("  Value:     {} {}", format_eth(tx.value().copied().unwrap_or_default()), symbol);
+    println!("  Calldata:  {} bytes, keccak256 {:?}", data.len(), H256::from(keccak256(&data)));
+}
+
+fn print_inclusion_estimate(estimate: &inclusion::InclusionEstimate) {
+    let tip = match estimate.tip_percentile {
+        Some(percentile) => format!("tip matches the {}th percentile of recent tips", percentile),
+        None => "tip is below the 10th percentile of recent tips".to_string(),
+    };
+    let headroom = match estimate.headroom_blocks {
+        Some(blocks) => format!("max fee covers {} block(s) of base fee rises", blocks),
+        None => "no base fee to outgrow".to_string(),
+    };
+    println!(
+        "Inclusion Estimate: {:.0}% in the next block, {:.0}% within 3 blocks (~{:.1} blocks expected); {}, {}",
+        estimate.per_block * 100.0,
+        estimate.within(3) * 100.0,
+        estimate.expected_blocks(),
+        tip,
+        headroom
+    );
+    if estimate.per_block < 0.5 {
+        println!("Warning: inclusion may be slow at this fee; consider a higher PRIORITY_FEE or MIN_PRIORITY_FEE and mining again");
+    }
+}
+
+/// Mines and sends a user operation instead of a transaction, for deployers whose EOA holds no
+/// gas on the chain: the smart account calls the deterministic deployment proxy (or TO_ADDRESS),
+/// the paymaster pays, and the nonce key is varied until the userOpHash matches HASH_PREFIX.
+async fn user_op(args: UserOpArgs, parameters: &Parameters) -> eyre::Result<Status> {
+    let owner: LocalWallet = env::var("PRIVATE_KEY")?.parse()?;
+    let chain_id = chains::chain_id_from_env()?;
+    let hash_prefix = hash_prefix_from_env()?;
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let bundler = rpc::connect(&RpcConfig::for_url(&args.bundler)?).await?;
+    for (name, remote) in [("RPC_URL", provider.get_chainid().await?), ("BUNDLER_URL", bundler.get_chainid().await?)] {
+        if remote != chain_id.into() {
+            eyre::bail!("{} points at chain {}, not {}", name, remote, chain_id);
+        }
+    }
+
+    let data = tx_spec.calldata.clone().unwrap_or_default();
+    let value = tx_spec.value.unwrap_or_default();
+    let (call_data, deployed_address) = match tx_spec.to {
+        Some(to) => (userop::execute_call(to, value, data), None),
+        None => {
+            let deployer: Address = userop::CREATE2_DEPLOYER.parse()?;
+            let salt = args.salt.unwrap_or_default();
+            let mut deploy = salt.as_bytes().to_vec();
+            deploy.extend_from_slice(&data);
+            let address = ethers::utils::get_create2_address(deployer, salt, data.clone());
+            (userop::execute_call(deployer, value, deploy.into()), Some(address))
+        }
+    };
+    let account_deployed = !provider.get_code(args.account, None).await?.is_empty();
+    let init_code = match (account_deployed, args.account_init_code) {
+        (true, _) => Bytes::default(),
+        (false, Some(init_code)) => init_code,
+        (false, None) => eyre::bail!("smart account {:?} is not deployed; set ACCOUNT_INIT_CODE to create it", args.account),
+    };
+    let paymaster_and_data = args.paymaster_and_data.unwrap_or_default();
+    if paymaster_and_data.is_empty() {
+        println!("Warning: no PAYMASTER_AND_DATA, the account pays for gas from its EntryPoint deposit");
+    }
+
+    let market = overhead::market_fees(&provider).await?;
+    let mut op = UserOperation {
+        sender: args.account,
+        nonce: U256::zero(),
+        init_code,
+        call_data,
+        call_gas_limit: U256::zero(),
+        verification_gas_limit: U256::zero(),
+        pre_verification_gas: U256::zero(),
+        max_fee_per_gas: market.max_fee_per_gas,
+        max_priority_fee_per_gas: market.priority_fee,
+        paymaster_and_data,
+        signature: Bytes::default(),
+    };
+    let gas = userop::estimate_gas(&bundler, &op, args.entry_point).await?;
+    op.call_gas_limit = gas.call_gas_limit;
+    op.verification_gas_limit = gas.verification_gas_limit;
+    op.pre_verification_gas = gas.pre_verification_gas;
+
+    println!(
+        "Mining a userOpHash starting with {} for {:?} on chain {}...",
+        hash_prefix,
+        args.account,
+        chains::describe(chain_id)
+    );
+    let stats = Arc::new(SearchStats::new(search::default_thread_count()));
+    let found = Arc::new(AtomicBool::new(false));
+    let mined = {
+        let mining = userop::mine(&op, args.entry_point, chain_id, &hash_prefix, stats.clone(), found.clone());
+        tokio::pin!(mining);
+        tokio::select! {
+            result = &mut mining => result?,
+            _ = tokio::signal::ctrl_c() => {
+                interrupt(&found);
+                mining.await?
+            }
+        }
+    };
+    print!("{}", stats.postmortem(&hash_prefix));
+    let Some((nonce, hash)) = mined else {
+        println!("No solution found");
+        return Ok(no_match_status());
+    };
+    op.nonce = nonce;
+
+    // The nonce key must be unused, and the hash must come out the same from the final operation
+    let expected_nonce = userop::entry_point_nonce(&provider, args.entry_point, args.account, nonce).await?;
+    if expected_nonce != nonce {
+        eyre::bail!("the EntryPoint expects nonce {} for this key, not {}; run again for a fresh key", expected_nonce, nonce);
+    }
+    if op.hash(args.entry_point, chain_id) != hash {
+        eyre::bail!("the user operation no longer hashes to the mined {:?}, refusing to send it", hash);
+    }
+    op.signature = userop::sign(&owner, hash).await?;
+
+    println!("Match found!");
+    let contract = deployed_address.map_or_else(|| "-".to_string(), |address| format!("{:?}", address));
+    notify::notify(
+        Event::MatchFound,
+        &[("tx_hash", format!("{:?}", hash)), ("chain", chains::describe(chain_id)), ("contract", contract)],
+    )
+    .await;
+    println!("UserOp Hash:      {:?}", hash);
+    println!("Smart Account:    {:?}", op.sender);
+    println!("Nonce:            {:#x}", op.nonce);
+    println!(
+        "Paymaster:        {}",
+        match op.paymaster_and_data.get(..20) {
+            Some(paymaster) => format!("{:?}", Address::from_slice(paymaster)),
+            None => "none".to_string(),
+        }
+    );
+    if let Some(address) = deployed_address {
+        println!("Contract Address: {:?}", address);
+    }
+    let gas_limit = op.call_gas_limit + op.verification_gas_limit + op.pre_verification_gas;
+    println!(
+        "Gas:              {} limit, at most {} {}",
+        gas_limit,
+        format_eth(gas_limit * op.max_fee_per_gas),
+        chains::symbol(chain_id)
+    );
+    print!("Send this user operation to the bundler? (y/n): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        println!("Aborted by user.");
+        return Ok(Status::UserAbort);
+    }
+
+    let sent = userop::send(&bundler, &op, args.entry_point).await.map_err(|e| exit::with_status(e, Status::BroadcastFailed))?;
+    if sent != hash {
+        eyre::bail!("bundler reports hash {:?} for mined hash {:?}", sent, hash);
+    }
+    println!("User operation sent, waiting for a bundle to include it...");
+    let receipt = userop::wait_for_receipt(&bundler, hash).await.map_err(|e| exit::with_status(e, Status::BroadcastFailed))?;
+    println!(
+        "Included in {:?}: {} (paid {} {})",
+        receipt.receipt.transaction_hash,
+        if receipt.success { "succeeded" } else { "reverted" },
+        format_eth(receipt.actual_gas_cost),
+        chains::symbol(chain_id)
+    );
+    print_explorer_links(chain_id, receipt.receipt.transaction_hash, deployed_address);
+    if let Some(address) = deployed_address.filter(|_| receipt.success) {
+        if provider.get_code(address, None).await?.is_empty() {
+            println!("Warning: no code at {:?}; the init code may have reverted inside the deployment proxy", address);
+        }
+    }
+    Ok(if receipt.success { Status::Sent } else { Status::BroadcastFailed })
+}
+
+/// `--fee-step`, or FEE_STEP, or 1 wei.
+fn fee_step_or_env(fee_step: Option<U256>) -> eyre::Result<U256> {
+    match fee_step {
+        Some(step) => Ok(step),
+        None => Ok(config::non_empty_var("FEE_STEP").map(|step| units::parse_amount(&step)).transpose()?.unwrap_or_else(U256::one)),
+    }
+}
+
+/// `--hashrate`, or the one profiled on this machine for `threads`, or a short benchmark.
+fn estimation_hashrate(hashrate: Option<f64>, benchmark: bool, threads: usize) -> eyre::Result<f64> {
+    Ok(match hashrate {
+        Some(hashrate) if hashrate > 0.0 => hashrate,
+        Some(_) => eyre::bail!("--hashrate must be positive"),
+        None => match profile::measured_hashrate(&config::profile_path(), threads).filter(|_| !benchmark) {
+            Some(hashrate) => {
+                println!("Using the hashrate measured by earlier searches on this machine (--benchmark to measure now)");
+                hashrate
+            }
+            None => {
+                println!("Benchmarking {} thread(s) for {}s...", threads, DIFFICULTY_BENCHMARK.as_secs());
+                difficulty::benchmark(DIFFICULTY_BENCHMARK, threads)
+            }
+        },
+    })
+}
+
+fn difficulty(args: DifficultyArgs) -> eyre::Result<()> {
+    // Reject bad patterns before spending time on the benchmark
+    for pattern in &args.patterns {
+        pattern::validate(pattern)?;
+    }
+    let fee_step = fee_step_or_env(args.fee_step)?;
+    let hashrate = estimation_hashrate(args.hashrate, args.benchmark, args.threads)?;
+    println!("At {} on {} thread(s), fee step {}:", units::format_hashrate(hashrate), args.threads, units::format_wei(fee_step));
+    println!(
+        "  {:<20} {:>16} {:>14} {:>14} {:>22}",
+        "Pattern", "Attempts", "Expected", "95% within", "Fee window per worker"
+    );
+    let mut overlapping = false;
+    for pattern in &args.patterns {
+        let estimate = Difficulty::estimate(pattern, hashrate, fee_step, args.threads)?;
+        overlapping |= estimate.overlaps_workers;
+        println!(
+            "  {:<20} {:>16} {:>14} {:>14} {:>16} gwei{}",
+            estimate.prefix,
+            units::format_estimate(estimate.expected_attempts),
+            units::format_duration(estimate.expected_seconds),
+            units::format_duration(estimate.seconds_95),
+            format!("{:.4}", estimate.fee_window_per_worker / 1e9),
+            if estimate.overlaps_workers { " *" } else { "" }
+        );
+    }
+    if overlapping {
+        println!(
+            "* the workers' fee ranges ({} gwei apart without MAX_FEE_PER_GAS) overlap before the expected attempts, so the sweep climbs past this",
+            format_gwei(search::THREAD_OFFSET_SPACING.into())
+        );
+    }
+    Ok(())
+}
+
+fn advise(args: AdviseArgs) -> eyre::Result<()> {
+    let word = match args.word {
+        Some(word) => word,
+        None => {
+            print!("Vanity word: ");
+            io::stdout().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            input.trim().to_string()
+        }
+    };
+    // Reject words that cannot be spelled before spending time on the benchmark
+    pattern::spell(&word)?;
+    let fee_step = fee_step_or_env(args.fee_step)?;
+    let gas_limit = match args.gas_limit {
+        Some(gas_limit) => Some(gas_limit),
+        None => config::non_empty_var("GAS_LIMIT").map(|gas_limit| gas_limit.parse::<u64>()).transpose()?,
+    };
+    let hashrate = estimation_hashrate(args.hashrate, args.benchmark, args.threads)?;
+    let (digits, options) = advise::advise(&word, hashrate, fee_step, args.threads, gas_limit.map(U256::from))?;
+
+    println!("{:?} spelled in hex: {}", word, digits);
+    println!("At {} on {} thread(s), fee step {}:", units::format_hashrate(hashrate), args.threads, units::format_wei(fee_step));
+    println!(
+        "  {:<10} {:<24} {:>16} {:>14} {:>14} {:>16}",
+        "Placement", "Looks like", "Attempts", "Expected", "95% within", "Fee overhead"
+    );
+    for option in &options {
+        let overhead = match option.fee_overhead {
+            Some(overhead) => format!("{} ETH", format_eth(overhead)),
+            None => format!("{:.4} gwei/gas", option.difficulty.fee_window_per_worker / 1e9),
+        };
+        println!(
+            "  {:<10} {:<24} {:>16} {:>14} {:>14} {:>16}{}",
+            option.placement,
+            option.difficulty.prefix,
+            units::format_estimate(option.difficulty.expected_attempts),
+            units::format_duration(option.difficulty.expected_seconds),
+            units::format_duration(option.difficulty.seconds_95),
+            overhead,
+            if option.placement.minable() { "" } else { " *" }
+        );
+    }
+    println!("* for comparison only: the search matches prefixes, so only the prefix placement can be mined");
+    if gas_limit.is_none() {
+        println!("Set GAS_LIMIT or --gas-limit to see the fee overhead in ETH");
+    }
+    match advise::longest_prefix_within(&digits, hashrate, args.within as f64 * 60.0) {
+        Some(prefix) if prefix == digits => println!("Suggested: HASH_PREFIX=0x{}, expected within {} minute(s)", prefix, args.within),
+        Some(prefix) => println!(
+            "The full word is not expected within {} minute(s); 0x{} is. Consider a shorter word or a longer search",
+            args.within, prefix
+        ),
+        None => println!("Not even one digit is expected within {} minute(s) at this hashrate", args.within),
+    }
+    Ok(())
+}
+
+fn parse_chain(value: &str) -> eyre::Result<u64> {
+    chains::resolve(value)
+}
+
+/// Prints explorer pages for a sent transaction and the contract it created, when the chain has one.
+fn print_explorer_links(chain_id: u64, tx_hash: H256, contract_address: Option<Address>) {
+    let Some(chain) = chains::by_id(chain_id) else {
+        return;
+    };
+    if let Some(url) = chain.tx_url(tx_hash) {
+        println!("Explorer: {}", url);
+    }
+    if let Some(url) = contract_address.and_then(|address| chain.address_url(address)) {
+        println!("Contract: {}", url);
+    }
+}
+
+/// Prints a markdown summary of a confirmed vanity transaction, ready to paste into an announcement.
+fn print_share_summary(chain_id: u64, hash_prefix: &str, receipt: &TransactionReceipt, contract_address: Option<Address>) {
+    if receipt.status != Some(1.into()) {
+        return;
+    }
+    println!("\nShareable summary:\n");
+    print!("{}", ShareSummary::from_receipt(chain_id, hash_prefix, receipt, contract_address).to_markdown());
+}
+
+fn record_deployment(
+    history_path: &str,
+    chain_id: u64,
+    from: Address,
+    nonce: U256,
+    tx: &Eip1559TransactionRequest,
+    receipt: &TransactionReceipt,
+) {
+    let deployment = Deployment {
+        chain_id,
+        tx_hash: receipt.transaction_hash,
+        from,
+        contract_address: receipt.contract_address,
+        nonce: nonce.as_u64(),
+        calldata_hash: tx.data.as_ref().map(|data| H256::from(keccak256(data))),
+        calldata_size: tx.data.as_ref().map(|data| data.len() as u64),
+        name: config::deployment_name(),
+        cost_wei: receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default(),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    if let Err(e) = HistoryDb::open(history_path).and_then(|db| db.record(&deployment)) {
+        println!("Warning: failed to record deployment in {}: {}", history_path, e);
+    }
+}
+
+/// Shows how the calldata differs from the last deployment under DEPLOYMENT_NAME and asks
+/// whether to go ahead, to catch stale or accidentally modified bytecode before hours of mining.
+/// Returns false if the user declines.
+fn confirm_calldata_change(history_path: &str, tx_spec: &TxSpec) -> eyre::Result<bool> {
+    let Some(name) = config::deployment_name() else {
+        return Ok(true);
+    };
+    let calldata = tx_spec.calldata.clone().unwrap_or_default();
+    let change = match HistoryDb::open(history_path).and_then(|db| redeploy::compare(&db, &name, &calldata)) {
+        Ok(Some(change)) => change,
+        Ok(None) => return Ok(true),
+        Err(e) => {
+            println!("Warning: could not compare the calldata with previous deployments: {}", e);
+            return Ok(true);
+        }
+    };
+    let previous = &change.previous;
+    let when = chrono::DateTime::from_timestamp(previous.timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    println!(
+        "Warning: the calldata differs from the last {:?} deployment ({} on chain {}, {:?})",
+        name,
+        when,
+        chains::describe(previous.chain_id),
+        previous.tx_hash
+    );
+    println!(
+        "  Size: {} -> {} bytes{}",
+        previous.calldata_size.map_or_else(|| "unknown".to_string(), |size| size.to_string()),
+        change.size,
+        change.size_delta().map(|delta| format!(" ({:+})", delta)).unwrap_or_default()
+    );
+    println!(
+        "  Hash: {} -> {:?}",
+        previous.calldata_hash.map_or_else(|| "none".to_string(), |hash| format!("{:?}", hash)),
+        change.hash
+    );
+    print!("Deploy the changed calldata? (y/n): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase() == "y")
+}
+
+/// A match an earlier run journaled for the same transaction and pattern, e.g. before an
+/// accidental abort, if it can still be sent and the user would rather send it than mine again.
+async fn reusable_match(provider: &rpc::RpcProvider, params: &SearchParams, from: Address, journal_path: &str) -> eyre::Result<Option<Match>> {
+    let entries = Journal::read_entries_with_passphrase(journal_path, config::journal_passphrase().as_deref())?;
+    let patterns = params.patterns.as_ref().map_or_else(|| vec![params.hash_prefix.clone()], |patterns| patterns.accepted());
+    let Some(entry) = reuse::find(&entries, &params.template, &patterns) else {
+        return Ok(None);
+    };
+    let when = chrono::DateTime::from_timestamp(entry.timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    println!(
+        "{} already holds a match for this transaction: {:?} at a max fee of {} gwei, found {}",
+        journal_path,
+        entry.tx_hash,
+        format_gwei(entry.max_fee_per_gas),
+        when
+    );
+    let account_nonce = provider.get_transaction_count(from, None).await?;
+    let base_fee = provider.get_block(BlockNumber::Latest).await?.and_then(|block| block.base_fee_per_gas).unwrap_or_default();
+    match reuse::check_fresh(entry, account_nonce, base_fee) {
+        Ok(Freshness::Ready) => {}
+        Ok(Freshness::Queued { account_nonce }) => println!(
+            "The account is still at nonce {}, so it can only be included after the transactions before nonce {}",
+            account_nonce, entry.nonce
+        ),
+        Err(staleness) => {
+            println!("It cannot be sent any more ({}), so mining again", staleness);
+            return Ok(None);
+        }
+    }
+    print!("Send it instead of mining again? (y/n): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        return Ok(None);
+    }
+    Ok(Some(reuse::to_match(entry, params.template.gas.unwrap_or_default())?))
+}
+
+/// Warns when the node disagrees with the nonce this tool last sent with (see `nonces`) and asks
+/// whether to search anyway, since a match is worthless if its nonce gets taken. Pending
+/// transactions are only a conflict when the search is not meant to run behind them
+/// (`standby`). Returns false if the user declines.
+async fn confirm_nonce_cache(
+    provider: &rpc::RpcProvider,
+    history_path: &str,
+    from: Address,
+    chain_id: u64,
+    standby: bool,
+) -> eyre::Result<bool> {
+    let mut conflicts = match nonces::cross_check(provider, history_path, from, chain_id).await {
+        Ok(conflicts) => conflicts,
+        Err(e) => {
+            println!("Warning: could not cross-check the nonce on chain {}: {}", chains::describe(chain_id), e);
+            return Ok(true);
+        }
+    };
+    conflicts.retain(|conflict| !(standby && matches!(conflict, NonceConflict::Pending { .. })));
+    if conflicts.is_empty() {
+        return Ok(true);
+    }
+    for conflict in &conflicts {
+        println!("Warning: chain {}: {}", chains::describe(chain_id), conflict);
+    }
+    print!("Search anyway? (y/n): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase() == "y")
+}
+
+/// Warns when `from` already used `nonce` on another chain for a different transaction, the
+/// classic cross-chain foot-gun when a deployer account is reused.
+fn warn_on_key_reuse(history_path: &str, chain_id: u64, from: Address, nonce: U256, template: &Eip1559TransactionRequest) {
+    let calldata_hash = template.data.as_ref().map(|data| H256::from(keccak256(data)));
+    let reused = HistoryDb::open(history_path).and_then(|db| db.key_reuse(from, nonce.as_u64(), chain_id, calldata_hash));
+    match reused {
+        Ok(reused) => {
+            for deployment in reused {
+                println!(
+                    "Warning: {:?} already used nonce {} on chain {} for different calldata (tx {:?}{}); the same nonce on chain {} will not be the same deployment",
+                    from,
+                    nonce,
+                    chains::describe(deployment.chain_id),
+                    deployment.tx_hash,
+                    deployment.contract_address.map(|a| format!(", contract {:?}", a)).unwrap_or_default(),
+                    chains::describe(chain_id)
+                );
+            }
+        }
+        Err(e) => println!("Warning: could not check {} for nonce reuse: {}", history_path, e),
+    }
+}
+
+/// A mined transaction waiting to be sent on one chain of a multi-chain run.
+struct ChainPlan {
+    chain_id: u64,
+    provider: rpc::RpcProvider,
+    broadcaster: Box<dyn Broadcaster>,
+    nonce: U256,
+    template: Eip1559TransactionRequest,
+    found: Match,
+}
+
+async fn multichain(args: MultichainArgs, parameters: &Parameters) -> eyre::Result<Status> {
+    let private_key = env::var("PRIVATE_KEY")?;
+    let hash_prefix = hash_prefix_from_env()?;
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let history_path = config::history_path();
+    let journal_path = config::journal_path();
+    let journal = Arc::new(Journal::open_with_passphrase(&journal_path, config::journal_passphrase().as_deref())?);
+    let wallet: LocalWallet = private_key.parse()?;
+    let from = wallet.address();
+
+    // Connect to every chain up front so a bad endpoint fails before hours of mining
+    let chains = connect_chains(&args.chains, from).await?;
+    for (chain_id, provider, _) in &chains {
+        BroadcastConfig::from_env(*chain_id)?;
+        if !confirm_nonce_cache(provider, &history_path, from, *chain_id, false).await? {
+            println!("Aborted by user.");
+            return Ok(Status::UserAbort);
+        }
+    }
+    if !confirm_calldata_change(&history_path, &tx_spec)? {
+        println!("Aborted by user.");
+        return Ok(Status::UserAbort);
+    }
+    let target_nonce = chains.iter().map(|(_, _, nonce)| *nonce).max().unwrap_or_default();
+    if args.same_address {
+        print_address_plan(&chains, from, target_nonce);
+    }
+
+    println!("Mining transaction hash prefix {} on {} chain(s)", hash_prefix, chains.len());
+    println!(
+        "Matches are journaled to {}{}",
+        journal.path().display(),
+        if journal.is_encrypted() { " (encrypted)" } else { "" }
+    );
+
+    let verifier = verifier()?;
+    let limits = resource_limits()?;
+    let mut plans = Vec::new();
+    for (chain_id, provider, current_nonce) in chains {
+        let nonce = if args.same_address { target_nonce } else { current_nonce };
+        let template = tx_spec.template(chain_id, nonce);
+        warn_on_key_reuse(&history_path, chain_id, from, nonce, &template);
+        let (fees, salt) = search_fees(chain_id, &provider, &tx_spec).await?;
+        let broadcaster = BroadcastConfig::from_env(chain_id)?.connect(provider.clone()).await?;
+        let signer = envelope::signer_for(wallet.clone().with_chain_id(chain_id), chain_id)?;
+        let params = SearchParams {
+            sampler: CandidateSampler::from_env(&format!("chain {} nonce {} prefix {}", chain_id, nonce, hash_prefix))?,
+            verifier: verifier.clone(),
+            limits,
+            ..search_params(signer, template.clone(), hash_prefix.clone(), &fees, salt)?
+        };
+
+        preflight(&params).map_err(|e| e.wrap_err(format!("chain {}", chain_id)))?;
+        println!("Chain {}: searching at nonce {}...", chain_id, nonce);
+        let (found_match, stats) = mine(params, journal.clone()).await?;
+        let Some(found_match) = found_match else {
+            println!("No solution found on chain {}, nothing was sent", chain_id);
+            return Ok(no_match_status());
+        };
+        println!(
+            "Chain {}: found 0x{} after {} attempts in {}",
+            chain_id,
+            hex::encode(found_match.tx_hash),
+            units::format_count(stats.total_attempts()),
+            units::format_duration(stats.elapsed().as_secs_f64())
+        );
+        verify_before_sending(&found_match.signed_rlp, found_match.tx_hash.into(), from, chain_id, nonce)
+            .map_err(|e| e.wrap_err(format!("chain {}: the signed transaction failed verification, nothing was sent", chain_id)))?;
+        plans.push(ChainPlan { chain_id, provider, broadcaster, nonce, template, found: found_match });
+    }
+
+    println!();
+    // Chains can pay fees in different currencies, so totals are kept per symbol
+    let mut totals: Vec<(&str, U256)> = Vec::new();
+    for plan in &plans {
+        let address = plan.template.to.is_none().then(|| get_contract_address(from, plan.nonce));
+        let symbol = chains::symbol(plan.chain_id);
+        let premium = match overhead::market_fees(&plan.provider).await {
+            Ok(market) => {
+                let gas_limit = plan.template.gas.unwrap_or_default();
+                let priority_fee = plan.found.tx.max_priority_fee_per_gas.unwrap_or_default();
+                let overhead = Overhead::new(gas_limit, plan.found.max_fee_per_gas, priority_fee, &market);
+                format!(" ({:+.1}% vs a plain transaction)", overhead.max_premium_percent())
+            }
+            Err(_) => String::new(),
+        };
+        println!(
+            "Chain {} | nonce {} | 0x{} | {} | up to {} {}{}",
+            chains::describe(plan.chain_id),
+            plan.nonce,
+            hex::encode(plan.found.tx_hash),
+            address.map(|a| format!("{:?}", a)).unwrap_or_else(|| "-".to_string()),
+            format_eth(plan.found.total_fee_wei),
+            symbol,
+            premium
+        );
+        match totals.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, total)) => *total += plan.found.total_fee_wei,
+            None => totals.push((symbol, plan.found.total_fee_wei)),
+        }
+    }
+    let total_fee = totals
+        .iter()
+        .map(|(symbol, total)| format!("{} {}", format_eth(*total), symbol))
+        .collect::<Vec<_>>()
+        .join(" + ");
+    print!("Send all {} transactions, up to {} in total? (y/n): ", plans.len(), total_fee);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        println!("Aborted by user.");
+        return Ok(Status::UserAbort);
+    }
+
+    // Deploy one chain at a time so a failure is noticed before the next one is sent.
+    // The exit status is the worst outcome: a failed chain, then a chain left unsent
+    let mut report = Vec::new();
+    let mut status = Status::Sent;
+    for plan in &plans {
+        let tx_hash_hex = format!("0x{}", hex::encode(plan.found.tx_hash));
+        let address = plan.template.to.is_none().then(|| get_contract_address(from, plan.nonce));
+
+        // With --same-address the nonce may still be ahead of the account on this chain
+        let mut current_nonce = plan.provider.get_transaction_count(from, None).await?;
+        if current_nonce < plan.nonce
+            && offer_fillers(&plan.provider, &wallet, plan.chain_id, current_nonce, plan.nonce, &history_path).await?
+        {
+            current_nonce = plan.nonce;
+        }
+        if current_nonce != plan.nonce {
+            let outcome = if current_nonce < plan.nonce {
+                format!("skipped: needs {} filler transaction(s) first", plan.nonce - current_nonce)
+            } else {
+                format!("skipped: nonce {} was used in the meantime", plan.nonce)
+            };
+            println!("Chain {}: {}", plan.chain_id, outcome);
+            report.push((plan.chain_id, tx_hash_hex, address, outcome));
+            if status == Status::Sent {
+                status = Status::NotSent;
+            }
+            continue;
+        }
+
+        println!("Chain {}: sending {} through {}...", plan.chain_id, tx_hash_hex, plan.broadcaster);
+        let contract = address.map_or_else(|| "-".to_string(), |address| format!("{:?}", address));
+        let event_vars = |extra: &[(&'static str, String)]| {
+            let mut vars =
+                vec![("tx_hash", tx_hash_hex.clone()), ("chain", chains::describe(plan.chain_id)), ("contract", contract.clone())];
+            vars.extend_from_slice(extra);
+            vars
+        };
+        let broadcast_span = info_span!("broadcast", tx_hash = %tx_hash_hex, chain_id = plan.chain_id);
+        let submission = plan.broadcaster.submit(&plan.found.signed_rlp).instrument(broadcast_span.clone()).await;
+        let receipt = match submission {
+            Ok(Submission::Saved { path, .. }) => {
+                let outcome = format!("saved to {}, not sent", path.display());
+                println!("Chain {}: {}", plan.chain_id, outcome);
+                report.push((plan.chain_id, tx_hash_hex, address, outcome));
+                if status == Status::Sent {
+                    status = Status::NotSent;
+                }
+                continue;
+            }
+            Ok(Submission::Sent(tx_hash)) => {
+                nonces::record_sent(&history_path, from, plan.chain_id, plan.nonce);
+                notify::notify(Event::BroadcastSent, &event_vars(&[])).await;
+                plan.broadcaster
+                    .wait_for_receipt(&plan.provider, tx_hash)
+                    .instrument(info_span!(parent: &broadcast_span, "inclusion"))
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        notify_outcome(&receipt, event_vars).await;
+        if receipt_status(receipt.as_ref().ok().and_then(Option::as_ref)) != Status::Sent {
+            status = Status::BroadcastFailed;
+        }
+
+        let outcome = match &receipt {
+            Ok(Some(receipt)) => {
+                record_deployment(&history_path, plan.chain_id, from, plan.nonce, &plan.found.tx, receipt);
+                match receipt.status.map(|s| s.as_u64()) {
+                    Some(0) => "reverted".to_string(),
+                    _ => "confirmed".to_string(),
+                }
+            }
+            Ok(None) => "dropped".to_string(),
+            Err(e) => format!("failed: {}", e),
+        };
+        let address = match &receipt {
+            Ok(Some(receipt)) => receipt.contract_address,
+            _ => address,
+        };
+        println!("Chain {}: {}", plan.chain_id, outcome);
+        if let Ok(Some(receipt)) = &receipt {
+            print_explorer_links(plan.chain_id, receipt.transaction_hash, receipt.contract_address);
+            print_share_summary(plan.chain_id, &hash_prefix, receipt, None);
+        }
+        report.push((plan.chain_id, tx_hash_hex, address, outcome));
+    }
+
+    println!();
+    println!("Deployment report:");
+    for (chain_id, tx_hash, address, outcome) in report {
+        println!(
+            "Chain {} | {} | {} | {}",
+            chains::describe(chain_id),
+            tx_hash,
+            address.map(|a| format!("{:?}", a)).unwrap_or_else(|| "-".to_string()),
+            outcome
+        );
+    }
+    Ok(status)
+}
+
+/// With CLONE_IMPLEMENTATION, says what is deployed and warns if the implementation has no code
+/// yet, since the proxy would then delegate to nothing.
+async fn check_clone_implementation(provider: &rpc::RpcProvider) -> eyre::Result<Option<Address>> {
+    let Some(implementation) = clone::implementation_from_env().map_err(exit::config_error)? else {
+        return Ok(None);
+    };
+    println!("Deploying an EIP-1167 minimal proxy for {:?} ({} bytes of runtime code)", implementation, clone::RUNTIME_SIZE);
+    if provider.get_code(implementation, None).await?.is_empty() {
+        println!("Warning: CLONE_IMPLEMENTATION {:?} has no code on this chain; calls to the proxy will do nothing until it is deployed", implementation);
+    }
+    Ok(Some(implementation))
+}
+
+/// Refuses a gas limit below the intrinsic gas and warns about a transaction too big for most
+/// mempools, before any time is spent mining it.
+fn preflight(params: &SearchParams) -> eyre::Result<()> {
+    let preflight = Preflight::of(params)?;
+    println!("Preflight: {}", preflight);
+    preflight.check()?;
+    if preflight.oversized() {
+        println!(
+            "Warning: the signed transaction is {} bytes, above the {} byte limit most nodes accept into their mempool; it may need a private relay",
+            preflight.signed_size,
+            intrinsic::MEMPOOL_SIZE_LIMIT
+        );
+    }
+    Ok(())
+}
+
+/// Fee profile and salt for a search on `chain_id`, narrowed to the market fee when
+/// MARKET_TOLERANCE is set. SALT picks the salt, which defaults to the tip in that case and to
+/// dust or padding when FEE_STEP or VANITY_FEE leave few fees to try.
+async fn search_fees(chain_id: u64, provider: &rpc::RpcProvider, tx_spec: &TxSpec) -> eyre::Result<(FeeProfile, Salt)> {
+    let mut fees = FeeProfile::from_env(chain_id)?;
+    if let Some(tolerance) = fees.market_tolerance {
+        fees = fees.centered_on(&overhead::market_fees(provider).await?)?;
+        println!(
+            "Keeping the max fee within {}% of the market fee: {} - {}",
+            tolerance,
+            units::format_count(fees.first_fee()),
+            units::format_wei(fees.max_fee_per_gas.unwrap_or_default())
+        );
+    }
+    if let Some(fee) = fees.vanity_fee {
+        println!("Paying exactly {} gwei per gas: max fee and priority fee are both pinned to it", format_gwei(fee));
+        let base_fee = overhead::market_fees(provider).await?.base_fee;
+        if base_fee > fee {
+            println!(
+                "Warning: the base fee is {} gwei, so the transaction cannot be included until it falls below {} gwei",
+                format_gwei(base_fee),
+                format_gwei(fee)
+            );
+        }
+    }
+    let salt = match config::non_empty_var("SALT") {
+        Some(salt) => salt.parse()?,
+        // A pinned fee leaves nothing but a salt to vary
+        None if fees.vanity_fee.is_some() && tx_spec.value.is_some() => Salt::Dust,
+        None if fees.vanity_fee.is_some() => Salt::Padding,
+        // Round fees leave a value or calldata to vary, as a changing tip would not be round
+        None if fees.fee_step > U256::one() && tx_spec.value.is_some() => Salt::Dust,
+        None if fees.fee_step > U256::one() => Salt::Padding,
+        // A narrow fee band alone rarely holds enough candidates, unless lanes already multiply it
+        None if fees.market_tolerance.is_some() && fees.priority_fee_lanes.is_empty() => Salt::Tip,
+        None => Salt::None,
+    };
+    if fees.vanity_fee.is_some() && matches!(salt, Salt::None | Salt::Tip) {
+        eyre::bail!("VANITY_FEE fixes both fees, so SALT must be padding or dust");
+    }
+    if salt == Salt::Tip && !fees.priority_fee_lanes.is_empty() {
+        eyre::bail!("PRIORITY_FEE_LANES already varies the tip, so SALT cannot be tip");
+    }
+    if salt == Salt::Dust && tx_spec.value.is_none() {
+        eyre::bail!("SALT=dust needs a TRANSFER_AMOUNT to add the dust to");
+    }
+    Ok((fees, salt))
+}
+
+/// Refuses a deployment whose contract is over the code size limit, which no node would include,
+/// and prints how to split it. The runtime size comes from simulating the deployment with
+/// `eth_call`, or from the init code if the simulation fails.
+async fn check_code_size(provider: &rpc::RpcProvider, from: Address, tx_spec: &TxSpec, chain_id: u64) -> eyre::Result<()> {
+    let (None, Some(init_code)) = (tx_spec.to, &tx_spec.calldata) else {
+        return Ok(());
+    };
+    let limit = codesize::max_code_size(chain_id)?;
+    let mut call = Eip1559TransactionRequest::new().from(from).data(init_code.clone());
+    call.value = tx_spec.value;
+    let (runtime_size, oversized) = match provider.call(&call.into(), None).await {
+        Ok(code) => (Some(code.len()), false),
+        Err(e) => (codesize::runtime_size_hint(init_code), codesize::is_code_size_error(&e.to_string())),
+    };
+    match SplitPlan::check(init_code.len(), runtime_size, oversized, limit) {
+        Some(plan) => {
+            println!("{}", plan);
+            Err(exit::config_error(eyre::eyre!("the contract is too big to deploy in one transaction, so it was not mined")))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Idles until the latest base fee is at or below `threshold`, reporting it as it changes.
+async fn wait_for_gas(provider: &rpc::RpcProvider, threshold: U256) -> eyre::Result<()> {
+    println!("Waiting for the base fee to drop to {} gwei...", format_gwei(threshold));
+    let mut last = None;
+    loop {
+        let block = provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| eyre::eyre!("latest block not available"))?;
+        let base_fee = block.base_fee_per_gas.unwrap_or_default();
+        if base_fee <= threshold {
+            println!("Base fee is {} gwei, starting the search", format_gwei(base_fee));
+            return Ok(());
+        }
+        if last != Some(base_fee) {
+            println!("Base fee is {} gwei, waiting...", format_gwei(base_fee));
+            last = Some(base_fee);
+        }
+        tokio::time::sleep(BASE_FEE_POLL_INTERVAL).await;
+    }
+}
+
+/// Polls the account's confirmed nonce until every transaction before `nonce` has confirmed.
+/// Returns false if another transaction confirmed at `nonce` in the meantime.
+async fn wait_for_turn(provider: &rpc::RpcProvider, from: Address, nonce: U256) -> bool {
+    println!("Standby: waiting for nonce {} to confirm...", nonce - 1);
+    let mut warned_dropped = false;
+    loop {
+        match provider.get_transaction_count(from, None).await {
+            Ok(account_nonce) => match standby::turn(account_nonce, nonce) {
+                Turn::Ready => {
+                    println!("Standby: nonce {} confirmed, sending", nonce - 1);
+                    return true;
+                }
+                Turn::Taken => return false,
+                Turn::Waiting => {}
+            },
+            Err(e) => println!("Warning: failed to check the account's nonce ({}), retrying", e),
+        }
+        let pending = provider.get_transaction_count(from, Some(BlockNumber::Pending.into())).await;
+        if !warned_dropped && matches!(pending, Ok(pending) if pending < nonce) {
+            println!("Warning: the node no longer has a pending transaction at nonce {}; it may have been dropped", nonce - 1);
+            warned_dropped = true;
+        }
+        tokio::time::sleep(standby::STANDBY_POLL_INTERVAL).await;
+    }
+}
+
+/// Polls until `tx_hash` confirms, backing off from `PREREQUISITE_POLL_MIN` to
+/// `PREREQUISITE_POLL_MAX`. Fails if it reverts, since whatever depends on it would too.
+async fn wait_for_prerequisite(provider: &rpc::RpcProvider, tx_hash: H256) -> eyre::Result<()> {
+    println!("Waiting for {:?} to confirm before broadcasting...", tx_hash);
+    let mut interval = PREREQUISITE_POLL_MIN;
+    let mut warned_unknown = false;
+    loop {
+        match provider.get_transaction_receipt(tx_hash).await {
+            Ok(Some(receipt)) if receipt.status == Some(0.into()) => {
+                eyre::bail!("prerequisite transaction {:?} reverted, not broadcasting", tx_hash)
+            }
+            Ok(Some(receipt)) => {
+                println!("{:?} confirmed in block {}", tx_hash, receipt.block_number.unwrap_or_default());
+                return Ok(());
+            }
+            Ok(None) => {
+                if !warned_unknown && matches!(provider.get_transaction(tx_hash).await, Ok(None)) {
+                    println!("Warning: the node does not know {:?} yet; still waiting", tx_hash);
+                    warned_unknown = true;
+                }
+            }
+            Err(e) => println!("Warning: failed to check {:?} ({}), retrying in {}s", tx_hash, e, interval.as_secs()),
+        }
+        tokio::time::sleep(interval).await;
+        interval = (interval * 2).min(PREREQUISITE_POLL_MAX);
+    }
+}
+
+/// Searches the same template again with the fee sweep moved to the current market fee.
+async fn remine(params: &SearchParams, market: &MarketFees, chain_id: u64, journal: Arc<Journal>) -> eyre::Result<(Option<Match>, Origin)> {
+    let fees = FeeProfile::from_env(chain_id)?.rebased_on(market)?;
+    println!("Remining from a max fee of {} gwei...", format_gwei(fees.first_fee()));
+    let params = SearchParams {
+        base_fee_start: fees.base_fee_start,
+        priority_fee: fees.priority_fee,
+        max_fee_per_gas: fees.max_fee_per_gas,
+        ..params.clone()
+    };
+    let (next_match, stats) = mine(params.clone(), journal).await?;
+    print!("{}", stats.postmortem(&params.hash_prefix));
+    print!("{}", stats.worker_report());
+    Ok((next_match, Origin::Search(stats)))
+}
+
+/// A line being read from stdin on its own thread, with when it was typed. A prompt that expires
+/// leaves it running for the next prompt, since a thread blocked on stdin cannot be cancelled.
+type PendingAnswer = tokio::sync::oneshot::Receiver<io::Result<(String, Instant)>>;
+
+/// Reads the answer to a prompt shown at `asked_at`, or returns why `expiry` ended the wait first.
+/// Lines typed before the prompt was shown are answers to an earlier one and are dropped.
+async fn read_answer_until(
+    pending: &mut Option<PendingAnswer>,
+    asked_at: Instant,
+    expiry: impl std::future::Future<Output = Expiry>,
+) -> eyre::Result<Result<String, Expiry>> {
+    tokio::pin!(expiry);
+    loop {
+        let answer = pending.get_or_insert_with(|| {
+            let (sender, receiver) = tokio::sync::oneshot::channel();
+            std::thread::spawn(move || {
+                let mut input = String::new();
+                let _ = sender.send(io::stdin().read_line(&mut input).map(|_| (input, Instant::now())));
+            });
+            receiver
+        });
+        tokio::select! {
+            result = answer => {
+                *pending = None;
+                let (input, typed_at) = result.map_err(|_| eyre::eyre!("stdin reader stopped"))??;
+                if typed_at >= asked_at {
+                    return Ok(Ok(input));
+                }
+            }
+            expiry = &mut expiry => return Ok(Err(expiry)),
+        }
+    }
+}
+
+/// Keeps the line above the confirmation prompt showing the latest base fee against the match's
+/// max fee, redrawing it in place so whatever has been typed at the prompt is left alone.
+async fn show_live_base_fee(provider: rpc::RpcProvider, max_fee_per_gas: U256) {
+    loop {
+        let block = provider.get_block(BlockNumber::Latest).await;
+        if let Some(base_fee) = block.ok().flatten().and_then(|block| block.base_fee_per_gas) {
+            // Save the cursor, rewrite the line above, then restore the cursor
+            print!("\x1b7\x1b[1A\r\x1b[2KLive: {}\x1b8", expiry::base_fee_status(base_fee, max_fee_per_gas));
+            let _ = io::stdout().flush();
+        }
+        tokio::time::sleep(PROMPT_BASE_FEE_INTERVAL).await;
+    }
+}
+
+/// Prints how long the search should take at the hashrate earlier searches measured on this
+/// machine, if there are any.
+fn print_expected_time(params: &SearchParams) {
+    let Some(hashrate) = profile::measured_hashrate(&config::profile_path(), params.thread_count) else {
+        return;
+    };
+    if let Ok(estimate) = Difficulty::estimate(&params.hash_prefix, hashrate, params.fee_step, params.thread_count) {
+        println!(
+            "Expected time at {} (measured on this machine): {}, 95% within {}",
+            units::format_hashrate(hashrate),
+            units::format_duration(estimate.expected_seconds),
+            units::format_duration(estimate.seconds_95)
+        );
+    }
+}
+
+/// Prints how many candidates the search can try before the fee range runs out, and the chance of
+/// that happening before a match.
+fn print_search_space(params: &SearchParams) {
+    if !params.priority_fee_lanes.is_empty() {
+        let lanes = params.priority_fee_lanes.iter().map(|&fee| format_gwei(fee)).collect::<Vec<_>>();
+        println!("Priority fee lanes: {} gwei, each sweeping the same max fees", lanes.join(", "));
+        if params.priority_fee_lanes.len() > params.thread_count {
+            println!(
+                "Warning: {} priority fee lanes but only {} threads, so only the first {} lanes are searched",
+                params.priority_fee_lanes.len(),
+                params.thread_count,
+                params.thread_count
+            );
+        }
+    }
+    let fee_step = params.fee_step.max(U256::one());
+    // Each lane sweeps the fee range again with its own tip, so it adds to the space
+    let lanes: Vec<SearchParams> = (0..params.priority_fee_lanes.len().min(params.thread_count)).map(|lane| params.on_lane(lane)).collect();
+    let fee_count = match lanes.is_empty() {
+        true => search::fee_count(params),
+        false => lanes.iter().map(search::fee_count).try_fold(U256::zero(), |total, fees| Some(total + fees?)),
+    };
+    match (fee_count, params.max_fee_per_gas) {
+        (Some(fees), Some(ceiling)) if params.salt == Salt::None => {
+            let per_worker = search::worker_spacing(lanes.first().unwrap_or(params)) / fee_step;
+            let miss = 1.0 / pattern::expected_attempts(&params.hash_prefix);
+            let exhaustion = (units::wei_to_f64(fees) * (-miss).ln_1p()).exp();
+            println!(
+                "Search space: {} max fees from {} to {} gwei, {} per worker; {:.1}% chance of running out before a match",
+                units::format_count(fees),
+                format_gwei(search::first_fee_for_salt(params, 0)),
+                format_gwei(ceiling),
+                units::format_count(per_worker),
+                exhaustion * 100.0
+            );
+            if exhaustion > EXHAUSTION_WARNING {
+                println!("Warning: the fee range may run out first; raise MAX_FEE_PER_GAS, lower FEE_STEP or set SALT to search further");
+            }
+        }
+        (Some(_), _) => println!("Search space: unlimited, each salt value sweeps the fee range again"),
+        (None, _) => println!(
+            "Search space: unlimited without MAX_FEE_PER_GAS, the workers start {} gwei apart",
+            format_gwei(search::worker_spacing(params))
+        ),
+    }
+}
+
+/// Folds a finished search's hashrate into this machine's profile. Throttled searches are left
+/// out, since they measure the throttling rather than the hardware.
+fn record_hashrate(params: &SearchParams, stats: &SearchStats) {
+    if params.throttle.is_some() {
+        return;
+    }
+    let path = config::profile_path();
+    if let Err(e) = profile::record_run(&path, params.thread_count, stats.total_attempts(), stats.elapsed()) {
+        println!("Warning: failed to update the hashrate profile {}: {}", path, e);
+    }
+}
+
+/// Set once Ctrl-C has stopped a search, so a search that ends without a match exits as aborted.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+fn interrupt(found: &AtomicBool) {
+    println!("Interrupted, stopping search...");
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    found.store(true, Ordering::Relaxed);
+}
+
+/// Why a search ended without a match: Ctrl-C, or the time limit or fee range running out.
+fn no_match_status() -> Status {
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        Status::UserAbort
+    } else {
+        Status::NoMatch
+    }
+}
+
+/// Runs a search that Ctrl-C stops early, returning the match (if any) and the search's stats.
+async fn mine(params: SearchParams, journal: Arc<Journal>) -> eyre::Result<(Option<Match>, Arc<SearchStats>)> {
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let found = Arc::new(AtomicBool::new(false));
+    print_search_space(&params);
+    print_expected_time(&params);
+    let search = search::run(params.clone(), stats.clone(), found.clone(), journal);
+    tokio::pin!(search);
+    let found_match = tokio::select! {
+        result = &mut search => result?,
+        _ = tokio::signal::ctrl_c() => {
+            interrupt(&found);
+            search.await?;
+            None
+        }
+    };
+    record_hashrate(&params, &stats);
+    Ok((found_match, stats))
+}
+
+/// Connects to each chain's RPC_<chain_id> endpoint, checks it serves that chain and fetches the
+/// account's nonce there.
+async fn connect_chains(chain_ids: &[u64], from: Address) -> eyre::Result<Vec<(u64, rpc::RpcProvider, U256)>> {
+    let cache = ChainCache::open(config::chain_cache_path());
+    let mut chains = Vec::new();
+    for &chain_id in chain_ids {
+        let config = RpcConfig::from_env_for_chain(chain_id)?;
+        let provider = rpc::connect(&config).await?;
+        let remote_chain_id = cache.chain_id_or_cached(&config.url, provider.get_chainid().await.map(|id| id.as_u64()).map_err(Into::into))?;
+        if remote_chain_id != chain_id {
+            eyre::bail!("RPC_{} points at chain {}, not {}", chain_id, remote_chain_id, chain_id);
+        }
+        let nonce = cache.nonce_or_cached(
+            chain_id,
+            from,
+            provider
+                .get_transaction_count(from, None)
+                .instrument(info_span!("rpc.eth_getTransactionCount", %from, chain_id))
+                .await
+                .map_err(Into::into),
+        )?;
+        chains.push((chain_id, provider, nonce));
+    }
+    Ok(chains)
+}
+
+fn print_address_plan(chains: &[(u64, rpc::RpcProvider, U256)], from: Address, target_nonce: U256) {
+    for (chain_id, _, nonce) in chains {
+        let chain = chains::describe(*chain_id);
+        if *nonce < target_nonce {
+            println!("Chain {}: nonce {}, needs {} filler transaction(s)", chain, nonce, target_nonce - nonce);
+        } else {
+            println!("Chain {}: nonce {}, ready", chain, nonce);
+        }
+    }
+    println!(
+        "Deploying at nonce {} gives contract address {:?} on every chain",
+        target_nonce,
+        get_contract_address(from, target_nonce)
+    );
+}
+
+async fn campaign(args: CampaignArgs, parameters: &Parameters) -> eyre::Result<Status> {
+    for pattern in &args.patterns {
+        pattern::validate(pattern)?;
+    }
+    // A stock of signed transactions can be sent by anyone who reads it
+    let passphrase = config::journal_passphrase()
+        .ok_or_else(|| eyre::eyre!("a campaign keeps its matches in the encrypted journal; set JOURNAL_PASSPHRASE"))?;
+    let chain_id = chains::chain_id_from_env()?;
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let journal_path = config::journal_path();
+    let journal = Arc::new(Journal::open_with_passphrase(&journal_path, Some(&passphrase))?);
+    let wallet: LocalWallet = env::var("PRIVATE_KEY")?.parse::<LocalWallet>()?.with_chain_id(chain_id);
+    let from = wallet.address();
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let current_nonce = provider.get_transaction_count(from, None).await?;
+    let start_nonce = args.start_nonce.unwrap_or(current_nonce);
+    eyre::ensure!(
+        start_nonce >= current_nonce,
+        "nonce {} has already been used, the account is at nonce {}",
+        start_nonce,
+        current_nonce
+    );
+    let (fees, salt) = search_fees(chain_id, &provider, &tx_spec).await?;
+
+    let slots = campaign::plan(&args.patterns, start_nonce, args.count);
+    let entries = Journal::read_entries_with_passphrase(&journal_path, Some(&passphrase))?;
+    println!(
+        "Campaign: {} pattern(s) x {} nonce(s) from nonce {} on chain {}, journaled to {}",
+        args.patterns.len(),
+        args.count,
+        start_nonce,
+        chains::describe(chain_id),
+        journal.path().display()
+    );
+    let signer = envelope::signer_for(wallet, chain_id)?;
+    let verifier = verifier()?;
+    let limits = resource_limits()?;
+    let mut collected = Vec::new();
+    for slot in &slots {
+        let template = tx_spec.template(chain_id, slot.nonce);
+        if let Some(entry) = entries.iter().find(|entry| campaign::fills(entry, slot, &template)) {
+            collected.push((slot.clone(), entry.tx_hash));
+            continue;
+        }
+        println!("[{}/{}] Mining 0x{} at nonce {}...", collected.len() + 1, slots.len(), slot.pattern, slot.nonce);
+        let params = SearchParams {
+            sampler: CandidateSampler::from_env(&format!("campaign chain {} nonce {} prefix 0x{}", chain_id, slot.nonce, slot.pattern))?,
+            verifier: verifier.clone(),
+            limits,
+            ..search_params(signer.clone(), template, pattern::to_prefix(&slot.pattern), &fees, salt)?
+        };
+        let Some(found_match) = mine(params, journal.clone()).await?.0 else {
+            println!("Campaign stopped with {} of {} matches; run it again to continue", collected.len(), slots.len());
+            return Ok(no_match_status());
+        };
+        collected.push((slot.clone(), H256::from(found_match.tx_hash)));
+    }
+
+    println!("Campaign complete: {} matches", collected.len());
+    for (slot, tx_hash) in &collected {
+        println!("  nonce {} | 0x{:<10} | {:?}", slot.nonce, slot.pattern, tx_hash);
+    }
+    println!("Send one when the account reaches its nonce with `broadcast <hash>`.");
+    Ok(Status::NotSent)
+}
+
+/// A job from a `jobs` file, ready to mine.
+struct PreparedJob<'a> {
+    spec: &'a JobSpec,
+    chain_id: u64,
+    from: Address,
+    nonce: U256,
+    params: SearchParams,
+}
+
+async fn run_jobs(args: JobsArgs) -> eyre::Result<Status> {
+    let specs = jobs::load(&args.file)?;
+    let total_threads = args.threads.map_or_else(search::default_thread_count, |threads| threads as usize);
+    let shares: Vec<f64> = specs.iter().map(|spec| spec.share).collect();
+    let journal = Arc::new(Journal::open_with_passphrase(config::journal_path(), config::journal_passphrase().as_deref())?);
+
+    let verifier = verifier()?;
+    let limits = resource_limits()?;
+
+    // Jobs on the same chain share one connection
+    let mut providers: BTreeMap<u64, rpc::RpcProvider> = BTreeMap::new();
+    let mut prepared = Vec::new();
+    for (spec, threads) in specs.iter().zip(jobs::split_threads(&shares, total_threads)) {
+        let chain_id = spec.chain_id()?;
+        let provider = match providers.get(&chain_id) {
+            Some(provider) => provider.clone(),
+            None => {
+                let provider = connect_job_chain(chain_id).await?;
+                providers.insert(chain_id, provider.clone());
+                provider
+            }
+        };
+        let key = config::non_empty_var(&spec.key_var)
+            .ok_or_else(|| eyre::eyre!("job {}: {} is not set", spec.name, spec.key_var))?;
+        let wallet = key.parse::<LocalWallet>()?.with_chain_id(chain_id);
+        let from = wallet.address();
+        let nonce = match spec.nonce {
+            Some(nonce) => nonce,
+            None => provider.get_transaction_count(from, None).await?,
+        };
+        if let Some(other) = prepared.iter().find(|job: &&PreparedJob| job.from == from && job.chain_id == chain_id && job.nonce == nonce) {
+            eyre::bail!(
+                "jobs {} and {} both mine nonce {} of {:?} on chain {}, only one of them could ever be sent",
+                other.spec.name,
+                spec.name,
+                nonce,
+                from,
+                chains::describe(chain_id)
+            );
+        }
+        let tx_spec = spec.tx_spec();
+        let (fees, salt) = search_fees(chain_id, &provider, &tx_spec).await?;
+        let hash_prefix = pattern::to_prefix(&spec.hash_prefix);
+        let params = SearchParams {
+            thread_count: threads,
+            sampler: CandidateSampler::from_env(&format!("job {} chain {} nonce {} prefix {}", spec.name, chain_id, nonce, hash_prefix))?,
+            verifier: verifier.clone(),
+            limits,
+            ..search_params(envelope::signer_for(wallet, chain_id)?, tx_spec.template(chain_id, nonce), hash_prefix, &fees, salt)?
+        };
+        println!(
+            "Job {}: prefix {} on chain {} for {:?} at nonce {}, {} thread(s)",
+            spec.name,
+            params.hash_prefix,
+            chains::describe(chain_id),
+            from,
+            nonce,
+            threads
+        );
+        prepared.push(PreparedJob { spec, chain_id, from, nonce, params });
+    }
+
+    let mut running: Vec<_> = prepared
+        .iter()
+        .map(|job| {
+            let stats = Arc::new(SearchStats::new(job.params.thread_count));
+            let found = Arc::new(AtomicBool::new(false));
+            let search = tokio::spawn(search::run(job.params.clone(), stats.clone(), found.clone(), journal.clone()));
+            (stats, found, Some(search))
+        })
+        .collect();
+    let mut outcomes: Vec<Option<(String, Status)>> = vec![None; prepared.len()];
+    let mut last_progress = Instant::now();
+    let mut interrupted = false;
+    while outcomes.iter().any(Option::is_none) {
+        tokio::select! {
+            _ = tokio::time::sleep(RATE_SAMPLE_INTERVAL) => {}
+            _ = tokio::signal::ctrl_c(), if !interrupted => {
+                println!("Interrupted, stopping all jobs...");
+                interrupted = true;
+                INTERRUPTED.store(true, Ordering::Relaxed);
+                for (_, found, _) in &running {
+                    found.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        for (i, (_, _, search)) in running.iter_mut().enumerate() {
+            let Some(handle) = search.take_if(|handle| handle.is_finished()) else {
+                continue;
+            };
+            let job = &prepared[i];
+            let (outcome, status) = match handle.await? {
+                Ok(Some(found_match)) => (save_job_match(job, &found_match).await, Status::NotSent),
+                Ok(None) => ("stopped".to_string(), no_match_status()),
+                Err(e) => (format!("failed: {}", e), exit::classify(&e)),
+            };
+            println!("Job {}: {}", job.spec.name, outcome);
+            outcomes[i] = Some((outcome, status));
+        }
+        if last_progress.elapsed() >= JOBS_PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let progress: Vec<String> = prepared
+                .iter()
+                .zip(&running)
+                .map(|(job, (stats, _, search))| match search {
+                    Some(_) => format!(
+                        "{} {} attempts at {}",
+                        job.spec.name,
+                        units::format_count(stats.total_attempts()),
+                        units::format_hashrate(stats.hashrate())
+                    ),
+                    None => format!("{} done", job.spec.name),
+                })
+                .collect();
+            println!("Jobs: {}", progress.join(" | "));
+        }
+    }
+
+    println!("Jobs finished:");
+    let outcomes: Vec<(String, Status)> = outcomes.into_iter().flatten().collect();
+    for (job, (outcome, _)) in prepared.iter().zip(&outcomes) {
+        println!("  {} | chain {} | nonce {} | {}", job.spec.name, job.chain_id, job.nonce, outcome);
+    }
+    Ok(jobs_status(outcomes.iter().map(|(_, status)| *status)))
+}
+
+/// A failed job decides the exit status, then a saved match, then why the others stopped.
+fn jobs_status(statuses: impl Iterator<Item = Status>) -> Status {
+    statuses
+        .max_by_key(|status| match status {
+            Status::NotSent => 1,
+            Status::NoMatch | Status::UserAbort => 0,
+            _ => 2,
+        })
+        .unwrap_or(Status::NoMatch)
+}
+
+/// RPC_<chain_id> if it is set, otherwise the default RPC, checked to serve `chain_id`.
+async fn connect_job_chain(chain_id: u64) -> eyre::Result<rpc::RpcProvider> {
+    let (config, name) = match config::non_empty_var(&format!("RPC_{}", chain_id)) {
+        Some(_) => (RpcConfig::from_env_for_chain(chain_id)?, format!("RPC_{}", chain_id)),
+        None => (RpcConfig::from_env()?, "RPC".to_string()),
+    };
+    let provider = rpc::connect(&config).await?;
+    let cache = ChainCache::open(config::chain_cache_path());
+    let remote_chain_id = cache.chain_id_or_cached(&config.url, provider.get_chainid().await.map(|id| id.as_u64()).map_err(Into::into))?;
+    eyre::ensure!(remote_chain_id == chain_id, "{} points at chain {}, not {}", name, remote_chain_id, chain_id);
+    Ok(provider)
+}
+
+/// Saves a job's match where `broadcast` finds it, describing the outcome.
+async fn save_job_match(job: &PreparedJob<'_>, found_match: &Match) -> String {
+    let path = config::chain_var("SIGNED_TX_PATH", job.chain_id).unwrap_or_else(|| broadcast::DEFAULT_SIGNED_TX_PATH.to_string());
+    let saver = match broadcast::FileOnly::new(path, config::journal_passphrase().as_deref()) {
+        Ok(saver) => saver,
+        Err(e) => return format!("found 0x{} (journaled), but saving it failed: {}", hex::encode(found_match.tx_hash), e),
+    };
+    match saver.submit(&found_match.signed_rlp).await {
+        Ok(_) => format!("found 0x{}, saved to {}", hex::encode(found_match.tx_hash), saver.path.display()),
+        Err(e) => format!("found 0x{} (journaled), but saving it failed: {}", hex::encode(found_match.tx_hash), e),
+    }
+}
+
+async fn audit(args: AuditArgs, parameters: &Parameters) -> eyre::Result<()> {
+    let hash_prefix = hash_prefix_from_env()?;
+    let chain_id = chains::chain_id_from_env()?;
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let wallet: LocalWallet = env::var("PRIVATE_KEY")?.parse::<LocalWallet>()?.with_chain_id(chain_id);
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let nonce = provider.get_transaction_count(wallet.address(), None).await?;
+    let (fees, salt) = search_fees(chain_id, &provider, &tx_spec).await?;
+    let params = search_params(envelope::signer_for(wallet, chain_id)?, tx_spec.template(chain_id, nonce), hash_prefix.clone(), &fees, salt)?;
+
+    println!("Auditing {} random candidates for prefix {} on chain {}...", args.samples, hash_prefix, chains::describe(chain_id));
+    // Each candidate is independent, so the sample is split across the workers
+    let threads = params.thread_count.max(1) as u64;
+    let reports: Vec<audit::AuditReport> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|i| {
+                let samples = args.samples / threads + u64::from(i < args.samples % threads);
+                let params = &params;
+                scope.spawn(move || audit::run(params, samples, &mut ethers::core::rand::thread_rng()))
+            })
+            .collect();
+        workers.into_iter().map(|worker| worker.join().expect("audit worker panicked")).collect()
+    });
+    let report = audit::AuditReport::merge(reports);
+    print!("{}", report);
+    eyre::ensure!(
+        report.passed(),
+        "the audit failed: the matcher, encoder or signer is not behaving like a uniform hash search"
+    );
+    println!("Audit passed: hashes are re-derived exactly and match at the theoretical rate.");
+    Ok(())
+}
+
+/// Works out both routes for the configured transaction and prints them side by side, with what
+/// the vanity hash adds in time and cost.
+async fn compare(args: CompareArgs, parameters: &Parameters) -> eyre::Result<()> {
+    let hash_prefix = hash_prefix_from_env()?;
+    let chain_id = chains::chain_id_from_env()?;
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let wallet: LocalWallet = env::var("PRIVATE_KEY")?.parse::<LocalWallet>()?.with_chain_id(chain_id);
+    let from = wallet.address();
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let nonce = provider.get_transaction_count(from, None).await?;
+    let (fees, salt) = search_fees(chain_id, &provider, &tx_spec).await?;
+    let params = SearchParams {
+        limits: resource_limits()?,
+        ..search_params(envelope::signer_for(wallet, chain_id)?, tx_spec.template(chain_id, nonce), hash_prefix.clone(), &fees, salt)?
+    };
+    let gas_limit = params.template.gas.unwrap_or_default();
+    let gas_used = simulate_gas(&provider, &TypedTransaction::Eip1559(params.template.clone()), from, gas_limit).await;
+    let market = overhead::market_fees(&provider).await?;
+    let block_seconds = compare::block_seconds(&provider).await?;
+    let inclusion_blocks = estimate_inclusion(&provider, market.max_fee_per_gas, market.priority_fee).await;
+    let immediate = compare::immediate(gas_used, gas_limit, &market, inclusion_blocks);
+
+    let (vanity, mining) = if args.mine {
+        println!("Mining {} on {} thread(s); the match is journaled but not sent...", hash_prefix, params.thread_count);
+        let journal = Arc::new(Journal::open_with_passphrase(config::journal_path(), config::journal_passphrase().as_deref())?);
+        let stats = Arc::new(SearchStats::new(params.thread_count));
+        let found = search::run(params.clone(), stats.clone(), Arc::new(AtomicBool::new(false)), journal)
+            .await?
+            .ok_or_else(|| eyre::eyre!("the search ended without a match, so there is no vanity route to compare"))?;
+        let tip = found.tx.max_priority_fee_per_gas.unwrap_or_default();
+        let route = Route {
+            mining_seconds: stats.elapsed().as_secs_f64(),
+            inclusion_blocks: estimate_inclusion(&provider, found.max_fee_per_gas, tip).await,
+            cost: EffectiveCost::new(gas_used, gas_limit, found.max_fee_per_gas, tip, market.base_fee),
+        };
+        (route, format!("Mined 0x{} in {} attempts.", hex::encode(found.tx_hash), units::format_count(stats.total_attempts())))
+    } else {
+        let hashrate = estimation_hashrate(args.hashrate, args.benchmark, params.thread_count)?;
+        let difficulty = Difficulty::estimate(&hash_prefix, hashrate, params.fee_step, params.thread_count)?;
+        let max_fee_per_gas = compare::expected_match_fee(&params, &difficulty);
+        let inclusion_blocks = estimate_inclusion(&provider, max_fee_per_gas, compare::expected_tip(&params)).await;
+        let route = compare::vanity(&params, &difficulty, gas_used, &market, inclusion_blocks);
+        (route, format!("Mining time is expected at {}.", units::format_hashrate(hashrate)))
+    };
+    print_comparison(&Comparison { immediate, vanity, block_seconds }, &hash_prefix, &mining, chain_id)
+}
+
+/// Expected blocks until a transaction paying these fees is included, `None` with a warning when
+/// the fee history cannot be fetched, so the comparison still runs without it.
+async fn estimate_inclusion<M: Middleware>(provider: &M, max_fee_per_gas: U256, priority_fee: U256) -> Option<f64>
+where
+    M::Error: 'static,
+{
+    match inclusion::estimate(provider, max_fee_per_gas, priority_fee).await {
+        Ok(estimate) => Some(estimate.expected_blocks()),
+        Err(e) => {
+            println!("Warning: could not fetch fee history for the inclusion estimate: {}", e);
+            None
+        }
+    }
+}
+
+fn print_comparison(comparison: &Comparison, hash_prefix: &str, mining: &str, chain_id: u64) -> eyre::Result<()> {
+    let Comparison { immediate, vanity, block_seconds } = comparison;
+    let symbol = chains::symbol(chain_id);
+    let blocks = |route: &Route| match route.inclusion_blocks {
+        Some(blocks) if blocks.is_finite() => format!("{:.1} blocks", blocks),
+        Some(_) => "never at today's fees".to_string(),
+        None => "unknown".to_string(),
+    };
+    let total = |route: &Route| route.total_seconds(*block_seconds).map_or_else(|| "-".to_string(), units::format_duration);
+    let row = |label: &str, immediate: String, vanity: String| println!("  {:<18} {:>24} {:>24}", label, immediate, vanity);
+
+    println!(
+        "Sending now versus mining {} first, on {} ({} gas used of {}):",
+        hash_prefix,
+        chains::describe(chain_id),
+        units::format_count(vanity.cost.gas_used),
+        units::format_count(vanity.cost.gas_limit)
+    );
+    row("", "Immediate".to_string(), "Vanity".to_string());
+    row("Mining", "-".to_string(), units::format_duration(vanity.mining_seconds));
+    row("Max fee", format!("{} gwei", format_gwei(immediate.cost.max_fee_per_gas)), format!("{} gwei", format_gwei(vanity.cost.max_fee_per_gas)));
+    row("Tip paid", format!("{} gwei", format_gwei(immediate.cost.effective_tip)), format!("{} gwei", format_gwei(vanity.cost.effective_tip)));
+    row("Inclusion", blocks(immediate), blocks(vanity));
+    row("Total time", total(immediate), total(vanity));
+    row("Expected cost", format!("{} {}", format_eth(immediate.cost.expected()), symbol), format!("{} {}", format_eth(vanity.cost.expected()), symbol));
+    row("Worst-case cost", format!("{} {}", format_eth(immediate.cost.worst_case()), symbol), format!("{} {}", format_eth(vanity.cost.worst_case()), symbol));
+    println!("{}", mining);
+    if block_seconds.is_none() {
+        println!("The chain's block timestamps give no block time, so inclusion is left in blocks and out of the total time");
+    }
+
+    let usd_price = native_usd_price(chain_id)?;
+    println!("The vanity hash adds:");
+    println!("  Time:            {}", units::format_duration(comparison.extra_seconds()));
+    println!(
+        "  Expected cost:   {}",
+        format_premium(vanity.cost.expected(), immediate.cost.expected(), comparison.expected_premium_percent(), chain_id, usd_price)
+    );
+    println!(
+        "  Worst-case cost: {}",
+        format_premium(vanity.cost.worst_case(), immediate.cost.worst_case(), comparison.worst_case_premium_percent(), chain_id, usd_price)
+    );
+    Ok(())
+}
+
+fn predict_address(args: PredictAddressArgs) -> eyre::Result<()> {
+    let predictions: Vec<Prediction> = match args.scheme {
+        PredictScheme::Create { sender, nonce } => nonce.into_iter().map(|nonce| Prediction::create(sender, nonce)).collect(),
+        PredictScheme::Create2 { deployer, salt, init_code_hash, init_code } => {
+            let init_code_hash = match (init_code_hash, init_code) {
+                (Some(hash), _) => hash,
+                (None, Some(code)) => H256(keccak256(&code)),
+                (None, None) => unreachable!("clap requires one of them"),
+            };
+            salt.into_iter().map(|salt| Prediction::create2(deployer, salt, init_code_hash)).collect()
+        }
+        PredictScheme::Create3 { deployer, salt } => salt.into_iter().map(|salt| Prediction::create3(deployer, salt)).collect(),
+    };
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&predictions)?);
+    } else {
+        for prediction in &predictions {
+            println!("{}", prediction);
+        }
+    }
+    Ok(())
+}
+
+async fn plan_address(args: PlanAddressArgs) -> eyre::Result<()> {
+    let wallet = env::var("PRIVATE_KEY")?.parse::<LocalWallet>()?;
+    let from = wallet.address();
+    let chains = connect_chains(&args.chains, from).await?;
+    let target_nonce = chains.iter().map(|(_, _, nonce)| *nonce).max().unwrap_or_default();
+    print_address_plan(&chains, from, target_nonce);
+    if chains.iter().any(|(_, _, nonce)| *nonce < target_nonce) {
+        println!("Fillers can be any transaction from {:?}, e.g. 0-value transfers to yourself.", from);
+        let history_path = config::history_path();
+        for (chain_id, provider, nonce) in &chains {
+            if *nonce < target_nonce {
+                offer_fillers(provider, &wallet, *chain_id, *nonce, target_nonce, &history_path).await?;
+            }
+        }
+        println!("Mine with `multichain --same-address`, or per chain with NONCE={}.", target_nonce);
+    }
+    Ok(())
+}
+
+/// Offers to advance the account's nonce on `chain_id` from `current_nonce` to `target_nonce`
+/// with zero-value self-transfers, after showing what they cost. Returns true once every filler
+/// is included, false if the user declines.
+async fn offer_fillers(
+    provider: &rpc::RpcProvider,
+    wallet: &LocalWallet,
+    chain_id: u64,
+    current_nonce: U256,
+    target_nonce: U256,
+    history_path: &str,
+) -> eyre::Result<bool> {
+    let market = overhead::market_fees(provider).await?;
+    let plan = FillerPlan::new(chain_id, wallet.address(), current_nonce, target_nonce, &market);
+    let symbol = chains::symbol(chain_id);
+    println!(
+        "Chain {}: {} filler transaction(s) are needed first, 0-value self-transfers at nonces {} to {}",
+        chains::describe(chain_id),
+        plan.count(),
+        current_nonce,
+        target_nonce - 1
+    );
+    println!(
+        "  Cost: about {} {} expected, at most {} {} ({} gas each at up to {} gwei)",
+        format_eth(plan.expected_cost(market.base_fee)),
+        symbol,
+        format_eth(plan.max_cost()),
+        symbol,
+        filler::FILLER_GAS,
+        format_gwei(plan.max_fee_per_gas)
+    );
+    print!("Send the fillers? (y/n): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        return Ok(false);
+    }
+    plan.send(provider, wallet, history_path).await?;
+    println!("Chain {}: the account is now at nonce {}", chains::describe(chain_id), target_nonce);
+    Ok(true)
+}
+
+/// Runs the whole flow on a testnet with a scaled-down prefix and checks the result on chain
+/// before starting the real search, so a bad calldata or gas limit costs testnet funds only.
+async fn rehearse(args: RehearseArgs, search_args: SearchArgs, parameters: &Parameters) -> eyre::Result<Status> {
+    let chain_id = chains::chain_id_from_env()?;
+    if args.testnet == chain_id {
+        eyre::bail!("the rehearsal chain must differ from the target chain {}", chains::describe(chain_id));
+    }
+    let hash_prefix = hash_prefix_from_env()?;
+    let digits = pattern::normalize(&hash_prefix);
+    let rehearsal_prefix = format!("0x{}", &digits[..digits.len().min(args.prefix_digits)]);
+    let tx_spec = TxSpec::from_env(parameters)?;
+    let history_path = config::history_path();
+    let journal_path = config::journal_path();
+    let journal = Arc::new(Journal::open_with_passphrase(&journal_path, config::journal_passphrase().as_deref())?);
+    let wallet: LocalWallet = env::var("PRIVATE_KEY")?.parse()?;
+    let from = wallet.address();
+
+    let (testnet, provider, nonce) = connect_chains(&[args.testnet], from).await?.remove(0);
+    let template = tx_spec.template(testnet, nonce);
+    let (fees, salt) = search_fees(testnet, &provider, &tx_spec).await?;
+    println!(
+        "Rehearsing on chain {} with prefix {} at nonce {}",
+        chains::describe(testnet),
+        rehearsal_prefix,
+        nonce
+    );
+    let params = SearchParams {
+        sampler: CandidateSampler::from_env(&format!("chain {} nonce {} prefix {}", testnet, nonce, rehearsal_prefix))?,
+        verifier: verifier()?,
+        limits: resource_limits()?,
+        ..search_params(envelope::signer_for(wallet.with_chain_id(testnet), testnet)?, template.clone(), rehearsal_prefix.clone(), &fees, salt)?
+    };
+    let Some(found_match) = mine(params, journal).await?.0 else {
+        println!("Rehearsal interrupted, the real search was not started");
+        return Ok(no_match_status());
+    };
+
+    let tx_hash = H256::from(found_match.tx_hash);
+    verify_before_sending(&found_match.signed_rlp, tx_hash, from, testnet, nonce)
+        .map_err(|e| e.wrap_err("the rehearsal transaction failed verification, nothing was sent"))?;
+    println!("Rehearsal: sending {:?}...", tx_hash);
+    // Testnets have no private relays, so the rehearsal always goes through the public RPC
+    let broadcaster = PublicRpc { provider: provider.clone() };
+    let receipt = async {
+        let Submission::Sent(sent) = broadcaster.submit(&found_match.signed_rlp).await? else {
+            eyre::bail!("the rehearsal transaction was not sent");
+        };
+        nonces::record_sent(&history_path, from, testnet, nonce);
+        broadcaster.wait_for_receipt(&provider, sent).instrument(info_span!("inclusion")).await
+    }
+    .instrument(info_span!("broadcast", tx_hash = %format!("{:?}", tx_hash), chain_id = testnet))
+    .await?;
+
+    // Everything the real run relies on: the hash, the inclusion, the execution and the address
+    let mut problems = Vec::new();
+    match &receipt {
+        None => problems.push("the transaction was dropped".to_string()),
+        Some(receipt) => {
+            if receipt.transaction_hash != tx_hash {
+                problems.push(format!("the node reports hash {:?}, not {:?}", receipt.transaction_hash, tx_hash));
+            }
+            if !format!("{:?}", receipt.transaction_hash).starts_with(&rehearsal_prefix) {
+                problems.push(format!("the included hash does not start with {}", rehearsal_prefix));
+            }
+            if receipt.status.map(|s| s.as_u64()) == Some(0) {
+                problems.push("the transaction reverted".to_string());
+            }
+            if template.to.is_none() {
+                let expected = get_contract_address(from, nonce);
+                match receipt.contract_address {
+                    None => problems.push("no contract was created".to_string()),
+                    Some(actual) if actual != expected => {
+                        problems.push(format!("the contract was created at {:?}, expected {:?}", actual, expected))
+                    }
+                    Some(_) => {
+                        if provider.get_code(expected, None).await?.is_empty() {
+                            problems.push(format!("no code was deployed at {:?}", expected));
+                        }
+                    }
+                }
+            }
+            record_deployment(&history_path, testnet, from, nonce, &found_match.tx, receipt);
+            print_explorer_links(testnet, receipt.transaction_hash, receipt.contract_address);
+        }
+    }
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("Rehearsal check failed: {}", problem);
+        }
+        eyre::bail!("rehearsal on chain {} failed, the real search was not started", chains::describe(testnet));
+    }
+
+    println!(
+        "Rehearsal passed, starting the real search on chain {} with prefix {}",
+        chains::describe(chain_id),
+        hash_prefix
+    );
+    run_search(search_args, parameters).await
+}
+
+async fn serve(args: ServeArgs) -> eyre::Result<()> {
+    let chain_id = chains::chain_id_from_env()?;
+    let config = ServerConfig {
+        wallet: env::var("PRIVATE_KEY")?.parse::<LocalWallet>()?.with_chain_id(chain_id),
+        rpc: RpcConfig::from_env()?,
+        chain_id,
+        fees: FeeProfile::from_env(chain_id)?,
+        thread_count: search::default_thread_count(),
+        scheduling: config::non_empty_var("SCHEDULING").map(|s| s.parse()).transpose()?.unwrap_or(server::Scheduling::Sequential),
+        journal_path: config::journal_path().into(),
+        journal_passphrase: config::journal_passphrase(),
+        history_path: config::history_path().into(),
+        broadcast: BroadcastConfig::from_env(chain_id)?,
+        api_token: config::non_empty_var("API_TOKEN"),
+        match_policy: match_policy()?,
+        max_retained_matches: max_retained_matches()?,
+        max_failure_rate: max_failure_rate()?,
+        batch_size: batch_size(),
+        thermal_watchdog: config::thermal_watchdog(),
+        limits: resource_limits()?,
+    };
+    // Jobs sweep fees without a salt, which a pinned fee leaves nothing to sweep
+    eyre::ensure!(config.fees.vanity_fee.is_none(), "VANITY_FEE is not supported by the API server");
+    server::serve(args.listen, args.grpc, config).await
+}
+
+fn show_history(args: HistoryArgs) -> eyre::Result<()> {
+    let path = config::history_path();
+    let db = HistoryDb::open(&path)?;
+    let deployments = db.list(&HistoryFilter {
+        chain_id: args.chain,
+        from: args.from,
+        limit: args.limit,
+    })?;
+
+    match args.export.as_deref() {
+        Some("csv") => print!("{}", history::to_csv(&deployments)),
+        Some("json") => println!("{}", serde_json::to_string_pretty(&deployments)?),
+        _ => {
+            if deployments.is_empty() {
+                println!("No deployments recorded in {}", path);
+            }
+            for d in &deployments {
+                let when = chrono::DateTime::from_timestamp(d.timestamp, 0)
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                println!(
+                    "{} | chain {} | nonce {} | {:?} | {} | {} {}",
+                    when,
+                    chains::describe(d.chain_id),
+                    d.nonce,
+                    d.tx_hash,
+                    d.contract_address.map(|a| format!("{:?}", a)).unwrap_or_else(|| "-".to_string()),
+                    format_eth(d.cost_wei),
+                    chains::symbol(d.chain_id),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Bisects the gas limit for the configured transaction and compares it with GAS_LIMIT, which
+/// is what the worst-case cost is charged against.
+async fn gas_limit(args: GasLimitArgs, parameters: &Parameters) -> eyre::Result<()> {
+    let from = env::var("PRIVATE_KEY")?.parse::<LocalWallet>()?.address();
+    let chain_id = chains::chain_id_from_env()?;
+    let configured = config::non_empty_var("GAS_LIMIT").map(|limit| limit.parse::<u64>()).transpose()?.map(U256::from);
+    let tx_spec = TxSpec::from_env_with_gas_limit(parameters, configured.unwrap_or_default())?;
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+
+    let sweep = gaslimit::sweep(&provider, tx_spec.template(chain_id, U256::zero()), from, args.margin).await?;
+    if let Some(estimate) = sweep.estimate {
+        println!("Estimated:   {} (eth_estimateGas)", estimate);
+    }
+    println!("Smallest:    {} ({} eth_calls)", sweep.minimal, sweep.calls);
+    println!("Recommended: {} (+{}%)", sweep.recommended, args.margin);
+    match configured {
+        Some(configured) if configured < sweep.minimal => {
+            println!("GAS_LIMIT {} is too low: the transaction would run out of gas", configured)
+        }
+        Some(configured) if configured > sweep.recommended => {
+            let max_fee = overhead::market_fees(&provider).await?.max_fee_per_gas;
+            println!(
+                "GAS_LIMIT {} is {} more than needed, adding {} {} to the worst-case cost at the current max fee of {} gwei",
+                configured,
+                configured - sweep.recommended,
+                format_eth((configured - sweep.recommended) * max_fee),
+                chains::symbol(chain_id),
+                format_gwei(max_fee)
+            );
+        }
+        Some(_) => println!("GAS_LIMIT is already within the margin"),
+        None => {}
+    }
+    println!("GAS_LIMIT={}", sweep.recommended);
+    Ok(())
+}
+
+/// Sends a transaction that was signed earlier, e.g. one saved by BROADCASTER=file, after the
+/// same checks and confirmation as a freshly mined one.
+async fn broadcast_saved(args: BroadcastArgs) -> eyre::Result<Status> {
+    let wallet: LocalWallet = env::var("PRIVATE_KEY")?.parse()?;
+    let from = wallet.address();
+    let chain_id = chains::chain_id_from_env()?;
+    let history_path = config::history_path();
+    let broadcast_config = BroadcastConfig::from_env(chain_id)?;
+    if let BroadcastConfig::File(path) = &broadcast_config {
+        let e = eyre::eyre!("BROADCASTER=file only saves to {}; set BROADCASTER to a backend that sends", path.display());
+        return Err(exit::config_error(e));
+    }
+
+    let raw_tx = match args.tx.parse::<H256>() {
+        Ok(tx_hash) => find_signed_tx(chain_id, tx_hash)?,
+        Err(_) => args.tx.parse::<Bytes>().map_err(|e| eyre::eyre!("not a transaction hash or raw signed transaction: {}", e))?,
+    };
+    let tx_hash = H256::from(keccak256(&raw_tx));
+    let nonce = envelope::decode_signed(&raw_tx)?.tx.nonce().copied().unwrap_or_default();
+
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let account_nonce = provider.get_transaction_count(from, None).await?;
+    eyre::ensure!(
+        account_nonce == nonce,
+        "the account is at nonce {}, but the transaction has nonce {}",
+        account_nonce,
+        nonce
+    );
+    let verified = verify_before_sending(&raw_tx, tx_hash, from, chain_id, nonce)
+        .map_err(|e| e.wrap_err("the signed transaction failed verification, refusing to send it"))?;
+    println!("Verified: {:?} signed by {:?} for chain {} at nonce {}", tx_hash, from, chain_id, nonce);
+    print_transaction_preview(&verified.tx, chain_id);
+    if let Some(check) = NodeCheck::from_env()? {
+        if let Some(status) = check_with_node(&provider, check, &verified.tx, from, nonce, raw_tx.len()).await? {
+            return Ok(status);
+        }
+    }
+
+    let broadcaster = broadcast_config.connect(provider.clone()).await?;
+    print!("Send this transaction through {}? (y/n): ", broadcaster);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        println!("Aborted by user.");
+        return Ok(Status::UserAbort);
+    }
+    if let Some(approval) = ApprovalConfig::from_env()? {
+        let contract_address = verified.tx.to().is_none().then(|| get_contract_address(from, nonce));
+        let request = ApprovalRequest::new(chain_id, raw_tx.clone(), &verified.tx, from, contract_address);
+        if let Some(status) = await_approval(&approval, &request).await? {
+            return Ok(status);
+        }
+    }
+
+    let Submission::Sent(sent) = broadcaster.submit(&raw_tx).await.map_err(|e| exit::with_status(e, Status::BroadcastFailed))? else {
+        unreachable!("file-only broadcasting was refused above");
+    };
+    nonces::record_sent(&history_path, from, chain_id, nonce);
+    let receipt = broadcaster.wait_for_receipt(&provider, sent).await.map_err(|e| exit::with_status(e, Status::BroadcastFailed))?;
+    println!("Transaction sent! Receipt: {:?}", receipt);
+    if let Some(receipt) = &receipt {
+        print_explorer_links(chain_id, receipt.transaction_hash, receipt.contract_address);
+        if let TypedTransaction::Eip1559(tx) = &verified.tx {
+            record_deployment(&history_path, chain_id, from, nonce, tx, receipt);
+        }
+    }
+    Ok(receipt_status(receipt.as_ref()))
+}
+
+async fn export_proof(args: ExportProofArgs) -> eyre::Result<()> {
+    let signer: LocalWallet = env::var("PRIVATE_KEY")?.parse()?;
+    let chain_id = chains::chain_id_from_env()?;
+    let provider = rpc::connect(&RpcConfig::from_env()?).await?;
+    let receipt = provider
+        .get_transaction_receipt(args.tx_hash)
+        .await?
+        .ok_or_else(|| eyre::eyre!("{:?} has no receipt on chain {}; only included transactions can be exported", args.tx_hash, chains::describe(chain_id)))?;
+    // The bytes as signed here, or else re-encoded from the node's copy, which must hash the same
+    let raw_tx = match find_signed_tx(chain_id, args.tx_hash) {
+        Ok(raw_tx) => raw_tx,
+        Err(e) => {
+            let tx = provider.get_transaction(args.tx_hash).await?.ok_or(e)?;
+            let raw_tx = tx.rlp();
+            eyre::ensure!(
+                H256::from(keccak256(&raw_tx)) == args.tx_hash,
+                "{:?} is not saved locally, and the node's copy does not re-encode to the same hash",
+                args.tx_hash
+            );
+            raw_tx
+        }
+    };
+
+    let exported_at = chrono::Utc::now().timestamp();
+    let files = proof::build(&raw_tx, &receipt, chain_id, &signer, exported_at)?;
+    let manifest = proof::check(&files)?;
+    if manifest.signed_by != manifest.from {
+        println!("Warning: signing with {:?}, which did not send the transaction ({:?})", manifest.signed_by, manifest.from);
+    }
+    let name = format!("proof-{:?}", args.tx_hash);
+    let output = args.output.unwrap_or_else(|| PathBuf::from(format!("{}.tar", name)));
+    std::fs::write(&output, proof::to_tar(&name, &files, exported_at))?;
+    println!("Wrote {} with:", output.display());
+    for (file, contents) in &files {
+        println!("  {:<14} {} bytes", file, contents.len());
+    }
+    if let (Some(predicted), Some(actual)) = (manifest.predicted_address, manifest.actual_address) {
+        println!("Predicted address {:?}, actual {:?}{}", predicted, actual, if predicted == actual { "" } else { " (differ!)" });
+    }
+    println!("Signed by {:?}; unpack it and run verify.sh (with RPC_URL set to also check the chain)", manifest.signed_by);
+    Ok(())
+}
+
+/// The signed transaction with `tx_hash` from SIGNED_TX_PATH, or failing that the journal.
+fn find_signed_tx(chain_id: u64, tx_hash: H256) -> eyre::Result<Bytes> {
+    let saved_path = config::chain_var("SIGNED_TX_PATH", chain_id).unwrap_or_else(|| broadcast::DEFAULT_SIGNED_TX_PATH.to_string());
+    if let Some(raw_tx) = broadcast::read_saved(&saved_path, config::journal_passphrase().as_deref())?.into_iter().find(|raw_tx| H256::from(keccak256(raw_tx)) == tx_hash) {
+        return Ok(raw_tx);
+    }
+    let journal_path = config::journal_path();
+    Journal::read_entries_with_passphrase(&journal_path, config::journal_passphrase().as_deref())?
+        .into_iter()
+        .find(|entry| entry.tx_hash == tx_hash)
+        .map(|entry| entry.raw_tx)
+        .ok_or_else(|| eyre::eyre!("{:?} is in neither {} nor {}", tx_hash, saved_path, journal_path))
+}
+
+/// Lints the merged configuration, prints what is wrong with it and the effective settings as
+/// JSON, and exits with the configuration error status if anything would stop a search.
+fn check_config(template: Option<&str>, shell_vars: &[String], loaded: &[String], parameters: &Parameters) -> eyre::Result<Status> {
+    // Loading again only finds the file, since .env never overrides what is already set
+    let dotenv_vars = match dotenv::dotenv() {
+        Ok(path) => lint::dotenv_names(&std::fs::read_to_string(path)?),
+        Err(_) => Vec::new(),
+    };
+    let mut templated = Vec::new();
+    if let Some(name) = template {
+        let templates = DeployTemplates::load(config::templates_path()).map_err(exit::config_error)?;
+        for applied in templates::apply(templates.get(name).map_err(exit::config_error)?, shell_vars) {
+            if let Applied::Set(setting) = applied {
+                templated.push(setting);
+            }
+        }
+    }
+    let settings: BTreeMap<String, lint::Setting> = env::vars()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| {
+            let origin = if loaded.contains(&name) {
+                lint::Origin::SecretStore
+            } else if templated.contains(&name) {
+                lint::Origin::Template(template.unwrap_or_default().to_string())
+            } else if dotenv_vars.contains(&name) && !shell_vars.contains(&name) {
+                lint::Origin::DotEnv
+            } else {
+                lint::Origin::Shell
+            };
+            (name, lint::Setting { value, origin })
+        })
+        .collect();
+
+    let mut problems = lint::lint(&settings);
+    let (errors, resolved) = lint::validate(parameters);
+    problems.extend(errors);
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    println!("{}", serde_json::to_string_pretty(&lint::effective(&settings, parameters, resolved, &problems))?);
+    Ok(if problems.iter().any(|problem| problem.severity == lint::Severity::Error) { Status::ConfigError } else { Status::Sent })
+}
+
+/// Checks the shipped vectors, and those in `--file`, printing the outcome of each.
+fn verify_vectors(args: VerifyVectorsArgs) -> eyre::Result<()> {
+    let mut corpus = vectors::corpus();
+    if let Some(path) = &args.file {
+        corpus.extend(vectors::load(path).map_err(exit::config_error)?);
+    }
+    let mut failed = 0;
+    for (name, outcome) in vectors::check_all(&corpus) {
+        match outcome {
+            Ok(()) => println!("  ok      {}", name),
+            Err(e) => {
+                failed += 1;
+                println!("  FAILED  {}: {:#}", name, e);
+            }
+        }
+    }
+    eyre::ensure!(
+        failed == 0,
+        "{} of {} vectors failed; the encoding, hashing or signing has changed, so do not mine with this build",
+        failed,
+        corpus.len()
+    );
+    println!("All {} vectors encode, hash and sign as recorded", corpus.len());
+    Ok(())
+}
+
+fn show_journal() -> eyre::Result<()> {
+    let path = config::journal_path();
+    for entry in Journal::read_entries_with_passphrase(&path, config::journal_passphrase().as_deref())? {
+        println!("{}", serde_json::to_string(&entry)?);
+    }
+    Ok(())
+}
+
+/// HASH_PREFIX, validated and normalized to lowercase with a `0x`. Mixed-case input is matched
+/// case-insensitively, so the way the hash will actually display is shown.
+fn hash_prefix_from_env() -> eyre::Result<String> {
+    let input = env::var("HASH_PREFIX")?;
+    pattern::validate(&input).map_err(|e| exit::config_error(eyre::eyre!("HASH_PREFIX: {}", e)))?;
+    let prefix = pattern::to_prefix(&input);
+    if input.trim_start_matches("0x") != prefix.trim_start_matches("0x") {
+        println!("Hashes are lowercase hex, so explorers will show the match as {}", pattern::display_preview(&input));
+    }
+    Ok(prefix)
+}
+
+/// MATCH_POLICY, which of several simultaneous matches to use: `first` (default) or `cheapest`.
+fn match_policy() -> eyre::Result<MatchPolicy> {
+    let policy = config::non_empty_var("MATCH_POLICY").map(|s| s.parse()).transpose().map_err(exit::config_error)?;
+    Ok(policy.unwrap_or(MatchPolicy::First))
+}
+
+/// The patterns listed in PATTERN_FILE, accepted instead of HASH_PREFIX alone and re-read on SIGHUP.
+fn live_patterns(hash_prefix: &str) -> eyre::Result<Option<Arc<LivePatterns>>> {
+    let Some(path) = config::non_empty_var("PATTERN_FILE") else {
+        return Ok(None);
+    };
+    let patterns = LivePatterns::load(path).map_err(|e| exit::config_error(e.wrap_err("invalid PATTERN_FILE")))?;
+    let accepted = patterns.accepted();
+    let reload = match cfg!(unix) {
+        true => format!("; edit it and send SIGHUP (kill -HUP {}) to change them", std::process::id()),
+        false => String::new(),
+    };
+    println!("Accepting any of {} from {}{}", accepted.join(", "), patterns.path().display(), reload);
+    if !accepted.iter().any(|prefix| prefix == hash_prefix) {
+        println!("Warning: {} does not list HASH_PREFIX {}, which only sets the estimates and STOP_POLICY", patterns.path().display(), hash_prefix);
+    }
+    Ok(Some(Arc::new(patterns)))
+}
+
+/// The external verifier from VERIFIER_COMMAND or VERIFIER_SOCKET that every match must pass.
+fn verifier() -> eyre::Result<Option<Arc<Verifier>>> {
+    let verifier = Verifier::from_env()?;
+    if let Some(verifier) = &verifier {
+        println!("Matches are checked by the {} before use", verifier.description());
+    }
+    Ok(verifier.map(Arc::new))
+}
+
+/// MAX_CPU_SECONDS and MAX_SIGNATURES, after which a search stops and can be resumed later.
+fn resource_limits() -> eyre::Result<ResourceLimits> {
+    let limits = ResourceLimits::from_env()?;
+    if !limits.is_unlimited() {
+        println!("Resource limits: {} per search", limits);
+    }
+    Ok(limits)
+}
+
+/// STOP_POLICY, when to settle for a match of only part of `hash_prefix`.
+fn stop_policy(hash_prefix: &str) -> eyre::Result<Option<StopPolicy>> {
+    let Some(policy) = config::non_empty_var("STOP_POLICY") else {
+        return Ok(None);
+    };
+    let policy: StopPolicy = policy.parse().map_err(|e: eyre::Report| exit::config_error(e.wrap_err("invalid STOP_POLICY")))?;
+    policy.validate(pattern::normalize(hash_prefix).len()).map_err(exit::config_error)?;
+    println!("Stop policy: the full prefix {}, or {}", hash_prefix, policy);
+    Ok(Some(policy))
+}
+
+/// MAX_RETAINED_MATCHES, how many simultaneous matches to hold in memory while choosing one. In
+/// low-memory mode only the first is kept by default; the others are in the journal.
+fn max_retained_matches() -> eyre::Result<usize> {
+    let default = if config::low_memory() { 1 } else { search::DEFAULT_MAX_RETAINED_MATCHES };
+    Ok(config::non_empty_var("MAX_RETAINED_MATCHES").map(|s| s.parse()).transpose()?.unwrap_or(default))
+}
+
+/// A search over `template` with the settings every search reads from the environment: MATCH_POLICY,
+/// MAX_RETAINED_MATCHES, MAX_FAILURE_RATE and the low-memory batch size. Callers add the sampler,
+/// verifier and limits themselves, since those print what they read.
+fn search_params(
+    signer: Arc<dyn CandidateSigner>,
+    template: Eip1559TransactionRequest,
+    hash_prefix: String,
+    fees: &FeeProfile,
+    salt: Salt,
+) -> eyre::Result<SearchParams> {
+    Ok(SearchParams {
+        match_policy: match_policy()?,
+        max_retained_matches: max_retained_matches()?,
+        max_failure_rate: max_failure_rate()?,
+        batch_size: batch_size(),
+        ..SearchParams::new(signer, template, hash_prefix, fees, salt)
+    })
+}
+
+/// Candidates each worker signs at a time, fewer in low-memory mode.
+fn batch_size() -> usize {
+    if config::low_memory() {
+        search::LOW_MEMORY_BATCH_SIZE
+    } else {
+        search::BATCH_SIZE
+    }
+}
+
+/// MAX_FAILURE_RATE, the percentage of candidates that may fail to sign before the search aborts.
+fn max_failure_rate() -> eyre::Result<f64> {
+    match config::non_empty_var("MAX_FAILURE_RATE") {
+        Some(rate) => match rate.parse::<f64>().map_err(|e| exit::config_error(eyre::eyre!("invalid MAX_FAILURE_RATE: {}", e)))? {
+            rate if (0.0..=100.0).contains(&rate) => Ok(rate / 100.0),
+            _ => Err(exit::config_error(eyre::eyre!("MAX_FAILURE_RATE must be a percentage between 0 and 100"))),
+        },
+        None => Ok(search::DEFAULT_MAX_FAILURE_RATE),
+    }
+}
+
+/// NATIVE_USD_PRICE (or NATIVE_USD_PRICE_<chain_id>), the price of the chain's native currency.
+fn native_usd_price(chain_id: u64) -> eyre::Result<Option<f64>> {
+    config::chain_var("NATIVE_USD_PRICE", chain_id)
+        .map(|price| price.parse().map_err(|e| eyre::eyre!("invalid NATIVE_USD_PRICE: {}", e)))
+        .transpose()
+}
+
+/// Formats the premium of `vanity` over `market` (wei) as e.g. `+0.0000012 ETH / +0.0041 USD (+45.2%)`.
+fn format_premium(vanity: U256, market: U256, percent: f64, chain_id: u64, usd_price: Option<f64>) -> String {
+    let premium = units::format_eth_difference(vanity, market);
+    match usd_price {
+        Some(price) => {
+            let premium_usd = (units::wei_to_f64(vanity) - units::wei_to_f64(market)) / 1e18 * price;
+            format!("{} {} / {:+.4} USD ({:+.1}%)", premium, chains::symbol(chain_id), premium_usd, percent)
+        }
+        None => format!("{} {} ({:+.1}%)", premium, chains::symbol(chain_id), percent),
+    }
 }
@@ -0,0 +1,90 @@
+use crate::stats::{FailureKind, SearchStats};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Serves search statistics in the Prometheus text exposition format on `GET /metrics`.
+/// Runs until the process exits.
+pub async fn serve(addr: SocketAddr, stats: Arc<SearchStats>) -> eyre::Result<()> {
+    let make_svc = make_service_fn(move |_conn| {
+        let stats = stats.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let stats = stats.clone();
+                async move { Ok::<_, Infallible>(handle(req, &stats)) }
+            }))
+        }
+    });
+
+    Server::try_bind(&addr)?.serve(make_svc).await?;
+    Ok(())
+}
+
+fn handle(req: Request<Body>, stats: &SearchStats) -> Response<Body> {
+    if req.uri().path() != "/metrics" {
+        let mut not_found = Response::new(Body::from("not found\n"));
+        *not_found.status_mut() = StatusCode::NOT_FOUND;
+        return not_found;
+    }
+
+    let mut response = Response::new(Body::from(render(stats)));
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    response
+}
+
+/// Renders the current statistics as Prometheus text.
+pub fn render(stats: &SearchStats) -> String {
+    let mut out = String::new();
+    let (fee_low, fee_high) = stats.fee_window();
+
+    metric(&mut out, "vanity_attempts_total", "counter", "Candidate transactions signed and hashed.", stats.total_attempts() as f64);
+    metric(&mut out, "vanity_hashrate", "gauge", "Average candidates per second since the search started.", stats.hashrate());
+    metric(&mut out, "vanity_fee_window_low_wei", "gauge", "Lowest max_fee_per_gas tried so far.", fee_low as f64);
+    metric(&mut out, "vanity_fee_window_high_wei", "gauge", "Highest max_fee_per_gas tried so far.", fee_high as f64);
+    metric(&mut out, "vanity_base_fee_wei", "gauge", "Latest observed network base fee.", stats.base_fee() as f64);
+    metric(&mut out, "vanity_uptime_seconds", "gauge", "Seconds since the search started.", stats.elapsed().as_secs_f64());
+
+    let _ = writeln!(out, "# HELP vanity_thread_attempts_total Candidates tried per worker thread.");
+    let _ = writeln!(out, "# TYPE vanity_thread_attempts_total counter");
+    for (i, worker) in stats.workers().iter().enumerate() {
+        let _ = writeln!(out, "vanity_thread_attempts_total{{thread=\"{}\"}} {}", i, worker.attempts());
+    }
+    let _ = writeln!(out, "# HELP vanity_thread_hashrate Average candidates per second per worker thread.");
+    let _ = writeln!(out, "# TYPE vanity_thread_hashrate gauge");
+    for i in 0..stats.workers().len() {
+        let _ = writeln!(out, "vanity_thread_hashrate{{thread=\"{}\"}} {}", i, stats.worker_hashrate(i));
+    }
+    let _ = writeln!(out, "# HELP vanity_thread_sign_failures_total Candidates that failed to sign or encode per worker thread.");
+    let _ = writeln!(out, "# TYPE vanity_thread_sign_failures_total counter");
+    for (i, worker) in stats.workers().iter().enumerate() {
+        let _ = writeln!(out, "vanity_thread_sign_failures_total{{thread=\"{}\"}} {}", i, worker.sign_failures());
+    }
+    let _ = writeln!(out, "# HELP vanity_sign_failures_total Candidates that failed to sign or encode, by kind of failure.");
+    let _ = writeln!(out, "# TYPE vanity_sign_failures_total counter");
+    for kind in FailureKind::ALL {
+        let _ = writeln!(out, "vanity_sign_failures_total{{kind=\"{}\"}} {}", kind, stats.total_failures(kind));
+    }
+    let _ = writeln!(out, "# HELP vanity_thread_duplicates_skipped_total Fees skipped per worker thread because another worker already tried them.");
+    let _ = writeln!(out, "# TYPE vanity_thread_duplicates_skipped_total counter");
+    for (i, worker) in stats.workers().iter().enumerate() {
+        let _ = writeln!(out, "vanity_thread_duplicates_skipped_total{{thread=\"{}\"}} {}", i, worker.duplicates_skipped());
+    }
+    let _ = writeln!(out, "# HELP vanity_thread_respawns_total Times a worker thread died and was restarted.");
+    let _ = writeln!(out, "# TYPE vanity_thread_respawns_total counter");
+    for (i, worker) in stats.workers().iter().enumerate() {
+        let _ = writeln!(out, "vanity_thread_respawns_total{{thread=\"{}\"}} {}", i, worker.respawns());
+    }
+    out
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: f64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
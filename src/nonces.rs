@@ -0,0 +1,81 @@
+use crate::history::HistoryDb;
+use ethers::prelude::*;
+use std::fmt;
+use std::path::Path;
+
+/// A disagreement between the node and the nonces this tool last sent with, found before a
+/// search starts. Any of them means the nonce being mined for may not be the one that lands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceConflict {
+    /// Transactions from the account are waiting in the mempool, so the latest nonce is about
+    /// to move.
+    Pending { latest: u64, pending: u64 },
+    /// The account sent transactions this tool did not, e.g. from a wallet or other tooling.
+    AdvancedElsewhere { last_used: u64, latest: u64 },
+    /// This tool already sent `last_used`, but the node has not seen it: the node may be behind,
+    /// or the transaction was dropped.
+    Behind { last_used: u64, latest: u64 },
+}
+
+impl fmt::Display for NonceConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending { latest, pending } => write!(
+                f,
+                "{} transaction(s) are pending (nonce {} confirmed, {} pending); a match mined now may be replaced or blocked",
+                pending - latest,
+                latest,
+                pending
+            ),
+            Self::AdvancedElsewhere { last_used, latest } => write!(
+                f,
+                "the nonce moved from {} to {} outside this tool since it last sent; check nothing else is using this key",
+                last_used + 1,
+                latest
+            ),
+            Self::Behind { last_used, latest } => write!(
+                f,
+                "this tool already sent nonce {}, but the node reports nonce {}; the node may be behind or the transaction was dropped",
+                last_used,
+                latest
+            ),
+        }
+    }
+}
+
+/// Compares the last nonce this tool sent with (if any) against the node's latest and pending
+/// transaction counts.
+pub fn conflicts(last_used: Option<u64>, latest: u64, pending: u64) -> Vec<NonceConflict> {
+    let mut conflicts = Vec::new();
+    if pending > latest {
+        conflicts.push(NonceConflict::Pending { latest, pending });
+    }
+    match last_used {
+        Some(last_used) if latest > last_used + 1 => conflicts.push(NonceConflict::AdvancedElsewhere { last_used, latest }),
+        Some(last_used) if latest <= last_used && pending <= last_used => {
+            conflicts.push(NonceConflict::Behind { last_used, latest })
+        }
+        _ => {}
+    }
+    conflicts
+}
+
+/// Cross-checks the nonce cache in the history database against the node for `from`.
+pub async fn cross_check<M: Middleware>(provider: &M, history_path: &str, from: Address, chain_id: u64) -> eyre::Result<Vec<NonceConflict>>
+where
+    M::Error: 'static,
+{
+    let last_used = HistoryDb::open(history_path)?.last_nonce(from, chain_id)?;
+    let latest = provider.get_transaction_count(from, Some(BlockNumber::Latest.into())).await?;
+    let pending = provider.get_transaction_count(from, Some(BlockNumber::Pending.into())).await?;
+    Ok(conflicts(last_used, latest.as_u64(), pending.as_u64()))
+}
+
+/// Records that `from` sent `nonce` on `chain_id`, warning rather than failing since the
+/// transaction is already out.
+pub fn record_sent(history_path: impl AsRef<Path>, from: Address, chain_id: u64, nonce: U256) {
+    let history_path = history_path.as_ref();
+    if let Err(e) = HistoryDb::open(history_path).and_then(|db| db.record_nonce(from, chain_id, nonce.as_u64())) {
+        println!("Warning: failed to record nonce {} in {}: {}", nonce, history_path.display(), e);
+    }
+}
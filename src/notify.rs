@@ -0,0 +1,266 @@
+use crate::exit;
+use crate::payload::MatchPayload;
+use crate::smtp::SmtpConfig;
+use eyre::{ensure, eyre};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Something worth telling someone about. Each event is routed to its own channels with
+/// `NOTIFY_ROUTE_<EVENT>` and worded with `NOTIFY_TEMPLATE_<EVENT>`, e.g. `NOTIFY_ROUTE_MATCH_FOUND`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Event {
+    /// A match is waiting for confirmation.
+    MatchFound,
+    /// A match went unconfirmed for too long and is being mined again.
+    MatchExpired,
+    BroadcastSent,
+    BroadcastConfirmed,
+    /// The transaction reverted, was dropped or could not be sent.
+    BroadcastFailed,
+}
+
+impl Event {
+    pub const ALL: [Event; 5] = [Event::MatchFound, Event::MatchExpired, Event::BroadcastSent, Event::BroadcastConfirmed, Event::BroadcastFailed];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::MatchFound => "match_found",
+            Event::MatchExpired => "match_expired",
+            Event::BroadcastSent => "broadcast_sent",
+            Event::BroadcastConfirmed => "broadcast_confirmed",
+            Event::BroadcastFailed => "broadcast_failed",
+        }
+    }
+
+    /// Used when `NOTIFY_TEMPLATE_<EVENT>` is not set.
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            Event::MatchFound => "Match {tx_hash} on chain {chain} is ready to confirm",
+            Event::MatchExpired => "Match {tx_hash} on chain {chain} expired ({reason}), mining again",
+            Event::BroadcastSent => "Sent {tx_hash} on chain {chain}",
+            Event::BroadcastConfirmed => "Confirmed {tx_hash} on chain {chain} in block {block}, contract {contract}",
+            Event::BroadcastFailed => "Transaction {tx_hash} on chain {chain} failed: {error}",
+        }
+    }
+
+    fn env_suffix(&self) -> String {
+        self.name().to_uppercase()
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Where a message goes, from `NOTIFY_CHANNEL_<NAME>=<kind>:<target>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Channel {
+    /// Slack incoming webhook, `slack:https://hooks.slack.com/...`.
+    Slack(String),
+    /// Discord webhook, `discord:https://discord.com/api/webhooks/...`.
+    Discord(String),
+    /// Telegram bot, `telegram:<bot token>@<chat id>`.
+    Telegram { token: String, chat_id: String },
+    /// Any URL accepting a JSON POST with the event, the text, every template variable and, for
+    /// match events, the match as `match` (see `payload::SCHEMA`).
+    Webhook(String),
+    /// Email through `SMTP_URL`, `email:oncall@example.com`.
+    Email(String),
+    /// `notify-send` on Linux, `osascript` on macOS, a PowerShell toast on Windows.
+    Desktop,
+}
+
+impl Channel {
+    pub fn parse(spec: &str) -> eyre::Result<Self> {
+        if spec == "desktop" {
+            return Ok(Channel::Desktop);
+        }
+        let (kind, target) = spec.split_once(':').ok_or_else(|| eyre!("expected <kind>:<target>, got {:?}", spec))?;
+        ensure!(!target.is_empty(), "{} channel has no target", kind);
+        Ok(match kind {
+            "slack" => Channel::Slack(target.to_string()),
+            "discord" => Channel::Discord(target.to_string()),
+            "webhook" => Channel::Webhook(target.to_string()),
+            "email" => Channel::Email(target.to_string()),
+            "telegram" => {
+                let (token, chat_id) =
+                    target.rsplit_once('@').ok_or_else(|| eyre!("telegram channel must be telegram:<bot token>@<chat id>"))?;
+                Channel::Telegram { token: token.to_string(), chat_id: chat_id.to_string() }
+            }
+            _ => eyre::bail!("unknown channel kind {:?} (slack, discord, telegram, webhook, email or desktop)", kind),
+        })
+    }
+
+    async fn send(&self, event: Event, text: &str, vars: &[(&str, String)], found: Option<&MatchPayload>) -> eyre::Result<()> {
+        match self {
+            Channel::Slack(url) => post_json(url, json!({ "text": text })).await,
+            Channel::Discord(url) => post_json(url, json!({ "content": text })).await,
+            Channel::Telegram { token, chat_id } => {
+                let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
+                post_json(&url, json!({ "chat_id": chat_id, "text": text })).await
+            }
+            Channel::Webhook(url) => {
+                let mut body = Map::new();
+                body.insert("event".into(), event.name().into());
+                body.insert("text".into(), text.into());
+                for (name, value) in vars {
+                    body.insert(name.to_string(), value.clone().into());
+                }
+                if let Some(found) = found {
+                    body.insert("match".into(), found.to_value());
+                }
+                post_json(url, Value::Object(body)).await
+            }
+            Channel::Email(to) => {
+                let smtp = SmtpConfig::from_env()?.ok_or_else(|| eyre!("email channels need SMTP_URL and SMTP_FROM"))?;
+                smtp.send(to, &format!("Vanity transaction: {}", event), text).await
+            }
+            Channel::Desktop => Ok(desktop_notification(text)?),
+        }
+    }
+}
+
+/// Which channels hear about which events, and what they are told.
+#[derive(Debug, Clone, Default)]
+pub struct Routes {
+    pub channels: BTreeMap<String, Channel>,
+    pub routes: BTreeMap<Event, Vec<String>>,
+    pub templates: BTreeMap<Event, String>,
+}
+
+impl Routes {
+    /// Reads `NOTIFY_CHANNEL_*`, `NOTIFY_ROUTE_*` and `NOTIFY_TEMPLATE_*`. The older
+    /// `NOTIFY_WEBHOOK` and `NOTIFY_DESKTOP` still page on match_found.
+    pub fn from_env() -> eyre::Result<Self> {
+        Self::from_vars(std::env::vars().filter(|(_, value)| !value.trim().is_empty())).map_err(exit::config_error)
+    }
+
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> eyre::Result<Self> {
+        let vars: BTreeMap<String, String> = vars.into_iter().collect();
+        let mut routes = Self::default();
+        for (key, value) in &vars {
+            if let Some(name) = key.strip_prefix("NOTIFY_CHANNEL_") {
+                let channel = Channel::parse(value.trim()).map_err(|e| e.wrap_err(format!("invalid {}", key)))?;
+                routes.channels.insert(name.to_lowercase(), channel);
+            }
+        }
+        for event in Event::ALL {
+            if let Some(names) = vars.get(&format!("NOTIFY_ROUTE_{}", event.env_suffix())) {
+                let names: Vec<String> =
+                    names.split(',').map(|name| name.trim().to_lowercase()).filter(|name| !name.is_empty()).collect();
+                for name in &names {
+                    ensure!(
+                        routes.channels.contains_key(name),
+                        "NOTIFY_ROUTE_{} names channel {:?}, but NOTIFY_CHANNEL_{} is not set",
+                        event.env_suffix(),
+                        name,
+                        name.to_uppercase()
+                    );
+                }
+                routes.routes.insert(event, names);
+            }
+            if let Some(template) = vars.get(&format!("NOTIFY_TEMPLATE_{}", event.env_suffix())) {
+                routes.templates.insert(event, template.clone());
+            }
+        }
+
+        let mut legacy = |name: &str, channel: Channel| {
+            routes.channels.insert(name.to_string(), channel);
+            routes.routes.entry(Event::MatchFound).or_default().push(name.to_string());
+            routes.routes.entry(Event::MatchExpired).or_default().push(name.to_string());
+        };
+        if let Some(url) = vars.get("NOTIFY_WEBHOOK") {
+            legacy("notify_webhook", Channel::Webhook(url.clone()));
+        }
+        if vars.contains_key("NOTIFY_DESKTOP") {
+            legacy("notify_desktop", Channel::Desktop);
+        }
+        Ok(routes)
+    }
+
+    /// The message for `event`, from its template with `{name}` replaced by each variable.
+    pub fn render(&self, event: Event, vars: &[(&str, String)]) -> String {
+        let template = self.templates.get(&event).map_or(event.default_template(), String::as_str);
+        render(template, vars)
+    }
+
+    /// The channels `event` is routed to.
+    pub fn channels_for(&self, event: Event) -> Vec<(&str, &Channel)> {
+        self.routes
+            .get(&event)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| self.channels.get(name).map(|channel| (name.as_str(), channel)))
+            .collect()
+    }
+}
+
+/// Replaces `{name}` with the matching variable; unknown placeholders are left as they are.
+pub fn render(template: &str, vars: &[(&str, String)]) -> String {
+    vars.iter().fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+/// Tells every channel routed for `event`. Template variables are `tx_hash`, `chain`, `contract`,
+/// `block` and `error`, where they apply. Failures are only warned about, since everything sent is
+/// also printed.
+pub async fn notify(event: Event, vars: &[(&str, String)]) {
+    notify_match(event, vars, None).await
+}
+
+/// Like [`notify`], also giving webhooks the match itself.
+pub async fn notify_match(event: Event, vars: &[(&str, String)], found: Option<&MatchPayload>) {
+    let routes = match Routes::from_env() {
+        Ok(routes) => routes,
+        Err(e) => {
+            println!("Warning: notifications are misconfigured: {:#}", e);
+            return;
+        }
+    };
+    let text = routes.render(event, vars);
+    for (name, channel) in routes.channels_for(event) {
+        if let Err(e) = channel.send(event, &text, vars, found).await {
+            println!("Warning: failed to send {} notification to channel {}: {}", event, name, e);
+        }
+    }
+}
+
+async fn post_json(url: &str, body: Value) -> eyre::Result<()> {
+    reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_notification(message: &str) -> std::io::Result<()> {
+    let script = format!("display notification {:?} with title \"Vanity transaction\"", message);
+    std::process::Command::new("osascript").args(["-e", &script]).spawn().map(|_| ())
+}
+
+/// A Windows toast through PowerShell, with the message passed in the environment to avoid quoting.
+#[cfg(target_os = "windows")]
+fn desktop_notification(message: &str) -> std::io::Result<()> {
+    const SCRIPT: &str = "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+        $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+        $text = $xml.GetElementsByTagName('text'); \
+        $text.Item(0).AppendChild($xml.CreateTextNode('Vanity transaction')) > $null; \
+        $text.Item(1).AppendChild($xml.CreateTextNode($env:VANITY_NOTIFICATION)) > $null; \
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('find_tx_hash_prefix').Show([Windows.UI.Notifications.ToastNotification]::new($xml))";
+    std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("VANITY_NOTIFICATION", message)
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn desktop_notification(message: &str) -> std::io::Result<()> {
+    std::process::Command::new("notify-send").args(["Vanity transaction", message]).spawn().map(|_| ())
+}
@@ -0,0 +1,126 @@
+use crate::config::{chain_var, non_empty_var};
+use crate::exit;
+use crate::overhead::MarketFees;
+use crate::units::parse_gwei;
+use ethers::types::U256;
+use eyre::eyre;
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+const ETHERSCAN_URL: &str = "https://api.etherscan.io/v2/api";
+const BLOCKNATIVE_URL: &str = "https://api.blocknative.com/gasprices/blockprices";
+/// Blocknative confidence level (percent) whose fees are used.
+const BLOCKNATIVE_CONFIDENCE: u64 = 90;
+const ORACLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where market fees come from. The built-in estimator works from the node's `eth_feeHistory`;
+/// the oracles help on chains where its percentiles are unreliable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeeSource {
+    FeeHistory,
+    /// Etherscan's gas tracker, with ETHERSCAN_API_KEY.
+    Etherscan { api_key: String },
+    /// Blocknative's block prices, with BLOCKNATIVE_API_KEY.
+    Blocknative { api_key: String },
+}
+
+impl fmt::Display for FeeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FeeHistory => write!(f, "fee_history"),
+            Self::Etherscan { .. } => write!(f, "etherscan"),
+            Self::Blocknative { .. } => write!(f, "blocknative"),
+        }
+    }
+}
+
+impl FeeSource {
+    /// FEE_SOURCE (or FEE_SOURCE_<chain_id>): `fee_history` (default), `etherscan` or `blocknative`.
+    pub fn from_env(chain_id: u64) -> eyre::Result<Self> {
+        Self::read_env(chain_id).map_err(exit::config_error)
+    }
+
+    fn read_env(chain_id: u64) -> eyre::Result<Self> {
+        let api_key = |name: &str| non_empty_var(name).ok_or_else(|| eyre!("FEE_SOURCE for chain {} needs {}", chain_id, name));
+        match chain_var("FEE_SOURCE", chain_id).as_deref() {
+            None | Some("fee_history") => Ok(Self::FeeHistory),
+            Some("etherscan") => Ok(Self::Etherscan { api_key: api_key("ETHERSCAN_API_KEY")? }),
+            Some("blocknative") => Ok(Self::Blocknative { api_key: api_key("BLOCKNATIVE_API_KEY")? }),
+            Some(other) => Err(eyre!("unknown FEE_SOURCE {:?}, expected fee_history, etherscan or blocknative", other)),
+        }
+    }
+
+    /// Fetches the oracle's current fees for `chain_id`, or `None` for the built-in estimator.
+    pub async fn fetch(&self, chain_id: u64) -> eyre::Result<Option<MarketFees>> {
+        let client = reqwest::Client::builder().timeout(ORACLE_TIMEOUT).build()?;
+        match self {
+            Self::FeeHistory => Ok(None),
+            Self::Etherscan { api_key } => {
+                let chain_id = chain_id.to_string();
+                let response = client
+                    .get(ETHERSCAN_URL)
+                    .query(&[("chainid", chain_id.as_str()), ("module", "gastracker"), ("action", "gasoracle"), ("apikey", api_key)])
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    // The URL carries the API key
+                    .map_err(|e| e.without_url())?
+                    .text()
+                    .await?;
+                etherscan_fees(&serde_json::from_str(&response)?).map(Some)
+            }
+            Self::Blocknative { api_key } => {
+                let response = client
+                    .get(BLOCKNATIVE_URL)
+                    .query(&[("chainid", chain_id)])
+                    .header(reqwest::header::AUTHORIZATION, api_key)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?;
+                blocknative_fees(&serde_json::from_str(&response)?).map(Some)
+            }
+        }
+    }
+}
+
+/// Reads the gas oracle result, whose prices are decimal gwei strings. The proposed price is
+/// taken as base fee plus tip.
+fn etherscan_fees(response: &Value) -> eyre::Result<MarketFees> {
+    let result = &response["result"];
+    let field = |name: &str| {
+        let value = result[name].as_str().ok_or_else(|| eyre!("etherscan: {} missing: {}", name, result))?;
+        gwei(value)
+    };
+    let base_fee = field("suggestBaseFee")?;
+    let priority_fee = field("ProposeGasPrice")?.saturating_sub(base_fee);
+    Ok(MarketFees { base_fee, max_fee_per_gas: base_fee * 2 + priority_fee, priority_fee })
+}
+
+/// Reads the next block's prices, whose fees are gwei numbers, at `BLOCKNATIVE_CONFIDENCE`.
+fn blocknative_fees(response: &Value) -> eyre::Result<MarketFees> {
+    let block = &response["blockPrices"][0];
+    let base_fee = block["baseFeePerGas"].as_f64().ok_or_else(|| eyre!("blocknative: baseFeePerGas missing"))?;
+    let estimate = block["estimatedPrices"]
+        .as_array()
+        .and_then(|prices| prices.iter().find(|p| p["confidence"].as_u64() == Some(BLOCKNATIVE_CONFIDENCE)))
+        .ok_or_else(|| eyre!("blocknative: no estimate at {}% confidence", BLOCKNATIVE_CONFIDENCE))?;
+    let number = |name: &str| estimate[name].as_f64().ok_or_else(|| eyre!("blocknative: {} missing", name));
+    Ok(MarketFees {
+        base_fee: gwei(&format!("{:.9}", base_fee))?,
+        max_fee_per_gas: gwei(&format!("{:.9}", number("maxFeePerGas")?))?,
+        priority_fee: gwei(&format!("{:.9}", number("maxPriorityFeePerGas")?))?,
+    })
+}
+
+/// Parses a decimal gwei amount, dropping anything below 1 wei.
+fn gwei(value: &str) -> eyre::Result<U256> {
+    let value = value.trim();
+    let value = match value.split_once('.') {
+        Some((integer, fraction)) => format!("{}.{}", integer, fraction.chars().take(9).collect::<String>()),
+        None => value.to_string(),
+    };
+    parse_gwei(&value)
+}
@@ -0,0 +1,140 @@
+use crate::oracle::FeeSource;
+use crate::units::wei_to_f64;
+use ethers::prelude::*;
+use ethers::types::U256;
+
+/// Current network fees (wei), as a wallet would recommend them for a plain transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct MarketFees {
+    pub base_fee: U256,
+    pub max_fee_per_gas: U256,
+    pub priority_fee: U256,
+}
+
+/// Current market fees from the chain's FEE_SOURCE, falling back to the fee history estimate if
+/// an oracle cannot be reached.
+pub async fn market_fees<M: Middleware>(provider: &M) -> eyre::Result<MarketFees>
+where
+    M::Error: 'static,
+{
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let source = FeeSource::from_env(chain_id)?;
+    match source.fetch(chain_id).await {
+        Ok(Some(fees)) => return Ok(fees),
+        Ok(None) => {}
+        Err(e) => println!("Warning: {} fee oracle failed ({}), using fee history instead", source, e),
+    }
+    fee_history_fees(provider).await
+}
+
+/// Fetches the latest base fee and recommends the average 10th percentile priority fee of the
+/// last 10 blocks, with a max fee of twice the base fee plus that priority fee.
+pub async fn fee_history_fees<M: Middleware>(provider: &M) -> eyre::Result<MarketFees>
+where
+    M::Error: 'static,
+{
+    let block = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| eyre::eyre!("latest block not available"))?;
+    let base_fee = block.base_fee_per_gas.unwrap_or_default();
+    let fee_history = provider.fee_history(10, BlockNumber::Latest, &[10.0]).await?;
+    let rewards: Vec<U256> = fee_history.reward.iter().filter_map(|reward| reward.first().copied()).collect();
+    let priority_fee = if rewards.is_empty() {
+        U256::zero()
+    } else {
+        rewards.iter().fold(U256::zero(), |acc, &x| acc + x) / U256::from(rewards.len())
+    };
+    Ok(MarketFees { base_fee, max_fee_per_gas: base_fee * 2 + priority_fee, priority_fee })
+}
+
+/// What a match costs compared with the same transaction sent at the recommended market fee.
+#[derive(Debug, Clone, Copy)]
+pub struct Overhead {
+    /// Worst case, gas limit × max fee.
+    pub vanity_max_cost: U256,
+    pub market_max_cost: U256,
+    /// Gas limit × what each would pay per gas if included at the current base fee.
+    pub vanity_expected_cost: U256,
+    pub market_expected_cost: U256,
+}
+
+impl Overhead {
+    pub fn new(gas_limit: U256, max_fee_per_gas: U256, priority_fee: U256, market: &MarketFees) -> Self {
+        let expected = |max_fee: U256, priority_fee: U256| gas_limit * max_fee.min(market.base_fee + priority_fee);
+        Self {
+            vanity_max_cost: gas_limit * max_fee_per_gas,
+            market_max_cost: gas_limit * market.max_fee_per_gas,
+            vanity_expected_cost: expected(max_fee_per_gas, priority_fee),
+            market_expected_cost: expected(market.max_fee_per_gas, market.priority_fee),
+        }
+    }
+
+    /// Extra worst-case cost as a percentage, negative when the match is cheaper than the market fee.
+    pub fn max_premium_percent(&self) -> f64 {
+        percent(self.vanity_max_cost, self.market_max_cost)
+    }
+
+    /// Extra cost as a percentage if included at the current base fee.
+    pub fn expected_premium_percent(&self) -> f64 {
+        percent(self.vanity_expected_cost, self.market_expected_cost)
+    }
+}
+
+fn percent(vanity: U256, market: U256) -> f64 {
+    if market.is_zero() {
+        0.0
+    } else {
+        (wei_to_f64(vanity) - wei_to_f64(market)) / wei_to_f64(market) * 100.0
+    }
+}
+
+/// What a transaction is expected to pay versus its worst case. EIP-1559 charges
+/// `gas used × (base fee + tip)`, with the tip cut short if the two exceed the max fee; the rest
+/// of `gas limit × max fee` is never taken.
+#[derive(Debug, Clone, Copy)]
+pub struct EffectiveCost {
+    pub gas_used: U256,
+    pub gas_limit: U256,
+    pub base_fee: U256,
+    /// Tip actually paid per gas, `min(priority fee, max fee - base fee)`.
+    pub effective_tip: U256,
+    pub max_fee_per_gas: U256,
+}
+
+impl EffectiveCost {
+    pub fn new(gas_used: U256, gas_limit: U256, max_fee_per_gas: U256, priority_fee: U256, base_fee: U256) -> Self {
+        Self {
+            gas_used: gas_used.min(gas_limit),
+            gas_limit,
+            base_fee,
+            effective_tip: priority_fee.min(max_fee_per_gas.saturating_sub(base_fee)),
+            max_fee_per_gas,
+        }
+    }
+
+    /// Gas price paid per unit at this base fee.
+    pub fn effective_gas_price(&self) -> U256 {
+        self.base_fee + self.effective_tip
+    }
+
+    /// `gas used × (base fee + tip)`.
+    pub fn expected(&self) -> U256 {
+        self.gas_used * self.effective_gas_price()
+    }
+
+    /// `gas limit × max fee`, the most the transaction can ever cost.
+    pub fn worst_case(&self) -> U256 {
+        self.gas_limit * self.max_fee_per_gas
+    }
+
+    /// Part of the worst case saved because not all the gas limit is used.
+    pub fn unused_gas_saving(&self) -> U256 {
+        (self.gas_limit - self.gas_used) * self.max_fee_per_gas
+    }
+
+    /// Part of the worst case saved because the base fee plus tip is below the max fee.
+    pub fn fee_saving(&self) -> U256 {
+        self.gas_used * self.max_fee_per_gas.saturating_sub(self.effective_gas_price())
+    }
+}
@@ -0,0 +1,75 @@
+/// Strips an optional `0x` and lowercases a hash pattern, leaving only the hex digits to match.
+pub fn normalize(pattern: &str) -> String {
+    let pattern = pattern.to_lowercase();
+    pattern.strip_prefix("0x").unwrap_or(&pattern).to_string()
+}
+
+/// Expected number of candidates to try before a hash starts with `pattern`, i.e. 16^digits.
+pub fn expected_attempts(pattern: &str) -> f64 {
+    16f64.powi(normalize(pattern).len() as i32)
+}
+
+/// Checks that a transaction hash can start with `pattern`: hashes are 64 hex digits, so letters
+/// past `f` can never appear. The error suggests look-alike digits where there are some.
+pub fn validate(pattern: &str) -> eyre::Result<()> {
+    let digits = normalize(pattern);
+    if digits.is_empty() {
+        eyre::bail!("hash pattern {:?} has no hex digits to match", pattern);
+    }
+    if digits.len() > 64 {
+        eyre::bail!("hash pattern {:?} is longer than a transaction hash (64 hex digits)", pattern);
+    }
+    let invalid: Vec<char> = digits.chars().filter(|c| !c.is_ascii_hexdigit()).collect();
+    if invalid.is_empty() {
+        return Ok(());
+    }
+    let mut message = format!(
+        "hash pattern {:?} contains {:?}, which can never appear in a transaction hash (only 0-9 and a-f do)",
+        pattern,
+        invalid.iter().collect::<String>()
+    );
+    if invalid.iter().all(|c| look_alike(*c).is_some()) {
+        let suggestion: String = digits.chars().map(|c| look_alike(c).unwrap_or(c)).collect();
+        message.push_str(&format!("; try 0x{}", suggestion));
+    }
+    eyre::bail!(message)
+}
+
+/// `0x` followed by the normalized pattern, as the searcher compares it against hashes.
+pub fn to_prefix(pattern: &str) -> String {
+    format!("0x{}", normalize(pattern))
+}
+
+/// How a hash starting with `pattern` renders on an explorer such as Etherscan: lowercase, with
+/// the digits left to chance shown as dots.
+pub fn display_preview(pattern: &str) -> String {
+    let digits = normalize(pattern);
+    format!("0x{}{}", digits, ".".repeat(64usize.saturating_sub(digits.len())))
+}
+
+/// Spells `word` in hex digits, replacing letters past `f` with look-alike digits, e.g. `coffee`
+/// as `c0ffee`. Spaces and dashes are dropped; fails on letters without a look-alike.
+pub fn spell(word: &str) -> eyre::Result<String> {
+    let mut digits = String::new();
+    for c in word.to_lowercase().chars().filter(|c| !matches!(c, ' ' | '-' | '_')) {
+        match c {
+            c if c.is_ascii_hexdigit() => digits.push(c),
+            c => digits.push(look_alike(c).ok_or_else(|| eyre::eyre!("{:?} has no look-alike hex digit, so {:?} cannot be spelled in a hash", c, word))?),
+        }
+    }
+    eyre::ensure!(!digits.is_empty(), "{:?} has nothing to spell", word);
+    eyre::ensure!(digits.len() <= 64, "{:?} is longer than a transaction hash (64 hex digits)", word);
+    Ok(digits)
+}
+
+fn look_alike(c: char) -> Option<char> {
+    match c {
+        'o' => Some('0'),
+        'i' | 'l' => Some('1'),
+        'z' => Some('2'),
+        's' => Some('5'),
+        'g' => Some('9'),
+        't' => Some('7'),
+        _ => None,
+    }
+}
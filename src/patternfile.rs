@@ -0,0 +1,90 @@
+use crate::pattern;
+use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// The patterns a running search accepts, read from PATTERN_FILE and replaced when it is re-read,
+/// so patterns can be added or tightened mid-run without losing the fees already swept.
+#[derive(Debug)]
+pub struct LivePatterns {
+    path: PathBuf,
+    accepted: RwLock<Vec<String>>,
+}
+
+impl LivePatterns {
+    pub fn load(path: impl Into<PathBuf>) -> eyre::Result<Self> {
+        let path = path.into();
+        let accepted = read(&path)?;
+        Ok(Self { path, accepted: RwLock::new(accepted) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The accepted patterns as lowercase hex with `0x`, in file order.
+    pub fn accepted(&self) -> Vec<String> {
+        self.accepted.read().clone()
+    }
+
+    /// Re-reads the file and accepts what it lists from now on. A file that cannot be read or has
+    /// an invalid pattern leaves the current patterns in place.
+    pub fn reload(&self) -> eyre::Result<Vec<String>> {
+        let accepted = read(&self.path)?;
+        *self.accepted.write() = accepted.clone();
+        Ok(accepted)
+    }
+
+    /// The first accepted pattern `tx_hash` starts with.
+    pub fn matching(&self, tx_hash: &[u8; 32]) -> Option<String> {
+        let hash = format!("0x{}", hex::encode(tx_hash));
+        self.accepted.read().iter().find(|prefix| hash.starts_with(prefix.as_str())).cloned()
+    }
+}
+
+/// Reads a pattern file: one pattern per line, validated and normalized like HASH_PREFIX. Blank
+/// lines and lines starting with `#` are skipped, as are repeats.
+pub fn read(path: &Path) -> eyre::Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| eyre::eyre!("cannot read {}: {}", path.display(), e))?;
+    let mut accepted: Vec<String> = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        pattern::validate(line).map_err(|e| eyre::eyre!("{} line {}: {}", path.display(), number + 1, e))?;
+        let prefix = pattern::to_prefix(line);
+        if !accepted.contains(&prefix) {
+            accepted.push(prefix);
+        }
+    }
+    eyre::ensure!(!accepted.is_empty(), "{} lists no patterns", path.display());
+    Ok(accepted)
+}
+
+/// Re-reads `patterns` on every SIGHUP until `found` is set, passing each outcome to `report`.
+#[cfg(unix)]
+pub async fn reload_on_hangup(
+    patterns: Arc<LivePatterns>,
+    found: Arc<AtomicBool>,
+    report: impl Fn(eyre::Result<Vec<String>>),
+) -> eyre::Result<()> {
+    use std::sync::atomic::Ordering;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    while hangups.recv().await.is_some() && !found.load(Ordering::Relaxed) {
+        report(patterns.reload());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn reload_on_hangup(
+    _patterns: Arc<LivePatterns>,
+    _found: Arc<AtomicBool>,
+    _report: impl Fn(eyre::Result<Vec<String>>),
+) -> eyre::Result<()> {
+    eyre::bail!("SIGHUP only exists on Unix, so PATTERN_FILE is read once; restart the search to change it")
+}
@@ -0,0 +1,154 @@
+use crate::address::get_contract_address;
+use crate::envelope;
+use crate::search::{self, SearchParams};
+use crate::stats::SearchStats;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, H256, U256};
+use ethers::utils::keccak256;
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+/// Names the payload, so consumers can tell it apart from other JSON they receive.
+pub const SCHEMA_NAME: &str = "match_found";
+/// Bumped whenever a field is removed or changes meaning; new optional fields keep the version.
+pub const SCHEMA_VERSION: u32 = 1;
+/// The JSON Schema of [`MatchPayload`], as printed by the `schema` subcommand.
+pub const SCHEMA: &str = include_str!("match_found.schema.json");
+
+/// A found match as reported everywhere it leaves the tool: `--json`, `--result-file`, webhooks
+/// and the API server's results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchPayload {
+    pub schema: String,
+    pub version: u32,
+    pub pattern: PatternFields,
+    pub matched: MatchedSpan,
+    pub tx: TxFields,
+    pub cost: CostFields,
+    /// How the search went; absent when the match was read back from the journal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<SearchFields>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternFields {
+    /// The prefix searched for, lowercase hex with `0x`.
+    pub prefix: String,
+    pub digits: usize,
+}
+
+/// Which hex digits of the hash (after `0x`) matched the pattern, as `[start, end)`. `end` is
+/// below `pattern.digits` for a partial match taken by a stop policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchedSpan {
+    pub start: usize,
+    pub end: usize,
+    /// The matched digits themselves.
+    pub digits: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxFields {
+    pub hash: H256,
+    /// The signed transaction, ready to send.
+    pub raw: Bytes,
+    pub chain_id: u64,
+    /// Signer recovered from `raw`.
+    pub from: Address,
+    pub nonce: U256,
+    pub to: Option<Address>,
+    /// Where a contract creation lands.
+    pub contract_address: Option<Address>,
+    pub value: U256,
+    pub gas_limit: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub calldata_size: usize,
+    pub calldata_hash: Option<H256>,
+}
+
+/// What the transaction can cost, in wei.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostFields {
+    /// Gas limit times max fee, the most it can ever pay.
+    pub worst_case_wei: U256,
+    /// Gas limit times priority fee, the most the block producer can receive above the base fee.
+    pub max_tip_wei: U256,
+    /// The PRIORITY_FEE_LANES lane the tip came from, counting from 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_fee_lane: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchFields {
+    pub attempts: u64,
+    pub elapsed_seconds: f64,
+    /// Signatures per second over the whole search.
+    pub hashrate: f64,
+    pub threads: usize,
+}
+
+impl MatchPayload {
+    /// The payload for the signed transaction `raw`, found for `hash_prefix`. Everything is read
+    /// from `raw` itself, so a match found now and one read back from the journal look the same.
+    pub fn from_raw(hash_prefix: &str, raw: &Bytes) -> eyre::Result<Self> {
+        let decoded = envelope::decode_signed(raw)?;
+        let from = decoded.signature.recover(decoded.sighash).map_err(|e| eyre!("cannot recover the signer: {}", e))?;
+        let tx = &decoded.tx;
+        let hash = keccak256(raw);
+        let nonce = tx.nonce().copied().unwrap_or_default();
+        let to = tx.to().and_then(|to| to.as_address()).copied();
+        let gas_limit = tx.gas().copied().unwrap_or_default();
+        let (max_fee_per_gas, max_priority_fee_per_gas) = match tx {
+            TypedTransaction::Eip1559(tx) => (tx.max_fee_per_gas.unwrap_or_default(), tx.max_priority_fee_per_gas.unwrap_or_default()),
+            tx => (tx.gas_price().unwrap_or_default(), tx.gas_price().unwrap_or_default()),
+        };
+        let data = tx.data();
+        let end = search::matched_digits(&hash, hash_prefix);
+        Ok(Self {
+            schema: SCHEMA_NAME.to_string(),
+            version: SCHEMA_VERSION,
+            pattern: PatternFields { prefix: hash_prefix.to_string(), digits: hash_prefix.len().saturating_sub(2) },
+            matched: MatchedSpan { start: 0, end, digits: hex::encode(hash)[..end].to_string() },
+            tx: TxFields {
+                hash: hash.into(),
+                raw: raw.clone(),
+                chain_id: tx.chain_id().unwrap_or_default().as_u64(),
+                from,
+                nonce,
+                to,
+                contract_address: to.is_none().then(|| get_contract_address(from, nonce)),
+                value: tx.value().copied().unwrap_or_default(),
+                gas_limit,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                calldata_size: data.map_or(0, |data| data.len()),
+                calldata_hash: data.map(|data| H256::from(keccak256(data))),
+            },
+            cost: CostFields {
+                worst_case_wei: gas_limit * max_fee_per_gas,
+                max_tip_wei: gas_limit * max_priority_fee_per_gas,
+                priority_fee_lane: None,
+            },
+            search: None,
+        })
+    }
+
+    /// Adds how the search that found it went, and the lane its tip came from.
+    pub fn with_search(mut self, params: &SearchParams, stats: &SearchStats) -> Self {
+        let lanes = &params.priority_fee_lanes;
+        self.cost.priority_fee_lane = lanes.iter().position(|&fee| fee == self.tx.max_priority_fee_per_gas);
+        self.search = Some(SearchFields {
+            attempts: stats.total_attempts(),
+            elapsed_seconds: stats.elapsed().as_secs_f64(),
+            hashrate: stats.hashrate(),
+            threads: params.thread_count,
+        });
+        self
+    }
+
+    /// As a JSON value, e.g. to embed in a webhook body or API response.
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("the payload serializes")
+    }
+}
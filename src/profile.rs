@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Default location of the hashrate profile, relative to the working directory.
+pub const DEFAULT_PROFILE_PATH: &str = "hashrate_profile.json";
+/// Searches shorter than this mostly measure start-up, so they are not recorded.
+pub const MIN_PROFILED_RUN: Duration = Duration::from_secs(10);
+/// Weight of the newest measurement in the running average, so the profile follows hardware or
+/// build changes without one unusual run throwing it off.
+const SMOOTHING: f64 = 0.3;
+
+/// Hashrates measured on this hardware, by host and worker count, so estimates use real numbers
+/// instead of a benchmark or a guess.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HashrateProfile {
+    pub entries: Vec<ProfileEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileEntry {
+    pub host: String,
+    pub threads: usize,
+    /// Candidates per second across all workers, averaged over runs.
+    pub hashrate: f64,
+    pub runs: u64,
+    /// Unix time of the last update.
+    pub updated: i64,
+}
+
+impl HashrateProfile {
+    /// Reads the profile at `path`; a missing file is an empty profile.
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        match std::fs::read(path.as_ref()) {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> eyre::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn entry(&self, host: &str, threads: usize) -> Option<&ProfileEntry> {
+        self.entries.iter().find(|entry| entry.host == host && entry.threads == threads)
+    }
+
+    /// Folds a measured `hashrate` into the entry for `host` and `threads`, returning the new average.
+    pub fn record(&mut self, host: &str, threads: usize, hashrate: f64, now: i64) -> f64 {
+        match self.entries.iter_mut().find(|entry| entry.host == host && entry.threads == threads) {
+            Some(entry) => {
+                entry.hashrate = entry.hashrate * (1.0 - SMOOTHING) + hashrate * SMOOTHING;
+                entry.runs += 1;
+                entry.updated = now;
+                entry.hashrate
+            }
+            None => {
+                self.entries.push(ProfileEntry { host: host.to_string(), threads, hashrate, runs: 1, updated: now });
+                hashrate
+            }
+        }
+    }
+}
+
+/// This machine's host name, or `unknown` if it cannot be read.
+#[cfg(unix)]
+pub fn host_name() -> String {
+    let mut buffer = [0u8; 256];
+    // SAFETY: gethostname writes at most `buffer.len()` bytes into the buffer.
+    if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } != 0 {
+        return "unknown".to_string();
+    }
+    let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..end]).into_owned()
+}
+
+#[cfg(not(unix))]
+pub fn host_name() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// The profiled hashrate of this machine with `threads` workers, if one was recorded at `path`.
+/// An unreadable profile is reported and treated as empty, since it only improves estimates.
+pub fn measured_hashrate(path: &str, threads: usize) -> Option<f64> {
+    match HashrateProfile::load(path) {
+        Ok(profile) => profile.entry(&host_name(), threads).map(|entry| entry.hashrate),
+        Err(e) => {
+            println!("Warning: ignoring the hashrate profile {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Records a run of `threads` workers doing `attempts` in `elapsed` in the profile at `path`.
+/// Runs shorter than [`MIN_PROFILED_RUN`] are skipped. Returns the updated average, if recorded.
+pub fn record_run(path: &str, threads: usize, attempts: u64, elapsed: Duration) -> eyre::Result<Option<f64>> {
+    if elapsed < MIN_PROFILED_RUN || attempts == 0 {
+        return Ok(None);
+    }
+    let mut profile = HashrateProfile::load(path)?;
+    let average = profile.record(&host_name(), threads, attempts as f64 / elapsed.as_secs_f64(), chrono::Utc::now().timestamp());
+    profile.save(path)?;
+    Ok(Some(average))
+}
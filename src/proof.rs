@@ -0,0 +1,213 @@
+use crate::address::get_contract_address;
+use crate::verify;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Signature, TransactionReceipt, H256, U256};
+use ethers::utils::{hash_message, keccak256};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Files covered by SHA256SUMS, in the order they are written.
+const HASHED_FILES: [&str; 5] = ["manifest.json", "raw_tx.hex", "calldata.hex", "receipt.json", "verify.sh"];
+const BLOCK: usize = 512;
+
+/// What a proof bundle attests to, as `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    /// Signer recovered from the raw transaction.
+    pub from: Address,
+    pub nonce: U256,
+    pub to: Option<Address>,
+    /// Where a CREATE from `from` at `nonce` lands, for a direct deployment.
+    pub predicted_address: Option<Address>,
+    /// The contract the receipt reports as created.
+    pub actual_address: Option<Address>,
+    pub block_number: Option<u64>,
+    /// 1 for success, 0 for a revert.
+    pub status: Option<u64>,
+    /// Unix timestamp of the export.
+    pub exported_at: i64,
+    /// Who signed the bundle, with PRIVATE_KEY.
+    pub signed_by: Address,
+}
+
+/// The message signed (EIP-191) for a bundle, binding the chain, the transaction and every file
+/// through the hash of SHA256SUMS, so the signature cannot be reused for anything else.
+pub fn proof_message(chain_id: u64, tx_hash: H256, sha256sums: &[u8]) -> String {
+    format!("Deployment proof for {:?} on chain {}, SHA256SUMS {}", tx_hash, chain_id, hex::encode(Sha256::digest(sha256sums)))
+}
+
+/// Packages `raw_tx` and its `receipt` with the calldata, the predicted and actual address and
+/// `verify.sh` to recompute them, signed by `signer`. Returns the files of the archive in order.
+pub fn build(
+    raw_tx: &[u8],
+    receipt: &TransactionReceipt,
+    chain_id: u64,
+    signer: &LocalWallet,
+    exported_at: i64,
+) -> eyre::Result<Vec<(String, Vec<u8>)>> {
+    let tx_hash = H256::from(keccak256(raw_tx));
+    eyre::ensure!(receipt.transaction_hash == tx_hash, "the receipt is for {:?}, not {:?}", receipt.transaction_hash, tx_hash);
+    let nonce = envelope_nonce(raw_tx)?;
+    let verified = verify::verify(raw_tx, &format!("{:?}", tx_hash), chain_id, nonce)?;
+    let to = verified.tx.to().and_then(|to| to.as_address()).copied();
+    let manifest = Manifest {
+        chain_id,
+        tx_hash,
+        from: verified.from,
+        nonce,
+        to,
+        predicted_address: to.is_none().then(|| get_contract_address(verified.from, nonce)),
+        actual_address: receipt.contract_address,
+        block_number: receipt.block_number.map(|block| block.as_u64()),
+        status: receipt.status.map(|status| status.as_u64()),
+        exported_at,
+        signed_by: signer.address(),
+    };
+    let calldata = verified.tx.data().cloned().unwrap_or_default();
+    let files: Vec<(&str, Vec<u8>)> = vec![
+        ("manifest.json", serde_json::to_vec_pretty(&manifest)?),
+        ("raw_tx.hex", format!("0x{}\n", hex::encode(raw_tx)).into_bytes()),
+        ("calldata.hex", format!("0x{}\n", hex::encode(&calldata)).into_bytes()),
+        ("receipt.json", serde_json::to_vec_pretty(receipt)?),
+        ("verify.sh", verify_script(&manifest).into_bytes()),
+    ];
+    let sha256sums: String =
+        files.iter().map(|(name, contents)| format!("{}  {}\n", hex::encode(Sha256::digest(contents)), name)).collect();
+    let signature = signer.sign_hash(hash_message(proof_message(chain_id, tx_hash, sha256sums.as_bytes())))?;
+
+    let mut archive: Vec<(String, Vec<u8>)> = files.into_iter().map(|(name, contents)| (name.to_string(), contents)).collect();
+    archive.push(("SHA256SUMS".to_string(), sha256sums.into_bytes()));
+    archive.push(("manifest.sig".to_string(), format!("0x{}\n", signature).into_bytes()));
+    Ok(archive)
+}
+
+fn envelope_nonce(raw_tx: &[u8]) -> eyre::Result<U256> {
+    let decoded = crate::envelope::decode_signed(raw_tx).map_err(|e| eyre::eyre!("failed to decode the signed transaction: {}", e))?;
+    decoded.tx.nonce().copied().ok_or_else(|| eyre::eyre!("the transaction has no nonce"))
+}
+
+/// Checks a bundle's files the way `verify.sh` does, without the chain: every hash in
+/// SHA256SUMS, the signature and the raw transaction against the manifest.
+pub fn check(files: &[(String, Vec<u8>)]) -> eyre::Result<Manifest> {
+    let file = |name: &str| {
+        files.iter().find(|(file, _)| file == name).map(|(_, contents)| contents.as_slice()).ok_or_else(|| eyre::eyre!("the bundle has no {}", name))
+    };
+    let sha256sums = file("SHA256SUMS")?;
+    let listed: BTreeMap<&str, &str> =
+        std::str::from_utf8(sha256sums)?.lines().filter_map(|line| line.split_once("  ")).map(|(hash, name)| (name, hash)).collect();
+    for name in HASHED_FILES {
+        let expected = listed.get(name).ok_or_else(|| eyre::eyre!("SHA256SUMS does not cover {}", name))?;
+        eyre::ensure!(hex::encode(Sha256::digest(file(name)?)) == *expected, "{} does not match SHA256SUMS", name);
+    }
+    let manifest: Manifest = serde_json::from_slice(file("manifest.json")?)?;
+    let signature: Signature = std::str::from_utf8(file("manifest.sig")?)?.trim().parse()?;
+    signature
+        .verify(proof_message(manifest.chain_id, manifest.tx_hash, sha256sums), manifest.signed_by)
+        .map_err(|_| eyre::eyre!("the bundle is not signed by {:?}", manifest.signed_by))?;
+    let raw_tx: ethers::types::Bytes = std::str::from_utf8(file("raw_tx.hex")?)?.trim().parse()?;
+    let verified = verify::verify(&raw_tx, &format!("{:?}", manifest.tx_hash), manifest.chain_id, manifest.nonce)?;
+    eyre::ensure!(verified.from == manifest.from, "the raw transaction is signed by {:?}, not {:?}", verified.from, manifest.from);
+    Ok(manifest)
+}
+
+/// A POSIX shell script recomputing the bundle with `sha256sum` and Foundry's `cast`, and with
+/// RPC_URL set, checking it against the chain.
+fn verify_script(manifest: &Manifest) -> String {
+    let lower = |address: Address| format!("{:?}", address);
+    let mut script = format!(
+        r#"#!/bin/sh
+# Recomputes this deployment proof. Needs sha256sum and Foundry's cast; set RPC_URL to also
+# compare it with the chain.
+set -e
+cd "$(dirname "$0")"
+lower() {{ tr 'A-F' 'a-f'; }}
+
+sha256sum -c SHA256SUMS
+RAW=$(cat raw_tx.hex)
+HASH=$(cast keccak "$RAW")
+[ "$HASH" = "{tx_hash:?}" ] || {{ echo "the raw transaction hashes to $HASH, not {tx_hash:?}"; exit 1; }}
+echo "raw_tx.hex hashes to {tx_hash:?}"
+MESSAGE="Deployment proof for {tx_hash:?} on chain {chain_id}, SHA256SUMS $(sha256sum SHA256SUMS | cut -d' ' -f1)"
+cast wallet verify --address {signed_by} "$MESSAGE" "$(cat manifest.sig)"
+"#,
+        tx_hash = manifest.tx_hash,
+        chain_id = manifest.chain_id,
+        signed_by = lower(manifest.signed_by),
+    );
+    if let (Some(predicted), Some(actual)) = (manifest.predicted_address, manifest.actual_address) {
+        script.push_str(&format!(
+            r#"PREDICTED=$(cast compute-address {from} --nonce {nonce} | awk '{{print $NF}}' | lower)
+[ "$PREDICTED" = "{predicted}" ] || {{ echo "a CREATE from {from} at nonce {nonce} lands on $PREDICTED"; exit 1; }}
+[ "{predicted}" = "{actual}" ] || {{ echo "predicted {predicted}, but the receipt reports {actual}"; exit 1; }}
+echo "Contract address {actual} is the CREATE address of {from} at nonce {nonce}"
+"#,
+            from = lower(manifest.from),
+            nonce = manifest.nonce,
+            predicted = lower(predicted),
+            actual = lower(actual),
+        ));
+    }
+    script.push_str(&format!(
+        r#"if [ -n "$RPC_URL" ]; then
+  [ "$(cast tx {tx_hash:?} --raw --rpc-url "$RPC_URL" | lower)" = "$(echo "$RAW" | lower)" ] || {{ echo "the chain has a different transaction at {tx_hash:?}"; exit 1; }}
+  echo "The chain has exactly this transaction, status $(cast receipt {tx_hash:?} status --rpc-url "$RPC_URL")"
+fi
+echo "All checks passed"
+"#,
+        tx_hash = manifest.tx_hash,
+    ));
+    script
+}
+
+/// Writes `files` as an uncompressed tar archive under the directory `root`.
+pub fn to_tar(root: &str, files: &[(String, Vec<u8>)], mtime: i64) -> Vec<u8> {
+    let mut archive = Vec::new();
+    for (name, contents) in files {
+        let mut header = [0u8; BLOCK];
+        let path = format!("{}/{}", root, name);
+        header[..path.len().min(100)].copy_from_slice(&path.as_bytes()[..path.len().min(100)]);
+        let mode = if name.ends_with(".sh") { "0000755" } else { "0000644" };
+        octal(&mut header[100..108], mode);
+        octal(&mut header[108..116], "0000000");
+        octal(&mut header[116..124], "0000000");
+        octal(&mut header[124..136], &format!("{:011o}", contents.len()));
+        octal(&mut header[136..148], &format!("{:011o}", mtime.max(0)));
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        // The checksum is taken with its own field as spaces
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(contents);
+        archive.resize(archive.len().div_ceil(BLOCK) * BLOCK, 0);
+    }
+    archive.resize(archive.len() + 2 * BLOCK, 0);
+    archive
+}
+
+fn octal(field: &mut [u8], digits: &str) {
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+/// Reads back the files of a tar archive written by [`to_tar`], without their directory.
+pub fn from_tar(archive: &[u8]) -> eyre::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= archive.len() && archive[offset] != 0 {
+        let header = &archive[offset..offset + BLOCK];
+        let field = |range: std::ops::Range<usize>| String::from_utf8_lossy(&header[range]).trim_end_matches(['\0', ' ']).to_string();
+        let path = field(0..100);
+        let size = usize::from_str_radix(&field(124..136), 8).map_err(|e| eyre::eyre!("bad size for {}: {}", path, e))?;
+        let start = offset + BLOCK;
+        eyre::ensure!(start + size <= archive.len(), "{} is cut short", path);
+        let name = path.rsplit_once('/').map_or(path.as_str(), |(_, name)| name).to_string();
+        files.push((name, archive[start..start + size].to_vec()));
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+    Ok(files)
+}
@@ -0,0 +1,32 @@
+use crate::history::{Deployment, HistoryDb};
+use ethers::types::H256;
+use ethers::utils::keccak256;
+
+/// Calldata about to be mined that differs from the last deployment under the same
+/// DEPLOYMENT_NAME, e.g. stale or locally modified bytecode.
+#[derive(Debug, Clone)]
+pub struct CalldataChange {
+    pub previous: Deployment,
+    pub size: u64,
+    pub hash: H256,
+}
+
+impl CalldataChange {
+    /// Bytes added (positive) or removed since the previous deployment, if its size was recorded.
+    pub fn size_delta(&self) -> Option<i64> {
+        self.previous.calldata_size.map(|previous| self.size as i64 - previous as i64)
+    }
+}
+
+/// Compares `calldata` with the latest deployment recorded as `name`. Returns `None` when there
+/// is no previous deployment or the calldata hash is unchanged.
+pub fn compare(db: &HistoryDb, name: &str, calldata: &[u8]) -> eyre::Result<Option<CalldataChange>> {
+    let Some(previous) = db.latest_named(name)? else {
+        return Ok(None);
+    };
+    let hash = H256::from(keccak256(calldata));
+    if previous.calldata_hash == Some(hash) {
+        return Ok(None);
+    }
+    Ok(Some(CalldataChange { previous, size: calldata.len() as u64, hash }))
+}
@@ -0,0 +1,111 @@
+use crate::envelope;
+use crate::journal::JournalEntry;
+use crate::payload::MatchPayload;
+use crate::search::{self, Match, SearchParams};
+use crate::stats::SearchStats;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{H256, U256};
+use ethers::utils::keccak256;
+use std::sync::Arc;
+
+/// Identifies the transaction a search mines for, whatever fees the match ends up signed with:
+/// keccak256 of the template's JSON without its fees. Journaled with every match so a re-run
+/// for the same transaction can find it.
+pub fn template_hash(template: &Eip1559TransactionRequest) -> H256 {
+    let template = Eip1559TransactionRequest { max_fee_per_gas: None, max_priority_fee_per_gas: None, ..template.clone() };
+    H256::from(keccak256(serde_json::to_vec(&template).expect("the template serializes")))
+}
+
+/// The most recent journaled match for `template` whose hash starts with one of `patterns`.
+pub fn find<'a>(entries: &'a [JournalEntry], template: &Eip1559TransactionRequest, patterns: &[String]) -> Option<&'a JournalEntry> {
+    let key = template_hash(template);
+    entries
+        .iter()
+        .filter(|entry| entry.template == Some(key))
+        .filter(|entry| patterns.iter().any(|prefix| search::hash_matches(&entry.tx_hash.0, prefix)))
+        .max_by_key(|entry| entry.timestamp)
+}
+
+/// A journaled match that can still be sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Freshness {
+    /// The account is at the match's nonce, so it can be sent now.
+    Ready,
+    /// The account is still behind the match's nonce, e.g. with a predecessor pending in standby,
+    /// so it is sendable once the transactions before it are included.
+    Queued { account_nonce: U256 },
+}
+
+/// Why a journaled match can no longer be sent as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Staleness {
+    /// The account has moved past the match's nonce, so it can never be included.
+    NonceUsed { account_nonce: U256 },
+    /// The match's max fee is below the current base fee, so it cannot be included until the
+    /// base fee falls back.
+    BelowBaseFee { base_fee: U256 },
+}
+
+impl std::fmt::Display for Staleness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonceUsed { account_nonce } => write!(f, "the account is already at nonce {}", account_nonce),
+            Self::BelowBaseFee { base_fee } => write!(f, "its max fee is below the current base fee of {} wei", base_fee),
+        }
+    }
+}
+
+/// Checks that `entry` can still be sent at the account's `account_nonce` and the latest `base_fee`.
+pub fn check_fresh(entry: &JournalEntry, account_nonce: U256, base_fee: U256) -> Result<Freshness, Staleness> {
+    if account_nonce > entry.nonce {
+        return Err(Staleness::NonceUsed { account_nonce });
+    }
+    if entry.max_fee_per_gas < base_fee {
+        return Err(Staleness::BelowBaseFee { base_fee });
+    }
+    match account_nonce < entry.nonce {
+        true => Ok(Freshness::Queued { account_nonce }),
+        false => Ok(Freshness::Ready),
+    }
+}
+
+/// The journaled match as the search would have returned it, for a transaction with `gas_limit`.
+pub fn to_match(entry: &JournalEntry, gas_limit: U256) -> eyre::Result<Match> {
+    let TypedTransaction::Eip1559(tx) = envelope::decode_signed(&entry.raw_tx)?.tx else {
+        eyre::bail!("journaled match {:?} is not an EIP-1559 transaction", entry.tx_hash);
+    };
+    Ok(Match {
+        signed_rlp: entry.raw_tx.clone(),
+        tx_hash: entry.tx_hash.0,
+        max_fee_per_gas: entry.max_fee_per_gas,
+        total_fee_wei: gas_limit * entry.max_fee_per_gas,
+        tx,
+    })
+}
+
+/// Where the match on offer came from. A reused match that goes stale is replaced by a freshly
+/// mined one, which is then reported as found, with the stats of the search that found it.
+#[derive(Clone)]
+pub enum Origin {
+    Journal,
+    Search(Arc<SearchStats>),
+}
+
+impl Origin {
+    /// The line that introduces the match.
+    pub fn announcement(&self) -> &'static str {
+        match self {
+            Self::Journal => "Reusing the journaled match",
+            Self::Search(_) => "Match found!",
+        }
+    }
+
+    /// `payload` with the search that found the match, if one did.
+    pub fn describe(&self, payload: MatchPayload, params: &SearchParams) -> MatchPayload {
+        match self {
+            Self::Journal => payload,
+            Self::Search(stats) => payload.with_search(params, stats),
+        }
+    }
+}
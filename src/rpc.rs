@@ -0,0 +1,432 @@
+use crate::config::{chain_var, non_empty_var};
+use crate::exit;
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::providers::{
+    Authorization, ConnectionDetails, HttpClientError, JsonRpcError, JwtAuth, JwtKey, RpcError, WsClientError,
+};
+use parking_lot::{Mutex, RwLock};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{de::DeserializeOwned, Serialize};
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
+
+// Engine-API style nodes reject tokens whose `iat` is more than 60s off, so re-sign well before that
+const JWT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where and how to reach the RPC node, shared by every binary.
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    pub url: String,
+    /// Extra headers sent with every HTTP request, e.g. provider API keys.
+    pub headers: Vec<(String, String)>,
+    pub auth: Option<RpcAuth>,
+    /// Proxy for this endpoint: `http://`, `https://`, `socks5://` or `socks5h://` (DNS resolved by
+    /// the proxy, as needed for Tor and `.onion` endpoints).
+    pub proxy: Option<String>,
+    pub connect_timeout: Duration,
+    /// Upper bound on a single JSON-RPC call, including waiting for the rate limiter.
+    pub request_timeout: Duration,
+    /// Maximum requests per second sent to this endpoint, shared by every task using it.
+    pub rate_limit: Option<f64>,
+}
+
+/// Credentials sent in the `Authorization` header.
+#[derive(Clone)]
+pub enum RpcAuth {
+    Basic { username: String, password: String },
+    Bearer(String),
+    /// HS256 secret used to sign a fresh engine-API style JWT.
+    Jwt([u8; 32]),
+}
+
+impl fmt::Debug for RpcAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the credentials themselves
+        f.write_str(match self {
+            Self::Basic { .. } => "Basic",
+            Self::Bearer(_) => "Bearer",
+            Self::Jwt(_) => "Jwt",
+        })
+    }
+}
+
+impl RpcConfig {
+    /// Reads `RPC` plus the optional `RPC_HEADERS`, `RPC_BASIC_AUTH`, `RPC_BEARER_TOKEN`,
+    /// `RPC_JWT_SECRET`, `RPC_PROXY`, `RPC_CONNECT_TIMEOUT`, `RPC_REQUEST_TIMEOUT` (seconds) and
+    /// `RPC_RATE_LIMIT` (requests per second) settings.
+    pub fn from_env() -> eyre::Result<Self> {
+        Self::from_vars(env::var("RPC")?, non_empty_var).map_err(exit::config_error)
+    }
+
+    /// Settings for one chain of a multi-chain run. The URL comes from `RPC_<chain_id>`, and every
+    /// other setting can be overridden for that chain the same way, e.g. `RPC_PROXY_10`.
+    pub fn from_env_for_chain(chain_id: u64) -> eyre::Result<Self> {
+        let url = non_empty_var(&format!("RPC_{}", chain_id))
+            .ok_or_else(|| exit::config_error(eyre::eyre!("RPC_{} is not set", chain_id)))?;
+        Self::from_vars(url, |name| chain_var(name, chain_id)).map_err(exit::config_error)
+    }
+
+    /// A bare endpoint with default timeouts and no credentials, e.g. a local node.
+    pub fn for_url(url: impl Into<String>) -> eyre::Result<Self> {
+        Self::from_vars(url.into(), |_| None)
+    }
+
+    fn from_vars(url: String, var: impl Fn(&str) -> Option<String>) -> eyre::Result<Self> {
+        let headers = match var("RPC_HEADERS") {
+            Some(headers) => parse_headers(&headers)?,
+            None => Vec::new(),
+        };
+        let auth = match (var("RPC_BASIC_AUTH"), var("RPC_BEARER_TOKEN"), var("RPC_JWT_SECRET")) {
+            (None, None, None) => None,
+            (Some(basic), None, None) => {
+                let (username, password) = basic
+                    .split_once(':')
+                    .ok_or_else(|| eyre::eyre!("RPC_BASIC_AUTH must be username:password"))?;
+                Some(RpcAuth::Basic { username: username.to_string(), password: password.to_string() })
+            }
+            (None, Some(token), None) => Some(RpcAuth::Bearer(token)),
+            (None, None, Some(secret)) => Some(RpcAuth::Jwt(load_jwt_secret(&secret)?)),
+            _ => eyre::bail!("set only one of RPC_BASIC_AUTH, RPC_BEARER_TOKEN and RPC_JWT_SECRET"),
+        };
+        let proxy = var("RPC_PROXY");
+        let connect_timeout = seconds(&var, "RPC_CONNECT_TIMEOUT")?.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+        let request_timeout = seconds(&var, "RPC_REQUEST_TIMEOUT")?.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        let rate_limit = match var("RPC_RATE_LIMIT") {
+            Some(rate) => match rate.parse::<f64>().map_err(|e| eyre::eyre!("invalid RPC_RATE_LIMIT: {}", e))? {
+                rate if rate > 0.0 => Some(rate),
+                _ => eyre::bail!("RPC_RATE_LIMIT must be a positive number of requests per second"),
+            },
+            None => None,
+        };
+        Ok(Self { url, headers, auth, proxy, connect_timeout, request_timeout, rate_limit })
+    }
+
+    fn authorization(&self) -> eyre::Result<Option<Authorization>> {
+        Ok(match &self.auth {
+            None => None,
+            Some(RpcAuth::Basic { username, password }) => Some(Authorization::basic(username, password)),
+            Some(RpcAuth::Bearer(token)) => Some(Authorization::bearer(token)),
+            Some(RpcAuth::Jwt(secret)) => {
+                let jwt = JwtAuth::new(JwtKey::from_slice(secret).map_err(|e| eyre::eyre!(e))?, None, None);
+                Some(Authorization::bearer(jwt.generate_token()?))
+            }
+        })
+    }
+}
+
+fn seconds(var: impl Fn(&str) -> Option<String>, name: &str) -> eyre::Result<Option<Duration>> {
+    let Some(secs) = var(name) else { return Ok(None) };
+    let secs: f64 = secs.parse().map_err(|e| eyre::eyre!("invalid {}: {}", name, e))?;
+    Ok(Some(Duration::try_from_secs_f64(secs).map_err(|e| eyre::eyre!("invalid {}: {}", name, e))?))
+}
+
+/// Parses `Name: value` pairs separated by `;`.
+fn parse_headers(headers: &str) -> eyre::Result<Vec<(String, String)>> {
+    headers
+        .split(';')
+        .filter(|h| !h.trim().is_empty())
+        .map(|header| {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| eyre::eyre!("invalid RPC_HEADERS entry {:?}, expected Name: value", header.trim()))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Reads a 32-byte hex JWT secret, either inline or from a file such as geth's `jwtsecret`.
+fn load_jwt_secret(secret: &str) -> eyre::Result<[u8; 32]> {
+    let hex_secret = if Path::new(secret).is_file() { std::fs::read_to_string(secret)? } else { secret.to_string() };
+    let bytes = hex::decode(hex_secret.trim().trim_start_matches("0x"))?;
+    bytes.try_into().map_err(|_| eyre::eyre!("RPC_JWT_SECRET must be 32 bytes of hex"))
+}
+
+/// Provider over whichever transport the RPC URL asks for.
+pub type RpcProvider = Provider<Transport>;
+
+/// JSON-RPC transport chosen from the URL scheme (WebSocket for `ws://` and `wss://`, HTTP
+/// otherwise), with the endpoint's request timeout and rate limit applied to every call.
+#[derive(Debug, Clone)]
+pub struct Transport {
+    connection: Connection,
+    request_timeout: Duration,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+#[derive(Debug, Clone)]
+enum Connection {
+    Http(HttpTransport),
+    Ws(Ws),
+}
+
+/// Spaces requests evenly so that no more than `rate` are sent per second.
+#[derive(Debug)]
+struct RateLimiter {
+    spacing: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64) -> Self {
+        Self { spacing: Duration::from_secs_f64(1.0 / rate), next_slot: Mutex::new(Instant::now()) }
+    }
+
+    async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.spacing;
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+}
+
+/// HTTP transport carrying the configured headers, re-signing its JWT before it goes stale.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    inner: Arc<HttpInner>,
+}
+
+#[derive(Debug)]
+struct HttpInner {
+    config: RpcConfig,
+    http: RwLock<(Http, Instant)>,
+}
+
+impl HttpTransport {
+    fn new(config: RpcConfig) -> eyre::Result<Self> {
+        let http = build_http(&config)?;
+        Ok(Self { inner: Arc::new(HttpInner { config, http: RwLock::new((http, Instant::now())) }) })
+    }
+
+    fn client(&self) -> eyre::Result<Http> {
+        let stale = matches!(self.inner.config.auth, Some(RpcAuth::Jwt(_)))
+            && self.inner.http.read().1.elapsed() > JWT_REFRESH_INTERVAL;
+        if stale {
+            let http = build_http(&self.inner.config)?;
+            *self.inner.http.write() = (http, Instant::now());
+        }
+        Ok(self.inner.http.read().0.clone())
+    }
+}
+
+fn build_http(config: &RpcConfig) -> eyre::Result<Http> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        let mut value = HeaderValue::from_str(value)?;
+        value.set_sensitive(true);
+        headers.insert(HeaderName::from_bytes(name.as_bytes())?, value);
+    }
+    if let Some(auth) = config.authorization()? {
+        let mut value = HeaderValue::from_str(&auth.to_string())?;
+        value.set_sensitive(true);
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+    }
+    let mut builder = reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.request_timeout);
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    let client = builder.build()?;
+    Ok(Http::new_with_client(reqwest::Url::parse(&config.url)?, client))
+}
+
+#[derive(Debug)]
+pub enum TransportError {
+    Http(HttpClientError),
+    Ws(WsClientError),
+    /// Subscriptions were requested over HTTP.
+    NoPubsub,
+    /// The client could not be rebuilt with fresh credentials.
+    Config(eyre::Report),
+    Timeout(Duration),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(e) => e.fmt(f),
+            Self::Ws(e) => e.fmt(f),
+            Self::NoPubsub => f.write_str("subscriptions require a ws:// or wss:// RPC URL"),
+            Self::Config(e) => e.fmt(f),
+            Self::Timeout(timeout) => write!(f, "RPC request timed out after {:?}", timeout),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::Http(e) => e.as_error_response(),
+            Self::Ws(e) => e.as_error_response(),
+            Self::NoPubsub | Self::Config(_) | Self::Timeout(_) => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::Http(e) => e.as_serde_error(),
+            Self::Ws(e) => e.as_serde_error(),
+            Self::NoPubsub | Self::Config(_) | Self::Timeout(_) => None,
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(e: TransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let call = async {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+            match &self.connection {
+                Connection::Http(http) => {
+                    let client = http.client().map_err(TransportError::Config)?;
+                    JsonRpcClient::request(&client, method, params).await.map_err(TransportError::Http)
+                }
+                Connection::Ws(ws) => JsonRpcClient::request(ws, method, params).await.map_err(TransportError::Ws),
+            }
+        };
+        tokio::time::timeout(self.request_timeout, call)
+            .await
+            .unwrap_or(Err(TransportError::Timeout(self.request_timeout)))
+    }
+}
+
+impl PubsubClient for Transport {
+    type NotificationStream = <Ws as PubsubClient>::NotificationStream;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match &self.connection {
+            Connection::Http(_) => Err(TransportError::NoPubsub),
+            Connection::Ws(ws) => PubsubClient::subscribe(ws, id).map_err(TransportError::Ws),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match &self.connection {
+            Connection::Http(_) => Err(TransportError::NoPubsub),
+            Connection::Ws(ws) => PubsubClient::unsubscribe(ws, id).map_err(TransportError::Ws),
+        }
+    }
+}
+
+/// Connects to `config.url` over WebSocket or HTTP depending on its scheme.
+pub async fn connect(config: &RpcConfig) -> eyre::Result<RpcProvider> {
+    let url = &config.url;
+    let connection = if url.starts_with("ws://") || url.starts_with("wss://") {
+        // The WebSocket handshake only carries an Authorization header, signed once at connect time
+        if !config.headers.is_empty() {
+            eyre::bail!("RPC_HEADERS is not supported for WebSocket RPC URLs, use an Authorization setting instead");
+        }
+        if config.proxy.is_some() {
+            eyre::bail!("RPC_PROXY is not supported for WebSocket RPC URLs, use an http(s):// RPC URL instead");
+        }
+        let details = ConnectionDetails::new(url, config.authorization()?);
+        let ws = tokio::time::timeout(config.connect_timeout, Ws::connect(details))
+            .await
+            .map_err(|_| eyre::eyre!("timed out connecting to {} after {:?}", url, config.connect_timeout))??;
+        Connection::Ws(ws)
+    } else {
+        Connection::Http(HttpTransport::new(config.clone())?)
+    };
+    Ok(Provider::new(Transport {
+        connection,
+        request_timeout: config.request_timeout,
+        limiter: config.rate_limit.map(|rate| Arc::new(RateLimiter::new(rate))),
+    }))
+}
+
+/// Whether `provider` can push new heads instead of being polled.
+pub fn supports_subscriptions(provider: &RpcProvider) -> bool {
+    matches!(provider.as_ref().connection, Connection::Ws(_))
+}
+
+/// Calls `on_block` for every new block, forever. WebSocket providers push new heads over an
+/// `eth_subscribe` subscription; HTTP providers (or a dropped subscription) fall back to polling
+/// the latest block every `poll_interval`.
+pub async fn watch_blocks(provider: &RpcProvider, poll_interval: Duration, mut on_block: impl FnMut(Block<H256>)) {
+    if supports_subscriptions(provider) {
+        match provider.subscribe_blocks().await {
+            Ok(mut heads) => {
+                while let Some(block) = heads.next().await {
+                    on_block(block);
+                }
+                println!("Warning: new-heads subscription ended, polling instead");
+            }
+            Err(e) => println!("Warning: failed to subscribe to new heads ({}), polling instead", e),
+        }
+    }
+
+    let mut last = None;
+    loop {
+        if let Ok(Some(block)) = provider.get_block(BlockNumber::Latest).await {
+            if block.number != last {
+                last = block.number;
+                on_block(block);
+            }
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// The most recent block seen by [`watch_blocks`], kept so the checks right before broadcasting
+/// do not wait on a round trip.
+#[derive(Debug, Default)]
+pub struct LatestBlock(Mutex<Option<(Block<H256>, Instant)>>);
+
+impl LatestBlock {
+    pub fn update(&self, block: Block<H256>) {
+        *self.0.lock() = Some((block, Instant::now()));
+    }
+
+    /// The latest block, if it was seen within `max_age`.
+    pub fn fresh(&self, max_age: Duration) -> Option<Block<H256>> {
+        self.0.lock().as_ref().filter(|(_, seen)| seen.elapsed() <= max_age).map(|(block, _)| block.clone())
+    }
+}
+
+/// Waits until `tx_hash` is mined, returning `None` if it drops out of the mempool. WebSocket
+/// providers check once per new head rather than on a polling interval.
+pub async fn wait_for_receipt(provider: &RpcProvider, tx_hash: H256) -> Result<Option<TransactionReceipt>, ProviderError> {
+    let heads = if supports_subscriptions(provider) { provider.subscribe_blocks().await.ok() } else { None };
+    let Some(mut heads) = heads else {
+        return PendingTransaction::new(tx_hash, provider).await;
+    };
+
+    loop {
+        if let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? {
+            return Ok(Some(receipt));
+        }
+        if provider.get_transaction(tx_hash).await?.is_none() {
+            return Ok(None);
+        }
+        if heads.next().await.is_none() {
+            return PendingTransaction::new(tx_hash, provider).await;
+        }
+    }
+}
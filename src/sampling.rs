@@ -0,0 +1,66 @@
+use crate::config;
+use crate::exit;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Default location of the candidate sample log, relative to the working directory.
+pub const DEFAULT_SAMPLE_PATH: &str = "candidate_samples.log";
+/// Leading hash bytes written per sample, enough to compare against the prefix.
+const SAMPLE_HASH_BYTES: usize = 4;
+
+/// Writes every Nth signed candidate to a log, to check what the workers actually try when a
+/// search never seems to find anything. The count is shared by all workers.
+pub struct CandidateSampler {
+    every: u64,
+    count: AtomicU64,
+    file: Mutex<LineWriter<File>>,
+}
+
+impl CandidateSampler {
+    /// Appends to the log at `path`, starting with a header describing the search.
+    pub fn create(path: impl AsRef<Path>, every: u64, description: &str) -> eyre::Result<Self> {
+        eyre::ensure!(every > 0, "the sample interval must be at least 1");
+        let mut file = LineWriter::new(OpenOptions::new().create(true).append(true).open(path)?);
+        writeln!(file, "# {}, every {} candidates", description, every)?;
+        writeln!(file, "# time candidate worker nonce max_fee_per_gas max_priority_fee_per_gas value calldata_len hash")?;
+        Ok(Self { every, count: AtomicU64::new(0), file: Mutex::new(file) })
+    }
+
+    /// The sampler configured by SAMPLE_EVERY and SAMPLE_PATH, if sampling is on.
+    pub fn from_env(description: &str) -> eyre::Result<Option<Arc<Self>>> {
+        let Some(every) = config::sample_every().map_err(exit::config_error)? else {
+            return Ok(None);
+        };
+        let path = config::sample_path();
+        let sampler = Self::create(&path, every, description).map_err(|e| e.wrap_err(format!("SAMPLE_PATH {}", path)))?;
+        println!("Sampling every {} candidates to {}", every, path);
+        Ok(Some(Arc::new(sampler)))
+    }
+
+    /// Counts one signed candidate and logs it if it is due. Write errors are ignored, since
+    /// sampling must never stop a search.
+    pub fn record(&self, worker: usize, tx: &Eip1559TransactionRequest, tx_hash: &[u8; 32]) {
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        if !count.is_multiple_of(self.every) {
+            return;
+        }
+        let _ = writeln!(
+            self.file.lock(),
+            "{} {} {} {} {} {} {} {} 0x{}",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            count,
+            worker,
+            tx.nonce.unwrap_or_default(),
+            tx.max_fee_per_gas.unwrap_or_default(),
+            tx.max_priority_fee_per_gas.unwrap_or_default(),
+            tx.value.unwrap_or_default(),
+            tx.data.as_ref().map_or(0, |data| data.len()),
+            hex::encode(&tx_hash[..SAMPLE_HASH_BYTES])
+        );
+    }
+}
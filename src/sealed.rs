@@ -0,0 +1,129 @@
+use aes::cipher::{KeyIvInit, StreamCipher};
+use ethers::core::rand::{thread_rng, RngCore};
+use eyre::{ensure, eyre};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+
+/// scrypt cost (2^15 rounds, 32 MiB), the same as `geth`'s standard keystore setting.
+pub const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// A key derived from a passphrase with scrypt: half for AES-256-CTR, half for HMAC-SHA256.
+/// Deriving is deliberately slow, so one key is kept per salt and reused for every record.
+#[derive(Clone)]
+pub struct SealingKey {
+    salt: [u8; 16],
+    log_n: u8,
+    cipher_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl SealingKey {
+    /// Derives a key with a fresh random salt.
+    pub fn generate(passphrase: &str) -> eyre::Result<Self> {
+        let mut salt = [0u8; 16];
+        thread_rng().fill_bytes(&mut salt);
+        Self::derive(passphrase, salt, SCRYPT_LOG_N)
+    }
+
+    pub fn derive(passphrase: &str, salt: [u8; 16], log_n: u8) -> eyre::Result<Self> {
+        let params = scrypt::Params::new(log_n, SCRYPT_R, SCRYPT_P).map_err(|e| eyre!("invalid scrypt cost: {}", e))?;
+        let mut output = [0u8; 64];
+        scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut output).map_err(|e| eyre!("scrypt failed: {}", e))?;
+        let mut key = Self { salt, log_n, cipher_key: [0; 32], mac_key: [0; 32] };
+        key.cipher_key.copy_from_slice(&output[..32]);
+        key.mac_key.copy_from_slice(&output[32..]);
+        Ok(key)
+    }
+
+    /// Whether this key was derived for `sealed`, i.e. can open it without deriving again.
+    pub fn matches(&self, sealed: &Sealed) -> bool {
+        sealed.salt == hex::encode(self.salt) && sealed.log_n == self.log_n
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Sealed {
+        let mut iv = [0u8; 16];
+        thread_rng().fill_bytes(&mut iv);
+        let mut ciphertext = plaintext.to_vec();
+        Aes256Ctr::new(&self.cipher_key.into(), &iv.into()).apply_keystream(&mut ciphertext);
+        Sealed {
+            kdf: "scrypt".to_string(),
+            salt: hex::encode(self.salt),
+            log_n: self.log_n,
+            iv: hex::encode(iv),
+            mac: hex::encode(self.mac(&iv, &ciphertext).finalize().into_bytes()),
+            ciphertext: hex::encode(ciphertext),
+        }
+    }
+
+    /// Decrypts `sealed`, failing if the passphrase is wrong or the record was altered.
+    pub fn open(&self, sealed: &Sealed) -> eyre::Result<Vec<u8>> {
+        ensure!(self.matches(sealed), "the record was sealed with a different salt");
+        let iv: [u8; 16] = hex::decode(&sealed.iv)?.try_into().map_err(|_| eyre!("IV must be 16 bytes"))?;
+        let mut plaintext = hex::decode(&sealed.ciphertext)?;
+        self.mac(&iv, &plaintext)
+            .verify_slice(&hex::decode(&sealed.mac)?)
+            .map_err(|_| eyre!("wrong passphrase, or the record has been tampered with"))?;
+        Aes256Ctr::new(&self.cipher_key.into(), &iv.into()).apply_keystream(&mut plaintext);
+        Ok(plaintext)
+    }
+
+    fn mac(&self, iv: &[u8], ciphertext: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.mac_key).expect("HMAC takes any key length");
+        mac.update(iv);
+        mac.update(ciphertext);
+        mac
+    }
+}
+
+/// An encrypted record as stored on disk, carrying what is needed to derive its key again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sealed {
+    pub kdf: String,
+    pub salt: String,
+    pub log_n: u8,
+    pub iv: String,
+    pub ciphertext: String,
+    pub mac: String,
+}
+
+impl Sealed {
+    /// Derives the key for this record from `passphrase`.
+    pub fn key(&self, passphrase: &str) -> eyre::Result<SealingKey> {
+        ensure!(self.kdf == "scrypt", "unsupported key derivation {:?}", self.kdf);
+        let salt: [u8; 16] = hex::decode(&self.salt)?.try_into().map_err(|_| eyre!("salt must be 16 bytes"))?;
+        SealingKey::derive(passphrase, salt, self.log_n)
+    }
+}
+
+/// Opens records sealed under one passphrase, deriving each distinct salt's key only once.
+pub struct Opener {
+    passphrase: String,
+    keys: Vec<SealingKey>,
+}
+
+impl Opener {
+    pub fn new(passphrase: &str) -> Self {
+        Self { passphrase: passphrase.to_string(), keys: Vec::new() }
+    }
+
+    /// The key of the first record opened, so new records can be sealed under the same salt
+    /// rather than adding another key derivation for every later reader.
+    pub fn first_key(&self) -> Option<SealingKey> {
+        self.keys.first().cloned()
+    }
+
+    pub fn open(&mut self, sealed: &Sealed) -> eyre::Result<Vec<u8>> {
+        if let Some(key) = self.keys.iter().find(|key| key.matches(sealed)) {
+            return key.open(sealed);
+        }
+        let key = sealed.key(&self.passphrase)?;
+        let plaintext = key.open(sealed)?;
+        self.keys.push(key);
+        Ok(plaintext)
+    }
+}
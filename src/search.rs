@@ -0,0 +1,899 @@
+use crate::fees::FeeProfile;
+use crate::journal::{Journal, JournalEntry};
+use crate::limits::ResourceLimits;
+use crate::patternfile::LivePatterns;
+use crate::reuse;
+use crate::sampling::CandidateSampler;
+use crate::stats::{self, SearchStats, WorkerStats};
+use crate::stop::{PartialMatches, StopPolicy};
+use crate::throttle::Throttle;
+use crate::verifier::{CandidateMessage, Verdict, Verifier};
+use ethers::prelude::*;
+use ethers::types::{
+    transaction::eip1559::Eip1559TransactionRequest,
+    transaction::eip2718::TypedTransaction,
+    Bytes, U256,
+};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::panic::AssertUnwindSafe;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+use tracing::{info_span, Instrument};
+
+// Constants for optimization
+pub const BUFFER_SIZE: usize = 1024;
+pub const BATCH_SIZE: usize = 1000;
+/// Batch size in low-memory mode, where each worker holds only a few copies of the calldata.
+pub const LOW_MEMORY_BATCH_SIZE: usize = 32;
+pub const DEFAULT_THREAD_COUNT: usize = 8;
+/// How far apart the workers start in wei when there is no fee ceiling to divide between them.
+pub const THREAD_OFFSET_SPACING: u64 = 100_000_000;
+pub const DEFAULT_MAX_RETAINED_MATCHES: usize = 16;
+/// Share of candidates a worker may fail to sign before the search is aborted. Signing a valid
+/// template essentially never fails, so anything above this points at the template or signer.
+pub const DEFAULT_MAX_FAILURE_RATE: f64 = 0.05;
+
+// Starting base fee and priority fee (wei); these worked well on Base, adjust for the target chain
+pub const BASE_FEE_START: u64 = 18_000_000;
+pub const PRIORITY_FEE: u64 = 1_250_000;
+
+/// What to search for: the unsigned template, the signer and the fee sweep starting point.
+#[derive(Clone)]
+pub struct SearchParams {
+    pub signer: Arc<dyn CandidateSigner>,
+    pub template: Eip1559TransactionRequest,
+    pub hash_prefix: String,
+    /// Patterns accepted instead of `hash_prefix` alone, from PATTERN_FILE. They can change while
+    /// the search runs; `hash_prefix` still drives the estimates and STOP_POLICY.
+    pub patterns: Option<Arc<LivePatterns>>,
+    pub base_fee_start: U256,
+    pub priority_fee: U256,
+    /// Workers stop once their sweep would sign above this fee, or wrap around if `salt` is set.
+    pub max_fee_per_gas: Option<U256>,
+    /// Only sign max fees that are multiples of this, e.g. whole gwei. 1 wei sweeps every fee.
+    pub fee_step: U256,
+    pub salt: Salt,
+    /// Tips the workers take turns over, from PRIORITY_FEE_LANES: worker `i` tips lane
+    /// `i % len` instead of `priority_fee`, and the workers on a lane split the fee range between
+    /// them. Empty when every worker tips `priority_fee`.
+    pub priority_fee_lanes: Vec<U256>,
+    /// Which match to return when several workers find one at nearly the same time.
+    pub match_policy: MatchPolicy,
+    /// Most matches held in memory at once. Each carries the full calldata twice, so large init
+    /// code adds up; beyond this the most expensive are dropped (the journal keeps them all).
+    pub max_retained_matches: usize,
+    /// Abort once a worker fails to sign more than this share of its candidates, checked after
+    /// every batch.
+    pub max_failure_rate: f64,
+    /// Candidates a worker builds and signs at a time, each holding its own copy of the calldata.
+    pub batch_size: usize,
+    pub thread_count: usize,
+    /// Tags journal entries when the search runs as a server job.
+    pub job_id: Option<u64>,
+    /// Fees already swept, e.g. restored from a checkpoint. Shared so the caller can save the
+    /// progress while the search runs; only used without a salt.
+    pub claims: Option<Arc<FeeClaims>>,
+    /// Logs every Nth candidate for debugging, from SAMPLE_EVERY.
+    pub sampler: Option<Arc<CandidateSampler>>,
+    /// Lowers the workers' priority and pauses or parks them on demand, e.g. for `--nice`.
+    pub throttle: Option<Arc<Throttle>>,
+    /// Ends the search early with a match of only part of the prefix, from STOP_POLICY.
+    pub stop_policy: Option<StopPolicy>,
+    /// External process that accepts or rejects each match before it is used, from
+    /// VERIFIER_COMMAND or VERIFIER_SOCKET. A rejected match is journaled and the search goes on.
+    pub verifier: Option<Arc<Verifier>>,
+    /// CPU time and signatures the search may use before it stops as if interrupted, from
+    /// MAX_CPU_SECONDS and MAX_SIGNATURES.
+    pub limits: ResourceLimits,
+}
+
+impl SearchParams {
+    /// A search for `hash_prefix` over `template` that sweeps the fees in `fees`, with every other
+    /// setting at its default: no patterns, sampler, throttle, stop policy, verifier or limits.
+    pub fn new(
+        signer: Arc<dyn CandidateSigner>,
+        template: Eip1559TransactionRequest,
+        hash_prefix: impl Into<String>,
+        fees: &FeeProfile,
+        salt: Salt,
+    ) -> Self {
+        Self {
+            signer,
+            template,
+            hash_prefix: hash_prefix.into(),
+            patterns: None,
+            base_fee_start: fees.base_fee_start,
+            priority_fee: fees.priority_fee,
+            max_fee_per_gas: fees.max_fee_per_gas,
+            fee_step: fees.fee_step,
+            salt,
+            priority_fee_lanes: fees.priority_fee_lanes.clone(),
+            match_policy: MatchPolicy::First,
+            max_retained_matches: DEFAULT_MAX_RETAINED_MATCHES,
+            max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+            batch_size: BATCH_SIZE,
+            thread_count: default_thread_count(),
+            job_id: None,
+            claims: None,
+            sampler: None,
+            throttle: None,
+            stop_policy: None,
+            verifier: None,
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// The same search over `template` instead. Swept fees only describe the template they were
+    /// swept for, so the copy starts without any.
+    pub fn with_template(&self, template: Eip1559TransactionRequest) -> Self {
+        Self { template, claims: None, ..self.clone() }
+    }
+
+    /// The priority-fee lane worker `i` tips on, `None` without lanes.
+    pub fn lane(&self, worker: usize) -> Option<usize> {
+        (!self.priority_fee_lanes.is_empty()).then(|| worker % self.priority_fee_lanes.len())
+    }
+
+    /// The lane whose tip `tx` pays, e.g. to report which lane found a match.
+    pub fn lane_of(&self, tx: &Eip1559TransactionRequest) -> Option<usize> {
+        let tip = tx.max_priority_fee_per_gas?;
+        self.priority_fee_lanes.iter().position(|&fee| fee == tip)
+    }
+
+    /// The search the workers on `lane` run between them: the same max fees, tipping the lane's
+    /// fee, over only their share of the threads.
+    pub fn on_lane(&self, lane: usize) -> Self {
+        let lanes = self.priority_fee_lanes.len();
+        let tip = self.priority_fee_lanes[lane];
+        Self {
+            base_fee_start: (self.base_fee_start + self.priority_fee).saturating_sub(tip),
+            priority_fee: tip,
+            thread_count: self.thread_count / lanes + usize::from(lane < self.thread_count % lanes),
+            priority_fee_lanes: Vec::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A second source of variation for when the fee range alone is too narrow, e.g. when fees must
+/// stay close to the market. Each worker then sweeps the whole fee range once per salt value,
+/// with the workers taking turns over the salt values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Salt {
+    /// Only the fee varies, and each worker sweeps its own fee range.
+    None,
+    /// Adds the salt in wei to the priority fee.
+    Tip,
+    /// Appends the salt to the calldata as 8 big-endian bytes.
+    Padding,
+    /// Adds the salt in wei to the transferred value.
+    Dust,
+}
+
+impl std::str::FromStr for Salt {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "none" => Ok(Self::None),
+            "tip" => Ok(Self::Tip),
+            "padding" => Ok(Self::Padding),
+            "dust" => Ok(Self::Dust),
+            _ => Err(eyre::eyre!("unknown salt {:?}, expected none, tip, padding or dust", s)),
+        }
+    }
+}
+
+/// Signs candidates for the search. `LocalWallet` is the real signer; tests substitute a cheap,
+/// deterministic one so the engine can run without keys.
+pub trait CandidateSigner: Send + Sync {
+    /// Signs `tx`, returning the signed RLP and the transaction hash.
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])>;
+}
+
+impl CandidateSigner for LocalWallet {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        encode_and_sign_eip1559(self, tx)
+    }
+}
+
+/// How to choose between matches found at nearly the same time. Every match is journaled either
+/// way, so the ones not chosen can still be sent later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchPolicy {
+    /// The match reported first.
+    First,
+    /// The match with the lowest max fee.
+    Cheapest,
+}
+
+impl std::str::FromStr for MatchPolicy {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "first" => Ok(Self::First),
+            "cheapest" => Ok(Self::Cheapest),
+            _ => Err(eyre::eyre!("unknown match policy {:?}, expected first or cheapest", s)),
+        }
+    }
+}
+
+/// A signed transaction whose hash matches the prefix.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub signed_rlp: Bytes,
+    pub tx_hash: [u8; 32],
+    pub max_fee_per_gas: U256,
+    pub total_fee_wei: U256,
+    /// The transaction as signed, including any salt.
+    pub tx: Eip1559TransactionRequest,
+}
+
+/// The candidate a worker signs for `max_fee_per_gas` and `salt` (ignored without a salt). The
+/// priority fee is capped at the max fee, since nodes reject a transaction tipping more than its
+/// max fee; the workers never sweep below [`first_fee_for_salt`], so the cap only guards callers
+/// picking fees themselves.
+pub fn candidate(params: &SearchParams, max_fee_per_gas: U256, salt: u64) -> Eip1559TransactionRequest {
+    let mut tx = params.template.clone();
+    tx.max_fee_per_gas = Some(max_fee_per_gas);
+    tx.max_priority_fee_per_gas = Some(params.priority_fee.min(max_fee_per_gas));
+    match params.salt {
+        Salt::None => {}
+        Salt::Tip => tx.max_priority_fee_per_gas = Some((params.priority_fee + salt).min(max_fee_per_gas)),
+        Salt::Padding => {
+            let mut data = tx.data.unwrap_or_default().to_vec();
+            data.extend_from_slice(&salt.to_be_bytes());
+            tx.data = Some(data.into());
+        }
+        Salt::Dust => tx.value = Some(tx.value.unwrap_or_default() + salt),
+    }
+    tx
+}
+
+/// The lowest max fee swept with `salt`: the start of the fee range, or for a tip salt the first
+/// fee step covering the salted priority fee.
+pub fn first_fee_for_salt(params: &SearchParams, salt: u64) -> U256 {
+    let fee_step = params.fee_step.max(U256::one());
+    let first_fee = round_up_to_step(params.base_fee_start + params.priority_fee, fee_step);
+    match params.salt {
+        Salt::Tip => first_fee.max(round_up_to_step(params.priority_fee + salt, fee_step)),
+        _ => first_fee,
+    }
+}
+
+/// Distinct max fees from the start of the fee range up to the ceiling, `None` without a ceiling.
+pub fn fee_count(params: &SearchParams) -> Option<U256> {
+    let fee_step = params.fee_step.max(U256::one());
+    let first_fee = first_fee_for_salt(params, 0);
+    params.max_fee_per_gas.map(|ceiling| if ceiling < first_fee { U256::zero() } else { (ceiling - first_fee) / fee_step + 1 })
+}
+
+/// How far apart the workers' starting fees are without a salt: with a ceiling, the fee range is
+/// split into one equal run of steps per worker, so it is swept exactly once; without one, a fixed
+/// `THREAD_OFFSET_SPACING`.
+pub fn worker_spacing(params: &SearchParams) -> U256 {
+    match fee_count(params) {
+        Some(fees) => {
+            let threads = U256::from(params.thread_count.max(1));
+            ((fees + threads - 1) / threads).max(U256::one()) * params.fee_step.max(U256::one())
+        }
+        None => U256::from(THREAD_OFFSET_SPACING),
+    }
+}
+
+/// Whether `tx_hash` starts with `hash_prefix`, given as lowercase hex with `0x`.
+pub fn hash_matches(tx_hash: &[u8; 32], hash_prefix: &str) -> bool {
+    format!("0x{}", hex::encode(tx_hash)).starts_with(hash_prefix)
+}
+
+/// How many leading digits of `hash_prefix` (lowercase hex with `0x`) `tx_hash` matches.
+pub fn matched_digits(tx_hash: &[u8; 32], hash_prefix: &str) -> usize {
+    let digits = hash_prefix.strip_prefix("0x").unwrap_or(hash_prefix).as_bytes();
+    hex::encode(tx_hash).bytes().zip(digits).take_while(|(a, b)| a == *b).count()
+}
+
+/// Rounds `fee` up to the next multiple of `step`.
+pub fn round_up_to_step(fee: U256, step: U256) -> U256 {
+    let step = step.max(U256::one());
+    let remainder = fee % step;
+    if remainder.is_zero() {
+        fee
+    } else {
+        fee - remainder + step
+    }
+}
+
+/// Fee ranges already handed to a worker. Without a salt each worker sweeps upwards from its own
+/// offset and can eventually run into the next worker's range; claiming every batch first means
+/// no max fee is signed twice.
+#[derive(Debug, Default)]
+pub struct FeeClaims {
+    /// Start of each claimed range to its (exclusive) end, merged where ranges touch.
+    ranges: Mutex<BTreeMap<U256, U256>>,
+}
+
+impl FeeClaims {
+    /// Claims restored from `ranges`, e.g. from a checkpoint.
+    pub fn from_ranges(ranges: impl IntoIterator<Item = (U256, U256)>) -> Self {
+        let claims = Self::default();
+        for (start, end) in ranges {
+            let _ = claims.claim(start, end);
+        }
+        claims
+    }
+
+    /// Every claimed range as `[start, end)`, in order.
+    pub fn ranges(&self) -> Vec<(U256, U256)> {
+        self.ranges.lock().iter().map(|(&start, &end)| (start, end)).collect()
+    }
+
+    /// Number of fees claimed so far at `step` apart.
+    pub fn fee_count(&self, step: U256) -> U256 {
+        let step = step.max(U256::one());
+        self.ranges.lock().iter().fold(U256::zero(), |count, (&start, &end)| count + (end - start + step - 1) / step)
+    }
+
+    /// Claims `[start, end)`, cut short where it runs into an existing claim, and returns the end
+    /// of what was claimed. If `start` itself is taken, returns `Err` with the end of that claim.
+    fn claim(&self, start: U256, end: U256) -> Result<U256, U256> {
+        let mut ranges = self.ranges.lock();
+        let previous = ranges.range(..=start).next_back().map(|(&s, &e)| (s, e));
+        if let Some((_, previous_end)) = previous.filter(|(_, e)| *e > start) {
+            return Err(previous_end);
+        }
+        let next = ranges.range(start..).next().map(|(&s, &e)| (s, e));
+        let mut end = next.map_or(end, |(next_start, _)| end.min(next_start));
+        let claimed_end = end;
+
+        if let Some((next_start, next_end)) = next.filter(|(s, _)| *s == end) {
+            ranges.remove(&next_start);
+            end = next_end;
+        }
+        match previous.filter(|(_, e)| *e == start) {
+            Some((previous_start, _)) => ranges.insert(previous_start, end),
+            None => ranges.insert(start, end),
+        };
+        Ok(claimed_end)
+    }
+}
+
+/// Thread count used when none is configured: one per core, capped at `DEFAULT_THREAD_COUNT`.
+pub fn default_thread_count() -> usize {
+    num_cpus::get().min(DEFAULT_THREAD_COUNT)
+}
+
+/// Sweeps `max_fee_per_gas` across `params.thread_count` workers until a matching hash is found.
+/// If several workers find one before they all stop, one is chosen by `params.match_policy`.
+/// `None` is returned if `found` is set by someone else (e.g. Ctrl-C) or every worker reaches the
+/// fee ceiling first, and an error if a worker fails to sign more than `params.max_failure_rate`
+/// of its candidates. The search also stops once it reaches `params.limits`, which is then
+/// recorded in `stats`. With `params.stop_policy`, partial matches are kept as well and the search
+/// stops as soon as the policy accepts one, which is returned if no full match was found.
+pub async fn run(
+    params: SearchParams,
+    stats: Arc<SearchStats>,
+    found: Arc<AtomicBool>,
+    journal: Arc<Journal>,
+) -> eyre::Result<Option<Match>> {
+    let (tx_result, mut rx_result) = mpsc::channel::<Match>(BUFFER_SIZE);
+    let claims = match params.priority_fee_lanes.len() {
+        0 => vec![params.claims.clone().unwrap_or_default()],
+        lanes => (0..lanes).map(|_| Arc::default()).collect(),
+    };
+    let partials = params.stop_policy.clone().map(|policy| Arc::new(PartialMatches::new(policy, first_fee_for_salt(&params, 0))));
+    let params = Arc::new(params);
+
+    let search_span = info_span!(
+        "search",
+        prefix = %params.hash_prefix,
+        threads = params.thread_count,
+        attempts = tracing::field::Empty,
+    );
+
+    let workers = Workers {
+        params: params.clone(),
+        tx_result,
+        found: found.clone(),
+        claims,
+        stats: stats.clone(),
+        journal: journal.clone(),
+        partials: partials.clone(),
+        positions: Arc::new((0..params.thread_count).map(|_| Mutex::new(None)).collect()),
+        span: search_span.clone(),
+    };
+    let mut tasks = JoinSet::new();
+    for i in 0..params.thread_count {
+        workers.spawn(&mut tasks, i);
+    }
+
+    // Stop everyone once the policy takes a partial match, rechecked as time passes
+    let policy_watch = partials.clone().map(|partials| {
+        let (stats, found) = (stats.clone(), found.clone());
+        tokio::spawn(async move {
+            let mut poll = tokio::time::interval(STOP_POLL_INTERVAL);
+            while !found.load(Ordering::Relaxed) {
+                poll.tick().await;
+                if partials.accepted(stats.elapsed()).is_some() {
+                    found.store(true, Ordering::Relaxed);
+                }
+            }
+        })
+    });
+
+    // Collect matches until every worker has stopped, so a match found just after another one
+    // is not lost, while keeping at most `max_retained_matches` of them in memory. A worker that
+    // panics is replaced, and one that stops sending heartbeats is reported.
+    let (matches, worker_error) = async {
+        let mut matches = Vec::new();
+        let retain = |found_match: Match, matches: &mut Vec<Match>| {
+            matches.push(found_match);
+            if matches.len() > params.max_retained_matches.max(1) {
+                evict_most_expensive(matches);
+            }
+        };
+        let mut worker_error = None;
+        let mut finished = vec![false; params.thread_count];
+        let mut stalled = vec![false; params.thread_count];
+        let mut stall_check = tokio::time::interval(WORKER_STALL_TIMEOUT / 4);
+        loop {
+            tokio::select! {
+                Some(found_match) = rx_result.recv() => retain(found_match, &mut matches),
+                joined = tasks.join_next() => match joined {
+                    None => break,
+                    Some(Ok((i, Ok(result)))) => {
+                        finished[i] = true;
+                        if let Err(e) = result {
+                            worker_error = worker_error.or(Some(e));
+                        }
+                    }
+                    Some(Ok((i, Err(panic)))) => {
+                        let reason = panic_message(panic.as_ref());
+                        if found.load(Ordering::Relaxed) {
+                            finished[i] = true;
+                        } else if stats.worker(i).respawns() < MAX_WORKER_RESPAWNS {
+                            stats.worker(i).record_respawn();
+                            println!("Warning: worker {} died ({}), restarting it where it left off", i, reason);
+                            workers.spawn(&mut tasks, i);
+                        } else {
+                            finished[i] = true;
+                            println!("Warning: worker {} died again ({}), continuing without it", i, reason);
+                            worker_error = worker_error.or(Some(eyre::eyre!("worker {} died {} times, last: {}", i, MAX_WORKER_RESPAWNS + 1, reason)));
+                        }
+                    }
+                    Some(Err(e)) => worker_error = worker_error.or(Some(e.into())),
+                },
+                // Throttled workers legitimately go quiet while parked
+                _ = stall_check.tick(), if params.throttle.is_none() => {
+                    for i in 0..params.thread_count {
+                        let quiet = !finished[i] && !found.load(Ordering::Relaxed) && stats.since_heartbeat(i) > WORKER_STALL_TIMEOUT;
+                        if quiet && !stalled[i] {
+                            println!("Warning: worker {} has not finished a batch in {}s, its signer may be stuck", i, stats.since_heartbeat(i).as_secs());
+                        }
+                        stalled[i] = quiet;
+                    }
+                }
+            }
+        }
+        // Matches sent just before the last worker stopped
+        while let Ok(found_match) = rx_result.try_recv() {
+            retain(found_match, &mut matches);
+        }
+        (matches, worker_error)
+    }
+    .instrument(search_span.clone())
+    .await;
+    search_span.record("attempts", stats.total_attempts());
+    if let Some(policy_watch) = policy_watch {
+        policy_watch.abort();
+    }
+
+    if let Some(limit) = stats.limit_reached() {
+        println!(
+            "Stopped at the {} after {} signatures and {:.1} CPU seconds",
+            limit,
+            stats.total_signatures(),
+            stats.total_cpu_time().as_secs_f64()
+        );
+    }
+
+    // A match is still worth reporting even if another worker gave up
+    let chosen = choose_match(matches, params.match_policy).or_else(|| {
+        let partials = partials?;
+        let (digits, partial) = partials.accepted(stats.elapsed())?;
+        journal_match(&journal, &params, &partial.tx, &partial.signed_rlp, partial.tx_hash);
+        println!(
+            "Stop policy: taking 0x{}, which matches {} of the {} digits of {}, at {:.2}% fee overhead after {}",
+            hex::encode(partial.tx_hash),
+            digits,
+            params.hash_prefix.len() - 2,
+            params.hash_prefix,
+            partials.overhead(partial.max_fee_per_gas) * 100.0,
+            crate::units::format_duration(stats.elapsed().as_secs_f64())
+        );
+        Some(partial)
+    });
+    match (chosen, worker_error) {
+        (None, Some(e)) => Err(e),
+        (chosen, _) => Ok(chosen),
+    }
+}
+
+/// Times a worker that panicked is started again before the search carries on without it.
+pub const MAX_WORKER_RESPAWNS: u64 = 3;
+/// A worker that has not finished a batch for this long is reported as stalled.
+const WORKER_STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How far a worker has got, recorded with every heartbeat so a replacement picks up after it.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    max_fee_per_gas: U256,
+    salt: u64,
+    /// End of the fee range the worker claimed for the batch it is signing, which nobody else
+    /// will sweep, so a replacement finishes it without claiming it again.
+    batch_end: Option<U256>,
+}
+
+type WorkerResult = (usize, std::thread::Result<eyre::Result<()>>);
+
+/// What the workers of one search share.
+#[derive(Clone)]
+struct Workers {
+    params: Arc<SearchParams>,
+    tx_result: mpsc::Sender<Match>,
+    found: Arc<AtomicBool>,
+    /// One set per priority-fee lane, or a single one without lanes.
+    claims: Vec<Arc<FeeClaims>>,
+    stats: Arc<SearchStats>,
+    journal: Arc<Journal>,
+    partials: Option<Arc<PartialMatches>>,
+    positions: Arc<Vec<Mutex<Option<Position>>>>,
+    span: tracing::Span,
+}
+
+impl Workers {
+    /// Starts worker `i`. Workers are CPU-bound, so they run on blocking threads to keep the async
+    /// runtime free for the metrics endpoint, monitors and signal handling. A panic is caught and
+    /// returned with the worker's index, so the worker can be replaced.
+    fn spawn(&self, tasks: &mut JoinSet<WorkerResult>, i: usize) {
+        let workers = self.clone();
+        let worker_span = info_span!(parent: &self.span, "worker", index = i);
+        tasks.spawn_blocking(move || {
+            let _span = worker_span.entered();
+            (i, std::panic::catch_unwind(AssertUnwindSafe(|| workers.sweep(i))))
+        });
+    }
+
+    fn sweep(&self, i: usize) -> eyre::Result<()> {
+        let Self { params, tx_result, found, claims, stats, journal, partials, positions, .. } = self;
+        if let Some(throttle) = &params.throttle {
+            throttle.enter_worker();
+        }
+        let gas_limit = params.template.gas.unwrap_or_default();
+        // On a lane, the worker only shares the fee range (or salt values) with the others on it
+        let (params, claims, slot) = match params.lane(i) {
+            Some(lane) => (&Arc::new(params.on_lane(lane)), &claims[lane], i / params.priority_fee_lanes.len()),
+            None => (params, &claims[0], i),
+        };
+        // With a salt the workers share the fee range and split the salt values instead
+        let base_fee_offset = match params.salt {
+            Salt::None => worker_spacing(params) * slot,
+            _ => U256::zero(),
+        };
+        let fee_step = params.fee_step.max(U256::one());
+        // A replacement for a worker that died picks up where its last heartbeat left off
+        let resumed = *positions[i].lock();
+        let mut salt = resumed.map_or(slot as u64, |position| position.salt);
+        let mut resumed_batch_end = resumed.and_then(|position| position.batch_end);
+        let mut max_fee_per_gas = match resumed {
+            Some(position) => position.max_fee_per_gas,
+            None => {
+                let start = round_up_to_step(params.base_fee_start + base_fee_offset + params.priority_fee, fee_step)
+                    .max(first_fee_for_salt(params, salt));
+                stats.worker(i).fee_start.store(start.low_u64(), Ordering::Relaxed);
+                start
+            }
+        };
+        stats.heartbeat(i);
+        let batch_size = params.batch_size.max(1);
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut cpu_mark = stats::thread_cpu_time();
+
+        while !found.load(Ordering::Relaxed) {
+            if let Some(throttle) = &params.throttle {
+                throttle.wait_until_active(i, found);
+            }
+            batch.clear();
+
+            // Salted workers never overlap, since each has its own salt values
+            let mut batch_end = resumed_batch_end.take();
+            if params.salt == Salt::None && batch_end.is_none() {
+                let wanted_end = max_fee_per_gas.saturating_add(fee_step * batch_size);
+                match claims.claim(max_fee_per_gas, wanted_end) {
+                    Ok(end) => {
+                        batch_end = Some(end);
+                        *positions[i].lock() = Some(Position { max_fee_per_gas, salt, batch_end });
+                    }
+                    Err(claimed_until) => {
+                        // Another worker already swept these fees, skip past them
+                        let skipped = (claimed_until - max_fee_per_gas + fee_step - 1) / fee_step;
+                        stats.worker(i).record_duplicates_skipped(skipped.low_u64());
+                        max_fee_per_gas = round_up_to_step(claimed_until, fee_step);
+                        continue;
+                    }
+                }
+            }
+
+            for _ in 0..batch_size {
+                if batch_end.is_some_and(|end| max_fee_per_gas >= end) {
+                    break;
+                }
+                if params.max_fee_per_gas.is_some_and(|ceiling| max_fee_per_gas > ceiling) {
+                    if params.salt == Salt::None {
+                        break;
+                    }
+                    salt += params.thread_count as u64;
+                    max_fee_per_gas = first_fee_for_salt(params, salt);
+                    // A tip salt eventually outgrows the fee range, and every later one would too
+                    if params.max_fee_per_gas.is_some_and(|ceiling| max_fee_per_gas > ceiling) {
+                        break;
+                    }
+                }
+                // Every candidate starts from the same snapshot; changing the template
+                // means restarting the search, see `run_with_updates`
+                batch.push(candidate(params, max_fee_per_gas, salt));
+                max_fee_per_gas = max_fee_per_gas.saturating_add(fee_step);
+            }
+            if batch.is_empty() {
+                // This worker's share of the fee range is used up
+                break;
+            }
+
+            if let Some(found_match) =
+                process_batch(&batch, params, gas_limit, found, i, stats.worker(i), journal, partials.as_deref())?
+            {
+                let _ = tx_result.blocking_send(found_match);
+                break;
+            }
+            *positions[i].lock() = Some(Position { max_fee_per_gas, salt, batch_end: None });
+            stats.heartbeat(i);
+            if let Some(now) = stats::thread_cpu_time() {
+                stats.worker(i).record_cpu_time(now.saturating_sub(cpu_mark.unwrap_or(now)));
+                cpu_mark = Some(now);
+            }
+            if let Some(limit) = params.limits.exceeded(stats.total_cpu_time(), stats.total_signatures()) {
+                stats.set_limit_reached(limit);
+                found.store(true, Ordering::Relaxed);
+                break;
+            }
+            if stats.worker(i).failure_rate() > params.max_failure_rate {
+                // The same template fails everywhere, so stop every worker rather than spin
+                found.store(true, Ordering::Relaxed);
+                eyre::bail!(
+                    "worker {} failed to sign {:.1}% of its candidates, above the limit of {:.1}%. Sign failures: {}. First error: {}",
+                    i,
+                    stats.worker(i).failure_rate() * 100.0,
+                    params.max_failure_rate * 100.0,
+                    stats.failure_summary(),
+                    stats.worker(i).first_failure().unwrap_or_default()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "panicked".to_string(),
+    }
+}
+
+/// How often `run_with_updates` checks whether `found` was set by someone else.
+const STOP_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Like [`run`], but restarts the search whenever a new template arrives on `templates`: the
+/// workers are stopped and started again on a snapshot of the new template, so no worker ever
+/// signs a mix of old and new fields. A match found for a replaced template is discarded (it is
+/// still in the journal).
+pub async fn run_with_updates(
+    mut params: SearchParams,
+    stats: Arc<SearchStats>,
+    found: Arc<AtomicBool>,
+    journal: Arc<Journal>,
+    mut templates: watch::Receiver<Eip1559TransactionRequest>,
+) -> eyre::Result<Option<Match>> {
+    let mut updates_open = true;
+    loop {
+        let round = Arc::new(AtomicBool::new(found.load(Ordering::Relaxed)));
+        let search = run(params.clone(), stats.clone(), round.clone(), journal.clone());
+        tokio::pin!(search);
+        let mut poll = tokio::time::interval(STOP_POLL_INTERVAL);
+        let template = loop {
+            tokio::select! {
+                biased;
+                changed = templates.changed(), if updates_open => {
+                    if changed.is_err() {
+                        updates_open = false;
+                        continue;
+                    }
+                    round.store(true, Ordering::Relaxed);
+                    let _ = (&mut search).await;
+                    break templates.borrow_and_update().clone();
+                }
+                result = &mut search => {
+                    if round.load(Ordering::Relaxed) {
+                        found.store(true, Ordering::Relaxed);
+                    }
+                    return result;
+                }
+                _ = poll.tick() => {
+                    if found.load(Ordering::Relaxed) {
+                        round.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        };
+        println!("Template changed, restarting the search");
+        params = params.with_template(template);
+    }
+}
+
+/// Drops the most expensive match other than the first one found; it remains in the journal.
+fn evict_most_expensive(matches: &mut Vec<Match>) {
+    if let Some(i) = (1..matches.len()).max_by_key(|&i| matches[i].total_fee_wei) {
+        let evicted = matches.remove(i);
+        println!(
+            "Dropped match 0x{} from memory to stay within MAX_RETAINED_MATCHES; it is kept in the journal",
+            hex::encode(evicted.tx_hash)
+        );
+    }
+}
+
+/// Picks one of `matches` (in the order they were found) by `policy` and mentions the others,
+/// which are already in the journal.
+fn choose_match(mut matches: Vec<Match>, policy: MatchPolicy) -> Option<Match> {
+    if matches.is_empty() {
+        return None;
+    }
+    let chosen = match policy {
+        MatchPolicy::First => 0,
+        MatchPolicy::Cheapest => (0..matches.len()).min_by_key(|&i| matches[i].total_fee_wei).unwrap_or(0),
+    };
+    let chosen = matches.remove(chosen);
+    for other in &matches {
+        println!(
+            "Also found 0x{} at max fee {}; it is kept in the journal but will not be sent",
+            hex::encode(other.tx_hash),
+            crate::units::format_wei(other.max_fee_per_gas)
+        );
+    }
+    Some(chosen)
+}
+
+/// Records a match in the journal, warning rather than failing if it cannot be written.
+fn journal_match(journal: &Journal, params: &SearchParams, tx: &Eip1559TransactionRequest, signed_rlp: &Bytes, tx_hash: [u8; 32]) {
+    let entry = JournalEntry {
+        timestamp: chrono::Utc::now().timestamp(),
+        chain_id: tx.chain_id.unwrap_or_default().as_u64(),
+        nonce: tx.nonce.unwrap_or_default(),
+        tx_hash: tx_hash.into(),
+        raw_tx: signed_rlp.clone(),
+        max_fee_per_gas: tx.max_fee_per_gas.unwrap_or_default(),
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+        job_id: params.job_id,
+        template: Some(reuse::template_hash(&params.template)),
+    };
+    if let Err(e) = journal.append(&entry) {
+        println!("Warning: failed to journal match 0x{}: {}", hex::encode(tx_hash), e);
+    }
+}
+
+/// Whether `params.verifier` (if any) lets the search use a candidate matching `digits` digits,
+/// printing why not. If it cannot be asked, every worker is stopped, since nothing may be used
+/// without its decision.
+fn verified(
+    params: &SearchParams,
+    tx: &Eip1559TransactionRequest,
+    signed_rlp: &Bytes,
+    tx_hash: [u8; 32],
+    digits: usize,
+    full_match: bool,
+    found: &AtomicBool,
+) -> eyre::Result<bool> {
+    let Some(verifier) = &params.verifier else {
+        return Ok(true);
+    };
+    match verifier.check(&CandidateMessage::new(tx, signed_rlp, tx_hash, digits, full_match)) {
+        Ok(Verdict::Accept) => Ok(true),
+        Ok(Verdict::Reject(reason)) => {
+            println!("The {} rejected 0x{}: {}; it is kept in the journal", verifier.description(), hex::encode(tx_hash), reason);
+            Ok(false)
+        }
+        Err(e) => {
+            found.store(true, Ordering::Relaxed);
+            Err(e)
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_batch(
+    batch: &[Eip1559TransactionRequest],
+    params: &SearchParams,
+    gas_limit: U256,
+    found: &AtomicBool,
+    worker: usize,
+    worker_stats: &WorkerStats,
+    journal: &Journal,
+    partials: Option<&PartialMatches>,
+) -> eyre::Result<Option<Match>> {
+    for tx in batch {
+        if found.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        worker_stats.record_attempt(tx.max_fee_per_gas.unwrap_or_default());
+
+        let (signed_rlp, tx_hash) = match params.signer.sign_candidate(tx) {
+            Ok(signed) => signed,
+            Err(e) => {
+                worker_stats.record_sign_failure(&e);
+                continue;
+            }
+        };
+        if let Some(sampler) = &params.sampler {
+            sampler.record(worker, tx, &tx_hash);
+        }
+        let matched = match &params.patterns {
+            Some(patterns) => patterns.matching(&tx_hash),
+            None => hash_matches(&tx_hash, &params.hash_prefix).then(|| params.hash_prefix.clone()),
+        };
+        if let Some(prefix) = matched {
+            // Journal before anything else so the match survives a crash or lost race
+            journal_match(journal, params, tx, &signed_rlp, tx_hash);
+            let digits = prefix.len().saturating_sub(2);
+            if !verified(params, tx, &signed_rlp, tx_hash, digits, true, found)? {
+                continue;
+            }
+
+            // Stop the other workers, but report this match even if one of them got there first
+            found.store(true, Ordering::Relaxed);
+            let max_fee_per_gas = tx.max_fee_per_gas.unwrap_or_default();
+            let total_fee_wei = gas_limit * max_fee_per_gas;
+            return Ok(Some(Match { signed_rlp, tx_hash, max_fee_per_gas, total_fee_wei, tx: tx.clone() }));
+        }
+        if let Some(partials) = partials {
+            let digits = matched_digits(&tx_hash, &params.hash_prefix);
+            if digits >= partials.min_digits() && verified(params, tx, &signed_rlp, tx_hash, digits, false, found)? {
+                let max_fee_per_gas = tx.max_fee_per_gas.unwrap_or_default();
+                let total_fee_wei = gas_limit * max_fee_per_gas;
+                partials.offer(digits, Match { signed_rlp, tx_hash, max_fee_per_gas, total_fee_wei, tx: tx.clone() });
+            }
+        }
+    }
+    Ok(None)
+}
+
+pub fn encode_and_sign_eip1559(
+    wallet: &LocalWallet,
+    eip1559_tx: &Eip1559TransactionRequest,
+) -> eyre::Result<(Bytes, [u8; 32])> {
+    // Convert to TypedTransaction
+    let typed_tx = TypedTransaction::Eip1559(eip1559_tx.clone());
+
+    // Sign the transaction
+    let signature = wallet.sign_transaction_sync(&typed_tx)?;
+
+    // Get the signed transaction bytes and hash
+    let signed_tx = typed_tx.rlp_signed(&signature);
+    let tx_hash: [u8; 32] = typed_tx.hash(&signature).into();
+
+    Ok((signed_tx, tx_hash))
+}
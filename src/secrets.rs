@@ -0,0 +1,195 @@
+use crate::config::non_empty_var;
+use serde_json::Value;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where Docker and Docker Compose mount secrets.
+pub const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+/// The service account token Kubernetes mounts into every pod.
+pub const DEFAULT_K8S_TOKEN_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount/token";
+const VAULT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Settings holding keys or credentials, looked up in SECRETS_DIR when they are not set.
+pub const SECRET_VARS: &[&str] = &[
+    "PRIVATE_KEY",
+    "RPC",
+    "RPC_HEADERS",
+    "RPC_BASIC_AUTH",
+    "RPC_BEARER_TOKEN",
+    "RPC_JWT_SECRET",
+    "JOURNAL_PASSPHRASE",
+    "BUNDLE_SIGNING_KEY",
+    "API_TOKEN",
+    "ETHERSCAN_API_KEY",
+    "BLOCKNATIVE_API_KEY",
+    "SMTP_URL",
+];
+
+/// Name endings of settings that hold credentials, e.g. a `jobs` key_var such as `DEPLOYER_KEY`.
+const SECRET_SUFFIXES: &[&str] = &["_KEY", "_TOKEN", "_SECRET", "_PASSWORD", "_PASSPHRASE"];
+
+/// Whether `NAME_FILE` and `NAME_VAULT` are read for `name`: the known secret settings, any RPC
+/// setting and names ending like a credential. Other `_FILE` variables such as SSL_CERT_FILE
+/// belong to other tools and are left alone.
+pub fn is_secret_name(name: &str) -> bool {
+    SECRET_VARS.contains(&name) || name.starts_with("RPC_") || SECRET_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Where an unset setting is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A mounted file, from `NAME_FILE` or SECRETS_DIR.
+    File(PathBuf),
+    /// A field of a Vault secret, from `NAME_VAULT=<path>#<field>`.
+    Vault { path: String, field: String },
+}
+
+/// Works out which settings come from a secret store, given the environment `vars` and the
+/// secrets directory. `NAME_FILE` and `NAME_VAULT` apply to names passing [`is_secret_name`];
+/// SECRETS_DIR is only searched for [`SECRET_VARS`], by name in upper or lower case.
+pub fn sources(vars: &[(String, String)], secrets_dir: &Path) -> eyre::Result<Vec<(String, Source)>> {
+    let is_set = |name: &str| vars.iter().any(|(key, value)| key == name && !value.is_empty());
+    let mut found = Vec::new();
+    for (key, value) in vars.iter().filter(|(_, value)| !value.is_empty()) {
+        let (name, source) = match (key.strip_suffix("_FILE"), key.strip_suffix("_VAULT")) {
+            (Some(name), _) if is_secret_name(name) => (name, Source::File(value.into())),
+            (_, Some(name)) if is_secret_name(name) => (name, parse_reference(value).map_err(|e| e.wrap_err(key.clone()))?),
+            _ => continue,
+        };
+        eyre::ensure!(!is_set(name), "{} and {} are both set, keep only one", name, key);
+        eyre::ensure!(
+            !found.iter().any(|(other, _)| other == name),
+            "{} is given by both {}_FILE and {}_VAULT, keep only one",
+            name,
+            name,
+            name
+        );
+        found.push((name.to_string(), source));
+    }
+    for name in SECRET_VARS {
+        if is_set(name) || found.iter().any(|(other, _)| other == name) {
+            continue;
+        }
+        let mounted = [secrets_dir.join(name), secrets_dir.join(name.to_lowercase())].into_iter().find(|path| path.is_file());
+        if let Some(path) = mounted {
+            found.push((name.to_string(), Source::File(path)));
+        }
+    }
+    Ok(found)
+}
+
+/// Parses a Vault reference such as `secret/data/deployer#private_key`.
+pub fn parse_reference(reference: &str) -> eyre::Result<Source> {
+    match reference.trim().trim_start_matches('/').rsplit_once('#') {
+        Some((path, field)) if !path.is_empty() && !field.is_empty() => {
+            Ok(Source::Vault { path: path.to_string(), field: field.to_string() })
+        }
+        _ => Err(eyre::eyre!("expected a Vault path and field such as secret/data/deployer#private_key, got {:?}", reference)),
+    }
+}
+
+/// A mounted secret's contents, without the trailing newline most tools write.
+pub fn read_file(path: &Path) -> eyre::Result<String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| eyre::eyre!("{}: {}", path.display(), e))?;
+    Ok(contents.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// `field` of a Vault read response, from a KV version 2 (`data.data`) or version 1 (`data`) engine.
+pub fn extract_field(response: &Value, field: &str) -> Option<String> {
+    let data = &response["data"];
+    let value = match &data["data"] {
+        Value::Object(fields) if data.get("metadata").is_some() => fields.get(field),
+        _ => data.get(field),
+    }?;
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// A logged-in Vault client, from VAULT_ADDR and VAULT_TOKEN or, in Kubernetes, VAULT_K8S_ROLE.
+pub struct Vault {
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Vault {
+    pub async fn from_env() -> eyre::Result<Self> {
+        let addr = non_empty_var("VAULT_ADDR").ok_or_else(|| eyre::eyre!("VAULT_ADDR is not set"))?;
+        let addr = addr.trim_end_matches('/').to_string();
+        let namespace = non_empty_var("VAULT_NAMESPACE");
+        let client = reqwest::Client::builder().timeout(VAULT_TIMEOUT).build()?;
+        let token = match (non_empty_var("VAULT_TOKEN"), non_empty_var("VAULT_K8S_ROLE")) {
+            (Some(token), _) => token,
+            (None, Some(role)) => {
+                let token_path = non_empty_var("VAULT_K8S_TOKEN_PATH").unwrap_or_else(|| DEFAULT_K8S_TOKEN_PATH.to_string());
+                let jwt = read_file(Path::new(&token_path))?;
+                let mount = non_empty_var("VAULT_K8S_MOUNT").unwrap_or_else(|| "kubernetes".to_string());
+                let url = format!("{}/v1/auth/{}/login", addr, mount);
+                let body = serde_json::json!({ "role": role, "jwt": jwt }).to_string();
+                let response = send(with_namespace(client.post(&url), &namespace).body(body)).await?;
+                response["auth"]["client_token"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| eyre::eyre!("Vault Kubernetes login for role {} returned no token", role))?
+            }
+            (None, None) => eyre::bail!("set VAULT_TOKEN (or VAULT_TOKEN_FILE), or VAULT_K8S_ROLE to log in with the pod's service account"),
+        };
+        Ok(Self { addr, token, namespace, client })
+    }
+
+    pub async fn read(&self, path: &str, field: &str) -> eyre::Result<String> {
+        let url = format!("{}/v1/{}", self.addr, path);
+        let request = with_namespace(self.client.get(&url), &self.namespace).header("X-Vault-Token", &self.token);
+        let response = send(request).await.map_err(|e| e.wrap_err(format!("reading Vault secret {}", path)))?;
+        extract_field(&response, field).ok_or_else(|| eyre::eyre!("Vault secret {} has no field {}", path, field))
+    }
+}
+
+fn with_namespace(request: reqwest::RequestBuilder, namespace: &Option<String>) -> reqwest::RequestBuilder {
+    match namespace {
+        Some(namespace) => request.header("X-Vault-Namespace", namespace),
+        None => request,
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> eyre::Result<Value> {
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    eyre::ensure!(status.is_success(), "Vault answered {}: {}", status, body.trim());
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Fills in settings that are not in the environment from mounted secret files and Vault, so
+/// keys never have to be passed as environment variables. Runs once at startup, after `.env`.
+/// Returns the names that were loaded, never their values.
+pub async fn load() -> eyre::Result<Vec<String>> {
+    let vars: Vec<(String, String)> = env::vars().collect();
+    let secrets_dir = non_empty_var("SECRETS_DIR").unwrap_or_else(|| DEFAULT_SECRETS_DIR.to_string());
+    let sources = sources(&vars, Path::new(&secrets_dir))?;
+
+    // Files first, so VAULT_TOKEN_FILE can supply the token for the Vault reads
+    let mut loaded = Vec::new();
+    for (name, source) in &sources {
+        if let Source::File(path) = source {
+            env::set_var(name, read_file(path).map_err(|e| e.wrap_err(format!("reading {}", name)))?);
+            loaded.push(name.clone());
+        }
+    }
+    let mut vault = None;
+    for (name, source) in &sources {
+        if let Source::Vault { path, field } = source {
+            if vault.is_none() {
+                vault = Some(Vault::from_env().await.map_err(|e| e.wrap_err(format!("{}_VAULT", name)))?);
+            }
+            let value = vault.as_ref().expect("logged in above").read(path, field).await?;
+            env::set_var(name, value);
+            loaded.push(name.clone());
+        }
+    }
+    Ok(loaded)
+}
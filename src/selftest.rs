@@ -0,0 +1,72 @@
+use crate::address::get_contract_address;
+use crate::search::encode_and_sign_eip1559;
+use crate::verify;
+use ethers::prelude::*;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::utils::keccak256;
+use eyre::{ensure, eyre};
+
+/// The private key from the EIP-155 example, never to be used for real funds.
+const TEST_KEY: &str = "4646464646464646464646464646464646464646464646464646464646464646";
+const TEST_ADDRESS: &str = "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f";
+/// The known-answer transaction from `test_transaction`, signed with `TEST_KEY`.
+const TEST_SIGNED_RLP: &str = "02f8770109843b9aca008504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000084deadbeefc001a010f90ccd40d0745f6cd66e3e7babdb22deda93055da19ddd28078e7b4a7a86eba027ae1fbaf5d724d56520aa1ba1caec735d590980b6968d2d74b7d3c1d55f4221";
+const TEST_TX_HASH: &str = "86794323d2607b20ddd95a6aff7e816b12123b11795524dc7fbae6fa59cbf28f";
+const EMPTY_KECCAK: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+/// A well-known CREATE: the first contract deployed by this sender.
+const CREATE_SENDER: &str = "0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0";
+const CREATE_ADDRESS: &str = "0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d";
+
+/// Known-answer test of the signing, encoding and hashing the search relies on, so a change in
+/// ethers or the encoders fails loudly at startup instead of producing hashes that never match
+/// what the network computes.
+pub fn run() -> eyre::Result<()> {
+    ensure!(hex::encode(keccak256([])) == EMPTY_KECCAK, "keccak256 of empty input is wrong");
+
+    let wallet: LocalWallet = TEST_KEY.parse()?;
+    ensure!(
+        wallet.address() == TEST_ADDRESS.parse::<Address>()?,
+        "test key derives address {:?}, expected {}",
+        wallet.address(),
+        TEST_ADDRESS
+    );
+
+    let (signed_rlp, tx_hash) = encode_and_sign_eip1559(&wallet, &test_transaction()?)?;
+    ensure!(
+        hex::encode(&signed_rlp) == TEST_SIGNED_RLP,
+        "signed test transaction encodes as 0x{}, expected 0x{}",
+        hex::encode(&signed_rlp),
+        TEST_SIGNED_RLP
+    );
+    ensure!(
+        hex::encode(tx_hash) == TEST_TX_HASH,
+        "test transaction hashes to 0x{}, expected 0x{}",
+        hex::encode(tx_hash),
+        TEST_TX_HASH
+    );
+    let verified = verify::verify(&signed_rlp, TEST_TX_HASH, 1, 9.into())
+        .map_err(|e| eyre!("test transaction does not verify: {}", e))?;
+    ensure!(verified.from == wallet.address(), "test transaction recovers to {:?}", verified.from);
+
+    let contract = get_contract_address(CREATE_SENDER.parse()?, 0.into());
+    ensure!(
+        contract == CREATE_ADDRESS.parse::<Address>()?,
+        "contract address for {} at nonce 0 computed as {:?}, expected {}",
+        CREATE_SENDER,
+        contract,
+        CREATE_ADDRESS
+    );
+    Ok(())
+}
+
+fn test_transaction() -> eyre::Result<Eip1559TransactionRequest> {
+    Ok(Eip1559TransactionRequest::new()
+        .chain_id(1u64)
+        .nonce(9u64)
+        .gas(21_000u64)
+        .max_fee_per_gas(20_000_000_000u64)
+        .max_priority_fee_per_gas(1_000_000_000u64)
+        .to("0x3535353535353535353535353535353535353535".parse::<Address>()?)
+        .value(1_000_000_000_000_000_000u64)
+        .data(vec![0xde, 0xad, 0xbe, 0xef]))
+}
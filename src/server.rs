@@ -0,0 +1,750 @@
+use crate::address::get_contract_address;
+use crate::broadcast::{BroadcastConfig, Submission};
+use crate::history::{Deployment, HistoryDb};
+use crate::nonces;
+use crate::envelope;
+use crate::fees::FeeProfile;
+use crate::journal::Journal;
+use crate::limits::ResourceLimits;
+use crate::pattern;
+use crate::payload::MatchPayload;
+use crate::rpc::{self, RpcConfig};
+use crate::sampling::CandidateSampler;
+use crate::search::{self, FeeClaims, SearchParams};
+use crate::grpc;
+use crate::stats::SearchStats;
+use crate::throttle::{self, Throttle, WatchdogAction};
+use crate::units;
+use crate::verify;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use ethers::types::{transaction::eip1559::Eip1559TransactionRequest, Address, Bytes, H256, U256};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const EVENT_BUFFER: usize = 256;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Settings shared by every job the server runs, loaded once from the environment.
+pub struct ServerConfig {
+    pub wallet: LocalWallet,
+    pub rpc: RpcConfig,
+    pub chain_id: u64,
+    pub fees: FeeProfile,
+    /// Total worker threads shared by all running jobs.
+    pub thread_count: usize,
+    pub scheduling: Scheduling,
+    pub journal_path: PathBuf,
+    pub journal_passphrase: Option<String>,
+    pub history_path: PathBuf,
+    pub broadcast: BroadcastConfig,
+    /// When set, every request must carry `Authorization: Bearer <token>`.
+    pub api_token: Option<String>,
+    pub match_policy: search::MatchPolicy,
+    pub max_retained_matches: usize,
+    pub max_failure_rate: f64,
+    /// Candidates each worker signs at a time, see `search::LOW_MEMORY_BATCH_SIZE`.
+    pub batch_size: usize,
+    /// Park workers of a running job while its throughput collapses, e.g. from thermal throttling.
+    pub thermal_watchdog: bool,
+    /// CPU time and signatures allowed per job; a job may ask for tighter limits.
+    pub limits: ResourceLimits,
+}
+
+/// How queued jobs share the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduling {
+    /// One job at a time, highest priority first.
+    Sequential,
+    /// Run jobs side by side as long as their thread allocations fit in the thread budget.
+    Shares,
+}
+
+impl std::str::FromStr for Scheduling {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "sequential" => Ok(Self::Sequential),
+            "shares" => Ok(Self::Shares),
+            _ => Err(eyre::eyre!("unknown scheduling mode {:?}, expected sequential or shares", s)),
+        }
+    }
+}
+
+/// Body of `POST /jobs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobRequest {
+    pub hash_prefix: String,
+    pub gas_limit: u64,
+    #[serde(default)]
+    pub calldata: Option<Bytes>,
+    #[serde(default)]
+    pub to: Option<Address>,
+    #[serde(default, deserialize_with = "units::deserialize_optional_amount")]
+    pub value: Option<U256>,
+    /// Nonce to mine at instead of the account's current nonce.
+    #[serde(default)]
+    pub nonce: Option<U256>,
+    /// Higher priority jobs start first; equal priorities run in submission order.
+    #[serde(default)]
+    pub priority: i32,
+    /// Worker threads for this job, defaulting to (and capped at) the server's thread budget.
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// Where the fee sweep starts, so a coordinator can hand miners disjoint fee ranges.
+    #[serde(default, deserialize_with = "units::deserialize_optional_amount")]
+    pub base_fee_start: Option<U256>,
+    /// CPU seconds this job may use, within the server's MAX_CPU_SECONDS.
+    #[serde(default)]
+    pub max_cpu_seconds: Option<f64>,
+    /// Candidates this job may sign, within the server's MAX_SIGNATURES.
+    #[serde(default)]
+    pub max_signatures: Option<u64>,
+    /// Fee ranges already swept, from the `swept` of a job stopped at its limits, to resume it.
+    #[serde(default)]
+    pub swept: Vec<(U256, U256)>,
+}
+
+impl JobRequest {
+    /// The job's own limits, tightened to `server` limits.
+    pub fn limits(&self, server: ResourceLimits) -> eyre::Result<ResourceLimits> {
+        let cpu_time = match self.max_cpu_seconds {
+            Some(seconds) if seconds > 0.0 && seconds.is_finite() => Some(Duration::from_secs_f64(seconds)),
+            Some(seconds) => eyre::bail!("max_cpu_seconds must be a positive number of seconds, not {}", seconds),
+            None => None,
+        };
+        eyre::ensure!(self.max_signatures != Some(0), "max_signatures must be at least 1");
+        Ok(ResourceLimits { cpu_time, signatures: self.max_signatures }.min(server))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Found,
+    Broadcast,
+    Failed,
+    Cancelled,
+    /// Stopped at its resource limits; resubmit it with `swept` to carry on.
+    Limited,
+}
+
+/// Job lifecycle and progress events published on `GET /events`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEvent {
+    pub job: u64,
+    pub event: &'static str,
+    pub attempts: u64,
+    pub hashrate: f64,
+    pub timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobResult {
+    pub tx_hash: H256,
+    pub raw_tx: Bytes,
+    pub nonce: U256,
+    pub max_fee_per_gas: U256,
+    pub contract_address: Option<Address>,
+    pub calldata_hash: Option<H256>,
+    /// The match in the shared payload schema; absent for jobs restored from the journal, which
+    /// does not record the pattern.
+    #[serde(rename = "match", skip_serializing_if = "Option::is_none")]
+    pub payload: Option<MatchPayload>,
+}
+
+struct Job {
+    status: JobStatus,
+    request: Option<JobRequest>,
+    threads: usize,
+    stats: Option<Arc<SearchStats>>,
+    stop: Arc<AtomicBool>,
+    result: Option<JobResult>,
+    error: Option<String>,
+    /// Fees the job has swept, kept so a job stopped at its limits can be resumed.
+    claims: Option<Arc<FeeClaims>>,
+}
+
+impl Job {
+    fn restored(result: JobResult) -> Self {
+        Self {
+            status: JobStatus::Found,
+            request: None,
+            threads: 0,
+            stats: None,
+            stop: Arc::new(AtomicBool::new(true)),
+            result: Some(result),
+            error: None,
+            claims: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobView {
+    pub id: u64,
+    pub status: JobStatus,
+    pub priority: i32,
+    pub threads: usize,
+    pub attempts: u64,
+    pub hashrate: f64,
+    /// CPU time the job's workers have used, for metering.
+    pub cpu_seconds: f64,
+    pub signatures: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// For a job stopped at its limits, the fee ranges it swept, to pass back as `swept`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swept: Option<Vec<(U256, U256)>>,
+}
+
+/// Why a job operation was refused, independent of the API it came in on.
+pub(crate) enum JobError {
+    NotFound(&'static str),
+    Conflict(&'static str),
+    Other(eyre::Report),
+}
+
+impl From<eyre::Report> for JobError {
+    fn from(e: eyre::Report) -> Self {
+        Self::Other(e)
+    }
+}
+
+impl From<ProviderError> for JobError {
+    fn from(e: ProviderError) -> Self {
+        Self::Other(e.into())
+    }
+}
+
+/// Job table and scheduler shared by the REST and gRPC front ends.
+pub(crate) struct State {
+    pub(crate) config: ServerConfig,
+    journal: Arc<Journal>,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+    events: broadcast::Sender<JobEvent>,
+}
+
+/// Runs the job API on `addr`, and the gRPC service on `grpc_addr` when given, until the process
+/// exits. Jobs that found a match before a restart are restored from the journal.
+pub async fn serve(addr: SocketAddr, grpc_addr: Option<SocketAddr>, config: ServerConfig) -> eyre::Result<()> {
+    let journal = Arc::new(Journal::open_with_passphrase(&config.journal_path, config.journal_passphrase.as_deref())?);
+    let jobs = restore_jobs(&config)?;
+    if !jobs.is_empty() {
+        println!("Restored {} job(s) from {}", jobs.len(), config.journal_path.display());
+    }
+    let (events, _) = broadcast::channel(EVENT_BUFFER);
+    let state = Arc::new(State { config, journal, jobs: Mutex::new(jobs), events });
+    spawn_progress_reporter(state.clone());
+
+    if let Some(grpc_addr) = grpc_addr {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(grpc_addr, state).await {
+                println!("gRPC server stopped: {}", e);
+            }
+        });
+    }
+
+    let make_svc = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle(req, state).await) }
+            }))
+        }
+    });
+
+    println!("Serving job API on http://{}", addr);
+    Server::try_bind(&addr)?.serve(make_svc).await?;
+    Ok(())
+}
+
+fn restore_jobs(config: &ServerConfig) -> eyre::Result<BTreeMap<u64, Job>> {
+    let mut jobs = BTreeMap::new();
+    for entry in Journal::read_entries_with_passphrase(&config.journal_path, config.journal_passphrase.as_deref())? {
+        let Some(id) = entry.job_id else { continue };
+        if jobs.contains_key(&id) {
+            continue;
+        }
+        let candidate = verify::verify(&entry.raw_tx, &format!("{:?}", entry.tx_hash), entry.chain_id, entry.nonce)?;
+        let contract_address = candidate.tx.to().is_none().then(|| get_contract_address(candidate.from, entry.nonce));
+        let calldata_hash = candidate.tx.data().map(|data| H256::from(keccak256(data)));
+        jobs.insert(
+            id,
+            Job::restored(JobResult {
+                tx_hash: entry.tx_hash,
+                raw_tx: entry.raw_tx,
+                nonce: entry.nonce,
+                max_fee_per_gas: entry.max_fee_per_gas,
+                contract_address,
+                calldata_hash,
+                payload: None,
+            }),
+        );
+    }
+    Ok(jobs)
+}
+
+async fn handle(req: Request<Body>, state: Arc<State>) -> Response<Body> {
+    if let Some(token) = &state.config.api_token {
+        let expected = format!("Bearer {}", token);
+        let authorized = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .is_some_and(|value| value.as_bytes() == expected.as_bytes());
+        if !authorized {
+            return error(StatusCode::UNAUTHORIZED, "missing or invalid bearer token");
+        }
+    }
+
+    let method = req.method().clone();
+    let path: Vec<String> = req.uri().path().trim_matches('/').split('/').map(str::to_string).collect();
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+
+    let result = match (method, path.as_slice()) {
+        (Method::POST, ["jobs"]) => submit_job(req, &state).await,
+        (Method::GET, ["jobs"]) => Ok(list_jobs(&state)),
+        (Method::GET, ["events"]) => Ok(event_stream(&state)),
+        (Method::DELETE, ["jobs", id]) => with_job_id(id, |id| cancel_job(id, &state)),
+        (Method::GET, ["jobs", id]) => with_job_id(id, |id| job_status(id, &state)),
+        (Method::GET, ["jobs", id, "result"]) => with_job_id(id, |id| job_result(id, &state)),
+        (Method::POST, ["jobs", id, "broadcast"]) => match id.parse() {
+            Ok(id) => broadcast_job(id, &state).await,
+            Err(_) => Ok(error(StatusCode::BAD_REQUEST, "invalid job id")),
+        },
+        _ => Ok(error(StatusCode::NOT_FOUND, "not found")),
+    };
+    result.unwrap_or_else(|e| error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()))
+}
+
+fn with_job_id(
+    id: &str,
+    f: impl FnOnce(u64) -> eyre::Result<Response<Body>>,
+) -> eyre::Result<Response<Body>> {
+    match id.parse() {
+        Ok(id) => f(id),
+        Err(_) => Ok(error(StatusCode::BAD_REQUEST, "invalid job id")),
+    }
+}
+
+async fn submit_job(req: Request<Body>, state: &Arc<State>) -> eyre::Result<Response<Body>> {
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let request: JobRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &format!("invalid job: {}", e))),
+    };
+    if let Err(e) = pattern::validate(&request.hash_prefix).and_then(|()| request.limits(ResourceLimits::default()).map(drop)) {
+        return Ok(error(StatusCode::BAD_REQUEST, &format!("invalid job: {}", e)));
+    }
+    let id = state.submit(request);
+    Ok(json(StatusCode::CREATED, &serde_json::json!({ "id": id })))
+}
+
+fn cancel_job(id: u64, state: &Arc<State>) -> eyre::Result<Response<Body>> {
+    respond(state.cancel(id).map(|()| (StatusCode::ACCEPTED, serde_json::json!({ "id": id }))))
+}
+
+fn list_jobs(state: &State) -> Response<Body> {
+    json(StatusCode::OK, &state.list())
+}
+
+fn job_status(id: u64, state: &State) -> eyre::Result<Response<Body>> {
+    respond(state.view(id).map(|view| (StatusCode::OK, view)))
+}
+
+fn job_result(id: u64, state: &State) -> eyre::Result<Response<Body>> {
+    respond(state.result(id).map(|result| (StatusCode::OK, result)))
+}
+
+async fn broadcast_job(id: u64, state: &Arc<State>) -> eyre::Result<Response<Body>> {
+    let tx_hash = state.broadcast(id).await;
+    respond(tx_hash.map(|tx_hash| (StatusCode::ACCEPTED, serde_json::json!({ "id": id, "tx_hash": tx_hash }))))
+}
+
+/// Streams job events to the client as server-sent events until it disconnects.
+fn event_stream(state: &State) -> Response<Body> {
+    let mut events = state.subscribe();
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Ok(data) = serde_json::to_string(&event) else { continue };
+            let chunk = format!("event: {}\ndata: {}\n\n", event.event, data);
+            if sender.send_data(chunk.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut response = Response::new(body);
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("text/event-stream"),
+    );
+    response
+}
+
+impl State {
+    /// Queues `request` and starts it if the scheduler allows, returning the new job id.
+    pub(crate) fn submit(self: &Arc<Self>, request: JobRequest) -> u64 {
+        let threads = request.threads.unwrap_or(self.config.thread_count).clamp(1, self.config.thread_count);
+        let id = {
+            let mut jobs = self.jobs.lock();
+            let id = jobs.keys().next_back().map_or(1, |last| last + 1);
+            jobs.insert(
+                id,
+                Job {
+                    status: JobStatus::Queued,
+                    request: Some(request),
+                    threads,
+                    stats: None,
+                    stop: Arc::new(AtomicBool::new(false)),
+                    result: None,
+                    error: None,
+                    claims: None,
+                },
+            );
+            id
+        };
+        self.publish(id, "queued", None);
+        self.schedule();
+        id
+    }
+
+    pub(crate) fn list(&self) -> Vec<JobView> {
+        self.jobs.lock().iter().map(|(id, job)| view(*id, job)).collect()
+    }
+
+    pub(crate) fn view(&self, id: u64) -> Result<JobView, JobError> {
+        self.jobs.lock().get(&id).map(|job| view(id, job)).ok_or(JobError::NotFound("no such job"))
+    }
+
+    pub(crate) fn result(&self, id: u64) -> Result<JobResult, JobError> {
+        match self.jobs.lock().get(&id) {
+            Some(Job { result: Some(result), .. }) => Ok(result.clone()),
+            Some(_) => Err(JobError::NotFound("job has no result yet")),
+            None => Err(JobError::NotFound("no such job")),
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
+    }
+
+    /// Cancels a queued job outright, or asks a running job's workers to stop.
+    pub(crate) fn cancel(self: &Arc<Self>, id: u64) -> Result<(), JobError> {
+        let cancelled_queued = {
+            let mut jobs = self.jobs.lock();
+            match jobs.get_mut(&id) {
+                Some(job) if job.status == JobStatus::Queued => {
+                    job.status = JobStatus::Cancelled;
+                    true
+                }
+                // The runner publishes the cancellation once the workers have stopped
+                Some(job) if job.status == JobStatus::Running => {
+                    job.stop.store(true, Ordering::Relaxed);
+                    false
+                }
+                Some(_) => return Err(JobError::Conflict("job is not queued or running")),
+                None => return Err(JobError::NotFound("no such job")),
+            }
+        };
+        if cancelled_queued {
+            self.publish(id, "cancelled", None);
+        }
+        Ok(())
+    }
+
+    /// Sends the job's transaction and records it in the history once it confirms.
+    pub(crate) async fn broadcast(self: &Arc<Self>, id: u64) -> Result<H256, JobError> {
+        let result = {
+            let jobs = self.jobs.lock();
+            match jobs.get(&id) {
+                Some(Job { status: JobStatus::Found, result: Some(result), .. }) => result.clone(),
+                Some(Job { status: JobStatus::Broadcast, .. }) => return Err(JobError::Conflict("job was already broadcast")),
+                Some(_) => return Err(JobError::Conflict("job has no result to broadcast")),
+                None => return Err(JobError::NotFound("no such job")),
+            }
+        };
+
+        verify::verify_for_broadcast(
+            &result.raw_tx,
+            result.tx_hash,
+            self.config.wallet.address(),
+            self.config.chain_id,
+            result.nonce,
+        )?;
+        let provider = rpc::connect(&self.config.rpc).await?;
+        let broadcaster = self.config.broadcast.connect(provider.clone()).await?;
+        let tx_hash = match broadcaster.submit(&result.raw_tx).await? {
+            Submission::Sent(tx_hash) => tx_hash,
+            // Nothing was sent, so the job keeps its result and can be broadcast again
+            Submission::Saved { tx_hash, .. } => {
+                self.publish(id, "saved", None);
+                return Ok(tx_hash);
+            }
+        };
+        nonces::record_sent(&self.config.history_path, self.config.wallet.address(), self.config.chain_id, result.nonce);
+        if let Some(job) = self.jobs.lock().get_mut(&id) {
+            job.status = JobStatus::Broadcast;
+        }
+        self.publish(id, "broadcast", None);
+
+        // Record the deployment once it confirms, without holding up the response
+        let state = self.clone();
+        tokio::spawn(async move {
+            if let Ok(Some(receipt)) = broadcaster.wait_for_receipt(&provider, tx_hash).await {
+                let deployment = Deployment {
+                    chain_id: state.config.chain_id,
+                    tx_hash: receipt.transaction_hash,
+                    from: state.config.wallet.address(),
+                    contract_address: receipt.contract_address,
+                    nonce: result.nonce.as_u64(),
+                    calldata_hash: result.calldata_hash,
+                    calldata_size: None,
+                    name: None,
+                    cost_wei: receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default(),
+                    timestamp: chrono::Utc::now().timestamp(),
+                };
+                if let Err(e) = HistoryDb::open(&state.config.history_path).and_then(|db| db.record(&deployment)) {
+                    println!("Warning: failed to record deployment for job {}: {}", id, e);
+                }
+            }
+        });
+
+        Ok(tx_hash)
+    }
+
+    /// Starts queued jobs, highest priority first, while the scheduling mode allows it.
+    fn schedule(self: &Arc<Self>) {
+        let mut started = Vec::new();
+        {
+            let mut jobs = self.jobs.lock();
+            loop {
+                let running: Vec<&Job> = jobs.values().filter(|job| job.status == JobStatus::Running).collect();
+                let threads_in_use: usize = running.iter().map(|job| job.threads).sum();
+                let sequential_busy = self.config.scheduling == Scheduling::Sequential && !running.is_empty();
+
+                let next = jobs
+                    .iter()
+                    .filter(|(_, job)| job.status == JobStatus::Queued)
+                    .min_by_key(|(id, job)| (std::cmp::Reverse(job.request.as_ref().map_or(0, |r| r.priority)), **id))
+                    .map(|(id, job)| (*id, job.threads));
+                let Some((id, threads)) = next else { break };
+                if sequential_busy || threads_in_use + threads > self.config.thread_count {
+                    break;
+                }
+
+                let job = jobs.get_mut(&id).expect("job exists");
+                let stats = Arc::new(SearchStats::new(job.threads));
+                let request = job.request.clone().expect("queued jobs keep their request");
+                let claims = Arc::new(FeeClaims::from_ranges(request.swept.iter().copied()));
+                job.status = JobStatus::Running;
+                job.stats = Some(stats.clone());
+                job.claims = Some(claims.clone());
+                started.push((id, request, job.threads, stats, claims, job.stop.clone()));
+            }
+        }
+
+        for (id, request, threads, stats, claims, stop) in started {
+            self.publish(id, "started", Some(&stats));
+            let state = self.clone();
+            tokio::spawn(async move {
+                let outcome = state.run_job(id, request, threads, stats.clone(), claims, stop).await;
+                let (event, detail) = {
+                    let mut jobs = state.jobs.lock();
+                    let job = jobs.get_mut(&id).expect("job exists");
+                    match outcome {
+                        Ok(Some(result)) => {
+                            job.status = JobStatus::Found;
+                            job.result = Some(result);
+                            ("found", None)
+                        }
+                        Ok(None) => match stats.limit_reached() {
+                            Some(limit) => {
+                                job.status = JobStatus::Limited;
+                                ("limited", Some(limit))
+                            }
+                            None => {
+                                job.status = JobStatus::Cancelled;
+                                ("cancelled", None)
+                            }
+                        },
+                        Err(e) => {
+                            job.status = JobStatus::Failed;
+                            job.error = Some(e.to_string());
+                            ("failed", None)
+                        }
+                    }
+                };
+                state.publish_detail(id, event, Some(&stats), detail);
+                state.schedule();
+            });
+        }
+    }
+
+    async fn run_job(
+        self: &Arc<Self>,
+        id: u64,
+        request: JobRequest,
+        threads: usize,
+        stats: Arc<SearchStats>,
+        claims: Arc<FeeClaims>,
+        stop: Arc<AtomicBool>,
+    ) -> eyre::Result<Option<JobResult>> {
+        let config = &self.config;
+        let limits = request.limits(config.limits)?;
+        let provider = rpc::connect(&config.rpc).await?;
+        let from = config.wallet.address();
+        let nonce = match request.nonce {
+            Some(nonce) => nonce,
+            None => provider.get_transaction_count(from, None).await?,
+        };
+
+        let mut template = Eip1559TransactionRequest::new();
+        template.nonce = Some(nonce);
+        template.gas = Some(request.gas_limit.into());
+        template.chain_id = Some(config.chain_id.into());
+        template.to = request.to.map(Into::into);
+        template.value = request.value;
+        template.data = request.calldata.clone();
+
+        let throttle = config.thermal_watchdog.then(|| Arc::new(Throttle::default()));
+        if let Some(throttle) = &throttle {
+            let state = self.clone();
+            let events_stats = stats.clone();
+            tokio::spawn(throttle::watch_throughput(throttle.clone(), stats.clone(), stop.clone(), move |action| {
+                let event = match action {
+                    WatchdogAction::Reduced { .. } => "throttled",
+                    WatchdogAction::Restored { .. } => "restored",
+                };
+                state.publish_detail(id, event, Some(&events_stats), Some(action.to_string()));
+            }));
+        }
+        let signer = envelope::signer_for(config.wallet.clone(), config.chain_id)?;
+        let params = SearchParams {
+            base_fee_start: request.base_fee_start.unwrap_or(config.fees.base_fee_start),
+            // Preempted jobs resume from their swept fee ranges, which only describe a single lane
+            priority_fee_lanes: Vec::new(),
+            match_policy: config.match_policy,
+            max_retained_matches: config.max_retained_matches,
+            max_failure_rate: config.max_failure_rate,
+            batch_size: config.batch_size,
+            thread_count: threads,
+            job_id: Some(id),
+            claims: Some(claims),
+            sampler: CandidateSampler::from_env(&format!("job {} nonce {} prefix {}", id, nonce, request.hash_prefix))?,
+            throttle,
+            limits,
+            ..SearchParams::new(signer, template, pattern::to_prefix(&request.hash_prefix), &config.fees, search::Salt::None)
+        };
+        let Some(found_match) = search::run(params.clone(), stats.clone(), stop, self.journal.clone()).await? else {
+            return Ok(None);
+        };
+        let payload = MatchPayload::from_raw(&params.hash_prefix, &found_match.signed_rlp)?.with_search(&params, &stats);
+
+        Ok(Some(JobResult {
+            tx_hash: found_match.tx_hash.into(),
+            raw_tx: found_match.signed_rlp,
+            nonce,
+            max_fee_per_gas: found_match.max_fee_per_gas,
+            contract_address: request.to.is_none().then(|| get_contract_address(from, nonce)),
+            calldata_hash: request.calldata.as_ref().map(|data| H256::from(keccak256(data))),
+            payload: Some(payload),
+        }))
+    }
+
+    fn publish(&self, job: u64, event: &'static str, stats: Option<&SearchStats>) {
+        self.publish_detail(job, event, stats, None);
+    }
+
+    fn publish_detail(&self, job: u64, event: &'static str, stats: Option<&SearchStats>, detail: Option<String>) {
+        // Sending only fails when nobody is listening
+        let _ = self.events.send(JobEvent {
+            job,
+            event,
+            attempts: stats.map_or(0, |s| s.total_attempts()),
+            hashrate: stats.map_or(0.0, |s| s.hashrate()),
+            timestamp: chrono::Utc::now().timestamp(),
+            detail,
+        });
+    }
+}
+
+/// Periodically publishes a progress event for every running job.
+fn spawn_progress_reporter(state: Arc<State>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(PROGRESS_INTERVAL).await;
+            let running: Vec<(u64, Arc<SearchStats>)> = state
+                .jobs
+                .lock()
+                .iter()
+                .filter(|(_, job)| job.status == JobStatus::Running)
+                .filter_map(|(id, job)| job.stats.clone().map(|stats| (*id, stats)))
+                .collect();
+            for (id, stats) in running {
+                state.publish(id, "progress", Some(&stats));
+            }
+        }
+    });
+}
+
+fn view(id: u64, job: &Job) -> JobView {
+    JobView {
+        id,
+        status: job.status,
+        priority: job.request.as_ref().map_or(0, |r| r.priority),
+        threads: job.threads,
+        attempts: job.stats.as_ref().map_or(0, |s| s.total_attempts()),
+        hashrate: job.stats.as_ref().map_or(0.0, |s| s.hashrate()),
+        cpu_seconds: job.stats.as_ref().map_or(0.0, |s| s.total_cpu_time().as_secs_f64()),
+        signatures: job.stats.as_ref().map_or(0, |s| s.total_signatures()),
+        error: job.error.clone(),
+        swept: job.claims.as_ref().filter(|_| job.status == JobStatus::Limited).map(|claims| claims.ranges()),
+    }
+}
+
+fn respond(outcome: Result<(StatusCode, impl Serialize), JobError>) -> eyre::Result<Response<Body>> {
+    match outcome {
+        Ok((status, body)) => Ok(json(status, &body)),
+        Err(JobError::NotFound(message)) => Ok(error(StatusCode::NOT_FOUND, message)),
+        Err(JobError::Conflict(message)) => Ok(error(StatusCode::CONFLICT, message)),
+        Err(JobError::Other(e)) => Err(e),
+    }
+}
+
+fn json(status: StatusCode, body: &impl Serialize) -> Response<Body> {
+    let mut response = Response::new(Body::from(serde_json::to_vec(body).unwrap_or_default()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json(status, &serde_json::json!({ "error": message }))
+}
@@ -0,0 +1,35 @@
+use ethers::types::U256;
+use std::time::Duration;
+
+/// How often a standby match checks whether the transactions ahead of it have confirmed.
+pub const STANDBY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where a transaction mined for a nonce stands against the account's confirmed nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    /// Earlier transactions are still pending.
+    Waiting,
+    /// Everything before it has confirmed, so it can be sent now.
+    Ready,
+    /// Another transaction confirmed at its nonce.
+    Taken,
+}
+
+pub fn turn(account_nonce: U256, nonce: U256) -> Turn {
+    match account_nonce.cmp(&nonce) {
+        std::cmp::Ordering::Less => Turn::Waiting,
+        std::cmp::Ordering::Equal => Turn::Ready,
+        std::cmp::Ordering::Greater => Turn::Taken,
+    }
+}
+
+/// The nonce to mine in standby: the first one after the account's pending transactions, given
+/// its confirmed (`latest`) and `pending` transaction counts.
+pub fn standby_nonce(confirmed: U256, pending: U256) -> eyre::Result<U256> {
+    eyre::ensure!(
+        pending > confirmed,
+        "--standby needs a pending transaction to mine behind, but the account has none at nonce {}",
+        confirmed
+    );
+    Ok(pending)
+}
@@ -0,0 +1,455 @@
+use crate::pattern;
+use crate::units::{format_count, format_duration, format_estimate, format_hashrate, format_wei};
+use ethers::abi;
+use ethers::signers::WalletError;
+use ethers::types::U256;
+use ethers::utils::rlp;
+use parking_lot::Mutex;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const HISTOGRAM_BUCKETS: usize = 8;
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Why a candidate could not be signed, so a faulty signer can be told apart from a template
+/// that cannot be encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The signer rejected the transaction or failed to produce a signature.
+    Signing,
+    /// The transaction could not be encoded, e.g. bad calldata or RLP.
+    Encoding,
+    Other,
+}
+
+impl FailureKind {
+    pub const ALL: [Self; 3] = [Self::Signing, Self::Encoding, Self::Other];
+
+    pub fn classify(error: &eyre::Report) -> Self {
+        if error.downcast_ref::<WalletError>().is_some() || error.downcast_ref::<ethers::core::k256::ecdsa::Error>().is_some() {
+            Self::Signing
+        } else if error.downcast_ref::<rlp::DecoderError>().is_some()
+            || error.downcast_ref::<abi::Error>().is_some()
+            || error.downcast_ref::<hex::FromHexError>().is_some()
+        {
+            Self::Encoding
+        } else {
+            Self::Other
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Signing => "signing",
+            Self::Encoding => "encoding",
+            Self::Other => "other",
+        })
+    }
+}
+
+/// Counters for a single search worker, updated lock-free from the hot loop.
+#[derive(Debug, Default)]
+pub struct WorkerStats {
+    pub attempts: AtomicU64,
+    /// Candidates that could not be signed or encoded.
+    pub sign_failures: AtomicU64,
+    /// `sign_failures` split by `FailureKind`.
+    failures_by_kind: [AtomicU64; 3],
+    /// The first error seen, shown when the search aborts.
+    first_failure: Mutex<Option<String>>,
+    /// First and most recent `max_fee_per_gas` (wei) this worker has tried.
+    pub fee_start: AtomicU64,
+    pub fee_current: AtomicU64,
+    /// Fees skipped because another worker had already tried them.
+    pub duplicates_skipped: AtomicU64,
+    /// CPU time this worker's thread spent searching, in nanoseconds.
+    pub cpu_nanos: AtomicU64,
+    /// Milliseconds into the search at which this worker last finished a batch.
+    pub heartbeat_millis: AtomicU64,
+    /// Times this worker died and was started again.
+    pub respawns: AtomicU64,
+}
+
+impl WorkerStats {
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn sign_failures(&self) -> u64 {
+        self.sign_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn duplicates_skipped(&self) -> u64 {
+        self.duplicates_skipped.load(Ordering::Relaxed)
+    }
+
+    pub fn record_attempt(&self, max_fee: U256) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        self.fee_current.store(max_fee.low_u64(), Ordering::Relaxed);
+    }
+
+    pub fn failures(&self, kind: FailureKind) -> u64 {
+        self.failures_by_kind[kind.index()].load(Ordering::Relaxed)
+    }
+
+    /// Share of attempts that failed to sign, 0 before the first attempt.
+    pub fn failure_rate(&self) -> f64 {
+        match self.attempts() {
+            0 => 0.0,
+            attempts => self.sign_failures() as f64 / attempts as f64,
+        }
+    }
+
+    pub fn first_failure(&self) -> Option<String> {
+        self.first_failure.lock().clone()
+    }
+
+    pub fn record_sign_failure(&self, error: &eyre::Report) {
+        self.sign_failures.fetch_add(1, Ordering::Relaxed);
+        self.failures_by_kind[FailureKind::classify(error).index()].fetch_add(1, Ordering::Relaxed);
+        self.first_failure.lock().get_or_insert_with(|| format!("{:#}", error));
+    }
+
+    pub fn record_duplicates_skipped(&self, count: u64) {
+        self.duplicates_skipped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn cpu_time(&self) -> Duration {
+        Duration::from_nanos(self.cpu_nanos.load(Ordering::Relaxed))
+    }
+
+    pub fn record_cpu_time(&self, spent: Duration) {
+        self.cpu_nanos.fetch_add(spent.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn respawns(&self) -> u64 {
+        self.respawns.load(Ordering::Relaxed)
+    }
+
+    pub fn record_respawn(&self) {
+        self.respawns.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Time since a search started. The system clock is used in practice; tests drive a simulated
+/// one so rates and reports come out exactly.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn elapsed(&self) -> Duration;
+}
+
+#[derive(Debug)]
+pub struct SystemClock(Instant);
+
+impl SystemClock {
+    pub fn start() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// Shared statistics for a running search, read by the metrics endpoint and reports.
+#[derive(Debug)]
+pub struct SearchStats {
+    clock: Arc<dyn Clock>,
+    workers: Vec<WorkerStats>,
+    /// Latest network base fee in wei, 0 until first observed.
+    base_fee: AtomicU64,
+    /// Aggregate hashrate sampled at regular intervals, for the post-mortem histogram.
+    rate_samples: Mutex<Vec<f64>>,
+    /// The resource limit that stopped the search, if one did.
+    limit_reached: Mutex<Option<String>>,
+}
+
+impl SearchStats {
+    pub fn new(worker_count: usize) -> Self {
+        Self::with_clock(worker_count, Arc::new(SystemClock::start()))
+    }
+
+    pub fn with_clock(worker_count: usize, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            workers: (0..worker_count).map(|_| WorkerStats::default()).collect(),
+            base_fee: AtomicU64::new(0),
+            rate_samples: Mutex::new(Vec::new()),
+            limit_reached: Mutex::new(None),
+        }
+    }
+
+    pub fn worker(&self, index: usize) -> &WorkerStats {
+        &self.workers[index]
+    }
+
+    pub fn workers(&self) -> &[WorkerStats] {
+        &self.workers
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    pub fn total_attempts(&self) -> u64 {
+        self.workers.iter().map(WorkerStats::attempts).sum()
+    }
+
+    /// Current attempt count of every worker, for computing rates over an interval.
+    pub fn attempts_snapshot(&self) -> Vec<u64> {
+        self.workers.iter().map(WorkerStats::attempts).collect()
+    }
+
+    pub fn total_duplicates_skipped(&self) -> u64 {
+        self.workers.iter().map(WorkerStats::duplicates_skipped).sum()
+    }
+
+    pub fn total_sign_failures(&self) -> u64 {
+        self.workers.iter().map(WorkerStats::sign_failures).sum()
+    }
+
+    /// Candidates signed so far, i.e. attempts that did not fail to sign.
+    pub fn total_signatures(&self) -> u64 {
+        self.total_attempts().saturating_sub(self.total_sign_failures())
+    }
+
+    /// CPU time the workers have spent searching, excluding the rest of the process (such as
+    /// other jobs on a server).
+    pub fn total_cpu_time(&self) -> Duration {
+        self.workers.iter().map(WorkerStats::cpu_time).sum()
+    }
+
+    /// Records that worker `index` is alive and making progress.
+    pub fn heartbeat(&self, index: usize) {
+        self.workers[index].heartbeat_millis.store(self.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Time since worker `index` last sent a heartbeat.
+    pub fn since_heartbeat(&self, index: usize) -> Duration {
+        self.elapsed().saturating_sub(Duration::from_millis(self.workers[index].heartbeat_millis.load(Ordering::Relaxed)))
+    }
+
+    pub fn total_respawns(&self) -> u64 {
+        self.workers.iter().map(WorkerStats::respawns).sum()
+    }
+
+    pub fn limit_reached(&self) -> Option<String> {
+        self.limit_reached.lock().clone()
+    }
+
+    /// Records the first resource limit the search ran into.
+    pub fn set_limit_reached(&self, limit: String) {
+        self.limit_reached.lock().get_or_insert(limit);
+    }
+
+    pub fn total_failures(&self, kind: FailureKind) -> u64 {
+        self.workers.iter().map(|w| w.failures(kind)).sum()
+    }
+
+    /// Sign failures broken down by kind, e.g. `12 (signing 0, encoding 12, other 0)`.
+    pub fn failure_summary(&self) -> String {
+        let kinds: Vec<String> =
+            FailureKind::ALL.iter().map(|&kind| format!("{} {}", kind, self.total_failures(kind))).collect();
+        format!("{} ({})", self.total_sign_failures(), kinds.join(", "))
+    }
+
+    /// One line per worker with its attempts, average hashrate, sign failures and skipped duplicates.
+    pub fn worker_report(&self) -> String {
+        let mut report = String::new();
+        for (i, worker) in self.workers.iter().enumerate() {
+            report.push_str(&format!(
+                "  worker {:>2}: {:>15} attempts, {:>10}, {} sign failures, {} duplicate fees skipped\n",
+                i,
+                format_count(worker.attempts()),
+                format_hashrate(self.worker_hashrate(i)),
+                format_count(worker.sign_failures()),
+                format_count(worker.duplicates_skipped()),
+            ));
+        }
+        report
+    }
+
+    /// Average attempts per second since the search started.
+    pub fn hashrate(&self) -> f64 {
+        rate(self.total_attempts(), self.elapsed())
+    }
+
+    pub fn worker_hashrate(&self, index: usize) -> f64 {
+        rate(self.workers[index].attempts(), self.elapsed())
+    }
+
+    /// Lowest and highest `max_fee_per_gas` tried so far across all workers.
+    pub fn fee_window(&self) -> (u64, u64) {
+        let low = self.workers.iter().map(|w| w.fee_start.load(Ordering::Relaxed)).min().unwrap_or(0);
+        let high = self.workers.iter().map(|w| w.fee_current.load(Ordering::Relaxed)).max().unwrap_or(0);
+        (low, high)
+    }
+
+    pub fn base_fee(&self) -> u64 {
+        self.base_fee.load(Ordering::Relaxed)
+    }
+
+    pub fn set_base_fee(&self, base_fee: U256) {
+        self.base_fee.store(base_fee.low_u64(), Ordering::Relaxed);
+    }
+
+    pub fn record_rate_sample(&self, rate: f64) {
+        self.rate_samples.lock().push(rate);
+    }
+
+    /// Summary of a finished (or aborted) search for `hash_pattern`: attempts versus the
+    /// theoretical expectation, time spent, fee range consumed and a hashrate histogram.
+    pub fn postmortem(&self, hash_pattern: &str) -> String {
+        let attempts = self.total_attempts();
+        let expected = pattern::expected_attempts(hash_pattern);
+        let luck = attempts as f64 / expected;
+        let wall = self.elapsed().as_secs_f64();
+        let (fee_low, fee_high) = self.fee_window();
+        let fee_consumed: u64 = self
+            .workers
+            .iter()
+            .map(|w| w.fee_current.load(Ordering::Relaxed).saturating_sub(w.fee_start.load(Ordering::Relaxed)))
+            .sum();
+
+        let mut out = String::from("Search post-mortem:\n");
+        let _ = writeln!(out, "  Attempts:           {}", format_count(attempts));
+        let _ = writeln!(
+            out,
+            "  Expected attempts:  {} ({} hex digits)",
+            format_estimate(expected),
+            pattern::normalize(hash_pattern).len()
+        );
+        let _ = writeln!(
+            out,
+            "  Luck factor:        {:.2}x expected ({})",
+            luck,
+            if luck <= 1.0 { "lucky" } else { "unlucky" }
+        );
+        let _ = writeln!(out, "  Wall-clock time:    {}", format_duration(wall));
+        match process_cpu_time() {
+            Some(cpu) if wall > 0.0 => {
+                let _ = writeln!(out, "  CPU time:           {} ({:.2} cores busy)", format_duration(cpu.as_secs_f64()), cpu.as_secs_f64() / wall);
+            }
+            _ => {
+                let _ = writeln!(out, "  CPU time:           unavailable");
+            }
+        }
+        let _ = writeln!(
+            out,
+            "  Fee range consumed: {} across workers (max fee {} - {})",
+            format_wei(fee_consumed.into()),
+            format_count(fee_low),
+            format_wei(fee_high.into())
+        );
+        let _ = writeln!(out, "  Duplicates skipped: {} fees already tried by another worker", format_count(self.total_duplicates_skipped()));
+        let _ = writeln!(out, "  Sign failures:      {}", self.failure_summary());
+        if self.total_respawns() > 0 {
+            let _ = writeln!(out, "  Worker restarts:    {} after panics", self.total_respawns());
+        }
+        out.push_str(&self.rate_histogram());
+        out
+    }
+
+    fn rate_histogram(&self) -> String {
+        let samples = self.rate_samples.lock();
+        if samples.is_empty() {
+            return String::new();
+        }
+        let min = samples.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = samples.iter().cloned().fold(0.0, f64::max);
+        let width = ((max - min) / HISTOGRAM_BUCKETS as f64).max(1.0);
+
+        let mut counts = [0usize; HISTOGRAM_BUCKETS];
+        for sample in samples.iter() {
+            let bucket = (((sample - min) / width) as usize).min(HISTOGRAM_BUCKETS - 1);
+            counts[bucket] += 1;
+        }
+        let most = counts.iter().copied().max().unwrap_or(1).max(1);
+
+        let mut out = format!("  Hashrate histogram ({} samples):\n", samples.len());
+        for (i, count) in counts.iter().enumerate() {
+            let low = min + width * i as f64;
+            let bar = "#".repeat(count * HISTOGRAM_WIDTH / most);
+            let _ = writeln!(out, "    {:>10} - {:>10} | {:<40} {}", format_hashrate(low), format_hashrate(low + width), bar, count);
+        }
+        out
+    }
+}
+
+/// User plus system CPU time consumed by this process so far.
+#[cfg(unix)]
+fn process_cpu_time() -> Option<Duration> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: getrusage only writes into the provided struct.
+    let usage = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) != 0 {
+            return None;
+        }
+        usage.assume_init()
+    };
+    let to_duration = |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+    Some(to_duration(usage.ru_utime) + to_duration(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// CPU time consumed by the calling thread so far, for metering a single worker.
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Option<Duration> {
+    let mut time = std::mem::MaybeUninit::<libc::timespec>::uninit();
+    // SAFETY: clock_gettime only writes into the provided struct.
+    let time = unsafe {
+        if libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, time.as_mut_ptr()) != 0 {
+            return None;
+        }
+        time.assume_init()
+    };
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+pub fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Returns the workers whose progress between two snapshots fell below `ratio` times the median
+/// progress, along with that median. Used to spot throttled or efficiency-core workers.
+pub fn find_slow_workers(previous: &[u64], current: &[u64], ratio: f64) -> (Vec<usize>, u64) {
+    let deltas: Vec<u64> = current
+        .iter()
+        .zip(previous)
+        .map(|(cur, prev)| cur.saturating_sub(*prev))
+        .collect();
+    let mut sorted = deltas.clone();
+    sorted.sort_unstable();
+    let median = sorted.get(sorted.len() / 2).copied().unwrap_or(0);
+
+    let slow = deltas
+        .iter()
+        .enumerate()
+        .filter(|(_, delta)| (**delta as f64) < median as f64 * ratio)
+        .map(|(i, _)| i)
+        .collect();
+    (slow, median)
+}
+
+fn rate(attempts: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        attempts as f64 / secs
+    } else {
+        0.0
+    }
+}
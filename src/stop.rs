@@ -0,0 +1,184 @@
+use crate::search::Match;
+use crate::units::{format_duration, wei_to_f64};
+use ethers::types::U256;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Accepts a match of at least `digits` leading digits of the hash prefix once the search has run
+/// for `after`, if its max fee is at most `max_overhead` (a fraction) above the lowest one swept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopRule {
+    pub digits: usize,
+    pub after: Duration,
+    pub max_overhead: Option<f64>,
+}
+
+impl StopRule {
+    pub fn accepts(&self, digits: usize, overhead: f64, elapsed: Duration) -> bool {
+        digits >= self.digits && elapsed >= self.after && self.max_overhead.is_none_or(|max| overhead <= max)
+    }
+}
+
+impl std::str::FromStr for StopRule {
+    type Err = eyre::Report;
+
+    /// `<digits> [after <duration>] [overhead<<percent>%]`, e.g. `6 after 2h overhead<1%`.
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let s = s.to_lowercase().replace("< ", "<").replace(" <", "<");
+        let mut tokens = s.split_whitespace();
+        let digits = tokens.next().ok_or_else(|| eyre::eyre!("empty stop rule"))?;
+        let digits = digits
+            .trim_start_matches(">=")
+            .parse::<usize>()
+            .map_err(|e| eyre::eyre!("stop rule {:?} must start with a digit count: {}", s, e))?;
+        eyre::ensure!(digits > 0, "stop rule {:?} must match at least one digit", s);
+        let mut rule = Self { digits, after: Duration::ZERO, max_overhead: None };
+        while let Some(token) = tokens.next() {
+            match token {
+                "after" => {
+                    let duration = tokens.next().ok_or_else(|| eyre::eyre!("stop rule {:?} has no duration after \"after\"", s))?;
+                    rule.after = parse_duration(duration)?;
+                }
+                token if token.starts_with("overhead<") => {
+                    let percent = token["overhead<".len()..].trim_end_matches('%');
+                    let percent = percent.parse::<f64>().map_err(|e| eyre::eyre!("invalid overhead {:?} in stop rule {:?}: {}", percent, s, e))?;
+                    eyre::ensure!(percent >= 0.0, "the overhead in stop rule {:?} cannot be negative", s);
+                    rule.max_overhead = Some(percent / 100.0);
+                }
+                token => eyre::bail!("unexpected {:?} in stop rule {:?}, expected \"after <duration>\" or \"overhead<<percent>%\"", token, s),
+            }
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for StopRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} digit(s)", self.digits)?;
+        if !self.after.is_zero() {
+            write!(f, " after {}", format_duration(self.after.as_secs_f64()))?;
+        }
+        if let Some(max_overhead) = self.max_overhead {
+            write!(f, " with fee overhead under {}%", max_overhead * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `90s`, `30m` (or `30min`), `2h` or `1d`; a bare number is in minutes.
+pub fn parse_duration(input: &str) -> eyre::Result<Duration> {
+    let split = input.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: f64 = amount.parse().map_err(|e| eyre::eyre!("invalid duration {:?}: {}", input, e))?;
+    let seconds = match unit {
+        "s" => 1.0,
+        "" | "m" | "min" => 60.0,
+        "h" => 3_600.0,
+        "d" => 86_400.0,
+        _ => eyre::bail!("unknown unit in duration {:?}, expected s, m, h or d", input),
+    };
+    Ok(Duration::from_secs_f64(amount * seconds))
+}
+
+/// When to settle for less than the full hash prefix, from STOP_POLICY: rules separated by `;`,
+/// any of which ends the search. A match of the whole prefix always does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopPolicy {
+    pub rules: Vec<StopRule>,
+}
+
+impl StopPolicy {
+    /// Fails if a rule asks for more digits than `prefix_digits`, which it could never see.
+    pub fn validate(&self, prefix_digits: usize) -> eyre::Result<()> {
+        for rule in &self.rules {
+            eyre::ensure!(
+                rule.digits <= prefix_digits,
+                "the stop rule \"{}\" asks for more digits than HASH_PREFIX has ({})",
+                rule,
+                prefix_digits
+            );
+        }
+        Ok(())
+    }
+
+    /// Fewest leading digits any rule accepts, below which partial matches are not worth keeping.
+    pub fn min_digits(&self) -> usize {
+        self.rules.iter().map(|rule| rule.digits).min().unwrap_or(usize::MAX)
+    }
+
+    pub fn accepts(&self, digits: usize, overhead: f64, elapsed: Duration) -> bool {
+        self.rules.iter().any(|rule| rule.accepts(digits, overhead, elapsed))
+    }
+}
+
+impl std::str::FromStr for StopPolicy {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let rules = s.split(';').filter(|rule| !rule.trim().is_empty()).map(str::parse).collect::<eyre::Result<Vec<StopRule>>>()?;
+        eyre::ensure!(!rules.is_empty(), "STOP_POLICY has no rules");
+        Ok(Self { rules })
+    }
+}
+
+impl fmt::Display for StopPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", or ")?;
+            }
+            write!(f, "{}", rule)?;
+        }
+        Ok(())
+    }
+}
+
+/// The cheapest match found so far for each number of leading digits, shared by the workers and
+/// checked against the policy while the search runs.
+pub struct PartialMatches {
+    policy: StopPolicy,
+    /// Lowest max fee the search signs, which overheads are measured against.
+    floor_fee: U256,
+    best: Mutex<BTreeMap<usize, Match>>,
+}
+
+impl PartialMatches {
+    pub fn new(policy: StopPolicy, floor_fee: U256) -> Self {
+        Self { policy, floor_fee, best: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn min_digits(&self) -> usize {
+        self.policy.min_digits()
+    }
+
+    /// Keeps `candidate`, matching `digits` leading digits, if the policy could take it and it is
+    /// the cheapest with that many.
+    pub fn offer(&self, digits: usize, candidate: Match) {
+        if digits < self.min_digits() {
+            return;
+        }
+        let mut best = self.best.lock();
+        if best.get(&digits).is_none_or(|kept| candidate.max_fee_per_gas < kept.max_fee_per_gas) {
+            best.insert(digits, candidate);
+        }
+    }
+
+    /// How far `max_fee_per_gas` is above the lowest fee swept, as a fraction.
+    pub fn overhead(&self, max_fee_per_gas: U256) -> f64 {
+        let floor = wei_to_f64(self.floor_fee.max(U256::one()));
+        (wei_to_f64(max_fee_per_gas.saturating_sub(self.floor_fee)) / floor).max(0.0)
+    }
+
+    /// The match the policy takes after `elapsed`, with its digit count: the one with the most
+    /// digits some rule accepts. Only the cheapest per digit count is kept, so it also has the
+    /// lowest overhead.
+    pub fn accepted(&self, elapsed: Duration) -> Option<(usize, Match)> {
+        let best = self.best.lock();
+        best.iter()
+            .rev()
+            .find(|(&digits, candidate)| self.policy.accepts(digits, self.overhead(candidate.max_fee_per_gas), elapsed))
+            .map(|(&digits, candidate)| (digits, candidate.clone()))
+    }
+}
@@ -0,0 +1,63 @@
+use crate::chains;
+use crate::pattern;
+use crate::units::format_eth;
+use ethers::types::{Address, TransactionReceipt, H256, U256};
+use std::fmt::Write;
+
+/// A confirmed vanity transaction, for the markdown block printed after inclusion.
+#[derive(Debug, Clone)]
+pub struct ShareSummary {
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    /// The pattern the hash was mined for, with or without `0x`.
+    pub pattern: String,
+    pub contract_address: Option<Address>,
+    pub block: Option<u64>,
+    /// What the transaction actually paid, from the receipt.
+    pub cost_wei: Option<U256>,
+}
+
+impl ShareSummary {
+    pub fn from_receipt(chain_id: u64, pattern: &str, receipt: &TransactionReceipt, contract_address: Option<Address>) -> Self {
+        Self {
+            chain_id,
+            tx_hash: receipt.transaction_hash,
+            pattern: pattern.to_string(),
+            contract_address: receipt.contract_address.or(contract_address),
+            block: receipt.block_number.map(|block| block.as_u64()),
+            cost_wei: receipt.gas_used.zip(receipt.effective_gas_price).map(|(gas, price)| gas * price),
+        }
+    }
+
+    /// The hash with the mined prefix in bold, e.g. `**0xdead**beef...`.
+    pub fn highlighted_hash(&self) -> String {
+        let digits = hex::encode(self.tx_hash);
+        let prefix_len = pattern::normalize(&self.pattern).len().min(digits.len());
+        format!("**0x{}**{}", &digits[..prefix_len], &digits[prefix_len..])
+    }
+
+    /// A compact markdown block ready to paste into an announcement or chat.
+    pub fn to_markdown(&self) -> String {
+        let chain = chains::by_id(self.chain_id);
+        let link = |text: String, url: Option<String>| match url {
+            Some(url) => format!("[{}]({})", text, url),
+            None => text,
+        };
+        let digits = pattern::normalize(&self.pattern).len();
+        let mut out = String::new();
+        let _ = writeln!(out, "**Vanity deployment on {}**", chains::describe(self.chain_id));
+        let _ = writeln!(out);
+        let _ = writeln!(out, "- Transaction: {}", link(self.highlighted_hash(), chain.and_then(|chain| chain.tx_url(self.tx_hash))));
+        if let Some(address) = self.contract_address {
+            let _ = writeln!(out, "- Contract: {}", link(format!("`{:?}`", address), chain.and_then(|chain| chain.address_url(address))));
+        }
+        if let Some(block) = self.block {
+            let _ = writeln!(out, "- Block: {}", block);
+        }
+        if let Some(cost) = self.cost_wei {
+            let _ = writeln!(out, "- Cost: {} {}", format_eth(cost), chains::symbol(self.chain_id));
+        }
+        let _ = writeln!(out, "- Odds: 1 in {:.0} ({} hex digits)", pattern::expected_attempts(&self.pattern), digits);
+        out
+    }
+}
@@ -0,0 +1,40 @@
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{trace, Resource};
+use std::env;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Flushes and shuts down the OTLP exporter when dropped.
+pub struct TelemetryGuard;
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+/// Installs an OTLP/HTTP span exporter when `OTEL_EXPORTER_OTLP_ENDPOINT` (or the traces-specific
+/// `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) is set; otherwise spans are discarded and this is a no-op.
+/// Must be called from within a Tokio runtime.
+pub fn init(service_name: &'static str) -> eyre::Result<Option<TelemetryGuard>> {
+    let configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+        .iter()
+        .any(|var| env::var(var).is_ok_and(|v| !v.is_empty()));
+    if !configured {
+        return Ok(None);
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().http())
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", service_name)])),
+        )
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()?;
+
+    Ok(Some(TelemetryGuard))
+}
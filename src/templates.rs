@@ -0,0 +1,98 @@
+use crate::secrets::is_secret_name;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+
+/// Where `deploy --template` looks for templates when DEPLOY_TEMPLATES is not set.
+pub const DEFAULT_TEMPLATES_PATH: &str = "deploy_templates.json";
+
+/// Named sets of settings for `deploy --template`, e.g. a team's chain, gas and fee settings,
+/// pattern and notification routing for production deployments on one chain. Read from a JSON
+/// object of template names, each an object of settings by their environment variable name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeployTemplates {
+    templates: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl DeployTemplates {
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| eyre::eyre!("cannot read deployment templates from {}: {}", path.display(), e))?;
+        Self::parse(&json).map_err(|e| e.wrap_err(format!("invalid deployment templates in {}", path.display())))
+    }
+
+    /// Settings are strings, numbers or booleans. Secrets are refused, since templates are meant
+    /// to be shared and committed; they stay in `.env` or a secret store.
+    pub fn parse(json: &str) -> eyre::Result<Self> {
+        let object: BTreeMap<String, BTreeMap<String, Value>> = serde_json::from_str(json)?;
+        let mut templates = BTreeMap::new();
+        for (name, settings) in object {
+            let mut values = BTreeMap::new();
+            for (setting, value) in settings {
+                eyre::ensure!(
+                    !setting.is_empty() && setting.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'),
+                    "template {:?}: {:?} is not a setting name such as CHAIN or GAS_LIMIT",
+                    name,
+                    setting
+                );
+                eyre::ensure!(
+                    !is_secret_name(&setting),
+                    "template {:?} sets {}, keep secrets out of templates (in .env or a secret store)",
+                    name,
+                    setting
+                );
+                let value = match value {
+                    Value::String(value) => value,
+                    Value::Number(value) => value.to_string(),
+                    Value::Bool(value) => if value { "1" } else { "0" }.to_string(),
+                    other => eyre::bail!("template {:?}: {} must be a string, number or boolean, not {}", name, setting, other),
+                };
+                values.insert(setting, value);
+            }
+            templates.insert(name, values);
+        }
+        Ok(Self { templates })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// The settings of template `name`, or an error listing the templates there are.
+    pub fn get(&self, name: &str) -> eyre::Result<&BTreeMap<String, String>> {
+        self.templates.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.names().collect();
+            if names.is_empty() {
+                eyre::eyre!("no deployment template {:?}, none are defined", name)
+            } else {
+                eyre::eyre!("no deployment template {:?}, expected one of {}", name, names.join(", "))
+            }
+        })
+    }
+}
+
+/// How a template setting was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Applied {
+    /// Set from the template, over any value from `.env`.
+    Set(String),
+    /// Left as set in the shell, which takes precedence for a single run.
+    KeptFromShell(String),
+}
+
+/// Applies `settings` to the environment, over values from `.env` but not over `shell_vars`,
+/// the variables set before `.env` was loaded.
+pub fn apply(settings: &BTreeMap<String, String>, shell_vars: &[String]) -> Vec<Applied> {
+    settings
+        .iter()
+        .map(|(name, value)| {
+            if shell_vars.contains(name) {
+                Applied::KeptFromShell(name.clone())
+            } else {
+                env::set_var(name, value);
+                Applied::Set(name.clone())
+            }
+        })
+        .collect()
+}
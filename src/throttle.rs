@@ -0,0 +1,305 @@
+use crate::stats::SearchStats;
+use crate::units::format_hashrate;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often a paused worker checks whether it may carry on.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How often `--idle-below` checks what the rest of the machine is doing.
+pub const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Nice value for workers in `--nice` mode, the lowest priority there is.
+const LOW_PRIORITY_NICENESS: libc::c_int = 19;
+
+/// Lets a long search share the machine: workers can run at low OS priority, be paused while
+/// something else needs the CPU, and be parked one by one while the machine is too hot.
+#[derive(Debug)]
+pub struct Throttle {
+    low_priority: bool,
+    paused: AtomicBool,
+    /// Workers with a lower index than this run; the rest wait.
+    active_workers: AtomicUsize,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl Throttle {
+    pub fn new(low_priority: bool) -> Self {
+        Self { low_priority, paused: AtomicBool::new(false), active_workers: AtomicUsize::new(usize::MAX) }
+    }
+
+    /// Called by each worker on its own thread before it starts.
+    pub fn enter_worker(&self) {
+        if self.low_priority {
+            if let Err(e) = lower_thread_priority() {
+                println!("Warning: could not lower the worker's priority: {}", e);
+            }
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn active_workers(&self) -> usize {
+        self.active_workers.load(Ordering::Relaxed)
+    }
+
+    pub fn set_active_workers(&self, count: usize) {
+        self.active_workers.store(count, Ordering::Relaxed);
+    }
+
+    /// Whether worker `index` may run right now.
+    pub fn is_active(&self, index: usize) -> bool {
+        !self.is_paused() && index < self.active_workers()
+    }
+
+    /// Blocks worker `index` while it is paused or parked, returning early once `stop` is set.
+    pub fn wait_until_active(&self, index: usize, stop: &AtomicBool) {
+        while !self.is_active(index) && !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(PAUSED_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Pauses the workers while other processes use more than `threshold` (0 to 1) of the machine's
+/// CPU and resumes them once usage falls back, until `stop` is set.
+pub async fn pause_while_busy(throttle: Arc<Throttle>, threshold: f64, stop: Arc<AtomicBool>) -> eyre::Result<()> {
+    let mut previous = CpuSample::read()?;
+    while !stop.load(Ordering::Relaxed) {
+        tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+        let current = CpuSample::read()?;
+        let usage = current.others_usage_since(&previous);
+        previous = current;
+        let busy = usage > threshold;
+        if busy != throttle.is_paused() {
+            throttle.set_paused(busy);
+            if busy {
+                println!("Pausing: other processes are using {:.0}% of the CPU", usage * 100.0);
+            } else {
+                println!("Resuming: other processes are down to {:.0}% of the CPU", usage * 100.0);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How often the thermal watchdog measures throughput.
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+/// Below this share of the best per-worker hashrate, throughput counts as collapsed.
+const COLLAPSED_RATIO: f64 = 0.6;
+/// At or above this share it counts as recovered.
+const RECOVERED_RATIO: f64 = 0.85;
+/// Consecutive collapsed intervals before a worker is parked.
+const COLLAPSED_INTERVALS: u32 = 3;
+/// Consecutive recovered intervals before a worker is brought back, longer so a machine on the
+/// edge of throttling does not flip back and forth.
+const RECOVERED_INTERVALS: u32 = 6;
+
+/// What the thermal watchdog did about the worker count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchdogAction {
+    /// Throughput per worker stayed collapsed, so workers were parked.
+    Reduced { active: usize, rate: f64, baseline: f64 },
+    /// Throughput recovered, so a parked worker was brought back.
+    Restored { active: usize, rate: f64, baseline: f64 },
+}
+
+impl std::fmt::Display for WatchdogAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reduced { active, rate, baseline } => write!(
+                f,
+                "throughput fell to {} per worker ({:.0}% of {}), throttling to {} workers",
+                format_hashrate(*rate),
+                rate / baseline * 100.0,
+                format_hashrate(*baseline),
+                active
+            ),
+            Self::Restored { active, rate, baseline } => write!(
+                f,
+                "throughput recovered to {} per worker ({:.0}% of {}), back up to {} workers",
+                format_hashrate(*rate),
+                rate / baseline * 100.0,
+                format_hashrate(*baseline),
+                active
+            ),
+        }
+    }
+}
+
+/// Parks workers while per-worker throughput stays well below the best seen, as happens when a
+/// laptop throttles its clock, and brings them back once it recovers.
+#[derive(Debug, Clone)]
+pub struct ThermalWatchdog {
+    thread_count: usize,
+    active: usize,
+    /// Best per-worker hashrate seen with every worker running.
+    baseline: f64,
+    collapsed_streak: u32,
+    recovered_streak: u32,
+}
+
+impl ThermalWatchdog {
+    pub fn new(thread_count: usize) -> Self {
+        Self { thread_count, active: thread_count, baseline: 0.0, collapsed_streak: 0, recovered_streak: 0 }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Takes the per-worker hashrate of the active workers over the last interval.
+    pub fn observe(&mut self, rate: f64) -> Option<WatchdogAction> {
+        // Fewer workers can each run faster on a cool chip, so only a full machine sets the bar
+        if self.active == self.thread_count && rate > self.baseline {
+            self.baseline = rate;
+        }
+        if self.baseline <= 0.0 {
+            return None;
+        }
+        let ratio = rate / self.baseline;
+        if ratio < COLLAPSED_RATIO {
+            self.recovered_streak = 0;
+            self.collapsed_streak += 1;
+            if self.collapsed_streak >= COLLAPSED_INTERVALS && self.active > 1 {
+                self.collapsed_streak = 0;
+                self.active -= (self.active / 4).max(1);
+                return Some(WatchdogAction::Reduced { active: self.active, rate, baseline: self.baseline });
+            }
+        } else if ratio >= RECOVERED_RATIO {
+            self.collapsed_streak = 0;
+            self.recovered_streak += 1;
+            if self.recovered_streak >= RECOVERED_INTERVALS && self.active < self.thread_count {
+                self.recovered_streak = 0;
+                self.active += 1;
+                return Some(WatchdogAction::Restored { active: self.active, rate, baseline: self.baseline });
+            }
+        } else {
+            self.collapsed_streak = 0;
+            self.recovered_streak = 0;
+        }
+        None
+    }
+}
+
+/// Runs a [`ThermalWatchdog`] over `stats` until `stop` is set or the active workers run out of
+/// fees, applying its decisions to `throttle` and reporting each one to `on_action`. Intervals
+/// spent paused are skipped.
+pub async fn watch_throughput(
+    throttle: Arc<Throttle>,
+    stats: Arc<SearchStats>,
+    stop: Arc<AtomicBool>,
+    mut on_action: impl FnMut(&WatchdogAction),
+) {
+    let mut watchdog = ThermalWatchdog::new(stats.workers().len());
+    let mut previous = stats.attempts_snapshot();
+    while !stop.load(Ordering::Relaxed) {
+        let was_paused = throttle.is_paused();
+        tokio::time::sleep(WATCHDOG_INTERVAL).await;
+        let current = stats.attempts_snapshot();
+        let active = watchdog.active();
+        let attempts: u64 = current.iter().zip(&previous).take(active).map(|(now, before)| now - before).sum();
+        previous = current;
+        if attempts == 0 && !was_paused && !throttle.is_paused() {
+            // The active workers reached the end of their fee ranges, so let the parked ones
+            // sweep theirs rather than wait forever
+            throttle.set_active_workers(usize::MAX);
+            return;
+        }
+        if was_paused || throttle.is_paused() || stop.load(Ordering::Relaxed) {
+            continue;
+        }
+        let rate = attempts as f64 / WATCHDOG_INTERVAL.as_secs_f64() / active as f64;
+        if let Some(action) = watchdog.observe(rate) {
+            throttle.set_active_workers(watchdog.active());
+            on_action(&action);
+        }
+    }
+    // The throttle can outlive this search, e.g. when remining
+    throttle.set_active_workers(usize::MAX);
+}
+
+/// Gives the calling thread the lowest scheduling priority. On Linux niceness is per thread, so
+/// the rest of the process (RPC, prompts, metrics) keeps its normal priority.
+#[cfg(unix)]
+fn lower_thread_priority() -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    // SAFETY: gettid takes no arguments and cannot fail.
+    let who = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+    #[cfg(not(target_os = "linux"))]
+    let who = 0;
+    // SAFETY: setpriority only reads its arguments.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, who, LOW_PRIORITY_NICENESS) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn lower_thread_priority() -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "thread priorities are only supported on Unix"))
+}
+
+/// Cumulative CPU time, in clock ticks: the whole machine's from `/proc/stat` and this
+/// process's from `/proc/self/stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSample {
+    pub busy: u64,
+    pub total: u64,
+    pub own: u64,
+}
+
+impl CpuSample {
+    /// Reads the current counters. Only Linux has them.
+    pub fn read() -> eyre::Result<Self> {
+        let system = std::fs::read_to_string("/proc/stat")?;
+        let own = std::fs::read_to_string("/proc/self/stat")?;
+        let (busy, total) = parse_proc_stat(&system).ok_or_else(|| eyre::eyre!("unrecognized /proc/stat"))?;
+        let own = parse_own_ticks(&own).ok_or_else(|| eyre::eyre!("unrecognized /proc/self/stat"))?;
+        Ok(Self { busy, total, own })
+    }
+
+    /// Share of the machine's CPU, from 0 to 1, that other processes used since `earlier`.
+    pub fn others_usage_since(&self, earlier: &Self) -> f64 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        let busy = self.busy.saturating_sub(earlier.busy);
+        let own = self.own.saturating_sub(earlier.own);
+        busy.saturating_sub(own) as f64 / total as f64
+    }
+}
+
+/// Busy and total ticks from the aggregate `cpu` line of `/proc/stat`. Idle and iowait count as
+/// idle; guest time is already included in user time.
+pub fn parse_proc_stat(contents: &str) -> Option<(u64, u64)> {
+    let line = contents.lines().find(|line| line.starts_with("cpu "))?;
+    let fields: Vec<u64> = line.split_whitespace().skip(1).take(8).map(|field| field.parse().ok()).collect::<Option<_>>()?;
+    if fields.len() < 4 {
+        return None;
+    }
+    let total: u64 = fields.iter().sum();
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    Some((total - idle, total))
+}
+
+/// User plus system ticks of a process from its `/proc/<pid>/stat`. The command name may contain
+/// spaces, so fields are counted from the closing parenthesis.
+pub fn parse_own_ticks(contents: &str) -> Option<u64> {
+    let after_name = &contents[contents.rfind(')')? + 1..];
+    let fields: Vec<&str> = after_name.split_whitespace().collect();
+    // utime and stime are fields 14 and 15 of the whole line, 12 and 13 after the name
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
@@ -0,0 +1,225 @@
+use ethers::types::U256;
+use serde::Deserialize;
+
+pub const GWEI_DECIMALS: u32 = 9;
+pub const ETH_DECIMALS: u32 = 18;
+
+/// Converts wei to a float for ratios and percentages, saturating at `f64::MAX`. Use the
+/// `format_*` functions for displaying amounts, which are exact.
+pub fn wei_to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
+/// Formats `value` as a decimal with `decimals` places, exactly and without trailing zeros,
+/// e.g. `format_units(1_500_000_000.into(), 9)` is `1.5`.
+pub fn format_units(value: U256, decimals: u32) -> String {
+    let digits = value.to_string();
+    let decimals = decimals as usize;
+    let (integer, fraction) = if digits.len() > decimals {
+        let (integer, fraction) = digits.split_at(digits.len() - decimals);
+        (integer.to_string(), fraction.to_string())
+    } else {
+        ("0".to_string(), format!("{:0>width$}", digits, width = decimals))
+    };
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+/// Wei in the chain's native unit (ETH on most chains).
+pub fn format_eth(wei: U256) -> String {
+    format_units(wei, ETH_DECIMALS)
+}
+
+pub fn format_gwei(wei: U256) -> String {
+    format_units(wei, GWEI_DECIMALS)
+}
+
+/// `a - b` in the native unit with an explicit sign, e.g. `+0.0012` or `-0.5`.
+pub fn format_eth_difference(a: U256, b: U256) -> String {
+    if a >= b {
+        format!("+{}", format_eth(a - b))
+    } else {
+        format!("-{}", format_eth(b - a))
+    }
+}
+
+/// A duration in its two largest units, e.g. `42s`, `3m 20s`, `1h 23m`, `2d 5h`.
+pub fn format_duration(seconds: f64) -> String {
+    const YEAR: f64 = 365.25 * 86_400.0;
+    if !seconds.is_finite() {
+        return "forever".to_string();
+    }
+    if seconds < 1.0 {
+        return format!("{:.2}s", seconds);
+    }
+    if seconds < 10.0 {
+        return format!("{:.1}s", seconds);
+    }
+    if seconds >= 100.0 * YEAR {
+        return format!("{} years", format_estimate(seconds / YEAR));
+    }
+    if seconds >= YEAR {
+        return format!("{:.1} years", seconds / YEAR);
+    }
+    let whole = seconds.round() as u64;
+    let (major, minor) = match whole {
+        0..=59 => return format!("{}s", whole),
+        60..=3_599 => ((whole / 60, "m"), (whole % 60, "s")),
+        3_600..=86_399 => ((whole / 3_600, "h"), (whole / 60 % 60, "m")),
+        _ => ((whole / 86_400, "d"), (whole / 3_600 % 24, "h")),
+    };
+    match minor {
+        (0, _) => format!("{}{}", major.0, major.1),
+        _ => format!("{}{} {}{}", major.0, major.1, minor.0, minor.1),
+    }
+}
+
+/// The digit group separator of THOUSANDS_SEPARATOR, `,` by default and nothing for `none`.
+pub fn thousands_separator() -> String {
+    match crate::config::non_empty_var("THOUSANDS_SEPARATOR") {
+        Some(separator) if separator.eq_ignore_ascii_case("none") => String::new(),
+        Some(separator) => separator,
+        None => ",".to_string(),
+    }
+}
+
+/// Groups the integer digits of `number` in threes with `separator`, e.g. `1234567.5` becomes
+/// `1,234,567.5`. Anything after the digits, such as a fraction, is kept as is.
+pub fn group_digits(number: &str, separator: &str) -> String {
+    let (sign, rest) = number.split_at(number.starts_with('-') as usize);
+    let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let (digits, tail) = rest.split_at(end);
+    let mut grouped = String::with_capacity(number.len() + digits.len() / 3 * separator.len());
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    format!("{}{}{}", sign, grouped, tail)
+}
+
+/// A whole number such as an attempt count or an amount of wei, with thousands separators.
+pub fn format_count(value: impl std::fmt::Display) -> String {
+    group_digits(&value.to_string(), &thousands_separator())
+}
+
+/// An estimated count, rounded and grouped like [`format_count`], or in scientific notation once
+/// it has more digits than anyone reads, e.g. `1.46e48`.
+pub fn format_estimate(value: f64) -> String {
+    if !value.is_finite() {
+        "infinite".to_string()
+    } else if value.abs() >= 1e15 {
+        format!("{:.2e}", value)
+    } else {
+        format_count(format!("{:.0}", value))
+    }
+}
+
+/// Attempts per second in the unit that keeps the figure short, e.g. `950 H/s`, `12.3 kH/s`,
+/// `4.56 MH/s`.
+pub fn format_hashrate(per_second: f64) -> String {
+    const UNITS: [(f64, &str); 3] = [(1e9, "GH/s"), (1e6, "MH/s"), (1e3, "kH/s")];
+    match UNITS.iter().find(|(unit, _)| per_second >= *unit) {
+        Some((unit, name)) => {
+            let scaled = per_second / unit;
+            let decimals = if scaled < 10.0 { 2 } else if scaled < 100.0 { 1 } else { 0 };
+            format!("{:.*} {}", decimals, scaled, name)
+        }
+        None => format!("{:.0} H/s", per_second),
+    }
+}
+
+/// An amount of wei with thousands separators, e.g. `18,000,000 wei`.
+pub fn format_wei(wei: U256) -> String {
+    format!("{} wei", format_count(wei))
+}
+
+/// Parses an amount given in wei (`18000000`), gwei (`1.5gwei`) or ETH (`0.01eth`, `0.01 ether`)
+/// into wei, rejecting anything that cannot be represented exactly. Digits may be grouped with
+/// underscores, e.g. `18_000_000`.
+pub fn parse_amount(input: &str) -> eyre::Result<U256> {
+    let lowercase = input.trim().to_lowercase().replace('_', "");
+    let unit_start = lowercase.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(lowercase.len());
+    let (number, unit) = lowercase.split_at(unit_start);
+    let (number, unit) = (number.trim(), unit.trim());
+    let decimals = match unit {
+        "" | "wei" => 0,
+        "gwei" => GWEI_DECIMALS,
+        "eth" | "ether" => ETH_DECIMALS,
+        _ => eyre::bail!("unknown unit {:?} in {:?}, expected wei, gwei or eth", unit, input),
+    };
+
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if integer.is_empty() && fraction.is_empty() {
+        eyre::bail!("missing number in {:?}", input);
+    }
+    if !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        eyre::bail!("{:?} is not a number, e.g. 18000000, 1.5gwei or 0.01eth", input);
+    }
+    if fraction.len() > decimals as usize {
+        eyre::bail!("{:?} is more precise than 1 wei", input);
+    }
+    let digits = format!("{}{:0<width$}", integer, fraction, width = decimals as usize);
+    U256::from_dec_str(&digits).map_err(|e| eyre::eyre!("invalid amount {:?}: {}", input, e))
+}
+
+/// Below this many wei, a fee given as a bare number was most likely meant in gwei.
+pub const UNITLESS_FEE_WARNING_BELOW: u64 = 1_000;
+
+/// Parses the fee setting `name` like [`parse_amount`], so a bare number stays wei as it always
+/// has been, with an error naming the setting and every accepted form.
+pub fn parse_fee(name: &str, input: &str) -> eyre::Result<U256> {
+    parse_amount(input).map_err(|e| {
+        eyre::eyre!(
+            "invalid {} {:?}: {}. A fee is a whole number of wei (18000000), or has a unit: gwei (1.5gwei) or eth (0.000000018eth)",
+            name,
+            input,
+            e
+        )
+    })
+}
+
+/// A warning for a fee `input` given without a unit that is so small it was probably meant in gwei.
+pub fn unitless_fee_warning(name: &str, input: &str, fee: U256) -> Option<String> {
+    let unitless = !input.contains(|c: char| c.is_ascii_alphabetic());
+    (unitless && !fee.is_zero() && fee < UNITLESS_FEE_WARNING_BELOW.into()).then(|| {
+        format!(
+            "Warning: {}={} is {} wei ({} gwei); write {}gwei if gwei was meant",
+            name,
+            input.trim(),
+            fee,
+            format_gwei(fee),
+            input.trim()
+        )
+    })
+}
+
+/// Deserializes an optional amount from a JSON number of wei, a `0x` hex string, or a string
+/// accepted by [`parse_amount`] such as `"1.5gwei"`.
+pub fn deserialize_optional_amount<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<U256>, D::Error> {
+    use serde::de::Error;
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Number(number)) => parse_amount(&number.to_string()).map(Some).map_err(D::Error::custom),
+        Some(serde_json::Value::String(text)) if text.starts_with("0x") => U256::from_str_radix(&text[2..], 16)
+            .map(Some)
+            .map_err(|e| D::Error::custom(format!("invalid hex amount {:?}: {}", text, e))),
+        Some(serde_json::Value::String(text)) => parse_amount(&text).map(Some).map_err(D::Error::custom),
+        Some(other) => Err(D::Error::custom(format!("expected an amount such as 18000000, \"1.5gwei\" or \"0x...\", not {}", other))),
+    }
+}
+
+/// Like [`parse_amount`], but a bare number is taken to be in gwei, e.g. `0.05` or `1.5gwei`.
+pub fn parse_gwei(input: &str) -> eyre::Result<U256> {
+    if input.contains(|c: char| c.is_ascii_alphabetic()) {
+        parse_amount(input)
+    } else {
+        parse_amount(&format!("{}gwei", input.trim()))
+    }
+}
@@ -0,0 +1,202 @@
+use crate::rpc::RpcProvider;
+use crate::stats::SearchStats;
+use ethers::abi::{self, ParamType, Token};
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use eyre::{ensure, eyre};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The v0.6 EntryPoint, deployed at the same address on every chain that supports ERC-4337.
+pub const ENTRY_POINT_V06: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
+/// Arachnid's deterministic deployment proxy: calldata `salt ++ init code` does a CREATE2.
+pub const CREATE2_DEPLOYER: &str = "0x4e59b44847b379578588920ca78fbf26c0b4956c";
+/// Placeholder signature for gas estimation, shaped like a real ECDSA signature so the account's
+/// validation runs the same path.
+const DUMMY_SIGNATURE: &str = "0xfffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c";
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An ERC-4337 v0.6 user operation, serialized the way bundlers expect it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    /// 192-bit key in the high bits and the sequence number for that key in the low 64 bits.
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// The fields covered by the hash, ABI-encoded with the dynamic ones hashed. The nonce is the
+    /// second word, which is what the miner varies.
+    fn packed(&self) -> Vec<u8> {
+        abi::encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ])
+    }
+
+    /// The userOpHash, as computed by `EntryPoint.getUserOpHash`. The signature is not covered.
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        user_op_hash(&self.packed(), entry_point, chain_id)
+    }
+
+    /// A copy with a placeholder signature, for `eth_estimateUserOperationGas`.
+    pub fn with_dummy_signature(&self) -> Self {
+        Self { signature: DUMMY_SIGNATURE.parse().expect("valid dummy signature"), ..self.clone() }
+    }
+}
+
+fn user_op_hash(packed: &[u8], entry_point: Address, chain_id: u64) -> H256 {
+    keccak256(abi::encode(&[
+        Token::FixedBytes(keccak256(packed).to_vec()),
+        Token::Address(entry_point),
+        Token::Uint(chain_id.into()),
+    ]))
+    .into()
+}
+
+/// The first nonce of `key`, which is free to choose: each key has its own sequence starting at 0.
+pub fn nonce_for_key(key: U256) -> U256 {
+    key << 64
+}
+
+/// `execute(address,uint256,bytes)` calldata, the entry point of SimpleAccount and most accounts
+/// derived from it.
+pub fn execute_call(dest: Address, value: U256, data: Bytes) -> Bytes {
+    let selector = abi::short_signature("execute", &[ParamType::Address, ParamType::Uint(256), ParamType::Bytes]);
+    let mut call = selector.to_vec();
+    call.extend(abi::encode(&[Token::Address(dest), Token::Uint(value), Token::Bytes(data.to_vec())]));
+    call.into()
+}
+
+/// Mines a nonce key for which the userOpHash starts with `hash_prefix`, leaving every other field
+/// (and so the fees) untouched. Workers take turns over keys from a random starting point, so
+/// repeated runs do not reuse keys. Returns the nonce and hash, or `None` if `found` is set by
+/// someone else.
+pub async fn mine(
+    op: &UserOperation,
+    entry_point: Address,
+    chain_id: u64,
+    hash_prefix: &str,
+    stats: Arc<SearchStats>,
+    found: Arc<AtomicBool>,
+) -> eyre::Result<Option<(U256, H256)>> {
+    let thread_count = stats.workers().len();
+    let start = U256::from(ethers::core::rand::random::<u64>()) << 64;
+    let packed = Arc::new(op.packed());
+    let max_fee = op.max_fee_per_gas;
+
+    let workers: Vec<_> = (0..thread_count)
+        .map(|i| {
+            let packed = packed.clone();
+            let stats = stats.clone();
+            let found = found.clone();
+            let hash_prefix = hash_prefix.to_string();
+            tokio::task::spawn_blocking(move || {
+                let mut packed = packed.to_vec();
+                let mut key = start + i;
+                while !found.load(Ordering::Relaxed) {
+                    let nonce = nonce_for_key(key);
+                    nonce.to_big_endian(&mut packed[32..64]);
+                    let hash = user_op_hash(&packed, entry_point, chain_id);
+                    stats.worker(i).record_attempt(max_fee);
+                    if format!("{:?}", hash).starts_with(&hash_prefix) {
+                        found.store(true, Ordering::Relaxed);
+                        return Some((nonce, hash));
+                    }
+                    key += U256::from(thread_count);
+                }
+                None
+            })
+        })
+        .collect();
+
+    let mut result = None;
+    for worker in workers {
+        result = result.or(worker.await?);
+    }
+    Ok(result)
+}
+
+/// Signs `hash` the way SimpleAccount validates it, as an EIP-191 personal message, and checks
+/// the signature recovers to the owner before it is used.
+pub async fn sign(owner: &LocalWallet, hash: H256) -> eyre::Result<Bytes> {
+    let signature = owner.sign_message(hash.as_bytes()).await?;
+    let recovered = signature.recover(hash.as_bytes())?;
+    ensure!(recovered == owner.address(), "signature recovers to {:?}, not the owner {:?}", recovered, owner.address());
+    Ok(signature.to_vec().into())
+}
+
+/// Nonce the EntryPoint expects next for `sender` and the key in the high bits of `nonce`.
+pub async fn entry_point_nonce(provider: &RpcProvider, entry_point: Address, sender: Address, nonce: U256) -> eyre::Result<U256> {
+    let selector = abi::short_signature("getNonce", &[ParamType::Address, ParamType::Uint(192)]);
+    let mut data = selector.to_vec();
+    data.extend(abi::encode(&[Token::Address(sender), Token::Uint(nonce >> 64)]));
+    let call = TransactionRequest::new().to(entry_point).data(data);
+    let output = provider.call(&call.into(), None).await?;
+    ensure!(output.len() == 32, "unexpected getNonce result {}", output);
+    Ok(U256::from_big_endian(&output))
+}
+
+/// Gas limits estimated by the bundler.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasEstimate {
+    pub pre_verification_gas: U256,
+    pub verification_gas_limit: U256,
+    pub call_gas_limit: U256,
+}
+
+/// `eth_estimateUserOperationGas` with a placeholder signature.
+pub async fn estimate_gas(bundler: &RpcProvider, op: &UserOperation, entry_point: Address) -> eyre::Result<GasEstimate> {
+    bundler.request("eth_estimateUserOperationGas", (op.with_dummy_signature(), entry_point))
+        .await
+        .map_err(|e| eyre!("bundler could not estimate gas: {}", e))
+}
+
+/// Submits `op` with `eth_sendUserOperation`, returning the hash the bundler computed.
+pub async fn send(bundler: &RpcProvider, op: &UserOperation, entry_point: Address) -> eyre::Result<H256> {
+    bundler.request("eth_sendUserOperation", (op, entry_point))
+        .await
+        .map_err(|e| eyre!("bundler rejected the user operation: {}", e))
+}
+
+/// The parts of `eth_getUserOperationReceipt` needed to report the outcome.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperationReceipt {
+    pub success: bool,
+    pub actual_gas_cost: U256,
+    pub receipt: TransactionReceipt,
+}
+
+/// Polls the bundler until the user operation is included.
+pub async fn wait_for_receipt(bundler: &RpcProvider, hash: H256) -> eyre::Result<UserOperationReceipt> {
+    loop {
+        let receipt: Option<UserOperationReceipt> =
+            bundler.request("eth_getUserOperationReceipt", [hash]).await?;
+        if let Some(receipt) = receipt {
+            return Ok(receipt);
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
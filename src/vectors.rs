@@ -0,0 +1,96 @@
+use crate::envelope::{self, Cip64, EnvelopeSigner, CIP64_TX_TYPE};
+use crate::search::CandidateSigner;
+use crate::verify;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use eyre::{ensure, eyre};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The corpus shipped with the tool: the EIP-155 example and transactions of each type this tool
+/// signs or decodes, on the chains it has presets for, as the pipeline encoded them when recorded.
+pub const CORPUS: &str = include_str!("vectors.json");
+
+/// A signed transaction with the hash and sender it is known to have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vector {
+    pub name: String,
+    /// The signed transaction as sent.
+    pub raw: Bytes,
+    pub hash: H256,
+    pub from: Address,
+    /// The key that signed it, for vectors made with a published test key. Signing the decoded
+    /// transaction again with it must give `raw` back byte for byte, since signatures are
+    /// deterministic (RFC 6979). Transactions recorded from a chain have no key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+/// Reads a JSON array of vectors.
+pub fn parse(json: &str) -> eyre::Result<Vec<Vector>> {
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn load(path: impl AsRef<Path>) -> eyre::Result<Vec<Vector>> {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path).map_err(|e| eyre!("cannot read vectors from {}: {}", path.display(), e))?;
+    parse(&json).map_err(|e| e.wrap_err(format!("invalid vectors in {}", path.display())))
+}
+
+/// The shipped corpus.
+pub fn corpus() -> Vec<Vector> {
+    parse(CORPUS).expect("the shipped vectors are valid JSON")
+}
+
+/// Runs `vector` through everything the search and the pre-broadcast checks rely on: hashing the
+/// raw bytes, decoding and re-encoding them, recovering the sender, the second-path cross-check,
+/// and, with a key, signing the decoded transaction again the way a search signs candidates.
+pub fn check(vector: &Vector) -> eyre::Result<()> {
+    let hash = H256::from(keccak256(&vector.raw));
+    ensure!(hash == vector.hash, "hashes to {:?}, recorded as {:?}", hash, vector.hash);
+
+    let decoded = envelope::decode_signed(&vector.raw).map_err(|e| eyre!("does not decode: {}", e))?;
+    let chain_id = decoded.tx.chain_id().ok_or_else(|| eyre!("has no chain id"))?.as_u64();
+    let nonce = decoded.tx.nonce().copied().ok_or_else(|| eyre!("has no nonce"))?;
+    let candidate = verify::verify_for_broadcast(&vector.raw, vector.hash, vector.from, chain_id, nonce)?;
+    verify::cross_check(&vector.raw, &candidate)?;
+
+    let Some(key) = &vector.key else {
+        return Ok(());
+    };
+    let wallet: LocalWallet = key.parse::<LocalWallet>().map_err(|e| eyre!("invalid key: {}", e))?.with_chain_id(chain_id);
+    ensure!(wallet.address() == vector.from, "key belongs to {:?}, not the sender", wallet.address());
+    let (signed, signed_hash) = sign_again(wallet, &vector.raw, decoded.tx)?;
+    ensure!(
+        signed.as_ref() == vector.raw.as_ref(),
+        "signs again as 0x{}, recorded as 0x{}",
+        hex::encode(&signed),
+        hex::encode(&vector.raw)
+    );
+    ensure!(H256::from(signed_hash) == vector.hash, "signs again with hash 0x{}", hex::encode(signed_hash));
+    Ok(())
+}
+
+/// Signs `tx`, decoded from `raw`, with the signer the search would use for its envelope.
+fn sign_again(wallet: LocalWallet, raw: &[u8], tx: TypedTransaction) -> eyre::Result<(Bytes, [u8; 32])> {
+    match tx {
+        TypedTransaction::Eip1559(_) if raw.first() == Some(&CIP64_TX_TYPE) => {
+            let (envelope, tx, _) = Cip64::decode_signed(raw)?;
+            EnvelopeSigner { wallet, envelope }.sign_candidate(&tx)
+        }
+        TypedTransaction::Eip1559(tx) => wallet.sign_candidate(&tx),
+        // Legacy and access-list transactions are only ever decoded, e.g. by `broadcast`
+        tx => {
+            let signature = wallet.sign_transaction_sync(&tx)?;
+            let signed = tx.rlp_signed(&signature);
+            let hash = keccak256(&signed);
+            Ok((signed, hash))
+        }
+    }
+}
+
+/// Checks every vector, returning each one's name with what went wrong, if anything.
+pub fn check_all(vectors: &[Vector]) -> Vec<(String, eyre::Result<()>)> {
+    vectors.iter().map(|vector| (vector.name.clone(), check(vector))).collect()
+}
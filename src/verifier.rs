@@ -0,0 +1,176 @@
+use crate::config::non_empty_var;
+use crate::exit;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::{Address, Bytes, NameOrAddress, H256, U256};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+
+/// A matched candidate, as one JSON line sent to the verifier.
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateMessage {
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    pub raw_tx: Bytes,
+    pub nonce: U256,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub gas_limit: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    /// Leading digits of HASH_PREFIX the hash matches, fewer than all for a STOP_POLICY partial.
+    pub matched_digits: usize,
+    pub full_match: bool,
+}
+
+impl CandidateMessage {
+    pub fn new(tx: &Eip1559TransactionRequest, raw_tx: &Bytes, tx_hash: [u8; 32], matched_digits: usize, full_match: bool) -> Self {
+        Self {
+            chain_id: tx.chain_id.unwrap_or_default().as_u64(),
+            tx_hash: tx_hash.into(),
+            raw_tx: raw_tx.clone(),
+            nonce: tx.nonce.unwrap_or_default(),
+            to: tx.to.as_ref().and_then(NameOrAddress::as_address).copied(),
+            value: tx.value.unwrap_or_default(),
+            gas_limit: tx.gas.unwrap_or_default(),
+            max_fee_per_gas: tx.max_fee_per_gas.unwrap_or_default(),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+            matched_digits,
+            full_match,
+        }
+    }
+}
+
+/// What the verifier answered for a candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Accept,
+    Reject(String),
+}
+
+/// Reads a verifier's reply line: `accept`, `reject` or `reject: <reason>`, or JSON such as
+/// `{"accept": false, "reason": "fee level is denylisted"}`.
+pub fn parse_verdict(line: &str) -> eyre::Result<Verdict> {
+    let line = line.trim();
+    if line.starts_with('{') {
+        let reply: Value = serde_json::from_str(line).map_err(|e| eyre::eyre!("invalid verifier reply {:?}: {}", line, e))?;
+        let accept = reply["accept"].as_bool().ok_or_else(|| eyre::eyre!("verifier reply {:?} has no boolean \"accept\"", line))?;
+        return Ok(if accept {
+            Verdict::Accept
+        } else {
+            Verdict::Reject(reply["reason"].as_str().unwrap_or("no reason given").to_string())
+        });
+    }
+    let (word, reason) = line.split_once(':').map_or((line, ""), |(word, reason)| (word, reason.trim()));
+    match word.trim().to_lowercase().as_str() {
+        "accept" => Ok(Verdict::Accept),
+        "reject" if reason.is_empty() => Ok(Verdict::Reject("no reason given".to_string())),
+        "reject" => Ok(Verdict::Reject(reason.to_string())),
+        _ => eyre::bail!("unexpected verifier reply {:?}, expected accept or reject", line),
+    }
+}
+
+struct Connection {
+    writer: Box<dyn Write + Send>,
+    reader: Box<dyn BufRead + Send>,
+    /// Kept so the verifier process is killed with the connection.
+    child: Option<Child>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// The shell VERIFIER_COMMAND runs in, and its flag for running a command line.
+#[cfg(unix)]
+const SHELL: (&str, &str) = ("sh", "-c");
+#[cfg(not(unix))]
+const SHELL: (&str, &str) = ("cmd", "/C");
+
+/// An external process that decides whether a matched candidate may be used, from
+/// VERIFIER_COMMAND (started once and fed on its stdin) or VERIFIER_SOCKET (a unix socket it
+/// listens on). Candidates are sent one at a time, each waiting for the reply line.
+pub struct Verifier {
+    description: String,
+    connection: Mutex<Connection>,
+}
+
+impl Verifier {
+    /// `None` without VERIFIER_COMMAND or VERIFIER_SOCKET, when every match is used as found.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        Self::read_env().map_err(exit::config_error)
+    }
+
+    fn read_env() -> eyre::Result<Option<Self>> {
+        match (non_empty_var("VERIFIER_COMMAND"), non_empty_var("VERIFIER_SOCKET")) {
+            (Some(_), Some(_)) => eyre::bail!("set either VERIFIER_COMMAND or VERIFIER_SOCKET, not both"),
+            (Some(command), None) => Self::spawn(&command).map(Some),
+            (None, Some(path)) => Self::connect(&path).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Starts `command` with `sh -c` (`cmd /C` on Windows), writing candidates to its stdin and
+    /// reading replies from its stdout. Its stderr is left on the terminal.
+    pub fn spawn(command: &str) -> eyre::Result<Self> {
+        let (shell, flag) = SHELL;
+        let mut child = Command::new(shell)
+            .args([flag, command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| eyre::eyre!("failed to start VERIFIER_COMMAND {:?}: {}", command, e))?;
+        let writer = child.stdin.take().ok_or_else(|| eyre::eyre!("the verifier has no stdin"))?;
+        let reader = child.stdout.take().ok_or_else(|| eyre::eyre!("the verifier has no stdout"))?;
+        Ok(Self {
+            description: format!("verifier `{}`", command),
+            connection: Mutex::new(Connection { writer: Box::new(writer), reader: Box::new(BufReader::new(reader)), child: Some(child) }),
+        })
+    }
+
+    /// Connects to a verifier listening on the unix socket at `path`.
+    #[cfg(unix)]
+    pub fn connect(path: &str) -> eyre::Result<Self> {
+        use std::os::unix::net::UnixStream;
+
+        let stream = UnixStream::connect(path).map_err(|e| eyre::eyre!("failed to connect to VERIFIER_SOCKET {}: {}", path, e))?;
+        let reader = stream.try_clone()?;
+        Ok(Self {
+            description: format!("verifier at {}", path),
+            connection: Mutex::new(Connection { writer: Box::new(stream), reader: Box::new(BufReader::new(reader)), child: None }),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect(path: &str) -> eyre::Result<Self> {
+        eyre::bail!("VERIFIER_SOCKET {} needs unix sockets, which this platform lacks; use VERIFIER_COMMAND instead", path)
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Sends `candidate` and waits for the verdict. A verifier that has exited or replies with
+    /// something else is an error, so nothing is used without its decision.
+    pub fn check(&self, candidate: &CandidateMessage) -> eyre::Result<Verdict> {
+        let mut connection = self.connection.lock();
+        let mut line = serde_json::to_string(candidate)?;
+        line.push('\n');
+        connection
+            .writer
+            .write_all(line.as_bytes())
+            .and_then(|()| connection.writer.flush())
+            .map_err(|e| eyre::eyre!("failed to send a candidate to the {}: {}", self.description, e))?;
+        let mut reply = String::new();
+        let read = connection.reader.read_line(&mut reply).map_err(|e| eyre::eyre!("failed to read from the {}: {}", self.description, e))?;
+        eyre::ensure!(read > 0, "the {} closed its output without replying", self.description);
+        parse_verdict(&reply)
+    }
+}
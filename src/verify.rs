@@ -0,0 +1,139 @@
+use crate::envelope::{self, DecodedTx};
+use crate::pattern;
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, Signature, H256, U256};
+use ethers::utils::keccak256;
+use eyre::{ensure, eyre};
+use rlp::{Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+/// A claimed match that passed every check in [`verify`].
+#[derive(Debug, Clone)]
+pub struct VerifiedCandidate {
+    pub tx: TypedTransaction,
+    pub signature: Signature,
+    pub from: Address,
+    pub hash: H256,
+}
+
+/// Independently checks a claimed match without trusting anything the searcher reported:
+/// decodes the raw signed RLP, recovers the signer, recomputes the hash from the raw bytes
+/// and checks it against the expected pattern, chain id and nonce, and that its fees are valid.
+pub fn verify(
+    raw_rlp: &[u8],
+    expected_pattern: &str,
+    expected_chain_id: u64,
+    expected_nonce: U256,
+) -> eyre::Result<VerifiedCandidate> {
+    let DecodedTx { tx, signature, sighash, reencoded } =
+        envelope::decode_signed(raw_rlp).map_err(|e| eyre!("failed to decode signed transaction: {}", e))?;
+
+    // Re-encoding must reproduce the exact bytes, otherwise the hash we check is not the
+    // hash the network will compute.
+    ensure!(reencoded.as_ref() == raw_rlp, "signed transaction is not canonically encoded");
+
+    let from = signature
+        .recover(sighash)
+        .map_err(|e| eyre!("failed to recover signer: {}", e))?;
+
+    let hash = H256::from(keccak256(raw_rlp));
+    let hash_hex = hex::encode(hash);
+    let pattern = pattern::normalize(expected_pattern);
+    ensure!(
+        hash_hex.starts_with(&pattern),
+        "hash 0x{} does not match pattern 0x{}",
+        hash_hex,
+        pattern
+    );
+
+    let chain_id = tx.chain_id().ok_or_else(|| eyre!("transaction has no chain id"))?;
+    ensure!(
+        chain_id.as_u64() == expected_chain_id,
+        "chain id {} does not match expected {}",
+        chain_id,
+        expected_chain_id
+    );
+
+    let nonce = tx.nonce().copied().ok_or_else(|| eyre!("transaction has no nonce"))?;
+    ensure!(
+        nonce == expected_nonce,
+        "nonce {} does not match expected {}",
+        nonce,
+        expected_nonce
+    );
+
+    if let TypedTransaction::Eip1559(tx) = &tx {
+        let max_fee = tx.max_fee_per_gas.unwrap_or_default();
+        let priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+        ensure!(
+            priority_fee <= max_fee,
+            "max priority fee {} is above max fee {}, every node rejects it",
+            priority_fee,
+            max_fee
+        );
+    }
+
+    Ok(VerifiedCandidate { tx, signature, from, hash })
+}
+
+/// Last check before a signed transaction is broadcast: it must decode, be signed by
+/// `expected_from` for `expected_chain_id` and `expected_nonce`, and hash to exactly the hash
+/// that was shown to the user.
+pub fn verify_for_broadcast(
+    raw_rlp: &[u8],
+    expected_hash: H256,
+    expected_from: Address,
+    expected_chain_id: u64,
+    expected_nonce: U256,
+) -> eyre::Result<VerifiedCandidate> {
+    let candidate = verify(raw_rlp, &format!("{:?}", expected_hash), expected_chain_id, expected_nonce)?;
+    ensure!(
+        candidate.from == expected_from,
+        "transaction is signed by {:?}, expected {:?}",
+        candidate.from,
+        expected_from
+    );
+    Ok(candidate)
+}
+
+/// Re-derives what [`verify`] checked along a second path, against a miscompiled or faulty keccak
+/// on the fast path: the raw bytes are hashed with the `sha3` crate instead of `tiny-keccak`, and
+/// the signer is recovered from a signing hash rebuilt from the raw fields and hashed the same way.
+pub fn cross_check(raw_rlp: &[u8], candidate: &VerifiedCandidate) -> eyre::Result<()> {
+    let hash = H256::from_slice(&Keccak256::digest(raw_rlp));
+    ensure!(hash == candidate.hash, "keccak implementations disagree: sha3 gives {:?}, tiny-keccak {:?}", hash, candidate.hash);
+
+    let sighash = H256::from_slice(&Keccak256::digest(signing_payload(raw_rlp)?));
+    let from = candidate.signature.recover(sighash).map_err(|e| eyre!("failed to recover signer from the rebuilt signing hash: {}", e))?;
+    ensure!(from == candidate.from, "rebuilt signing hash recovers {:?}, not {:?}", from, candidate.from);
+    Ok(())
+}
+
+/// The bytes a signed transaction's signature covers, rebuilt from its raw encoding: the fields
+/// before `v, r, s` of a typed envelope, or an EIP-155 legacy transaction's fields with the chain id.
+fn signing_payload(raw_rlp: &[u8]) -> eyre::Result<Vec<u8>> {
+    let (prefix, list) = match raw_rlp.first() {
+        Some(&tx_type) if tx_type < 0xc0 => (Some(tx_type), Rlp::new(&raw_rlp[1..])),
+        Some(_) => (None, Rlp::new(raw_rlp)),
+        None => eyre::bail!("empty transaction"),
+    };
+    let fields = list.item_count()?;
+    ensure!(fields > 3, "a signed transaction has only {} fields", fields);
+    let unsigned = fields - 3;
+    let mut stream = match prefix {
+        Some(_) => RlpStream::new_list(unsigned),
+        None => RlpStream::new_list(unsigned + 3),
+    };
+    for i in 0..unsigned {
+        stream.append_raw(list.at(i)?.as_raw(), 1);
+    }
+    if prefix.is_none() {
+        let v: u64 = list.val_at(unsigned)?;
+        ensure!(v >= 35, "legacy transaction without EIP-155 replay protection");
+        stream.append(&((v - 35) / 2));
+        stream.append_empty_data();
+        stream.append_empty_data();
+    }
+    let mut payload = prefix.map(|tx_type| vec![tx_type]).unwrap_or_default();
+    payload.extend_from_slice(&stream.out());
+    Ok(payload)
+}
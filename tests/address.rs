@@ -0,0 +1,33 @@
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::address::{get_contract_address, get_create3_address, Prediction, CREATE3_PROXY_INIT_CODE};
+
+fn address(hex: &str) -> Address {
+    hex.parse().unwrap()
+}
+
+#[test]
+fn predicts_create_and_create2_addresses() {
+    let sender = address("0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+    assert_eq!(get_contract_address(sender, U256::zero()), address("0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"));
+    assert_eq!(Prediction::create(sender, U256::one()).address, address("0x343c43a37d37dff08ae8c4a11544c718abb4fcf8"));
+
+    // EIP-1014 example 1
+    let prediction = Prediction::create2(Address::zero(), H256::zero(), H256(keccak256([0u8])));
+    assert_eq!(prediction.address, address("0x4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"));
+    assert_eq!(prediction.to_string(), format!("CREATE2 from {:?} with salt {:?} and init code hash {:?}: {:?}", Address::zero(), H256::zero(), H256(keccak256([0u8])), prediction.address));
+}
+
+#[test]
+fn predicts_create3_through_the_proxy() {
+    assert_eq!(
+        H256(keccak256(CREATE3_PROXY_INIT_CODE)),
+        "0x21c35dbe1b344a2488cf3321d6ce542f8e9f305544ff09e4993a62319a497c1f".parse().unwrap()
+    );
+    let factory = address("0x9fbb3df7c40da2e5a0de984ffe2ccb7c47cd0abf");
+    let salt = H256::repeat_byte(0x42);
+    let proxy = Prediction::create2(factory, salt, H256(keccak256(CREATE3_PROXY_INIT_CODE))).address;
+    assert_eq!(get_create3_address(factory, salt), get_contract_address(proxy, U256::one()));
+    // Independent of any init code, unlike CREATE2
+    assert_ne!(get_create3_address(factory, salt), get_create3_address(factory, H256::zero()));
+}
@@ -0,0 +1,33 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::advise::{self, Placement};
+use find_tx_hash_prefix::pattern;
+
+#[test]
+fn spells_words_with_look_alike_digits() {
+    assert_eq!(pattern::spell("Coffee").unwrap(), "c0ffee");
+    assert_eq!(pattern::spell("dead beef").unwrap(), "deadbeef");
+    assert!(pattern::spell("xyz").is_err());
+}
+
+#[test]
+fn a_word_anywhere_is_cheaper_than_at_either_end() {
+    assert_eq!(Placement::Prefix.expected_attempts(4), 65536.0);
+    assert_eq!(Placement::Suffix.expected_attempts(4), 65536.0);
+    // Roughly one in 16^4 at each of the 61 positions
+    let anywhere = Placement::Contains.expected_attempts(4);
+    assert!((anywhere - 65536.0 / 61.0).abs() < 10.0, "{}", anywhere);
+    assert!(Placement::Contains.expected_attempts(40).is_finite());
+}
+
+#[test]
+fn prices_each_placement_and_suggests_a_prefix() {
+    let (digits, options) = advise::advise("cafe", 1000.0, U256::one(), 1, Some(U256::from(100_000))).unwrap();
+    assert_eq!(digits, "cafe");
+    assert_eq!(options.iter().map(|option| option.placement).collect::<Vec<_>>(), Placement::ALL);
+    assert!(options.iter().filter(|option| option.placement.minable()).count() == 1);
+    assert_eq!(options[0].fee_overhead, Some(U256::from(100_000u64 * 65536)));
+
+    assert_eq!(advise::longest_prefix_within("cafe", 1000.0, 3600.0), Some("cafe"));
+    assert_eq!(advise::longest_prefix_within("cafe", 1000.0, 10.0), Some("caf"));
+    assert_eq!(advise::longest_prefix_within("cafe", 1.0, 1.0), None);
+}
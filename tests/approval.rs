@@ -0,0 +1,50 @@
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::H256;
+use ethers::utils::hash_message;
+use find_tx_hash_prefix::approval::{self, Decision};
+use serde_json::json;
+
+fn wallet(seed: u8) -> LocalWallet {
+    LocalWallet::from_bytes(&[seed; 32]).unwrap()
+}
+
+fn approve(approver: &LocalWallet, message: &str) -> serde_json::Value {
+    let signature = approver.sign_hash(hash_message(message)).unwrap();
+    json!({ "status": "approved", "approver": format!("{:?}", approver.address()), "signature": signature.to_string() })
+}
+
+#[test]
+fn accepts_only_a_listed_approvers_signature_over_the_exact_transaction() {
+    let (deployer, approver, outsider) = (wallet(1), wallet(2), wallet(3));
+    let message = approval::approval_message(8453, H256::repeat_byte(0xab));
+    let approvers = [approver.address()];
+
+    let decision = approval::decide(&approve(&approver, &message), &message, &approvers, deployer.address()).unwrap();
+    assert_eq!(decision, Decision::Approved(approver.address()));
+
+    let other_tx = approval::approval_message(8453, H256::repeat_byte(0xcd));
+    assert!(approval::decide(&approve(&approver, &other_tx), &message, &approvers, deployer.address()).is_err());
+    assert!(approval::decide(&approve(&outsider, &message), &message, &approvers, deployer.address()).is_err());
+    let mut forged = approve(&outsider, &message);
+    forged["approver"] = json!(format!("{:?}", approver.address()));
+    assert!(approval::decide(&forged, &message, &approvers, deployer.address()).is_err());
+}
+
+#[test]
+fn the_deployer_cannot_approve_itself() {
+    let deployer = wallet(1);
+    let message = approval::approval_message(1, H256::zero());
+    let approvers = [deployer.address()];
+    assert!(approval::decide(&approve(&deployer, &message), &message, &approvers, deployer.address()).is_err());
+}
+
+#[test]
+fn reads_pending_and_rejected_answers() {
+    let deployer = wallet(1).address();
+    assert_eq!(approval::decide(&json!({ "status": "pending" }), "", &[], deployer).unwrap(), Decision::Pending);
+    assert_eq!(
+        approval::decide(&json!({ "status": "rejected", "reason": "wrong calldata" }), "", &[], deployer).unwrap(),
+        Decision::Rejected("wrong calldata".to_string())
+    );
+    assert!(approval::decide(&json!({ "status": "maybe" }), "", &[], deployer).is_err());
+}
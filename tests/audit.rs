@@ -0,0 +1,64 @@
+mod common;
+
+use common::{params, template, MockSigner};
+use ethers::core::rand::{rngs::StdRng, SeedableRng};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::Bytes;
+use find_tx_hash_prefix::audit::{self, AuditReport};
+use find_tx_hash_prefix::search::{CandidateSigner, Salt};
+use std::sync::Arc;
+
+/// Reports every hash with its first digit forced to 0, the kind of encoder bug that makes a
+/// search look suspiciously lucky.
+struct LuckySigner;
+
+impl CandidateSigner for LuckySigner {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        let (signed_rlp, mut tx_hash) = MockSigner.sign_candidate(tx)?;
+        tx_hash[0] &= 0x0f;
+        Ok((signed_rlp, tx_hash))
+    }
+}
+
+#[test]
+fn a_correct_search_passes() {
+    let mut params = params(template(), "0x0ba", 1);
+    params.salt = Salt::Padding;
+    let report = audit::run(&params, 2_000, &mut StdRng::seed_from_u64(7));
+    assert!(report.passed(), "{}", report);
+    assert_eq!(report.prefix_matches.len(), 3);
+    assert_eq!(report.first_digit_counts.iter().sum::<u64>(), 2_000);
+    assert!(report.to_string().contains("3 digit(s)"));
+}
+
+#[test]
+fn flags_hashes_that_are_not_derived_from_the_signed_bytes() {
+    let mut params = params(template(), "0x0ba", 1);
+    params.signer = Arc::new(LuckySigner);
+    let report = audit::run(&params, 1_000, &mut StdRng::seed_from_u64(7));
+    assert!(!report.passed());
+    assert!(report.hash_mismatches > 900);
+    assert!(report.rates()[0].suspicious());
+    assert!(report.first_digit_chi_square() > audit::CHI_SQUARE_LIMIT);
+    assert!(report.first_problem.unwrap().contains("is reported as hash 0x0"));
+}
+
+#[test]
+fn merges_reports_from_several_workers() {
+    let params = params(template(), "0x0ba", 1);
+    let parts: Vec<AuditReport> = (0..3).map(|seed| audit::run(&params, 200, &mut StdRng::seed_from_u64(seed))).collect();
+    let first_digit_zeros: u64 = parts.iter().map(|part| part.first_digit_counts[0]).sum();
+    let merged = AuditReport::merge(parts);
+    assert_eq!(merged.samples, 600);
+    assert_eq!(merged.prefix_matches.len(), 3);
+    assert_eq!(merged.first_digit_counts[0], first_digit_zeros);
+}
+
+#[test]
+fn scores_deviations_from_the_theoretical_rate() {
+    assert_eq!(audit::z_score(100, 1_600, 1.0 / 16.0), 0.0);
+    assert!((audit::z_score(130, 1_600, 1.0 / 16.0) - 3.098).abs() < 0.001);
+    assert_eq!(audit::chi_square(&[10; 16]), 0.0);
+    assert_eq!(audit::chi_square(&[0; 16]), 0.0);
+    assert_eq!(audit::chi_square(&[16, 0]), 16.0);
+}
@@ -0,0 +1,24 @@
+use find_tx_hash_prefix::brand::associations;
+use serde_json::json;
+
+#[test]
+fn keeps_named_items_sharing_the_prefix() {
+    let response = json!({ "items": [
+        { "type": "contract", "name": "Some Vault", "address_hash": "0xBA5E000000000000000000000000000000000001" },
+        { "type": "token", "name": "Base Token", "symbol": "BT", "address_hash": "0xba5e000000000000000000000000000000000002", "exchange_rate": "1.02" },
+        { "type": "contract", "name": "Base Token", "address_hash": "0xba5e000000000000000000000000000000000002" },
+        { "type": "address", "name": null, "address_hash": "0xba5e000000000000000000000000000000000003" },
+        { "type": "token", "name": "Baseline", "address_hash": "0x1234000000000000000000000000000000000004" },
+        { "type": "label", "name": "Bridge", "address": "0xba5e000000000000000000000000000000000005" },
+    ] });
+    let found = associations(&response, "0xBA5E");
+    let names: Vec<&str> = found.iter().map(|association| association.name.as_str()).collect();
+    assert_eq!(names, ["Base Token (BT)", "Bridge", "Some Vault"]);
+    assert!(found[0].prominent && found[1].prominent && !found[2].prominent);
+}
+
+#[test]
+fn reads_empty_results() {
+    assert!(associations(&json!({ "items": [] }), "ba5e").is_empty());
+    assert!(associations(&json!({}), "ba5e").is_empty());
+}
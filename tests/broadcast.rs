@@ -0,0 +1,61 @@
+use ethers::types::{Block, Bytes, H256};
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::broadcast::{self, Broadcaster, FileOnly, Submission};
+use find_tx_hash_prefix::rpc::LatestBlock;
+use std::collections::HashSet;
+use std::time::Duration;
+
+#[tokio::test]
+async fn file_only_appends_without_sending() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("signed_transactions.txt");
+    let broadcaster = FileOnly::new(path.clone(), None).unwrap();
+    assert!(broadcast::read_saved(&path, None).unwrap().is_empty());
+
+    let first = Bytes::from(vec![0x02, 0xf8, 0x01]);
+    let second = Bytes::from(vec![0x02, 0xf8, 0x02]);
+    for raw_tx in [&first, &second] {
+        let submission = broadcaster.submit(raw_tx).await.unwrap();
+        assert_eq!(submission, Submission::Saved { tx_hash: H256::from(keccak256(raw_tx)), path: path.clone() });
+    }
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "0x02f801\n0x02f802\n");
+    assert_eq!(broadcast::read_saved(&path, None).unwrap(), [first, second]);
+}
+
+#[tokio::test]
+async fn file_only_encrypts_with_the_journal_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("signed_transactions.txt");
+    std::fs::write(&path, "0x02f801\n").unwrap();
+    let broadcaster = FileOnly::new(path.clone(), Some("correct horse")).unwrap();
+    let raw_tx = Bytes::from(vec![0x02, 0xf8, 0x02]);
+    broadcaster.submit(&raw_tx).await.unwrap();
+
+    let on_disk = std::fs::read_to_string(&path).unwrap();
+    assert!(!on_disk.contains("02f802"), "signed transaction written in the clear: {}", on_disk);
+    assert_eq!(broadcast::read_saved(&path, Some("correct horse")).unwrap(), [Bytes::from(vec![0x02, 0xf8, 0x01]), raw_tx.clone()]);
+    assert!(broadcast::read_saved(&path, None).unwrap_err().to_string().contains("JOURNAL_PASSPHRASE"));
+    assert!(broadcast::read_saved(&path, Some("wrong")).is_err());
+
+    // An encrypted file is never appended to in the clear, and later lines reuse its salt
+    let e = FileOnly::new(path.clone(), None).err().expect("an encrypted file needs its passphrase to append");
+    assert!(e.to_string().contains("JOURNAL_PASSPHRASE"));
+    FileOnly::new(path.clone(), Some("correct horse")).unwrap().submit(&raw_tx).await.unwrap();
+    let salts: HashSet<String> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .map(|line| line["sealed"]["salt"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(salts.len(), 1);
+}
+
+#[test]
+fn latest_block_is_only_trusted_while_fresh() {
+    let latest = LatestBlock::default();
+    assert!(latest.fresh(Duration::from_secs(60)).is_none());
+    latest.update(Block { base_fee_per_gas: Some(7.into()), ..Default::default() });
+    assert_eq!(latest.fresh(Duration::from_secs(60)).unwrap().base_fee_per_gas, Some(7.into()));
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(latest.fresh(Duration::from_millis(1)).is_none());
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{template, MockSigner};
+use ethers::types::{H256, U256};
+use find_tx_hash_prefix::campaign::{self, Slot};
+use find_tx_hash_prefix::journal::JournalEntry;
+use find_tx_hash_prefix::search::CandidateSigner;
+
+fn signed_entry(tx: &ethers::types::transaction::eip1559::Eip1559TransactionRequest) -> JournalEntry {
+    let (raw_tx, tx_hash) = MockSigner.sign_candidate(tx).unwrap();
+    JournalEntry {
+        timestamp: 0,
+        chain_id: 1,
+        nonce: tx.nonce.unwrap(),
+        tx_hash: H256(tx_hash),
+        raw_tx,
+        max_fee_per_gas: U256::zero(),
+        max_priority_fee_per_gas: U256::zero(),
+        job_id: None,
+        template: None,
+    }
+}
+
+#[test]
+fn plans_every_pattern_nonce_by_nonce() {
+    let slots = campaign::plan(&["0xDEAD".to_string(), "beef".to_string()], 7.into(), 2);
+    let slot = |pattern: &str, nonce: u64| Slot { pattern: pattern.to_string(), nonce: nonce.into() };
+    assert_eq!(slots, [slot("dead", 7), slot("beef", 7), slot("dead", 8), slot("beef", 8)]);
+}
+
+#[test]
+fn journal_entries_fill_slots_for_the_same_transaction_only() {
+    let mut salted = template();
+    salted.max_fee_per_gas = Some(2_000_000.into());
+    let mut data = salted.data.clone().unwrap().to_vec();
+    data.extend_from_slice(&42u64.to_be_bytes());
+    salted.data = Some(data.into());
+    let entry = signed_entry(&salted);
+    let pattern = hex::encode(&entry.tx_hash[..1]);
+
+    let slot = Slot { pattern: pattern.clone(), nonce: 7.into() };
+    assert!(campaign::fills(&entry, &slot, &template()));
+    assert!(!campaign::fills(&entry, &Slot { pattern, nonce: 8.into() }, &template()));
+    let other_pattern = if slot.pattern == "00" { "11" } else { "00" };
+    assert!(!campaign::fills(&entry, &Slot { pattern: other_pattern.into(), ..slot.clone() }, &template()));
+
+    let mut other_code = template();
+    other_code.data = Some(vec![0x60, 0x00].into());
+    assert!(!campaign::fills(&entry, &slot, &other_code));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{journal, params, template};
+use ethers::types::{Address, U256};
+use find_tx_hash_prefix::checkpoint::{self, Checkpoint, TemplateFingerprint};
+use find_tx_hash_prefix::search::{self, FeeClaims};
+use find_tx_hash_prefix::stats::SearchStats;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn resumes_only_the_same_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("search_checkpoint.json");
+    let fingerprint = TemplateFingerprint::of(&params(template(), "0xdead", 1), Address::zero());
+    assert_eq!(checkpoint::resume(&path, &fingerprint, false).unwrap().ranges(), []);
+
+    let claims = FeeClaims::from_ranges([(U256::from(100), U256::from(200)), (U256::from(200), U256::from(250))]);
+    Checkpoint::new(fingerprint.clone(), &claims).save(&path).unwrap();
+    let resumed = checkpoint::resume(&path, &fingerprint, false).unwrap();
+    assert_eq!(resumed.ranges(), [(U256::from(100), U256::from(250))]);
+    assert_eq!(resumed.fee_count(U256::one()), U256::from(150));
+
+    let mut changed = template();
+    changed.nonce = Some(8.into());
+    let changed = TemplateFingerprint::of(&params(changed, "0xdead", 1), Address::zero());
+    let error = checkpoint::resume(&path, &changed, false).unwrap_err().to_string();
+    assert!(error.contains("nonce: \"0x7\" -> \"0x8\""), "{}", error);
+    assert!(error.contains("--force-restart"), "{}", error);
+    assert_eq!(checkpoint::resume(&path, &changed, true).unwrap().ranges(), []);
+}
+
+#[test]
+fn skips_fees_swept_before_the_checkpoint() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0", 1);
+    let first_fee = params.base_fee_start + params.priority_fee;
+    let swept_until = first_fee + 5_000;
+    params.claims = Some(Arc::new(FeeClaims::from_ranges([(first_fee, swept_until)])));
+
+    let stats = Arc::new(SearchStats::new(1));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime
+        .block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal))
+        .unwrap()
+        .expect("a one-digit prefix is always found");
+    assert!(found_match.max_fee_per_gas >= swept_until);
+    assert_eq!(stats.total_duplicates_skipped(), 5_000);
+}
@@ -0,0 +1,28 @@
+use ethers::types::Address;
+use find_tx_hash_prefix::clone;
+
+const IMPLEMENTATION: &str = "0xbebebebebebebebebebebebebebebebebebebebe";
+
+#[test]
+fn builds_the_eip_1167_bytecode() {
+    let implementation: Address = IMPLEMENTATION.parse().unwrap();
+    // The reference bytecode from the EIP
+    assert_eq!(
+        hex::encode(clone::runtime_code(implementation)),
+        "363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3"
+    );
+    let init_code = clone::init_code(implementation);
+    assert_eq!(init_code.len(), 10 + clone::RUNTIME_SIZE);
+    assert_eq!(hex::encode(&init_code[..10]), "3d602d80600a3d3981f3");
+    assert_eq!(clone::RUNTIME_SIZE, 45);
+}
+
+#[test]
+fn recognizes_a_deployed_proxy() {
+    let implementation: Address = IMPLEMENTATION.parse().unwrap();
+    let runtime = clone::runtime_code(implementation);
+    assert_eq!(clone::implementation_of(&runtime), Some(implementation));
+    assert_eq!(clone::implementation_of(&clone::init_code(implementation)), None);
+    assert_eq!(clone::implementation_of(&runtime[..44]), None);
+    assert_eq!(clone::implementation_of(&[]), None);
+}
@@ -0,0 +1,40 @@
+use find_tx_hash_prefix::codesize::{self, SplitPlan, MAX_CODE_SIZE};
+
+/// Solidity init code ending in the epilogue that returns `size` bytes of runtime code.
+fn init_code(size: u16, push0: bool) -> Vec<u8> {
+    let mut code = vec![0x60, 0x80, 0x60, 0x40, 0x52, 0x34, 0x80, 0x15];
+    code.extend([0x61, (size >> 8) as u8, size as u8, 0x80, 0x61, 0x00, 0x20]);
+    let zero: &[u8] = if push0 { &[0x5f] } else { &[0x60, 0x00] };
+    code.extend(zero);
+    code.push(0x39);
+    code.extend(zero);
+    code.push(0xf3);
+    code
+}
+
+#[test]
+fn reads_the_runtime_size_from_solidity_init_code() {
+    assert_eq!(codesize::runtime_size_hint(&init_code(30_000, true)), Some(30_000));
+    assert_eq!(codesize::runtime_size_hint(&init_code(1_234, false)), Some(1_234));
+    assert_eq!(codesize::runtime_size_hint(&[0x60, 0x80, 0x60, 0x40, 0x52]), None);
+}
+
+#[test]
+fn plans_a_split_only_for_code_over_the_limit() {
+    assert_eq!(SplitPlan::check(1_000, Some(MAX_CODE_SIZE), false, MAX_CODE_SIZE), None);
+    let plan = SplitPlan::check(1_000, Some(MAX_CODE_SIZE * 2 + 1), false, MAX_CODE_SIZE).unwrap();
+    assert_eq!(plan.pieces(), Some(3));
+    assert!(plan.to_string().contains("EIP-170"));
+
+    let reported = SplitPlan::check(1_000, None, true, MAX_CODE_SIZE).unwrap();
+    assert_eq!(reported.pieces(), None);
+    let init_code = SplitPlan::check(MAX_CODE_SIZE * 2 + 1, None, false, MAX_CODE_SIZE).unwrap();
+    assert!(init_code.to_string().contains("EIP-3860"));
+}
+
+#[test]
+fn recognizes_node_errors_for_oversized_code() {
+    assert!(codesize::is_code_size_error("execution reverted: max code size exceeded"));
+    assert!(codesize::is_code_size_error("EVM error CreateContractSizeLimit"));
+    assert!(!codesize::is_code_size_error("execution reverted"));
+}
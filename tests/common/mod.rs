@@ -0,0 +1,98 @@
+//! Key-free stand-ins for the search engine's dependencies.
+
+// Each test crate uses its own subset of these
+#![allow(dead_code)]
+
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, Signature, U256};
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::journal::Journal;
+use find_tx_hash_prefix::limits::ResourceLimits;
+use find_tx_hash_prefix::search::{CandidateSigner, MatchPolicy, Salt, SearchParams, BATCH_SIZE, DEFAULT_MAX_FAILURE_RATE};
+use find_tx_hash_prefix::stats::Clock;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// x coordinate of the secp256k1 generator, so `r` is always a point on the curve.
+const GENERATOR_X: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+/// Signs with a signature derived from the sighash instead of a key: deterministic, cheap, and
+/// encoded and decoded exactly like a real signature, though it recovers to no particular address.
+#[derive(Debug, Default)]
+pub struct MockSigner;
+
+impl CandidateSigner for MockSigner {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        let typed_tx = TypedTransaction::Eip1559(tx.clone());
+        let sighash = typed_tx.sighash();
+        // 128 bits keeps `s` nonzero and in the lower half of the curve order
+        let s = U256::from_big_endian(&sighash.as_bytes()[..16]) | U256::one();
+        let signature = Signature { r: U256::from_str_radix(GENERATOR_X, 16)?, s, v: 0 };
+        let signed_rlp = typed_tx.rlp_signed(&signature);
+        let tx_hash = keccak256(&signed_rlp);
+        Ok((signed_rlp, tx_hash))
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct ManualClock(Mutex<Duration>);
+
+impl ManualClock {
+    pub fn advance(&self, by: Duration) {
+        *self.0.lock() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn elapsed(&self) -> Duration {
+        *self.0.lock()
+    }
+}
+
+/// A contract deployment template on chain 1 at nonce 7.
+pub fn template() -> Eip1559TransactionRequest {
+    let mut tx = Eip1559TransactionRequest::new();
+    tx.data = Some(vec![0x60, 0x80, 0x60, 0x40, 0x52].into());
+    tx.nonce = Some(7.into());
+    tx.gas = Some(100_000.into());
+    tx.chain_id = Some(1u64.into());
+    tx
+}
+
+/// Search parameters for `template` with the mock signer.
+pub fn params(template: Eip1559TransactionRequest, hash_prefix: &str, thread_count: usize) -> SearchParams {
+    SearchParams {
+        signer: Arc::new(MockSigner),
+        template,
+        hash_prefix: hash_prefix.to_string(),
+        patterns: None,
+        base_fee_start: 1_000_000.into(),
+        priority_fee: 1_000.into(),
+        max_fee_per_gas: None,
+        fee_step: U256::one(),
+        salt: Salt::None,
+        priority_fee_lanes: Vec::new(),
+        match_policy: MatchPolicy::First,
+        max_retained_matches: 16,
+        max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+        batch_size: BATCH_SIZE,
+        thread_count,
+        job_id: None,
+        claims: None,
+        sampler: None,
+        throttle: None,
+        stop_policy: None,
+        verifier: None,
+        limits: ResourceLimits::default(),
+    }
+}
+
+/// A journal in a fresh temporary directory, which is removed when the guard is dropped.
+pub fn journal() -> (Arc<Journal>, tempfile::TempDir) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let journal = Journal::open(dir.path().join("found_candidates.jsonl")).expect("journal");
+    (Arc::new(journal), dir)
+}
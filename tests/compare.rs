@@ -0,0 +1,78 @@
+mod common;
+
+use common::{params, template};
+use ethers::types::U256;
+use find_tx_hash_prefix::compare::{self, Comparison, Route};
+use find_tx_hash_prefix::difficulty::Difficulty;
+use find_tx_hash_prefix::overhead::{EffectiveCost, MarketFees};
+use find_tx_hash_prefix::search::{self, Salt};
+
+fn route(mining_seconds: f64, inclusion_blocks: f64, max_fee_per_gas: u64) -> Route {
+    Route {
+        mining_seconds,
+        inclusion_blocks: Some(inclusion_blocks),
+        cost: EffectiveCost::new(50_000.into(), 60_000.into(), max_fee_per_gas.into(), 1_000.into(), 900_000.into()),
+    }
+}
+
+#[test]
+fn a_match_is_expected_past_the_mean_worker_start_capped_by_the_ceiling() {
+    let mut params = params(template(), "0x00", 1);
+    let difficulty = Difficulty::from_attempts("0x00".into(), 256.0, 1_000.0, params.fee_step, 1);
+    let first_fee = search::first_fee_for_salt(&params, 0);
+    assert_eq!(compare::expected_match_fee(&params, &difficulty), first_fee + 256);
+
+    params.thread_count = 3;
+    let spread = Difficulty::from_attempts("0x00".into(), 256.0, 1_000.0, params.fee_step, 3);
+    let expected = first_fee + search::worker_spacing(&params) + U256::from(85);
+    assert_eq!(compare::expected_match_fee(&params, &spread), expected);
+
+    // Salted workers all start at the same fee
+    params.salt = Salt::Padding;
+    assert_eq!(compare::expected_match_fee(&params, &spread), first_fee + 85);
+
+    params.max_fee_per_gas = Some(first_fee + 10);
+    assert_eq!(compare::expected_match_fee(&params, &spread), first_fee + 10);
+}
+
+#[test]
+fn lanes_are_expected_to_tip_their_average() {
+    let mut params = params(template(), "0x0", 2);
+    assert_eq!(compare::expected_tip(&params), params.priority_fee);
+    params.priority_fee_lanes = vec![1_000.into(), 2_000.into(), 6_000.into()];
+    assert_eq!(compare::expected_tip(&params), U256::from(3_000));
+}
+
+#[test]
+fn the_vanity_route_mines_for_the_expected_time() {
+    let params = params(template(), "0x00", 1);
+    let difficulty = Difficulty::from_attempts("0x00".into(), 256.0, 128.0, params.fee_step, 1);
+    let market = MarketFees { base_fee: 900_000.into(), max_fee_per_gas: 1_800_000.into(), priority_fee: 1_000.into() };
+    let vanity = compare::vanity(&params, &difficulty, 50_000.into(), &market, Some(1.5));
+    assert_eq!(vanity.mining_seconds, 2.0);
+    assert_eq!(vanity.cost.max_fee_per_gas, compare::expected_match_fee(&params, &difficulty));
+    assert_eq!(compare::immediate(50_000.into(), 60_000.into(), &market, Some(1.0)).mining_seconds, 0.0);
+}
+
+#[test]
+fn reports_the_time_and_cost_the_vanity_hash_adds() {
+    let comparison = Comparison { immediate: route(0.0, 1.0, 2_000_000), vanity: route(30.0, 3.0, 2_500_000), block_seconds: Some(2.0) };
+    assert_eq!(comparison.extra_seconds(), 34.0);
+    assert_eq!(comparison.vanity.total_seconds(comparison.block_seconds), Some(36.0));
+    assert_eq!(comparison.worst_case_premium_percent(), 25.0);
+    // Both pay the base fee plus the same tip, so only the worst case differs
+    assert_eq!(comparison.expected_premium_percent(), 0.0);
+
+    let untimed = Comparison { block_seconds: None, ..comparison };
+    assert_eq!(untimed.extra_seconds(), 30.0);
+    assert_eq!(untimed.vanity.total_seconds(None), None);
+}
+
+#[test]
+fn an_unknown_inclusion_is_left_out_of_the_times() {
+    let mut comparison = Comparison { immediate: route(0.0, 1.0, 2_000_000), vanity: route(30.0, 3.0, 2_500_000), block_seconds: Some(2.0) };
+    comparison.vanity.inclusion_blocks = None;
+    assert_eq!(comparison.extra_seconds(), 30.0);
+    assert_eq!(comparison.vanity.total_seconds(comparison.block_seconds), None);
+    assert_eq!(comparison.immediate.total_seconds(comparison.block_seconds), Some(2.0));
+}
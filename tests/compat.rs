@@ -0,0 +1,76 @@
+mod common;
+
+use common::template;
+use ethers::providers::{JsonRpcError, MockResponse, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, U256};
+use find_tx_hash_prefix::compat::{self, call_object, is_unsupported, simulation_verdict, NodeCheck, NodeVerdict, MAX_POOL_TX_SIZE};
+use serde_json::json;
+
+fn tx() -> TypedTransaction {
+    let mut tx = template();
+    tx.max_fee_per_gas = Some(2_000_000.into());
+    tx.max_priority_fee_per_gas = Some(1_000.into());
+    tx.into()
+}
+
+#[test]
+fn parses_node_check() {
+    assert_eq!("auto".parse::<NodeCheck>().unwrap(), NodeCheck::Auto);
+    assert_eq!("1".parse::<NodeCheck>().unwrap(), NodeCheck::Auto);
+    assert_eq!("simulate".parse::<NodeCheck>().unwrap(), NodeCheck::Simulate);
+    assert_eq!("estimate".parse::<NodeCheck>().unwrap(), NodeCheck::Estimate);
+    assert!("yes".parse::<NodeCheck>().is_err());
+}
+
+#[test]
+fn reads_simulation_results() {
+    let ok = json!([{ "calls": [{ "status": "0x1", "gasUsed": "0xea60" }] }]);
+    assert_eq!(simulation_verdict(&ok), NodeVerdict::Accepted { method: "eth_simulateV1", gas_used: Some(60_000.into()) });
+
+    let reverted = json!([{ "calls": [{ "status": "0x0", "error": { "message": "execution reverted" } }] }]);
+    assert_eq!(simulation_verdict(&reverted), NodeVerdict::Rejected { method: "eth_simulateV1", reason: "reverted: execution reverted".into() });
+
+    assert!(matches!(simulation_verdict(&json!([])), NodeVerdict::Rejected { .. }));
+}
+
+#[test]
+fn calls_carry_the_signed_fields() {
+    let call = call_object(&tx(), Address::repeat_byte(1));
+    assert_eq!(call["from"], json!(Address::repeat_byte(1)));
+    assert_eq!(call["nonce"], json!(U256::from(7)));
+    assert_eq!(call["maxFeePerGas"], json!(U256::from(2_000_000)));
+    assert_eq!(call["input"], call["data"]);
+}
+
+#[test]
+fn tells_missing_methods_from_rejections() {
+    assert!(is_unsupported(-32601, "the method eth_simulateV1 does not exist/is not available"));
+    assert!(is_unsupported(-32000, "method not supported"));
+    assert!(!is_unsupported(-32000, "max fee per gas less than block base fee"));
+}
+
+#[tokio::test]
+async fn rejects_oversized_transactions_without_asking() {
+    let (provider, _mock) = Provider::mocked();
+    let verdict = compat::check(&provider, NodeCheck::Auto, &tx(), Address::zero(), MAX_POOL_TX_SIZE + 1).await;
+    assert!(matches!(verdict, NodeVerdict::Rejected { method: "size check", .. }));
+}
+
+#[tokio::test]
+async fn falls_back_to_estimate_gas() {
+    let (provider, mock) = Provider::mocked();
+    // Responses are taken from the back
+    mock.push(U256::from(60_000)).unwrap();
+    mock.push_response(MockResponse::Error(JsonRpcError { code: -32601, message: "method not found".into(), data: None }));
+    let verdict = compat::check(&provider, NodeCheck::Auto, &tx(), Address::zero(), 200).await;
+    assert_eq!(verdict, NodeVerdict::Accepted { method: "eth_estimateGas", gas_used: Some(60_000.into()) });
+}
+
+#[tokio::test]
+async fn reports_node_rejections() {
+    let (provider, mock) = Provider::mocked();
+    mock.push_response(MockResponse::Error(JsonRpcError { code: -38011, message: "max fee per gas less than block base fee".into(), data: None }));
+    let verdict = compat::check(&provider, NodeCheck::Auto, &tx(), Address::zero(), 200).await;
+    assert_eq!(verdict, NodeVerdict::Rejected { method: "eth_simulateV1", reason: "max fee per gas less than block base fee".into() });
+}
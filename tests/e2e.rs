@@ -0,0 +1,10 @@
+use find_tx_hash_prefix::e2e;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deploys_a_vanity_transaction_on_anvil() {
+    if !e2e::anvil_available() {
+        eprintln!("skipping: anvil is not installed");
+        return;
+    }
+    e2e::run().await.unwrap();
+}
@@ -0,0 +1,52 @@
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::{Address, U256};
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::envelope::{decode_signed, signer_for, Cip64, EnvelopeSigner, CIP64_TX_TYPE};
+use find_tx_hash_prefix::search::CandidateSigner;
+use find_tx_hash_prefix::verify::verify;
+
+const CELO: u64 = 42220;
+
+fn celo_tx() -> Eip1559TransactionRequest {
+    Eip1559TransactionRequest::new()
+        .chain_id(CELO)
+        .nonce(7)
+        .max_fee_per_gas(25_000_000_000u64)
+        .max_priority_fee_per_gas(1_000_000_000u64)
+        .gas(60_000)
+        .data(vec![0x60, 0x80, 0x60, 0x40])
+}
+
+#[test]
+fn cip64_round_trips_and_verifies() {
+    let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng()).with_chain_id(CELO);
+    let fee_currency: Address = "0x765DE816845861e75A25fCA122bb6898B8B1282a".parse().unwrap();
+    let signer = EnvelopeSigner { wallet: wallet.clone(), envelope: Cip64 { fee_currency } };
+
+    let (raw, hash) = signer.sign_candidate(&celo_tx()).unwrap();
+    assert_eq!(raw[0], CIP64_TX_TYPE);
+    assert_eq!(hash, keccak256(&raw));
+
+    let (envelope, _, _) = Cip64::decode_signed(&raw).unwrap();
+    assert_eq!(envelope.fee_currency, fee_currency);
+    let decoded = decode_signed(&raw).unwrap();
+    assert_eq!(decoded.reencoded, raw);
+    assert_eq!(decoded.signature.recover(decoded.sighash).unwrap(), wallet.address());
+
+    let verified = verify(&raw, "", CELO, U256::from(7)).unwrap();
+    assert_eq!(verified.from, wallet.address());
+    // The fee currency is part of the signed payload, so it changes the hash being mined
+    let other = EnvelopeSigner { wallet: wallet.clone(), envelope: Cip64 { fee_currency: Address::zero() } };
+    assert_ne!(other.sign_candidate(&celo_tx()).unwrap().1, hash);
+}
+
+#[test]
+fn fee_currency_needs_a_chain_that_supports_it() {
+    let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+    std::env::set_var("FEE_CURRENCY_10", "0x765DE816845861e75A25fCA122bb6898B8B1282a");
+    assert!(signer_for(wallet.clone(), 10).is_err());
+    std::env::set_var("FEE_CURRENCY_42220", "0x765DE816845861e75A25fCA122bb6898B8B1282a");
+    let (raw, _) = signer_for(wallet, CELO).unwrap().sign_candidate(&celo_tx()).unwrap();
+    assert_eq!(raw[0], CIP64_TX_TYPE);
+}
@@ -0,0 +1,57 @@
+use ethers::providers::ProviderError;
+use find_tx_hash_prefix::calldata::Parameters;
+use find_tx_hash_prefix::config::TxSpec;
+use find_tx_hash_prefix::exit::{self, Status};
+use find_tx_hash_prefix::fees::FeeProfile;
+use std::collections::HashSet;
+
+#[test]
+fn codes_are_distinct_and_zero_means_sent() {
+    let codes: HashSet<u8> = Status::ALL.iter().map(|status| status.code()).collect();
+    assert_eq!(codes.len(), Status::ALL.len());
+    assert_eq!(Status::Sent.code(), 0);
+    assert_eq!(Status::ConfigError.code(), 2);
+    assert_eq!(Status::NoMatch.code(), 5);
+}
+
+#[test]
+fn a_tag_survives_context_and_keeps_the_message() {
+    let tagged = exit::with_status(eyre::eyre!("relay refused the bundle"), Status::BroadcastFailed);
+    assert_eq!(tagged.to_string(), "relay refused the bundle");
+    let wrapped = tagged.wrap_err("chain 8453");
+    assert_eq!(exit::classify(&wrapped), Status::BroadcastFailed);
+    assert!(format!("{:?}", wrapped).contains("relay refused the bundle"));
+}
+
+#[test]
+fn infers_the_status_from_the_error_chain() {
+    let rpc = eyre::Report::new(ProviderError::CustomError("connection refused".to_string())).wrap_err("fetching the nonce");
+    assert_eq!(exit::classify(&rpc), Status::RpcError);
+    let missing = eyre::Report::new(std::env::VarError::NotPresent);
+    assert_eq!(exit::classify(&missing), Status::ConfigError);
+    assert_eq!(exit::classify(&eyre::eyre!("something else")), Status::Error);
+}
+
+#[test]
+fn invalid_settings_are_configuration_errors() {
+    std::env::set_var("FEE_STEP_424242", "0");
+    let e = FeeProfile::from_env(424242).unwrap_err();
+    assert_eq!(exit::classify(&e), Status::ConfigError);
+    assert!(e.to_string().contains("FEE_STEP"));
+}
+
+#[test]
+fn a_missing_or_invalid_transfer_recipient_is_a_configuration_error() {
+    std::env::set_var("TRANSFER_AMOUNT", "1");
+    std::env::remove_var("TO_ADDRESS");
+    let e = TxSpec::from_env_with_gas_limit(&Parameters::new(), 21_000.into()).err().expect("a transfer needs TO_ADDRESS");
+    assert_eq!(exit::classify(&e).code(), 2);
+    assert!(e.to_string().contains("TO_ADDRESS is required"));
+
+    std::env::set_var("TO_ADDRESS", "0xnot-an-address");
+    let e = TxSpec::from_env_with_gas_limit(&Parameters::new(), 21_000.into()).err().expect("TO_ADDRESS must parse");
+    assert_eq!(exit::classify(&e).code(), 2);
+    assert!(e.to_string().contains("invalid TO_ADDRESS"));
+    std::env::remove_var("TRANSFER_AMOUNT");
+    std::env::remove_var("TO_ADDRESS");
+}
@@ -0,0 +1,28 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::expiry::{base_fee_status, check, Expiry};
+use std::time::{Duration, Instant};
+
+#[test]
+fn expires_after_the_window_or_once_the_base_fee_passes_the_match() {
+    let found_at = Instant::now();
+    let window = Duration::from_secs(600);
+    let max_fee = U256::from(2_000_000_000u64);
+
+    assert_eq!(check(found_at, found_at + Duration::from_secs(599), window, Some(max_fee), max_fee), None);
+    assert_eq!(check(found_at, found_at + window, window, None, max_fee), Some(Expiry::Elapsed(window)));
+    // A base fee equal to the max fee can still be included
+    let above = max_fee + 1;
+    assert_eq!(
+        check(found_at, found_at, window, Some(above), max_fee),
+        Some(Expiry::BaseFeeAbove { base_fee: above, max_fee_per_gas: max_fee })
+    );
+    assert_eq!(Expiry::Elapsed(window).to_string(), "not confirmed within 10 min");
+}
+
+#[test]
+fn describes_whether_the_match_is_still_includable() {
+    let gwei = |n: u64| U256::from(n) * 1_000_000_000u64;
+    assert_eq!(base_fee_status(gwei(10), gwei(25)), "base fee 10 gwei, max fee 25 gwei: includable (2.5x headroom)");
+    assert_eq!(base_fee_status(gwei(30), gwei(25)), "base fee 30 gwei, max fee 25 gwei: not includable until the base fee drops");
+    assert_eq!(base_fee_status(U256::zero(), gwei(1)), "base fee 0 gwei, max fee 1 gwei: includable");
+}
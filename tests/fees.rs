@@ -0,0 +1,62 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::fees::FeeProfile;
+use find_tx_hash_prefix::units;
+
+#[test]
+fn rejects_a_priority_fee_above_the_max_fee() {
+    std::env::set_var("PRIORITY_FEE_434343", "2000");
+    std::env::set_var("MAX_FEE_PER_GAS_434343", "1000");
+    let e = FeeProfile::from_env(434343).unwrap_err();
+    assert!(e.to_string().contains("PRIORITY_FEE"), "{}", e);
+
+    std::env::set_var("PRIORITY_FEE_434344", "500");
+    std::env::set_var("MIN_PRIORITY_FEE_434344", "2000");
+    std::env::set_var("MAX_FEE_PER_GAS_434344", "1000");
+    let e = FeeProfile::from_env(434344).unwrap_err();
+    assert!(e.to_string().contains("MIN_PRIORITY_FEE"), "{}", e);
+
+    std::env::set_var("PRIORITY_FEE_434345", "1000");
+    std::env::set_var("MAX_FEE_PER_GAS_434345", "1000");
+    std::env::set_var("BASE_FEE_START_434345", "0");
+    assert!(FeeProfile::from_env(434345).is_ok());
+}
+
+#[test]
+fn reads_fees_with_units() {
+    std::env::set_var("BASE_FEE_START_434346", "0.018gwei");
+    std::env::set_var("PRIORITY_FEE_434346", "1_250_000");
+    std::env::set_var("MAX_FEE_PER_GAS_434346", "0.0000000001 eth");
+    let profile = FeeProfile::from_env(434346).unwrap();
+    assert_eq!(profile.base_fee_start, 18_000_000.into());
+    assert_eq!(profile.priority_fee, 1_250_000.into());
+    assert_eq!(profile.max_fee_per_gas, Some(100_000_000.into()));
+
+    std::env::set_var("MAX_FEE_PER_GAS_434347", "1.5gwie");
+    let e = format!("{:#}", FeeProfile::from_env(434347).unwrap_err());
+    assert!(e.contains("MAX_FEE_PER_GAS for chain 434347") && e.contains("gwei (1.5gwei)"), "{}", e);
+}
+
+#[test]
+fn warns_about_tiny_fees_without_a_unit() {
+    assert!(units::unitless_fee_warning("MAX_FEE_PER_GAS", "20", 20.into()).unwrap().contains("20gwei"));
+    assert!(units::unitless_fee_warning("MAX_FEE_PER_GAS", "20wei", 20.into()).is_none());
+    assert!(units::unitless_fee_warning("PRIORITY_FEE", "0", 0.into()).is_none());
+    assert!(units::unitless_fee_warning("MAX_FEE_PER_GAS", "18000000", 18_000_000.into()).is_none());
+}
+
+#[test]
+fn reads_amounts_from_json() {
+    #[derive(serde::Deserialize)]
+    struct Body {
+        #[serde(default, deserialize_with = "units::deserialize_optional_amount")]
+        fee: Option<U256>,
+    }
+    let fee = |json: &str| serde_json::from_str::<Body>(json).map(|body| body.fee);
+    assert_eq!(fee(r#"{"fee": "0x3e8"}"#).unwrap(), Some(1_000.into()));
+    assert_eq!(fee(r#"{"fee": 1000}"#).unwrap(), Some(1_000.into()));
+    assert_eq!(fee(r#"{"fee": "1.5gwei"}"#).unwrap(), Some(1_500_000_000u64.into()));
+    assert_eq!(fee(r#"{"fee": null}"#).unwrap(), None);
+    assert_eq!(fee(r#"{}"#).unwrap(), None);
+    assert!(fee(r#"{"fee": "1.5 gwie"}"#).is_err());
+    assert!(fee(r#"{"fee": [1]}"#).is_err());
+}
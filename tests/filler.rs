@@ -0,0 +1,24 @@
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::U256;
+use find_tx_hash_prefix::filler::{FillerPlan, FILLER_GAS};
+use find_tx_hash_prefix::overhead::MarketFees;
+use find_tx_hash_prefix::verify;
+
+#[test]
+fn signs_one_self_transfer_per_missing_nonce() {
+    let wallet = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+    let market = MarketFees { base_fee: 10.into(), max_fee_per_gas: 25.into(), priority_fee: 5.into() };
+    let plan = FillerPlan::new(8453, wallet.address(), 3.into(), 6.into(), &market);
+
+    assert_eq!(plan.count(), 3);
+    assert_eq!(plan.max_cost(), U256::from(FILLER_GAS * 25 * 3));
+    assert_eq!(plan.expected_cost(10.into()), U256::from(FILLER_GAS * 15 * 3));
+
+    let signed = plan.sign(&wallet).unwrap();
+    assert_eq!(signed.len(), 3);
+    for ((raw, hash), nonce) in signed.iter().zip(3u64..) {
+        let verified = verify::verify_for_broadcast(raw, *hash, wallet.address(), 8453, nonce.into()).unwrap();
+        assert_eq!(verified.tx.to_addr(), Some(&wallet.address()));
+        assert_eq!(verified.tx.value().copied().unwrap_or_default(), U256::zero());
+    }
+}
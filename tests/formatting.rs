@@ -0,0 +1,44 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::units::{format_count, format_duration, format_estimate, format_hashrate, format_wei, group_digits};
+
+#[test]
+fn groups_digits_in_threes() {
+    assert_eq!(group_digits("0", ","), "0");
+    assert_eq!(group_digits("999", ","), "999");
+    assert_eq!(group_digits("1000", ","), "1,000");
+    assert_eq!(group_digits("1234567.89", " "), "1 234 567.89");
+    assert_eq!(group_digits("-1234567", "."), "-1.234.567");
+    assert_eq!(group_digits("1234567", ""), "1234567");
+    assert_eq!(format_count(18_000_000u64), "18,000,000");
+    assert_eq!(format_wei(U256::from(1_500_000_000u64)), "1,500,000,000 wei");
+}
+
+#[test]
+fn estimates_switch_to_scientific_notation() {
+    assert_eq!(format_estimate(65_536.0), "65,536");
+    assert_eq!(format_estimate(16f64.powi(40)), "1.46e48");
+    assert_eq!(format_estimate(f64::INFINITY), "infinite");
+}
+
+#[test]
+fn hashrates_use_the_shortest_unit() {
+    assert_eq!(format_hashrate(0.0), "0 H/s");
+    assert_eq!(format_hashrate(950.4), "950 H/s");
+    assert_eq!(format_hashrate(12_345.0), "12.3 kH/s");
+    assert_eq!(format_hashrate(4_560_000.0), "4.56 MH/s");
+    assert_eq!(format_hashrate(250_000_000.0), "250 MH/s");
+    assert_eq!(format_hashrate(1.5e9), "1.50 GH/s");
+}
+
+#[test]
+fn durations_show_their_two_largest_units() {
+    assert_eq!(format_duration(0.25), "0.25s");
+    assert_eq!(format_duration(4.0), "4.0s");
+    assert_eq!(format_duration(42.0), "42s");
+    assert_eq!(format_duration(200.0), "3m 20s");
+    assert_eq!(format_duration(4_980.0), "1h 23m");
+    assert_eq!(format_duration(7_200.0), "2h");
+    assert_eq!(format_duration(2.0 * 86_400.0 + 5.0 * 3_600.0), "2d 5h");
+    assert_eq!(format_duration(3.0 * 365.25 * 86_400.0), "3.0 years");
+    assert_eq!(format_duration(f64::INFINITY), "forever");
+}
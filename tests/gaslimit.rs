@@ -0,0 +1,27 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::gaslimit::{bisect, with_margin};
+
+#[tokio::test]
+async fn bisects_to_the_smallest_working_limit() {
+    let mut calls = 0;
+    let minimal = bisect(20_999.into(), 30_000_000.into(), |gas| {
+        calls += 1;
+        async move { Ok(gas >= U256::from(57_321)) }
+    })
+    .await
+    .unwrap();
+    assert_eq!(minimal, Some(57_321.into()));
+    assert!(calls <= 26, "{} calls", calls);
+
+    let never = bisect(20_999.into(), 30_000_000.into(), |_| async { Ok(false) }).await.unwrap();
+    assert_eq!(never, None);
+    // The intrinsic cost alone can be enough
+    let transfer = bisect(20_999.into(), 60_000.into(), |gas| async move { Ok(gas >= U256::from(21_000)) }).await.unwrap();
+    assert_eq!(transfer, Some(21_000.into()));
+}
+
+#[test]
+fn margin_rounds_up() {
+    assert_eq!(with_margin(57_321.into(), 10), U256::from(63_054));
+    assert_eq!(with_margin(21_000.into(), 0), U256::from(21_000));
+}
@@ -0,0 +1,52 @@
+use find_tx_hash_prefix::gastrend::{FeeSample, GasHistory, Trend, MIN_WINDOW_SAMPLES};
+
+/// Monday 2024-01-01 00:00 UTC.
+const MONDAY: i64 = 1_704_067_200;
+const HOUR: i64 = 60 * 60;
+const WEEK: i64 = 7 * 24 * HOUR;
+
+fn sample(timestamp: i64, base_fee: u64) -> FeeSample {
+    FeeSample { chain_id: 8453, timestamp, base_fee: base_fee.into(), priority_fee: 1_000.into() }
+}
+
+#[test]
+fn keeps_samples_in_sqlite_and_csv() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["gas.db", "gas.csv"] {
+        let history = GasHistory::open(dir.path().join(name)).unwrap();
+        history.record(&sample(MONDAY + HOUR, 2_000)).unwrap();
+        history.record(&sample(MONDAY, 1_000)).unwrap();
+        history.record(&FeeSample { chain_id: 1, ..sample(MONDAY, 5_000) }).unwrap();
+
+        let reopened = GasHistory::open(dir.path().join(name)).unwrap();
+        assert_eq!(reopened.samples(8453, 0).unwrap(), [sample(MONDAY, 1_000), sample(MONDAY + HOUR, 2_000)], "{}", name);
+        assert_eq!(reopened.samples(8453, MONDAY + 1).unwrap().len(), 1, "{}", name);
+    }
+}
+
+#[test]
+fn finds_the_cheapest_hour_of_the_week() {
+    let mut samples = Vec::new();
+    for week in 0..MIN_WINDOW_SAMPLES as i64 {
+        // Sunday 03:00 is cheap every week, Monday 00:00 expensive
+        samples.push(sample(MONDAY + week * WEEK + 6 * 24 * HOUR + 3 * HOUR, 9_000));
+        samples.push(sample(MONDAY + week * WEEK, 99_000));
+    }
+    // A single cheaper reading is not enough to call a window
+    samples.push(sample(MONDAY + 2 * 24 * HOUR, 0));
+
+    let trend = Trend::new(&samples);
+    assert_eq!(trend.overall.samples, 2 * MIN_WINDOW_SAMPLES + 1);
+    assert_eq!(trend.by_weekday[6].samples, MIN_WINDOW_SAMPLES);
+    assert_eq!(trend.by_hour[3].mean, 10_000.0);
+    let (weekday, hour, bucket) = trend.cheapest_window().unwrap();
+    assert_eq!((weekday, hour, bucket.samples), (6, 3, MIN_WINDOW_SAMPLES));
+    assert!(trend.report().contains("Cheapest window: Sun 03:00-04:00 UTC"));
+}
+
+#[test]
+fn reports_too_few_samples() {
+    let trend = Trend::new(&[sample(MONDAY, 1_000)]);
+    assert!(trend.cheapest_window().is_none());
+    assert!(trend.report().contains("not enough samples yet"));
+}
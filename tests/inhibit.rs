@@ -0,0 +1,26 @@
+#![cfg(target_os = "linux")]
+
+use find_tx_hash_prefix::inhibit::SleepInhibitor;
+use std::os::unix::fs::PermissionsExt;
+
+/// Puts a stand-in `systemd-inhibit` running `script` first on the PATH.
+fn fake_systemd_inhibit(dir: &tempfile::TempDir, script: &str) {
+    let path = dir.path().join("systemd-inhibit");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::env::set_var("PATH", format!("{}:{}", dir.path().display(), std::env::var("PATH").unwrap()));
+}
+
+#[test]
+fn holds_while_the_helper_runs_and_reports_when_it_cannot() {
+    let dir = tempfile::tempdir().unwrap();
+    fake_systemd_inhibit(&dir, "exec sleep 60");
+    let mut inhibitor = SleepInhibitor::acquire("test").unwrap();
+    assert!(inhibitor.is_held());
+    drop(inhibitor);
+
+    // Without logind, systemd-inhibit exits straight away
+    let dir = tempfile::tempdir().unwrap();
+    fake_systemd_inhibit(&dir, "exit 1");
+    assert!(SleepInhibitor::acquire("test").is_err());
+}
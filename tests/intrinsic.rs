@@ -0,0 +1,37 @@
+mod common;
+
+use common::{params, template};
+use ethers::types::{Address, U256};
+use find_tx_hash_prefix::intrinsic::{self, Preflight};
+use find_tx_hash_prefix::search::Salt;
+
+#[test]
+fn charges_calldata_and_creation() {
+    let mut tx = template();
+    // 0x6080604052 has no zero bytes, and creating a contract adds one init code word
+    assert_eq!(intrinsic::intrinsic_gas(&tx), 21_000 + 5 * 16 + 32_000 + 2);
+    tx.to = Some(Address::repeat_byte(1).into());
+    tx.data = Some(vec![0, 0, 1].into());
+    assert_eq!(intrinsic::intrinsic_gas(&tx), 21_000 + 2 * 4 + 16);
+}
+
+#[test]
+fn rejects_a_gas_limit_below_the_intrinsic_gas() {
+    let mut params = params(template(), "0x0", 1);
+    params.salt = Salt::Padding;
+    params.template.gas = Some(U256::from(53_082 + 8 * 16));
+    let preflight = Preflight::of(&params).unwrap();
+    assert_eq!(preflight.intrinsic_gas, 53_082 + 8 * 16);
+    assert!(preflight.check().is_ok());
+    assert!(!preflight.oversized());
+
+    params.template.gas = Some(U256::from(53_000));
+    assert!(Preflight::of(&params).unwrap().check().is_err());
+}
+
+#[test]
+fn flags_transactions_too_big_for_the_mempool() {
+    let mut params = params(template(), "0x0", 1);
+    params.template.data = Some(vec![1; intrinsic::MEMPOOL_SIZE_LIMIT].into());
+    assert!(Preflight::of(&params).unwrap().oversized());
+}
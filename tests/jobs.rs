@@ -0,0 +1,55 @@
+use find_tx_hash_prefix::jobs::{self, JobSpec};
+use std::io::Write;
+
+fn spec(name: &str, hash_prefix: &str) -> JobSpec {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "chain": "base",
+        "hash_prefix": hash_prefix,
+        "gas_limit": 21000,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn splits_threads_by_share_with_at_least_one_each() {
+    assert_eq!(jobs::split_threads(&[1.0, 1.0], 8), [4, 4]);
+    assert_eq!(jobs::split_threads(&[1.0, 3.0], 8), [2, 6]);
+    assert_eq!(jobs::split_threads(&[1.0, 1.0, 1.0], 8), [3, 3, 2]);
+    assert_eq!(jobs::split_threads(&[1.0, 100.0], 4), [1, 3]);
+    // More jobs than threads still gives every job a worker
+    assert_eq!(jobs::split_threads(&[1.0, 1.0, 1.0], 2), [1, 1, 1]);
+}
+
+#[test]
+fn loads_jobs_with_defaults() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(
+        file,
+        r#"[{{"name": "token", "chain": "8453", "hash_prefix": "0xdead", "gas_limit": 100000, "calldata": "0x6080"}},
+            {{"name": "vault", "key_var": "VAULT_KEY", "chain": "base", "hash_prefix": "beef", "gas_limit": 21000, "share": 2.5}}]"#
+    )
+    .unwrap();
+    let specs = jobs::load(file.path()).unwrap();
+    assert_eq!(specs.len(), 2);
+    assert_eq!(specs[0].key_var, "PRIVATE_KEY");
+    assert_eq!(specs[0].share, 1.0);
+    assert_eq!(specs[0].tx_spec().calldata.unwrap().to_vec(), [0x60, 0x80]);
+    assert_eq!(specs[1].key_var, "VAULT_KEY");
+    assert_eq!(specs[1].chain_id().unwrap(), 8453);
+    assert_eq!(specs[1].share, 2.5);
+}
+
+#[test]
+fn rejects_inconsistent_jobs() {
+    assert!(jobs::validate(&[]).is_err());
+    assert!(jobs::validate(&[spec("a", "0xdead"), spec("a", "0xbeef")]).is_err());
+    assert!(jobs::validate(&[spec("a", "0xnothex")]).is_err());
+    let mut idle = spec("a", "0xdead");
+    idle.share = 0.0;
+    assert!(jobs::validate(&[idle]).is_err());
+    let mut unknown_chain = spec("a", "0xdead");
+    unknown_chain.chain = "nowhere".to_string();
+    assert!(jobs::validate(&[unknown_chain]).is_err());
+    assert!(jobs::validate(&[spec("a", "0xdead"), spec("b", "0xdead")]).is_ok());
+}
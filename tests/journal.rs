@@ -0,0 +1,94 @@
+use ethers::types::{Bytes, H256, U256};
+use find_tx_hash_prefix::journal::{Journal, JournalEntry};
+use std::collections::HashSet;
+
+fn entry(nonce: u64) -> JournalEntry {
+    JournalEntry {
+        timestamp: 0,
+        chain_id: 1,
+        nonce: U256::from(nonce),
+        tx_hash: H256::repeat_byte(0xab),
+        raw_tx: Bytes::from(vec![0x02, 0xf8, 0x01]),
+        max_fee_per_gas: U256::from(1_000_000_000u64),
+        max_priority_fee_per_gas: U256::from(1_000u64),
+        job_id: None,
+        template: None,
+    }
+}
+
+#[test]
+fn encrypted_entries_need_the_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("found_candidates.jsonl");
+    let journal = Journal::open_with_passphrase(&path, Some("correct horse")).unwrap();
+    journal.append(&entry(7)).unwrap();
+
+    let on_disk = std::fs::read_to_string(&path).unwrap();
+    assert!(!on_disk.contains("02f801"), "raw transaction written in the clear: {}", on_disk);
+
+    let entries = Journal::read_entries_with_passphrase(&path, Some("correct horse")).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].nonce, U256::from(7));
+    assert_eq!(entries[0].raw_tx, entry(7).raw_tx);
+
+    assert!(Journal::read_entries(&path).is_err());
+    assert!(Journal::read_entries_with_passphrase(&path, Some("wrong")).is_err());
+    // Reopening with another passphrase would leave the journal unreadable as a whole
+    assert!(Journal::open_with_passphrase(&path, Some("wrong")).is_err());
+}
+
+#[test]
+fn reads_plain_entries_alongside_encrypted_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("found_candidates.jsonl");
+    Journal::open(&path).unwrap().append(&entry(1)).unwrap();
+    Journal::open_with_passphrase(&path, Some("pw")).unwrap().append(&entry(2)).unwrap();
+
+    let nonces: Vec<U256> = Journal::read_entries_with_passphrase(&path, Some("pw")).unwrap().iter().map(|e| e.nonce).collect();
+    assert_eq!(nonces, [U256::from(1), U256::from(2)]);
+}
+
+#[test]
+fn an_encrypted_journal_is_not_reopened_in_the_clear() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("found_candidates.jsonl");
+    Journal::open_with_passphrase(&path, Some("pw")).unwrap().append(&entry(1)).unwrap();
+
+    let e = Journal::open(&path).err().expect("an encrypted journal needs its passphrase to append");
+    assert!(e.to_string().contains("JOURNAL_PASSPHRASE"));
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("02f801"));
+}
+
+#[test]
+fn later_runs_seal_with_the_salt_already_in_the_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("found_candidates.jsonl");
+    for nonce in 1..=3 {
+        Journal::open_with_passphrase(&path, Some("pw")).unwrap().append(&entry(nonce)).unwrap();
+    }
+    let salts: HashSet<String> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["sealed"]["salt"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(salts.len(), 1);
+    assert_eq!(Journal::read_entries_with_passphrase(&path, Some("pw")).unwrap().len(), 3);
+}
+
+#[test]
+fn only_a_torn_last_line_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("found_candidates.jsonl");
+    let journal = Journal::open(&path).unwrap();
+    journal.append(&entry(1)).unwrap();
+    journal.append(&entry(2)).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+
+    std::fs::write(&path, format!("{}{{\"timestamp\":", contents)).unwrap();
+    assert_eq!(Journal::read_entries(&path).unwrap().len(), 2);
+
+    let (first, second) = contents.split_once('\n').unwrap();
+    std::fs::write(&path, format!("{}\n{{\"timestamp\":\n{}", first, second)).unwrap();
+    let e = Journal::read_entries(&path).unwrap_err();
+    assert!(e.to_string().contains("line 2"), "{}", e);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{journal, params, template, MockSigner};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::{Bytes, U256};
+use find_tx_hash_prefix::fees::FeeProfile;
+use find_tx_hash_prefix::search::{self, CandidateSigner, SearchParams};
+use find_tx_hash_prefix::stats::SearchStats;
+use parking_lot::Mutex;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Remembers the tip and max fee of every candidate it signs.
+#[derive(Default)]
+struct RecordingSigner(Mutex<Vec<(U256, U256)>>);
+
+impl CandidateSigner for RecordingSigner {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        self.0.lock().push((tx.max_priority_fee_per_gas.unwrap_or_default(), tx.max_fee_per_gas.unwrap_or_default()));
+        MockSigner.sign_candidate(tx)
+    }
+}
+
+fn laned(hash_prefix: &str, thread_count: usize, lanes: &[u64]) -> SearchParams {
+    let mut params = params(template(), hash_prefix, thread_count);
+    params.priority_fee_lanes = lanes.iter().map(|&fee| fee.into()).collect();
+    params
+}
+
+#[test]
+fn every_lane_sweeps_the_same_max_fees_once() {
+    let (journal, _dir) = journal();
+    let signer = Arc::new(RecordingSigner::default());
+    let mut params = laned("0x0000000000000000", 5, &[500, 2_000]);
+    params.signer = signer.clone();
+    let first_fee = search::first_fee_for_salt(&params, 0);
+    params.max_fee_per_gas = Some(first_fee + 99);
+
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime.block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal)).unwrap();
+
+    assert!(found_match.is_none());
+    let signed = signer.0.lock().clone();
+    assert_eq!(signed.len(), 200);
+    assert_eq!(signed.iter().collect::<BTreeSet<_>>().len(), 200, "no candidate is signed twice");
+    for tip in [500u64, 2_000] {
+        let fees: BTreeSet<_> = signed.iter().filter(|(t, _)| *t == tip.into()).map(|(_, fee)| *fee).collect();
+        assert_eq!(fees, (0..100u64).map(|i| first_fee + i).collect());
+    }
+    // Workers 0, 2 and 4 share the first lane, 1 and 3 the second
+    assert_eq!(stats.worker(0).fee_start.load(Ordering::Relaxed), first_fee.low_u64());
+    assert_eq!(stats.worker(1).fee_start.load(Ordering::Relaxed), first_fee.low_u64());
+    assert_eq!(stats.worker(3).fee_start.load(Ordering::Relaxed), first_fee.low_u64() + 50);
+}
+
+#[test]
+fn a_lane_above_the_start_begins_at_the_first_fee_covering_it() {
+    let params = laned("0x0", 2, &[1_000, 1_001_050]);
+    let first_fee = search::first_fee_for_salt(&params, 0);
+    assert_eq!(search::first_fee_for_salt(&params.on_lane(0), 0), first_fee);
+    assert_eq!(search::first_fee_for_salt(&params.on_lane(1), 0), U256::from(1_001_050));
+    assert_eq!(params.on_lane(1).thread_count, 1);
+    assert_eq!(params.lane(3), Some(1));
+    assert_eq!(common::params(template(), "0x0", 2).lane(3), None);
+}
+
+#[test]
+fn reports_the_lane_that_found_the_match() {
+    let (journal, _dir) = journal();
+    let params = laned("0x0", 4, &[700, 800, 900]);
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime
+        .block_on(search::run(params.clone(), stats, Arc::new(AtomicBool::new(false)), journal))
+        .unwrap()
+        .expect("a one-digit prefix is always found");
+
+    let lane = params.lane_of(&found_match.tx).expect("the match tips one of the lanes");
+    assert_eq!(found_match.tx.max_priority_fee_per_gas, Some(params.priority_fee_lanes[lane]));
+    assert_eq!(common::params(template(), "0x0", 1).lane_of(&found_match.tx), None);
+}
+
+#[test]
+fn reads_lanes_and_holds_them_to_the_fee_bounds() {
+    std::env::set_var("PRIORITY_FEE_LANES_434360", "0.01gwei, 20000000,0.05 gwei");
+    let profile = FeeProfile::from_env(434360).unwrap();
+    assert_eq!(profile.priority_fee_lanes, vec![U256::from(10_000_000), U256::from(20_000_000), U256::from(50_000_000)]);
+
+    std::env::set_var("PRIORITY_FEE_LANES_434361", "0.01gwei,0.01gwei");
+    assert!(FeeProfile::from_env(434361).unwrap_err().to_string().contains("twice"));
+
+    std::env::set_var("PRIORITY_FEE_LANES_434362", "1000,5gwei");
+    std::env::set_var("MAX_FEE_PER_GAS_434362", "1gwei");
+    assert!(FeeProfile::from_env(434362).unwrap_err().to_string().contains("MAX_FEE_PER_GAS"));
+
+    std::env::set_var("PRIORITY_FEE_LANES_434363", "1000,2000");
+    std::env::set_var("MIN_PRIORITY_FEE_434363", "1500");
+    assert!(FeeProfile::from_env(434363).unwrap_err().to_string().contains("MIN_PRIORITY_FEE"));
+
+    std::env::set_var("PRIORITY_FEE_LANES_434364", "1000,2000");
+    std::env::set_var("VANITY_FEE_434364", "4.20");
+    assert!(FeeProfile::from_env(434364).unwrap_err().to_string().contains("VANITY_FEE"));
+}
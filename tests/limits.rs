@@ -0,0 +1,57 @@
+mod common;
+
+use common::{journal, params, template};
+use find_tx_hash_prefix::limits::ResourceLimits;
+use find_tx_hash_prefix::search;
+use find_tx_hash_prefix::server::JobRequest;
+use find_tx_hash_prefix::stats::SearchStats;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+const UNREACHABLE: &str = "0x0123456789abcdef";
+
+#[test]
+fn reports_the_limit_reached() {
+    let limits = ResourceLimits { cpu_time: Some(Duration::from_secs(60)), signatures: Some(1_000) };
+    assert_eq!(limits.exceeded(Duration::from_secs(59), 999), None);
+    assert_eq!(limits.exceeded(Duration::from_secs(60), 0).as_deref(), Some("limit of 60 CPU seconds"));
+    assert_eq!(limits.exceeded(Duration::ZERO, 1_000).as_deref(), Some("limit of 1000 signatures"));
+    assert_eq!(limits.to_string(), "at most 60 CPU seconds and 1000 signatures");
+    assert!(ResourceLimits::default().is_unlimited());
+    assert_eq!(ResourceLimits::default().exceeded(Duration::MAX, u64::MAX), None);
+
+    let job = ResourceLimits { cpu_time: Some(Duration::from_secs(600)), signatures: Some(10) };
+    assert_eq!(job.min(limits), ResourceLimits { cpu_time: Some(Duration::from_secs(60)), signatures: Some(10) });
+    assert_eq!(ResourceLimits::default().min(limits), limits);
+}
+
+#[test]
+fn stops_at_the_signature_limit() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), UNREACHABLE, 2);
+    params.batch_size = 100;
+    params.limits = ResourceLimits { cpu_time: None, signatures: Some(1_000) };
+
+    let stats = Arc::new(SearchStats::new(2));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found = runtime.block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal)).unwrap();
+    assert!(found.is_none());
+    assert_eq!(stats.limit_reached().as_deref(), Some("limit of 1000 signatures"));
+    // Each worker finishes the batch it is on
+    assert!((1_000..=1_200).contains(&stats.total_signatures()), "{}", stats.total_signatures());
+    assert!(stats.total_cpu_time() > Duration::ZERO);
+}
+
+#[test]
+fn a_job_can_only_tighten_the_server_limits() {
+    let job: JobRequest =
+        serde_json::from_str(r#"{"hash_prefix": "0xabc", "gas_limit": 100000, "max_cpu_seconds": 30, "swept": [["0x10", "0x20"]]}"#).unwrap();
+    let server = ResourceLimits { cpu_time: Some(Duration::from_secs(10)), signatures: Some(5_000) };
+    assert_eq!(job.limits(server).unwrap(), server);
+    assert_eq!(job.limits(ResourceLimits::default()).unwrap().cpu_time, Some(Duration::from_secs(30)));
+    assert_eq!(job.swept, [(0x10.into(), 0x20.into())]);
+
+    let job: JobRequest = serde_json::from_str(r#"{"hash_prefix": "0xabc", "gas_limit": 100000, "max_signatures": 0}"#).unwrap();
+    assert!(job.limits(server).is_err());
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::{journal, params, template};
+use find_tx_hash_prefix::calldata::{self, Parameters};
+use find_tx_hash_prefix::search::{self, LOW_MEMORY_BATCH_SIZE};
+use find_tx_hash_prefix::stats::SearchStats;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+fn load(contents: &str, parameters: &Parameters) -> eyre::Result<Vec<u8>> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("calldata.hex");
+    std::fs::write(&path, contents).unwrap();
+    calldata::load(&path, parameters).map(|bytes| bytes.to_vec())
+}
+
+#[test]
+fn streams_calldata_from_a_file() {
+    let none = Parameters::new();
+    assert_eq!(load("0x6080\n6040 52\n", &none).unwrap(), [0x60, 0x80, 0x60, 0x40, 0x52]);
+    assert_eq!(load("60806040", &none).unwrap(), [0x60, 0x80, 0x60, 0x40]);
+    assert_eq!(load("", &none).unwrap(), [] as [u8; 0]);
+    assert!(load("0x608", &none).is_err());
+    assert!(load("0x60zz", &none).is_err());
+    assert!(load("0x600x", &none).is_err());
+
+    let parameters = Parameters::from([("FLAG".to_string(), "true".to_string())]);
+    let rendered = load("0x6080\n${FLAG}\n", &parameters).unwrap();
+    assert_eq!(rendered.len(), 2 + 32);
+    assert_eq!(rendered[33], 1);
+    assert!(load("0x6080${FLAG}", &none).is_err());
+}
+
+#[test]
+fn small_batches_sweep_the_same_candidates() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let run = |batch_size: usize| {
+        let (journal, _dir) = journal();
+        let mut params = params(template(), "0x00", 1);
+        params.batch_size = batch_size;
+        let stats = Arc::new(SearchStats::new(1));
+        let found = runtime.block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal)).unwrap();
+        (found.expect("a two-digit prefix").tx_hash, stats.total_attempts())
+    };
+    assert_eq!(run(LOW_MEMORY_BATCH_SIZE), run(search::BATCH_SIZE));
+}
@@ -0,0 +1,33 @@
+use ethers::types::Address;
+use find_tx_hash_prefix::history::HistoryDb;
+use find_tx_hash_prefix::nonces::{conflicts, NonceConflict};
+
+#[test]
+fn agrees_when_the_node_is_one_past_the_last_sent_nonce() {
+    assert!(conflicts(Some(4), 5, 5).is_empty());
+    assert!(conflicts(None, 5, 5).is_empty());
+}
+
+#[test]
+fn flags_pending_foreign_and_missing_transactions() {
+    assert_eq!(conflicts(None, 5, 7), vec![NonceConflict::Pending { latest: 5, pending: 7 }]);
+    assert_eq!(conflicts(Some(4), 9, 9), vec![NonceConflict::AdvancedElsewhere { last_used: 4, latest: 9 }]);
+    assert_eq!(conflicts(Some(4), 4, 4), vec![NonceConflict::Behind { last_used: 4, latest: 4 }]);
+    // Our last transaction is still pending, which is only worth the pending warning
+    assert_eq!(conflicts(Some(4), 4, 5), vec![NonceConflict::Pending { latest: 4, pending: 5 }]);
+}
+
+#[test]
+fn remembers_the_last_nonce_per_key_and_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = HistoryDb::open(dir.path().join("deployments.db")).unwrap();
+    let from = Address::repeat_byte(0xaa);
+
+    assert_eq!(db.last_nonce(from, 1).unwrap(), None);
+    db.record_nonce(from, 1, 3).unwrap();
+    db.record_nonce(from, 1, 4).unwrap();
+    db.record_nonce(from, 10, 0).unwrap();
+    assert_eq!(db.last_nonce(from, 1).unwrap(), Some(4));
+    assert_eq!(db.last_nonce(from, 10).unwrap(), Some(0));
+    assert_eq!(db.last_nonce(Address::zero(), 1).unwrap(), None);
+}
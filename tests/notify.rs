@@ -0,0 +1,57 @@
+use find_tx_hash_prefix::notify::{render, Channel, Event, Routes};
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn parses_every_channel_kind() {
+    assert_eq!(Channel::parse("slack:https://hooks.example/a").unwrap(), Channel::Slack("https://hooks.example/a".into()));
+    assert_eq!(Channel::parse("discord:https://d.example/b").unwrap(), Channel::Discord("https://d.example/b".into()));
+    assert_eq!(
+        Channel::parse("telegram:123:ABC@-100200").unwrap(),
+        Channel::Telegram { token: "123:ABC".into(), chat_id: "-100200".into() }
+    );
+    assert_eq!(Channel::parse("email:oncall@example.com").unwrap(), Channel::Email("oncall@example.com".into()));
+    assert_eq!(Channel::parse("desktop").unwrap(), Channel::Desktop);
+    assert!(Channel::parse("pager:123").is_err());
+    assert!(Channel::parse("slack:").is_err());
+}
+
+#[test]
+fn routes_events_to_named_channels_with_templates() {
+    let routes = Routes::from_vars(vars(&[
+        ("NOTIFY_CHANNEL_ONCALL", "webhook:https://oncall.example"),
+        ("NOTIFY_CHANNEL_TEAM", "slack:https://team.example"),
+        ("NOTIFY_ROUTE_MATCH_FOUND", "oncall"),
+        ("NOTIFY_ROUTE_BROADCAST_CONFIRMED", "team, ONCALL"),
+        ("NOTIFY_TEMPLATE_BROADCAST_CONFIRMED", "{contract} is live on {chain}"),
+    ]))
+    .unwrap();
+
+    let names = |event| routes.channels_for(event).into_iter().map(|(name, _)| name.to_string()).collect::<Vec<_>>();
+    assert_eq!(names(Event::MatchFound), ["oncall"]);
+    assert_eq!(names(Event::BroadcastConfirmed), ["team", "oncall"]);
+    assert!(names(Event::BroadcastFailed).is_empty());
+
+    let event_vars = [("contract", "0xabc".to_string()), ("chain", "Base (8453)".to_string())];
+    assert_eq!(routes.render(Event::BroadcastConfirmed, &event_vars), "0xabc is live on Base (8453)");
+    assert_eq!(routes.render(Event::MatchFound, &[("tx_hash", "0x01".into()), ("chain", "1".into())]), "Match 0x01 on chain 1 is ready to confirm");
+}
+
+#[test]
+fn rejects_routes_to_unknown_channels() {
+    assert!(Routes::from_vars(vars(&[("NOTIFY_ROUTE_BROADCAST_FAILED", "nobody")])).is_err());
+}
+
+#[test]
+fn legacy_settings_page_on_match_found() {
+    let routes = Routes::from_vars(vars(&[("NOTIFY_WEBHOOK", "https://hook.example"), ("NOTIFY_DESKTOP", "1")])).unwrap();
+    assert_eq!(routes.channels_for(Event::MatchFound).len(), 2);
+    assert!(routes.channels_for(Event::BroadcastSent).is_empty());
+}
+
+#[test]
+fn leaves_unknown_placeholders_alone() {
+    assert_eq!(render("{tx_hash} {unknown}", &[("tx_hash", "0x1".into())]), "0x1 {unknown}");
+}
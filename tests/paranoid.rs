@@ -0,0 +1,50 @@
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, TransactionRequest, H256, U256};
+use find_tx_hash_prefix::envelope::{Cip64, EnvelopeSigner};
+use find_tx_hash_prefix::search::{encode_and_sign_eip1559, CandidateSigner};
+use find_tx_hash_prefix::verify::{cross_check, verify};
+
+fn tx(chain_id: u64) -> Eip1559TransactionRequest {
+    Eip1559TransactionRequest::new()
+        .chain_id(chain_id)
+        .nonce(7)
+        .max_fee_per_gas(25_000_000_000u64)
+        .max_priority_fee_per_gas(1_000_000_000u64)
+        .gas(60_000)
+        .data(vec![0x60, 0x80, 0x60, 0x40])
+}
+
+fn wallet(chain_id: u64) -> LocalWallet {
+    LocalWallet::new(&mut ethers::core::rand::thread_rng()).with_chain_id(chain_id)
+}
+
+#[test]
+fn confirms_eip1559_cip64_and_legacy_transactions() {
+    let (raw, _) = encode_and_sign_eip1559(&wallet(1), &tx(1)).unwrap();
+    cross_check(&raw, &verify(&raw, "", 1, 7.into()).unwrap()).unwrap();
+
+    let celo = EnvelopeSigner { wallet: wallet(42220), envelope: Cip64 { fee_currency: Address::repeat_byte(1) } };
+    let (raw, _) = celo.sign_candidate(&tx(42220)).unwrap();
+    cross_check(&raw, &verify(&raw, "", 42220, 7.into()).unwrap()).unwrap();
+
+    let wallet = wallet(1);
+    let legacy: TypedTransaction = TransactionRequest::new().chain_id(1).nonce(7).gas(21_000).gas_price(1).to(Address::zero()).into();
+    let raw = legacy.rlp_signed(&wallet.sign_transaction_sync(&legacy).unwrap());
+    cross_check(&raw, &verify(&raw, "", 1, 7.into()).unwrap()).unwrap();
+}
+
+#[test]
+fn rejects_a_hash_or_signer_the_second_path_disagrees_with() {
+    let (raw, _) = encode_and_sign_eip1559(&wallet(1), &tx(1)).unwrap();
+    let verified = verify(&raw, "", 1, U256::from(7)).unwrap();
+
+    let mut wrong_hash = verified.clone();
+    wrong_hash.hash = H256::repeat_byte(0xba);
+    assert!(cross_check(&raw, &wrong_hash).unwrap_err().to_string().contains("keccak implementations disagree"));
+
+    let mut wrong_signer = verified;
+    wrong_signer.from = Address::repeat_byte(2);
+    assert!(cross_check(&raw, &wrong_signer).unwrap_err().to_string().contains("recovers"));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{journal, params, template};
+use find_tx_hash_prefix::patternfile::{self, LivePatterns};
+use find_tx_hash_prefix::search;
+use find_tx_hash_prefix::stats::SearchStats;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn reads_patterns_like_hash_prefix_skipping_comments_and_repeats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("patterns.txt");
+    std::fs::write(&path, "# accepted prefixes\n0xC0FFEE\n\n  dead  \n0xc0ffee\n").unwrap();
+    assert_eq!(patternfile::read(&path).unwrap(), vec!["0xc0ffee".to_string(), "0xdead".to_string()]);
+
+    std::fs::write(&path, "0xdead\n0xcafe\n0xbeer\n").unwrap();
+    let error = patternfile::read(&path).unwrap_err().to_string();
+    assert!(error.contains("line 3"), "{}", error);
+
+    std::fs::write(&path, "# nothing yet\n").unwrap();
+    assert!(patternfile::read(&path).unwrap_err().to_string().contains("no patterns"));
+}
+
+#[test]
+fn a_reload_replaces_the_patterns_unless_the_file_is_invalid() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("patterns.txt");
+    std::fs::write(&path, "0xab\n").unwrap();
+    let patterns = LivePatterns::load(&path).unwrap();
+    let mut hash = [0u8; 32];
+    hash[0] = 0xab;
+    hash[1] = 0xcd;
+    assert_eq!(patterns.matching(&hash).as_deref(), Some("0xab"));
+
+    // Tightened: the shorter prefix no longer matches
+    std::fs::write(&path, "0xabce\n0x12\n").unwrap();
+    assert_eq!(patterns.reload().unwrap(), vec!["0xabce".to_string(), "0x12".to_string()]);
+    assert_eq!(patterns.matching(&hash), None);
+
+    std::fs::write(&path, "0xabcz\n").unwrap();
+    assert!(patterns.reload().is_err());
+    assert_eq!(patterns.accepted(), vec!["0xabce".to_string(), "0x12".to_string()]);
+}
+
+#[test]
+fn the_search_accepts_a_pattern_added_to_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("patterns.txt");
+    std::fs::write(&path, "0x0000000000000000\n").unwrap();
+    let patterns = Arc::new(LivePatterns::load(&path).unwrap());
+    std::fs::write(&path, "0x0000000000000000\n0x1\n").unwrap();
+    patterns.reload().unwrap();
+
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0000000000000000", 2);
+    params.patterns = Some(patterns);
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime
+        .block_on(search::run(params, stats, Arc::new(AtomicBool::new(false)), journal))
+        .unwrap()
+        .expect("a one-digit pattern is always found");
+    assert!(search::hash_matches(&found_match.tx_hash, "0x1"));
+}
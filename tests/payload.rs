@@ -0,0 +1,76 @@
+mod common;
+
+use common::{journal, params, template};
+use find_tx_hash_prefix::address::get_contract_address;
+use find_tx_hash_prefix::payload::{self, MatchPayload};
+use find_tx_hash_prefix::search;
+use find_tx_hash_prefix::stats::SearchStats;
+use find_tx_hash_prefix::vectors;
+use serde_json::Value;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Checks that every property the schema requires at each level is present in `value`.
+fn assert_has_required(schema: &Value, value: &Value, path: &str) {
+    for name in schema["required"].as_array().into_iter().flatten() {
+        let name = name.as_str().unwrap();
+        assert!(value.get(name).is_some(), "{}.{} is required by the schema", path, name);
+    }
+    for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+        if let Some(field) = value.get(name).filter(|field| field.is_object()) {
+            assert_has_required(property, field, &format!("{}.{}", path, name));
+        }
+    }
+    for name in value.as_object().into_iter().flatten().map(|(name, _)| name) {
+        assert!(schema["properties"].get(name).is_some(), "{}.{} is not in the schema", path, name);
+    }
+}
+
+#[test]
+fn describes_a_found_match_with_its_matched_span_and_search() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0", 2);
+    params.priority_fee_lanes = vec![1_000.into(), 2_000.into()];
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime
+        .block_on(search::run(params.clone(), stats.clone(), Arc::new(AtomicBool::new(false)), journal))
+        .unwrap()
+        .expect("a one-digit prefix is always found");
+
+    let found = MatchPayload::from_raw("0x0", &found_match.signed_rlp).unwrap().with_search(&params, &stats);
+    assert_eq!((found.schema.as_str(), found.version), (payload::SCHEMA_NAME, payload::SCHEMA_VERSION));
+    assert_eq!((found.matched.start, found.matched.end, found.matched.digits.as_str()), (0, 1, "0"));
+    assert_eq!(found.tx.hash.0, found_match.tx_hash);
+    assert_eq!(found.tx.nonce, 7.into());
+    assert_eq!(found.tx.contract_address, Some(get_contract_address(found.tx.from, 7.into())));
+    assert_eq!(found.tx.max_fee_per_gas, found_match.max_fee_per_gas);
+    assert_eq!(found.cost.worst_case_wei, found_match.total_fee_wei);
+    assert_eq!(found.cost.priority_fee_lane, params.lane_of(&found_match.tx));
+    assert_eq!(found.search.as_ref().unwrap().attempts, stats.total_attempts());
+
+    let schema: Value = serde_json::from_str(payload::SCHEMA).unwrap();
+    assert_has_required(&schema, &found.to_value(), "match");
+    let json = serde_json::to_string(&found).unwrap();
+    assert_eq!(serde_json::from_str::<MatchPayload>(&json).unwrap(), found);
+}
+
+#[test]
+fn reads_everything_from_the_raw_transaction() {
+    let vector = vectors::corpus().into_iter().find(|vector| vector.name == "eip1559-base-create").unwrap();
+    let digits = hex::encode(vector.hash);
+    let differs = if &digits[3..4] == "0" { "1" } else { "0" };
+    let hash_prefix = format!("0x{}{}f", &digits[..3], differs);
+    let found = MatchPayload::from_raw(&hash_prefix, &vector.raw).unwrap();
+
+    assert_eq!(found.tx.from, vector.from);
+    assert_eq!(found.tx.hash, vector.hash);
+    assert_eq!(found.tx.chain_id, 8453);
+    assert_eq!(found.pattern.digits, 5);
+    // A partial match only spans the digits it shares with the pattern
+    assert_eq!((found.matched.start, found.matched.end), (0, 3));
+    assert!(found.search.is_none() && found.to_value().get("search").is_none());
+
+    let schema: Value = serde_json::from_str(payload::SCHEMA).unwrap();
+    assert_has_required(&schema, &found.to_value(), "match");
+}
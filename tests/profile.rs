@@ -0,0 +1,29 @@
+use find_tx_hash_prefix::profile::{self, HashrateProfile};
+use std::time::Duration;
+
+#[test]
+fn averages_runs_per_host_and_thread_count() {
+    let mut profile = HashrateProfile::default();
+    assert_eq!(profile.record("laptop", 8, 1000.0, 1), 1000.0);
+    assert_eq!(profile.record("laptop", 8, 2000.0, 2), 1300.0);
+    profile.record("laptop", 4, 600.0, 3);
+    profile.record("server", 8, 9000.0, 4);
+
+    let entry = profile.entry("laptop", 8).unwrap();
+    assert_eq!((entry.hashrate, entry.runs, entry.updated), (1300.0, 2, 2));
+    assert_eq!(profile.entry("laptop", 4).unwrap().hashrate, 600.0);
+    assert!(profile.entry("laptop", 16).is_none());
+}
+
+#[test]
+fn records_runs_to_disk_skipping_short_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hashrate_profile.json");
+    let path = path.to_str().unwrap();
+    assert!(HashrateProfile::load(path).unwrap().entries.is_empty());
+
+    assert_eq!(profile::record_run(path, 2, 500, Duration::from_secs(1)).unwrap(), None);
+    assert_eq!(profile::record_run(path, 2, 50_000, Duration::from_secs(20)).unwrap(), Some(2500.0));
+    assert_eq!(profile::measured_hashrate(path, 2), Some(2500.0));
+    assert_eq!(profile::measured_hashrate(path, 3), None);
+}
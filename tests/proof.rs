@@ -0,0 +1,65 @@
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{TransactionReceipt, H256};
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::address::get_contract_address;
+use find_tx_hash_prefix::proof;
+
+fn deployment(wallet: &LocalWallet) -> (Vec<u8>, TransactionReceipt) {
+    let tx: TypedTransaction = Eip1559TransactionRequest::new()
+        .chain_id(8453)
+        .nonce(5)
+        .gas(100_000)
+        .max_fee_per_gas(2_000_000_000u64)
+        .max_priority_fee_per_gas(1_000_000u64)
+        .data(vec![0x60, 0x80, 0x60, 0x40, 0x52])
+        .into();
+    let signature = wallet.sign_transaction_sync(&tx).unwrap();
+    let raw_tx = tx.rlp_signed(&signature).to_vec();
+    let receipt = TransactionReceipt {
+        transaction_hash: H256::from(keccak256(&raw_tx)),
+        contract_address: Some(get_contract_address(wallet.address(), 5.into())),
+        status: Some(1.into()),
+        block_number: Some(16.into()),
+        ..Default::default()
+    };
+    (raw_tx, receipt)
+}
+
+#[test]
+fn bundles_a_deployment_that_checks_out() {
+    let wallet = LocalWallet::from_bytes(&[1; 32]).unwrap().with_chain_id(8453u64);
+    let (raw_tx, receipt) = deployment(&wallet);
+    let files = proof::build(&raw_tx, &receipt, 8453, &wallet, 1_700_000_000).unwrap();
+
+    let manifest = proof::check(&files).unwrap();
+    assert_eq!(manifest.from, wallet.address());
+    assert_eq!(manifest.signed_by, wallet.address());
+    assert_eq!(manifest.predicted_address, receipt.contract_address);
+    assert_eq!(manifest.actual_address, receipt.contract_address);
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert!(names.contains(&"verify.sh") && names.contains(&"manifest.sig"));
+
+    let archive = proof::to_tar("proof", &files, 1_700_000_000);
+    assert_eq!(archive.len() % 512, 0);
+    assert_eq!(proof::from_tar(&archive).unwrap(), files);
+}
+
+#[test]
+fn detects_tampering_and_foreign_receipts() {
+    let wallet = LocalWallet::from_bytes(&[1; 32]).unwrap().with_chain_id(8453u64);
+    let (raw_tx, receipt) = deployment(&wallet);
+    let files = proof::build(&raw_tx, &receipt, 8453, &wallet, 1_700_000_000).unwrap();
+
+    for target in ["calldata.hex", "SHA256SUMS", "manifest.sig"] {
+        let mut tampered = files.clone();
+        let (_, contents) = tampered.iter_mut().find(|(name, _)| name == target).unwrap();
+        contents[3] ^= 1;
+        assert!(proof::check(&tampered).is_err(), "{} was changed", target);
+    }
+
+    let mut other = receipt.clone();
+    other.transaction_hash = H256::repeat_byte(1);
+    assert!(proof::build(&raw_tx, &other, 8453, &wallet, 1_700_000_000).is_err());
+}
@@ -0,0 +1,56 @@
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::history::{Deployment, HistoryDb};
+use find_tx_hash_prefix::redeploy;
+
+fn deployment(name: &str, calldata: &[u8], timestamp: i64) -> Deployment {
+    Deployment {
+        chain_id: 8453,
+        tx_hash: H256::random(),
+        from: Address::random(),
+        contract_address: Some(Address::random()),
+        nonce: 0,
+        calldata_hash: Some(H256::from(keccak256(calldata))),
+        calldata_size: Some(calldata.len() as u64),
+        name: Some(name.to_string()),
+        cost_wei: U256::from(1),
+        timestamp,
+    }
+}
+
+#[test]
+fn flags_changed_calldata_against_the_latest_named_deployment() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = HistoryDb::open(dir.path().join("deployments.db")).unwrap();
+    db.record(&deployment("Token", &[1, 2, 3], 1)).unwrap();
+    db.record(&deployment("Token", &[1, 2, 3, 4], 2)).unwrap();
+    db.record(&deployment("Other", &[9], 3)).unwrap();
+
+    assert!(redeploy::compare(&db, "Token", &[1, 2, 3, 4]).unwrap().is_none());
+    assert!(redeploy::compare(&db, "Unknown", &[1]).unwrap().is_none());
+
+    let change = redeploy::compare(&db, "Token", &[1, 2]).unwrap().expect("calldata changed");
+    assert_eq!(change.previous.timestamp, 2);
+    assert_eq!(change.size, 2);
+    assert_eq!(change.size_delta(), Some(-2));
+}
+
+#[test]
+fn upgrades_databases_created_before_names_were_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("deployments.db");
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE deployments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, chain_id INTEGER NOT NULL, tx_hash TEXT NOT NULL,
+                sender TEXT NOT NULL, contract_address TEXT, nonce INTEGER NOT NULL, calldata_hash TEXT,
+                cost_wei TEXT NOT NULL, timestamp INTEGER NOT NULL
+            );",
+        )
+        .unwrap();
+
+    let db = HistoryDb::open(&path).unwrap();
+    db.record(&deployment("Token", &[1], 1)).unwrap();
+    assert_eq!(db.latest_named("Token").unwrap().unwrap().calldata_size, Some(1));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{journal, params, template, MockSigner};
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::{Bytes, U256};
+use find_tx_hash_prefix::search::{self, CandidateSigner, MAX_WORKER_RESPAWNS};
+use find_tx_hash_prefix::stats::SearchStats;
+use parking_lot::Mutex;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Panics on its first `panics` calls, then signs like `MockSigner`.
+struct FlakySigner {
+    panics: u64,
+    calls: AtomicU64,
+}
+
+impl CandidateSigner for FlakySigner {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        if self.calls.fetch_add(1, Ordering::Relaxed) < self.panics {
+            panic!("signer crashed");
+        }
+        MockSigner.sign_candidate(tx)
+    }
+}
+
+#[test]
+fn replaces_a_worker_that_panics() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x00", 1);
+    params.signer = Arc::new(FlakySigner { panics: 1, calls: AtomicU64::new(0) });
+    let stats = Arc::new(SearchStats::new(1));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found = runtime
+        .block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal))
+        .unwrap()
+        .expect("the replacement worker finds a match");
+    assert!(found.tx_hash.starts_with(&[0x00]));
+    assert_eq!(stats.worker(0).respawns(), 1);
+}
+
+/// Panics on call number `panic_at`, and remembers the max fee of every candidate it signs.
+struct RecordingSigner {
+    panic_at: u64,
+    calls: AtomicU64,
+    signed: Mutex<BTreeSet<U256>>,
+}
+
+impl CandidateSigner for RecordingSigner {
+    fn sign_candidate(&self, tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        if self.calls.fetch_add(1, Ordering::Relaxed) == self.panic_at {
+            panic!("signer crashed");
+        }
+        self.signed.lock().insert(tx.max_fee_per_gas.unwrap_or_default());
+        MockSigner.sign_candidate(tx)
+    }
+}
+
+#[test]
+fn the_replacement_finishes_the_batch_its_predecessor_claimed() {
+    let (journal, _dir) = journal();
+    // A prefix no candidate matches, so the worker sweeps up to the ceiling
+    let mut params = params(template(), "0x0000000000000000", 1);
+    params.batch_size = 10;
+    let start = search::first_fee_for_salt(&params, 0);
+    params.max_fee_per_gas = Some(start + 29);
+    let signer = Arc::new(RecordingSigner { panic_at: 5, calls: AtomicU64::new(0), signed: Mutex::new(BTreeSet::new()) });
+    params.signer = signer.clone();
+    let stats = Arc::new(SearchStats::new(1));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found = runtime.block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal)).unwrap();
+    assert!(found.is_none());
+    assert_eq!(stats.worker(0).respawns(), 1);
+    let expected: BTreeSet<U256> = (0..30u64).map(|offset| start + offset).collect();
+    assert_eq!(*signer.signed.lock(), expected);
+    assert_eq!(stats.total_duplicates_skipped(), 0);
+}
+
+#[test]
+fn gives_up_on_a_worker_that_keeps_panicking() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x00", 1);
+    params.signer = Arc::new(FlakySigner { panics: u64::MAX, calls: AtomicU64::new(0) });
+    let stats = Arc::new(SearchStats::new(1));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let result = runtime.block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal));
+    assert!(result.unwrap_err().to_string().contains("signer crashed"));
+    assert_eq!(stats.worker(0).respawns(), MAX_WORKER_RESPAWNS);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{journal, params, template};
+use ethers::types::U256;
+use find_tx_hash_prefix::journal::Journal;
+use find_tx_hash_prefix::payload::MatchPayload;
+use find_tx_hash_prefix::reuse::{self, Freshness, Origin, Staleness};
+use find_tx_hash_prefix::search;
+use find_tx_hash_prefix::stats::SearchStats;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn the_template_hash_ignores_fees_but_not_the_transaction() {
+    let mut repriced = template();
+    repriced.max_fee_per_gas = Some(123.into());
+    repriced.max_priority_fee_per_gas = Some(4.into());
+    assert_eq!(reuse::template_hash(&repriced), reuse::template_hash(&template()));
+
+    let mut renonced = template();
+    renonced.nonce = Some(8.into());
+    assert_ne!(reuse::template_hash(&renonced), reuse::template_hash(&template()));
+}
+
+#[test]
+fn finds_a_journaled_match_for_the_same_template_and_pattern() {
+    let (journal, _dir) = journal();
+    let params = params(template(), "0x0", 1);
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime
+        .block_on(search::run(params.clone(), Arc::new(SearchStats::new(1)), Arc::new(AtomicBool::new(false)), journal.clone()))
+        .unwrap()
+        .expect("a one-digit prefix is always found");
+
+    let entries = Journal::read_entries(journal.path()).unwrap();
+    let entry = reuse::find(&entries, &params.template, &["0x0".to_string()]).expect("the match is journaled with its template");
+    assert_eq!(entry.tx_hash.0, found_match.tx_hash);
+    let reused = reuse::to_match(entry, params.template.gas.unwrap()).unwrap();
+    assert_eq!((reused.signed_rlp, reused.total_fee_wei), (found_match.signed_rlp, found_match.total_fee_wei));
+    assert_eq!(reused.tx.max_fee_per_gas, found_match.tx.max_fee_per_gas);
+
+    assert!(reuse::find(&entries, &params.template, &["0x1".to_string()]).is_none());
+    let mut other = template();
+    other.nonce = Some(8.into());
+    assert!(reuse::find(&entries, &other, &["0x0".to_string()]).is_none());
+}
+
+#[test]
+fn a_match_is_stale_once_its_nonce_is_used_or_the_base_fee_passes_it_but_not_while_queued() {
+    let (journal, _dir) = journal();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let params = params(template(), "0x0", 1);
+    runtime.block_on(search::run(params, Arc::new(SearchStats::new(1)), Arc::new(AtomicBool::new(false)), journal.clone())).unwrap();
+    let entry = &Journal::read_entries(journal.path()).unwrap()[0];
+    let max_fee = entry.max_fee_per_gas;
+
+    assert_eq!(reuse::check_fresh(entry, 7.into(), max_fee), Ok(Freshness::Ready));
+    // Behind the match's nonce is not stale, only waiting on the transactions before it
+    assert_eq!(reuse::check_fresh(entry, 6.into(), max_fee), Ok(Freshness::Queued { account_nonce: 6.into() }));
+    assert_eq!(reuse::check_fresh(entry, 8.into(), U256::zero()), Err(Staleness::NonceUsed { account_nonce: 8.into() }));
+    assert_eq!(reuse::check_fresh(entry, 7.into(), max_fee + 1), Err(Staleness::BelowBaseFee { base_fee: max_fee + 1 }));
+}
+
+#[test]
+fn a_remined_match_replacing_a_stale_reused_one_is_reported_as_found_with_its_search() {
+    let (journal, _dir) = journal();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let params = params(template(), "0x0", 1);
+    let stats = Arc::new(SearchStats::new(1));
+    let found_match = runtime
+        .block_on(search::run(params.clone(), stats.clone(), Arc::new(AtomicBool::new(false)), journal))
+        .unwrap()
+        .expect("a one-digit prefix is always found");
+    let payload = || MatchPayload::from_raw(&params.hash_prefix, &found_match.signed_rlp).unwrap();
+
+    let reused = Origin::Journal;
+    assert_eq!(reused.announcement(), "Reusing the journaled match");
+    assert!(reused.describe(payload(), &params).search.is_none());
+
+    // The reused match went stale, so the search ran again
+    let remined = Origin::Search(stats.clone());
+    assert_eq!(remined.announcement(), "Match found!");
+    let search = remined.describe(payload(), &params).search.expect("a remined match carries its search");
+    assert_eq!(search.attempts, stats.total_attempts());
+    assert!(search.attempts > 0);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::{journal, params, template};
+use find_tx_hash_prefix::sampling::CandidateSampler;
+use find_tx_hash_prefix::search;
+use find_tx_hash_prefix::stats::SearchStats;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn logs_every_nth_candidate() {
+    let (journal, dir) = journal();
+    let path = dir.path().join("candidate_samples.log");
+    let mut params = params(template(), "0xffffff", 1);
+    params.max_fee_per_gas = Some(params.base_fee_start + params.priority_fee + 99);
+    params.sampler = Some(Arc::new(CandidateSampler::create(&path, 10, "test search").unwrap()));
+
+    let stats = Arc::new(SearchStats::new(1));
+    let found = search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal).await.unwrap();
+    assert!(found.is_none());
+    assert_eq!(stats.total_attempts(), 100);
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let samples: Vec<&str> = log.lines().filter(|line| !line.starts_with('#')).collect();
+    assert!(log.starts_with("# test search, every 10 candidates\n"));
+    assert_eq!(samples.len(), 10);
+    let fields: Vec<&str> = samples[0].split(' ').collect();
+    // candidate, worker, nonce, max fee, tip, value, calldata length, then the hash's first 4 bytes
+    assert_eq!(fields[1..8], ["10", "0", "7", "1001009", "1000", "0", "5"]);
+    assert_eq!(fields[8].len(), 10);
+}
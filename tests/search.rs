@@ -0,0 +1,253 @@
+mod common;
+
+use common::{journal, params, template, ManualClock};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::U256;
+use ethers::utils::keccak256;
+use find_tx_hash_prefix::journal::Journal;
+use ethers::types::transaction::eip1559::Eip1559TransactionRequest;
+use ethers::types::Bytes;
+use ethers::utils::rlp::DecoderError;
+use find_tx_hash_prefix::search::{self, CandidateSigner, Match, Salt, SearchParams};
+use find_tx_hash_prefix::stats::{FailureKind, SearchStats};
+use proptest::prelude::*;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn run(params: SearchParams, journal: Arc<Journal>, found: bool) -> (Option<Match>, Arc<SearchStats>) {
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let found = Arc::new(AtomicBool::new(found));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found_match = runtime.block_on(search::run(params, stats.clone(), found, journal)).unwrap();
+    (found_match, stats)
+}
+
+#[test]
+fn finds_a_match_for_the_prefix() {
+    let (journal, _dir) = journal();
+    let (found_match, stats) = run(params(template(), "0x0", 2), journal.clone(), false);
+
+    let found_match = found_match.expect("a one-digit prefix is always found");
+    assert!(hex::encode(found_match.tx_hash).starts_with('0'));
+    assert_eq!(keccak256(&found_match.signed_rlp), found_match.tx_hash);
+    assert!(stats.total_attempts() >= 1);
+    let entries = Journal::read_entries(journal.path()).unwrap();
+    assert!(entries.iter().any(|entry| entry.tx_hash.0 == found_match.tx_hash));
+}
+
+#[test]
+fn returns_nothing_when_already_stopped() {
+    let (journal, _dir) = journal();
+    let (found_match, stats) = run(params(template(), "0x0", 2), journal, true);
+    assert!(found_match.is_none());
+    assert_eq!(stats.total_attempts(), 0);
+}
+
+#[test]
+fn sweeps_every_fee_up_to_the_ceiling_exactly_once() {
+    let (journal, _dir) = journal();
+    let step = U256::from(1_000_000u64);
+    let mut params = params(template(), "0x0000000000000000", 4);
+    params.base_fee_start = U256::zero();
+    params.priority_fee = U256::zero();
+    params.fee_step = step;
+    // Far enough to run through the offsets of the other workers
+    params.max_fee_per_gas = Some(U256::from(search::THREAD_OFFSET_SPACING) * 3);
+
+    let (found_match, stats) = run(params.clone(), journal, false);
+
+    assert!(found_match.is_none());
+    let distinct_fees = params.max_fee_per_gas.unwrap() / step + 1;
+    assert_eq!(U256::from(stats.total_attempts()), distinct_fees);
+}
+
+#[test]
+fn splits_the_fee_range_evenly_between_workers() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0000000000000000", 3);
+    params.fee_step = U256::from(10);
+    params.max_fee_per_gas = Some(params.base_fee_start + params.priority_fee + 990);
+    assert_eq!(search::fee_count(&params), Some(U256::from(100)));
+    assert_eq!(search::worker_spacing(&params), U256::from(340));
+
+    let (found_match, stats) = run(params.clone(), journal, false);
+    assert!(found_match.is_none());
+    assert_eq!(stats.total_attempts(), 100);
+    let first_fee = search::first_fee_for_salt(&params, 0).low_u64();
+    for i in 0..3 {
+        assert_eq!(stats.worker(i).fee_start.load(std::sync::atomic::Ordering::Relaxed), first_fee + 340 * i as u64);
+    }
+
+    params.max_fee_per_gas = None;
+    assert_eq!(search::fee_count(&params), None);
+    assert_eq!(search::worker_spacing(&params), U256::from(search::THREAD_OFFSET_SPACING));
+    params.max_fee_per_gas = Some(U256::from(5));
+    assert_eq!(search::fee_count(&params), Some(U256::zero()));
+}
+
+#[test]
+fn a_tip_salt_never_signs_a_tip_above_the_max_fee() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0000000000000000", 1);
+    params.base_fee_start = U256::zero();
+    params.fee_step = U256::one();
+    params.salt = Salt::Tip;
+    params.max_fee_per_gas = Some(params.priority_fee + 20);
+
+    let (found_match, stats) = run(params.clone(), journal, false);
+
+    // Salt n sweeps 1000 + n up to 1020, until the tip outgrows the range after salt 20
+    assert!(found_match.is_none());
+    assert_eq!(stats.total_attempts(), (1..=21).sum::<u64>());
+    let clamped = search::candidate(&params, params.priority_fee, 50);
+    assert_eq!(clamped.max_priority_fee_per_gas, Some(params.priority_fee));
+}
+
+/// Fails every candidate the way a template that cannot be encoded would.
+struct BrokenSigner;
+
+impl CandidateSigner for BrokenSigner {
+    fn sign_candidate(&self, _tx: &Eip1559TransactionRequest) -> eyre::Result<(Bytes, [u8; 32])> {
+        Err(DecoderError::RlpIncorrectListLen.into())
+    }
+}
+
+#[test]
+fn aborts_when_candidates_keep_failing_to_sign() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0", 2);
+    params.signer = Arc::new(BrokenSigner);
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let error = runtime
+        .block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal))
+        .expect_err("a signer that always fails must abort the search");
+
+    assert!(error.to_string().contains("RlpIncorrectListLen"), "{}", error);
+    assert!(stats.total_failures(FailureKind::Encoding) >= search::BATCH_SIZE as u64);
+    assert_eq!(stats.total_failures(FailureKind::Signing), 0);
+    assert_eq!(stats.total_sign_failures(), stats.total_attempts());
+}
+
+#[test]
+fn rates_follow_the_clock() {
+    let clock = Arc::new(ManualClock::default());
+    let stats = SearchStats::with_clock(2, clock.clone());
+    for _ in 0..300 {
+        stats.worker(0).record_attempt(U256::from(10));
+    }
+    for _ in 0..100 {
+        stats.worker(1).record_attempt(U256::from(20));
+    }
+    clock.advance(Duration::from_secs(4));
+
+    assert_eq!(stats.elapsed(), Duration::from_secs(4));
+    assert_eq!(stats.hashrate(), 100.0);
+    assert_eq!(stats.worker_hashrate(0), 75.0);
+    assert_eq!(stats.worker_hashrate(1), 25.0);
+    assert!(stats.postmortem("0x00").contains("Wall-clock time:    4.0s"));
+}
+
+fn salts() -> impl Strategy<Value = Salt> {
+    prop_oneof![Just(Salt::None), Just(Salt::Tip), Just(Salt::Padding), Just(Salt::Dust)]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(24))]
+
+    #[test]
+    fn reported_matches_satisfy_the_pattern_and_decode_to_the_template(
+        prefix in "[0-9a-f]{1,2}",
+        fee_step in 1u64..1_000_000,
+        salt in salts(),
+        value in 0u64..1_000,
+        threads in 1usize..4,
+    ) {
+        let (journal, _dir) = journal();
+        let mut template = template();
+        template.value = Some(value.into());
+        let mut params = params(template.clone(), &format!("0x{}", prefix), threads);
+        params.fee_step = fee_step.into();
+        params.salt = salt;
+        let first_fee = search::round_up_to_step(params.base_fee_start + params.priority_fee, params.fee_step);
+        if salt != Salt::None {
+            // A short band makes the workers wrap around onto new salt values
+            params.max_fee_per_gas = Some(first_fee + params.fee_step * 20);
+        }
+
+        let (found_match, _) = run(params.clone(), journal.clone(), false);
+        let found_match = found_match.expect("a short prefix is always found");
+
+        prop_assert!(hex::encode(found_match.tx_hash).starts_with(&prefix));
+        prop_assert_eq!(keccak256(&found_match.signed_rlp), found_match.tx_hash);
+
+        let (decoded, _) = TypedTransaction::decode_signed(&rlp::Rlp::new(&found_match.signed_rlp)).unwrap();
+        let TypedTransaction::Eip1559(decoded) = decoded else {
+            return Err(TestCaseError::fail("not an EIP-1559 transaction"));
+        };
+        prop_assert_eq!(decoded.nonce, template.nonce);
+        prop_assert_eq!(decoded.gas, template.gas);
+        prop_assert_eq!(decoded.chain_id, template.chain_id);
+        prop_assert_eq!(&decoded.to, &template.to);
+
+        let max_fee = decoded.max_fee_per_gas.unwrap();
+        prop_assert_eq!(max_fee, found_match.max_fee_per_gas);
+        prop_assert!((max_fee % params.fee_step).is_zero());
+        prop_assert!(max_fee >= first_fee);
+        if let Some(ceiling) = params.max_fee_per_gas {
+            prop_assert!(max_fee <= ceiling);
+        }
+
+        let template_data = template.data.clone().unwrap();
+        let data = decoded.data.clone().unwrap_or_default();
+        let priority_fee = decoded.max_priority_fee_per_gas.unwrap();
+        prop_assert!(priority_fee <= max_fee);
+        let decoded_value = decoded.value.unwrap_or_default();
+        match salt {
+            Salt::Padding => {
+                prop_assert_eq!(data.len(), template_data.len() + 8);
+                prop_assert!(data.starts_with(&template_data));
+            }
+            _ => prop_assert_eq!(&data, &template_data),
+        }
+        match salt {
+            Salt::Tip => prop_assert!(priority_fee >= params.priority_fee),
+            _ => prop_assert_eq!(priority_fee, params.priority_fee),
+        }
+        match salt {
+            Salt::Dust => prop_assert!(decoded_value >= U256::from(value)),
+            _ => prop_assert_eq!(decoded_value, U256::from(value)),
+        }
+
+        let entries = Journal::read_entries(journal.path()).unwrap();
+        prop_assert!(entries.iter().any(|entry| entry.tx_hash.0 == found_match.tx_hash));
+    }
+}
+
+#[test]
+fn restarts_on_a_new_template() {
+    let (journal, _dir) = journal();
+    let params = params(template(), "0x0", 2);
+    let stats = Arc::new(SearchStats::new(params.thread_count));
+    let (sender, templates) = tokio::sync::watch::channel(template());
+    let mut updated = template();
+    updated.nonce = Some(8.into());
+    sender.send(updated).unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found = Arc::new(AtomicBool::new(false));
+    let found_match = runtime
+        .block_on(search::run_with_updates(params.clone(), stats.clone(), found.clone(), journal.clone(), templates))
+        .unwrap()
+        .expect("a one-digit prefix is always found");
+    assert_eq!(found_match.tx.nonce, Some(8.into()));
+    assert!(found.load(std::sync::atomic::Ordering::Relaxed));
+
+    // Stopping from outside still ends the search
+    let (_sender, templates) = tokio::sync::watch::channel(template());
+    let stopped = Arc::new(AtomicBool::new(true));
+    let nothing = runtime.block_on(search::run_with_updates(params, stats, stopped, journal, templates)).unwrap();
+    assert!(nothing.is_none());
+}
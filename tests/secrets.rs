@@ -0,0 +1,80 @@
+use find_tx_hash_prefix::secrets::{self, Source};
+use serde_json::json;
+use std::path::PathBuf;
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn finds_file_and_vault_references_for_unset_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let sources = secrets::sources(
+        &vars(&[
+            ("PRIVATE_KEY_FILE", "/run/secrets/deployer_key"),
+            ("RPC_8453_VAULT", "secret/data/rpc#base"),
+            ("JOURNAL_PASSPHRASE", ""),
+            ("HASH_PREFIX", "0xdead"),
+        ]),
+        dir.path(),
+    )
+    .unwrap();
+    assert_eq!(
+        sources,
+        [
+            ("PRIVATE_KEY".to_string(), Source::File("/run/secrets/deployer_key".into())),
+            ("RPC_8453".to_string(), Source::Vault { path: "secret/data/rpc".to_string(), field: "base".to_string() }),
+        ]
+    );
+}
+
+#[test]
+fn searches_the_secrets_directory_for_known_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("private_key"), "0xabc\n").unwrap();
+    std::fs::write(dir.path().join("RPC_BEARER_TOKEN"), "token").unwrap();
+    std::fs::write(dir.path().join("journal_passphrase"), "set in the environment").unwrap();
+    std::fs::write(dir.path().join("hash_prefix"), "not a secret").unwrap();
+    let sources = secrets::sources(&vars(&[("JOURNAL_PASSPHRASE", "hunter2")]), dir.path()).unwrap();
+    assert_eq!(
+        sources,
+        [
+            ("PRIVATE_KEY".to_string(), Source::File(dir.path().join("private_key"))),
+            ("RPC_BEARER_TOKEN".to_string(), Source::File(dir.path().join("RPC_BEARER_TOKEN"))),
+        ]
+    );
+    assert_eq!(secrets::read_file(&dir.path().join("private_key")).unwrap(), "0xabc");
+}
+
+#[test]
+fn leaves_other_tools_file_variables_alone() {
+    let dir = PathBuf::from("/nonexistent");
+    let sources = secrets::sources(
+        &vars(&[("SSL_CERT_FILE", "/etc/ssl/cert.pem"), ("TF_CLI_CONFIG_FILE", "/tf"), ("DEPLOYER_KEY_FILE", "/key")]),
+        &dir,
+    )
+    .unwrap();
+    assert_eq!(sources, [("DEPLOYER_KEY".to_string(), Source::File("/key".into()))]);
+}
+
+#[test]
+fn rejects_settings_given_twice() {
+    let dir = PathBuf::from("/nonexistent");
+    assert!(secrets::sources(&vars(&[("PRIVATE_KEY", "0xabc"), ("PRIVATE_KEY_FILE", "/key")]), &dir).is_err());
+    assert!(secrets::sources(&vars(&[("RPC_FILE", "/rpc"), ("RPC_VAULT", "secret/data/rpc#url")]), &dir).is_err());
+    assert!(secrets::sources(&vars(&[("RPC_VAULT", "secret/data/rpc")]), &dir).is_err());
+}
+
+#[test]
+fn reads_fields_from_both_kv_engine_versions() {
+    assert_eq!(
+        secrets::parse_reference("/secret/data/deployer#private_key").unwrap(),
+        Source::Vault { path: "secret/data/deployer".to_string(), field: "private_key".to_string() }
+    );
+    let v2 = json!({"data": {"data": {"private_key": "0xabc"}, "metadata": {"version": 3}}});
+    let v1 = json!({"data": {"private_key": "0xdef", "port": 8545}});
+    assert_eq!(secrets::extract_field(&v2, "private_key").as_deref(), Some("0xabc"));
+    assert_eq!(secrets::extract_field(&v1, "private_key").as_deref(), Some("0xdef"));
+    assert_eq!(secrets::extract_field(&v1, "port").as_deref(), Some("8545"));
+    assert_eq!(secrets::extract_field(&v2, "missing"), None);
+}
@@ -0,0 +1,16 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::standby::{self, Turn};
+
+#[test]
+fn mines_after_the_pending_transactions() {
+    assert_eq!(standby::standby_nonce(5.into(), 6.into()).unwrap(), U256::from(6));
+    assert_eq!(standby::standby_nonce(5.into(), 8.into()).unwrap(), U256::from(8));
+    assert!(standby::standby_nonce(5.into(), 5.into()).is_err());
+}
+
+#[test]
+fn sends_only_when_the_account_reaches_the_nonce() {
+    assert_eq!(standby::turn(5.into(), 6.into()), Turn::Waiting);
+    assert_eq!(standby::turn(6.into(), 6.into()), Turn::Ready);
+    assert_eq!(standby::turn(7.into(), 6.into()), Turn::Taken);
+}
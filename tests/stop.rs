@@ -0,0 +1,111 @@
+mod common;
+
+use common::{journal, params, template, ManualClock};
+use ethers::types::U256;
+use find_tx_hash_prefix::journal::Journal;
+use find_tx_hash_prefix::search::{self, Match};
+use find_tx_hash_prefix::stats::SearchStats;
+use find_tx_hash_prefix::stop::{parse_duration, PartialMatches, StopPolicy, StopRule};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+const UNREACHABLE: &str = "0x0123456789abcdef";
+
+fn partial(max_fee_per_gas: u64) -> Match {
+    let mut tx = template();
+    tx.max_fee_per_gas = Some(max_fee_per_gas.into());
+    Match {
+        signed_rlp: Default::default(),
+        tx_hash: [max_fee_per_gas as u8; 32],
+        max_fee_per_gas: max_fee_per_gas.into(),
+        total_fee_wei: U256::from(max_fee_per_gas) * 100_000,
+        tx,
+    }
+}
+
+#[test]
+fn parses_rules_separated_by_semicolons() {
+    let policy: StopPolicy = "8; 6 after 2h overhead<1%".parse().unwrap();
+    assert_eq!(
+        policy.rules,
+        [
+            StopRule { digits: 8, after: Duration::ZERO, max_overhead: None },
+            StopRule { digits: 6, after: Duration::from_secs(7_200), max_overhead: Some(0.01) },
+        ]
+    );
+    assert_eq!(policy.to_string(), "8 digit(s), or 6 digit(s) after 2h with fee overhead under 1%");
+    assert_eq!(">=5 overhead < 2.5%".parse::<StopRule>().unwrap().max_overhead, Some(0.025));
+    assert_eq!(policy.min_digits(), 6);
+
+    assert!("".parse::<StopPolicy>().is_err());
+    assert!("six".parse::<StopPolicy>().is_err());
+    assert!("6 after".parse::<StopPolicy>().is_err());
+    assert!("6 before 2h".parse::<StopPolicy>().is_err());
+    assert!("6 overhead<-1%".parse::<StopPolicy>().is_err());
+
+    assert!(policy.validate(8).is_ok());
+    assert!(policy.validate(7).is_err());
+}
+
+#[test]
+fn parses_durations() {
+    assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("30min").unwrap(), Duration::from_secs(1_800));
+    assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5_400));
+    assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(120));
+    assert!(parse_duration("2w").is_err());
+}
+
+#[test]
+fn takes_the_most_digits_a_rule_accepts() {
+    let policy: StopPolicy = "4; 2 after 2h overhead<1%".parse().unwrap();
+    let partials = PartialMatches::new(policy, 1_000.into());
+    partials.offer(1, partial(1_000));
+    partials.offer(2, partial(1_050));
+    assert!(partials.accepted(Duration::from_secs(3 * 3_600)).is_none(), "1 digit is too few, and 2 cost 5% extra");
+
+    partials.offer(3, partial(1_005));
+    partials.offer(3, partial(1_008));
+    assert!(partials.accepted(Duration::from_secs(3_600)).is_none());
+    let (digits, taken) = partials.accepted(Duration::from_secs(3 * 3_600)).unwrap();
+    assert_eq!((digits, taken.max_fee_per_gas), (3, 1_005.into()));
+    assert!((partials.overhead(1_005.into()) - 0.005).abs() < 1e-12);
+
+    partials.offer(4, partial(5_000));
+    assert_eq!(partials.accepted(Duration::ZERO).unwrap().0, 4);
+}
+
+#[test]
+fn stops_at_a_partial_match_the_policy_accepts() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), UNREACHABLE, 1);
+    params.max_fee_per_gas = Some(params.base_fee_start + params.priority_fee + 20_000);
+    params.stop_policy = Some("2".parse().unwrap());
+
+    let stats = Arc::new(SearchStats::new(1));
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found = runtime.block_on(search::run(params, stats.clone(), Arc::new(AtomicBool::new(false)), journal.clone())).unwrap();
+    let found = found.expect("a hash starting with 01 within 20000 candidates");
+    assert!(search::matched_digits(&found.tx_hash, UNREACHABLE) >= 2);
+    assert!(!search::hash_matches(&found.tx_hash, UNREACHABLE));
+    assert!(Journal::read_entries(journal.path()).unwrap().iter().any(|entry| entry.tx_hash.0 == found.tx_hash));
+}
+
+#[test]
+fn waits_for_a_rule_to_come_into_effect() {
+    let run = |elapsed: Duration| {
+        let (journal, _dir) = journal();
+        let mut params = params(template(), UNREACHABLE, 1);
+        params.max_fee_per_gas = Some(params.base_fee_start + params.priority_fee + 999);
+        params.stop_policy = Some("1 after 1h".parse().unwrap());
+        let clock = Arc::new(ManualClock::default());
+        clock.advance(elapsed);
+        let stats = Arc::new(SearchStats::with_clock(1, clock));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(search::run(params, stats, Arc::new(AtomicBool::new(false)), journal)).unwrap()
+    };
+    assert!(run(Duration::from_secs(60)).is_none());
+    let found = run(Duration::from_secs(2 * 3_600)).expect("a hash starting with 0 within 1000 candidates");
+    assert!(search::matched_digits(&found.tx_hash, UNREACHABLE) >= 1);
+}
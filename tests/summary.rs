@@ -0,0 +1,42 @@
+use ethers::types::{Address, TransactionReceipt, U256, U64};
+use find_tx_hash_prefix::summary::ShareSummary;
+
+fn receipt() -> TransactionReceipt {
+    TransactionReceipt {
+        transaction_hash: "0x0ba1000000000000000000000000000000000000000000000000000000000042".parse().unwrap(),
+        contract_address: Some(Address::repeat_byte(0x11)),
+        block_number: Some(U64::from(123)),
+        gas_used: Some(U256::from(21_000)),
+        effective_gas_price: Some(U256::from(1_000_000_000u64)),
+        status: Some(U64::one()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn highlights_the_matched_prefix() {
+    let summary = ShareSummary::from_receipt(8453, "0x0BA", &receipt(), None);
+    assert_eq!(summary.highlighted_hash(), "**0x0ba**1000000000000000000000000000000000000000000000000000000000042");
+}
+
+#[test]
+fn links_to_the_explorer_when_the_chain_has_one() {
+    let markdown = ShareSummary::from_receipt(8453, "0ba", &receipt(), None).to_markdown();
+    assert!(markdown.starts_with("**Vanity deployment on base (8453)**\n"));
+    assert!(markdown.contains("- Transaction: [**0x0ba**1"));
+    assert!(markdown.contains("](https://basescan.org/tx/0x0ba1"));
+    assert!(markdown.contains("](https://basescan.org/address/0x1111111111111111111111111111111111111111)"));
+    assert!(markdown.contains("- Block: 123\n"));
+    assert!(markdown.contains("- Cost: 0.000021 ETH\n"));
+    assert!(markdown.contains("- Odds: 1 in 4096 (3 hex digits)\n"));
+}
+
+#[test]
+fn falls_back_to_plain_text_without_an_explorer() {
+    let mut receipt = receipt();
+    receipt.contract_address = None;
+    let markdown = ShareSummary::from_receipt(31337, "0ba", &receipt, Some(Address::repeat_byte(0x22))).to_markdown();
+    assert!(markdown.contains("- Transaction: **0x0ba**1"));
+    assert!(markdown.contains("- Contract: `0x2222222222222222222222222222222222222222`\n"));
+    assert!(!markdown.contains("]("));
+}
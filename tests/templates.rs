@@ -0,0 +1,45 @@
+use find_tx_hash_prefix::templates::{self, Applied, DeployTemplates};
+
+const TEMPLATES: &str = r#"{
+    "prod-base": {"CHAIN": "base", "GAS_LIMIT": 3000000, "HASH_PREFIX": "0xba5e", "KEEP_AWAKE": true},
+    "staging": {}
+}"#;
+
+#[test]
+fn reads_named_templates() {
+    let templates = DeployTemplates::parse(TEMPLATES).unwrap();
+    assert_eq!(templates.names().collect::<Vec<_>>(), ["prod-base", "staging"]);
+    let prod = templates.get("prod-base").unwrap();
+    assert_eq!(prod["CHAIN"], "base");
+    assert_eq!(prod["GAS_LIMIT"], "3000000");
+    assert_eq!(prod["KEEP_AWAKE"], "1");
+    assert!(templates.get("staging").unwrap().is_empty());
+
+    let error = templates.get("prod").unwrap_err().to_string();
+    assert!(error.contains("prod-base, staging"), "{}", error);
+}
+
+#[test]
+fn refuses_secrets_and_odd_settings() {
+    assert!(DeployTemplates::parse(r#"{"prod": {"PRIVATE_KEY": "0x01"}}"#).is_err());
+    assert!(DeployTemplates::parse(r#"{"prod": {"RPC_8453": "https://example.com/key"}}"#).is_err());
+    assert!(DeployTemplates::parse(r#"{"prod": {"DEPLOYER_KEY": "0x01"}}"#).is_err());
+    assert!(DeployTemplates::parse(r#"{"prod": {"gas_limit": 1}}"#).is_err());
+    assert!(DeployTemplates::parse(r#"{"prod": {"GAS_LIMIT": [1]}}"#).is_err());
+    assert!(DeployTemplates::parse(r#"["prod"]"#).is_err());
+}
+
+#[test]
+fn the_shell_overrides_a_template() {
+    let templates = DeployTemplates::parse(r#"{"t": {"TEMPLATE_TEST_FROM_TEMPLATE": "a", "TEMPLATE_TEST_FROM_SHELL": "b"}}"#).unwrap();
+    std::env::set_var("TEMPLATE_TEST_FROM_TEMPLATE", "dotenv");
+    std::env::set_var("TEMPLATE_TEST_FROM_SHELL", "shell");
+
+    let applied = templates::apply(templates.get("t").unwrap(), &["TEMPLATE_TEST_FROM_SHELL".to_string()]);
+    assert_eq!(
+        applied,
+        [Applied::KeptFromShell("TEMPLATE_TEST_FROM_SHELL".into()), Applied::Set("TEMPLATE_TEST_FROM_TEMPLATE".into())]
+    );
+    assert_eq!(std::env::var("TEMPLATE_TEST_FROM_TEMPLATE").unwrap(), "a");
+    assert_eq!(std::env::var("TEMPLATE_TEST_FROM_SHELL").unwrap(), "shell");
+}
@@ -0,0 +1,71 @@
+use find_tx_hash_prefix::throttle::{parse_own_ticks, parse_proc_stat, CpuSample, ThermalWatchdog, Throttle, WatchdogAction};
+use std::sync::atomic::AtomicBool;
+
+#[test]
+fn parses_cpu_counters() {
+    let system = "cpu  100 5 50 800 40 3 2 0 0 0\ncpu0 100 5 50 800 40 3 2 0 0 0\nintr 12345\n";
+    assert_eq!(parse_proc_stat(system), Some((160, 1000)));
+    assert_eq!(parse_proc_stat("intr 1\n"), None);
+
+    // A command name with spaces and parentheses must not shift the fields
+    let own = "4242 (find (tx) hash) R 1 4242 4242 0 -1 4194304 500 0 0 0 1200 34 0 0 39 19 9 0 100 0 0";
+    assert_eq!(parse_own_ticks(own), Some(1234));
+    assert_eq!(parse_own_ticks("4242 (short) R 1"), None);
+}
+
+#[test]
+fn excludes_own_usage() {
+    let earlier = CpuSample { busy: 1_000, total: 10_000, own: 500 };
+    // 400 of the 1000 ticks were busy, 300 of them this process's
+    let later = CpuSample { busy: 1_400, total: 11_000, own: 800 };
+    assert!((later.others_usage_since(&earlier) - 0.1).abs() < 1e-9);
+    assert_eq!(earlier.others_usage_since(&earlier), 0.0);
+}
+
+#[test]
+fn paused_workers_wake_on_stop() {
+    let throttle = Throttle::new(false);
+    throttle.set_paused(true);
+    throttle.wait_until_active(0, &AtomicBool::new(true));
+    throttle.set_paused(false);
+    throttle.wait_until_active(0, &AtomicBool::new(false));
+}
+
+#[test]
+fn parks_workers_while_throughput_collapses() {
+    let mut watchdog = ThermalWatchdog::new(8);
+    for _ in 0..5 {
+        assert_eq!(watchdog.observe(1_000.0), None);
+    }
+    // A single slow interval is noise
+    assert_eq!(watchdog.observe(400.0), None);
+    assert_eq!(watchdog.observe(1_000.0), None);
+
+    assert_eq!(watchdog.observe(500.0), None);
+    assert_eq!(watchdog.observe(500.0), None);
+    assert_eq!(watchdog.observe(500.0), Some(WatchdogAction::Reduced { active: 6, rate: 500.0, baseline: 1_000.0 }));
+    assert_eq!(watchdog.active(), 6);
+
+    // Faster workers with part of the machine parked do not raise the bar
+    for _ in 0..5 {
+        assert_eq!(watchdog.observe(1_200.0), None);
+    }
+    assert_eq!(watchdog.observe(1_200.0), Some(WatchdogAction::Restored { active: 7, rate: 1_200.0, baseline: 1_000.0 }));
+
+    let mut single = ThermalWatchdog::new(1);
+    single.observe(1_000.0);
+    for _ in 0..10 {
+        assert_eq!(single.observe(100.0), None);
+    }
+    assert_eq!(single.active(), 1);
+}
+
+#[test]
+fn parked_workers_wait() {
+    let throttle = Throttle::default();
+    throttle.set_active_workers(2);
+    assert!(throttle.is_active(1));
+    assert!(!throttle.is_active(2));
+    throttle.set_paused(true);
+    assert!(!throttle.is_active(0));
+}
@@ -0,0 +1,56 @@
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Bytes, Signature, U256};
+use find_tx_hash_prefix::stats::SearchStats;
+use find_tx_hash_prefix::userop::{self, UserOperation};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+fn user_op() -> UserOperation {
+    UserOperation {
+        sender: Address::repeat_byte(0x11),
+        nonce: U256::zero(),
+        init_code: Bytes::default(),
+        call_data: userop::execute_call(Address::repeat_byte(0x22), U256::zero(), vec![0x60, 0x80].into()),
+        call_gas_limit: 100_000.into(),
+        verification_gas_limit: 150_000.into(),
+        pre_verification_gas: 50_000.into(),
+        max_fee_per_gas: 2_000_000_000u64.into(),
+        max_priority_fee_per_gas: 1_000_000_000u64.into(),
+        paymaster_and_data: Bytes::default(),
+        signature: Bytes::default(),
+    }
+}
+
+#[test]
+fn execute_call_uses_the_simple_account_selector() {
+    let call = userop::execute_call(Address::repeat_byte(0x22), U256::one(), Bytes::default());
+    assert_eq!(call[..4], [0xb6, 0x1d, 0x27, 0xf6]);
+}
+
+#[test]
+fn dummy_signature_is_shaped_like_an_ecdsa_signature() {
+    assert_eq!(user_op().with_dummy_signature().signature.len(), 65);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mined_nonce_reproduces_the_hash_and_signs_for_the_owner() {
+    let entry_point: Address = userop::ENTRY_POINT_V06.parse().unwrap();
+    let mut op = user_op();
+    let stats = Arc::new(SearchStats::new(2));
+
+    let (nonce, hash) = userop::mine(&op, entry_point, 1, "0x00", stats.clone(), Arc::new(AtomicBool::new(false)))
+        .await
+        .unwrap()
+        .expect("a two-digit prefix is always found");
+
+    assert!(format!("{:?}", hash).starts_with("0x00"));
+    assert_eq!(nonce.low_u64(), 0, "a fresh nonce key starts at sequence 0");
+    op.nonce = nonce;
+    assert_eq!(op.hash(entry_point, 1), hash);
+    assert_ne!(op.hash(entry_point, 2), hash, "the chain id is part of the hash");
+    assert!(stats.total_attempts() >= 1);
+
+    let owner = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+    let signature = Signature::try_from(userop::sign(&owner, hash).await.unwrap().as_ref()).unwrap();
+    assert_eq!(signature.recover(hash.as_bytes()).unwrap(), owner.address());
+}
@@ -0,0 +1,39 @@
+use ethers::types::U256;
+use find_tx_hash_prefix::fees::FeeProfile;
+use find_tx_hash_prefix::overhead::MarketFees;
+use find_tx_hash_prefix::units::parse_gwei;
+
+fn profile() -> FeeProfile {
+    FeeProfile {
+        base_fee_start: 18_000_000.into(),
+        priority_fee: 1_000_000.into(),
+        min_priority_fee: None,
+        max_fee_per_gas: None,
+        market_tolerance: None,
+        fee_step: 1_000_000_000u64.into(),
+        vanity_fee: None,
+        priority_fee_lanes: Vec::new(),
+    }
+}
+
+#[test]
+fn pins_both_fees_to_the_vanity_fee() {
+    let fee = parse_gwei("4.20").unwrap();
+    let pinned = profile().pinned_to(fee).unwrap();
+    assert_eq!(pinned.first_fee(), fee);
+    assert_eq!(pinned.priority_fee, fee);
+    assert_eq!(pinned.max_fee_per_gas, Some(fee));
+
+    // Remining cannot move a fixed fee, only refuse once the base fee passes it
+    let market = |base_fee: U256| MarketFees { base_fee, max_fee_per_gas: base_fee * 2, priority_fee: U256::zero() };
+    assert_eq!(pinned.clone().rebased_on(&market(fee)).unwrap().first_fee(), fee);
+    assert!(pinned.rebased_on(&market(fee + 1)).is_err());
+}
+
+#[test]
+fn rejects_a_vanity_fee_outside_the_other_bounds() {
+    let fee = parse_gwei("4.20").unwrap();
+    assert!(FeeProfile { max_fee_per_gas: Some(parse_gwei("4").unwrap()), ..profile() }.pinned_to(fee).is_err());
+    assert!(FeeProfile { min_priority_fee: Some(parse_gwei("5").unwrap()), ..profile() }.pinned_to(fee).is_err());
+    assert!(FeeProfile { market_tolerance: Some(5.0), ..profile() }.pinned_to(fee).is_err());
+}
@@ -0,0 +1,57 @@
+use ethers::types::Bytes;
+use find_tx_hash_prefix::vectors::{self, Vector};
+
+/// The signed example from the EIP-155 specification, independent of the recorded corpus.
+const EIP155_EXAMPLE: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+fn vector(name: &str) -> Vector {
+    vectors::corpus().into_iter().find(|vector| vector.name == name).unwrap()
+}
+
+#[test]
+fn the_whole_corpus_verifies() {
+    let corpus = vectors::corpus();
+    assert!(corpus.len() >= 9);
+    for (name, outcome) in vectors::check_all(&corpus) {
+        assert!(outcome.is_ok(), "{}: {:?}", name, outcome);
+    }
+    for prefix in ["eip155-", "legacy-", "eip2930-", "eip1559-", "cip64-"] {
+        assert!(corpus.iter().any(|vector| vector.name.starts_with(prefix)), "no {} vector", prefix);
+    }
+}
+
+#[test]
+fn the_corpus_holds_the_published_eip155_example() {
+    assert_eq!(hex::encode(&vector("eip155-example-ethereum-legacy").raw), EIP155_EXAMPLE);
+}
+
+#[test]
+fn a_changed_byte_or_sender_is_caught() {
+    let mut tampered = vector("eip1559-base-create");
+    let mut raw = tampered.raw.to_vec();
+    raw[20] ^= 1;
+    tampered.raw = Bytes::from(raw);
+    assert!(vectors::check(&tampered).unwrap_err().to_string().contains("hashes to"));
+
+    let mut wrong_sender = vector("eip1559-base-create");
+    wrong_sender.from = "0x3535353535353535353535353535353535353535".parse().unwrap();
+    assert!(vectors::check(&wrong_sender).is_err());
+}
+
+#[test]
+fn a_recorded_transaction_needs_no_key() {
+    let mut recorded = vector("cip64-celo-create-fee-currency");
+    recorded.key = None;
+    vectors::check(&recorded).unwrap();
+
+    let mut wrong_key = vector("eip2930-ethereum-access-list");
+    wrong_key.key = Some("0101010101010101010101010101010101010101010101010101010101010101".to_string());
+    assert!(vectors::check(&wrong_key).unwrap_err().to_string().contains("key belongs to"));
+}
+
+#[test]
+fn vectors_round_trip_through_json() {
+    let corpus = vectors::corpus();
+    let json = serde_json::to_string(&corpus).unwrap();
+    assert_eq!(vectors::parse(&json).unwrap(), corpus);
+}
@@ -0,0 +1,74 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{journal, params, template};
+use find_tx_hash_prefix::journal::Journal;
+use find_tx_hash_prefix::search;
+use find_tx_hash_prefix::stats::SearchStats;
+use find_tx_hash_prefix::verifier::{parse_verdict, CandidateMessage, Verdict, Verifier};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixListener;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+#[test]
+fn reads_verdicts() {
+    assert_eq!(parse_verdict("accept\n").unwrap(), Verdict::Accept);
+    assert_eq!(parse_verdict("REJECT").unwrap(), Verdict::Reject("no reason given".into()));
+    assert_eq!(parse_verdict("reject: fee level is denylisted").unwrap(), Verdict::Reject("fee level is denylisted".into()));
+    assert_eq!(parse_verdict(r#"{"accept": true}"#).unwrap(), Verdict::Accept);
+    assert_eq!(parse_verdict(r#"{"accept": false, "reason": "bad"}"#).unwrap(), Verdict::Reject("bad".into()));
+    assert!(parse_verdict("maybe").is_err());
+    assert!(parse_verdict(r#"{"accept": "yes"}"#).is_err());
+}
+
+#[test]
+fn keeps_searching_past_a_rejected_match() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0", 1);
+    params.verifier = Some(Arc::new(Verifier::spawn("read l; echo 'reject: denylisted'; while read l; do echo accept; done").unwrap()));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let found = runtime
+        .block_on(search::run(params, Arc::new(SearchStats::new(1)), Arc::new(AtomicBool::new(false)), journal.clone()))
+        .unwrap()
+        .expect("a second hash starting with 0");
+    let entries = Journal::read_entries(journal.path()).unwrap();
+    assert_eq!(entries.len(), 2, "the rejected match is journaled too");
+    assert_eq!(entries[1].tx_hash.0, found.tx_hash);
+}
+
+#[test]
+fn fails_when_the_verifier_does_not_answer() {
+    let (journal, _dir) = journal();
+    let mut params = params(template(), "0x0", 1);
+    params.verifier = Some(Arc::new(Verifier::spawn("exit 0").unwrap()));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let result = runtime.block_on(search::run(params, Arc::new(SearchStats::new(1)), Arc::new(AtomicBool::new(false)), journal));
+    assert!(result.is_err());
+}
+
+#[test]
+fn asks_a_verifier_on_a_unix_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("verifier.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let candidate: serde_json::Value = serde_json::from_str(&line).unwrap();
+        writeln!(writer, r#"{{"accept": false, "reason": "nonce {}"}}"#, candidate["nonce"].as_str().unwrap()).unwrap();
+    });
+
+    let verifier = Verifier::connect(path.to_str().unwrap()).unwrap();
+    let mut tx = template();
+    tx.nonce = Some(7.into());
+    let candidate = CandidateMessage::new(&tx, &Default::default(), [0; 32], 4, true);
+    assert_eq!(verifier.check(&candidate).unwrap(), Verdict::Reject("nonce 0x7".into()));
+    server.join().unwrap();
+}